@@ -1,8 +1,23 @@
-use criterion::{criterion_group, criterion_main, Criterion, black_box};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, black_box};
 use rand::Rng;
 use rust_orderbook_benchmark::rb_tree::rb_tree::RBTree;
+use rust_orderbook_benchmark::avl_tree::avl_tree::AVLTree;
+use rust_orderbook_benchmark::classic_rb_tree::classic_rb_tree::ClassicRBTree;
+use rust_orderbook_benchmark::persistent_rb_tree::persistent_rb_tree::PersistentRBTree;
 use rust_orderbook_benchmark::btree_map::btree_map::BTreeMap;
 use rust_orderbook_benchmark::bptree::bptree::BPTree;
+use rust_orderbook_benchmark::cow_bptree::cow_bptree::CowBPTree;
+use rust_orderbook_benchmark::skiplist::skiplist::SkipList;
+use rust_orderbook_benchmark::concurrent_skiplist::concurrent_skiplist::ConcurrentSkipList;
+use rust_orderbook_benchmark::price_ladder::price_ladder::PriceLadder;
+use rust_orderbook_benchmark::latency::latency::LatencyRecorder;
+use rust_orderbook_benchmark::runner::runner::{generate_pairs, Workload};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Barrier, Mutex, RwLock};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
 fn generate_random_pairs(n: usize) -> Vec<(u32, u32)> {
     let mut rng = rand::thread_rng();
@@ -25,7 +40,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in &data_100k {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
 
@@ -35,7 +50,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in &data_50k {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
 
@@ -46,7 +61,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in &data_100k {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
     group.bench_function("bptree_insert_low_freq", |b| {
@@ -55,7 +70,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in data_100k.iter().step_by(10) {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
 
@@ -66,7 +81,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in data_100k.iter().take(100) {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
     group.bench_function("bptree_insert_large_batch", |b| {
@@ -75,7 +90,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in &data_100k {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
 
@@ -86,7 +101,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in &data_100k {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
     group.bench_function("bptree_insert_batch", |b| {
@@ -97,7 +112,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
                     bpt.insert(*k, *v);
                 }
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
 
@@ -108,7 +123,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in &data_100k {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
     group.bench_function("bptree_insert_single_op", |b| {
@@ -117,7 +132,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in &data_100k {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
 
@@ -126,7 +141,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
         b.iter(|| {
             let mut bpt = BPTree::new(3);
             bpt.insert(1, 1);
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
     group.bench_function("bptree_single_delete", |b| {
@@ -134,7 +149,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             let mut bpt = BPTree::new(3);
             bpt.insert(1, 1);
             bpt.delete(&1);
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
     group.bench_function("bptree_single_query", |b| {
@@ -159,7 +174,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in &data_100k {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
     group.bench_function("bptree_bulk_delete", |b| {
@@ -175,7 +190,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
                 .collect();
             // 批量删除
             bpt.bulk_delete(&delete_keys);
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
     group.bench_function("bptree_bulk_query", |b| {
@@ -206,7 +221,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in data_100k.iter().take(10) {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
     group.bench_function("bptree_range_delete", |b| {
@@ -218,7 +233,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, _) in data_100k.iter().take(10) {
                 bpt.delete(k);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
     group.bench_function("bptree_range_query", |b| {
@@ -242,7 +257,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in &data_1m {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
 
@@ -252,7 +267,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
             for (k, v) in &data_5m {
                 bpt.insert(*k, *v);
             }
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
 
@@ -306,19 +321,19 @@ fn bench_b_plus_tree(c: &mut Criterion) {
                 bpt.delete(k);
             }
             
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
 
     // 测试不同 min_degree 对性能的影响
     for degree in [4, 8, 16, 32, 64] {
-        group.bench_function(&format!("bptree_insert_1m_degree_{}", degree), |b| {
+        group.bench_function(format!("bptree_insert_1m_degree_{}", degree), |b| {
             b.iter(|| {
                 let mut bpt = BPTree::new(degree);
                 for (k, v) in &data_1m {
                     bpt.insert(*k, *v);
                 }
-                black_box(bpt.approximate_memory_usage());
+                black_box(bpt.memory_usage());
             })
         });
     }
@@ -338,7 +353,7 @@ fn bench_b_plus_tree(c: &mut Criterion) {
                 .collect();
             // 批量删除
             bpt.bulk_delete(&delete_keys);
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
         })
     });
 
@@ -356,7 +371,60 @@ fn bench_b_plus_tree(c: &mut Criterion) {
                 .map(|(k, _)| *k)
                 .collect();
             bpt.bulk_delete(&delete_keys);
-            black_box(bpt.approximate_memory_usage());
+            black_box(bpt.memory_usage());
+        })
+    });
+
+    // 价位吃光后移除: 按句柄 (打墓碑) vs 按 key 重新下降
+    group.bench_function("bptree_remove_at_10k", |b| {
+        b.iter(|| {
+            let mut bpt = BPTree::new(3);
+            let handles: Vec<_> = data_100k.iter().take(10_000).map(|(k, v)| bpt.insert(*k, *v)).collect();
+            for h in &handles {
+                bpt.remove_at(*h);
+            }
+            black_box(bpt.len());
+        })
+    });
+    group.bench_function("bptree_delete_by_key_10k", |b| {
+        b.iter(|| {
+            let mut bpt = BPTree::new(3);
+            for (k, v) in data_100k.iter().take(10_000) {
+                bpt.insert(*k, *v);
+            }
+            for (k, _) in data_100k.iter().take(10_000) {
+                bpt.delete(k);
+            }
+            black_box(bpt.len());
+        })
+    });
+
+    // 撮合式扫单: 游标逐档吃掉 1000 个价位 vs 每次按 key 删除
+    group.bench_function("bptree_cursor_sweep_1k", |b| {
+        b.iter(|| {
+            let mut bpt = BPTree::new(3);
+            for (k, v) in data_100k.iter().take(10_000) {
+                bpt.insert(*k, *v);
+            }
+            let mut cursor = bpt.cursor_mut();
+            for _ in 0..1000 {
+                black_box(cursor.remove_current());
+            }
+        })
+    });
+    group.bench_function("bptree_delete_sweep_1k", |b| {
+        b.iter(|| {
+            let mut bpt = BPTree::new(3);
+            for (k, v) in data_100k.iter().take(10_000) {
+                bpt.insert(*k, *v);
+            }
+            let mut keys: Vec<u32> = data_100k.iter().take(10_000).map(|(k, _)| *k).collect();
+            keys.sort_unstable();
+            keys.dedup();
+            for k in keys.iter().take(1000) {
+                black_box(bpt.get(k));
+                bpt.delete(k);
+            }
         })
     });
 
@@ -591,6 +659,297 @@ fn bench_rb_tree(c: &mut Criterion) {
         })
     });
 
+    // 价位吃光后移除: 按句柄 (打墓碑) vs 按 key 重新下降
+    group.bench_function("rb_tree_remove_at_10k", |b| {
+        b.iter(|| {
+            let mut tree = RBTree::new();
+            let handles: Vec<_> = data_100k.iter().take(10_000).map(|(k, v)| tree.insert(*k, *v)).collect();
+            for h in &handles {
+                tree.remove_at(*h);
+            }
+            black_box(tree.len());
+        })
+    });
+    group.bench_function("rb_tree_delete_by_key_10k", |b| {
+        b.iter(|| {
+            let mut tree = RBTree::new();
+            for (k, v) in data_100k.iter().take(10_000) {
+                tree.insert(*k, *v);
+            }
+            for (k, _) in data_100k.iter().take(10_000) {
+                tree.delete(k);
+            }
+            black_box(tree.len());
+        })
+    });
+
+    // 总是删最优价位 (最小 key): 端点沿最左路径摘除, O(log n); 建树不计时
+    group.bench_function("rb_tree_remove_best_10k", |b| {
+        b.iter_batched(
+            || {
+                let mut tree = RBTree::new();
+                let handles: Vec<_> = (0..10_000u32).map(|k| tree.insert(k, k)).collect();
+                (tree, handles)
+            },
+            |(mut tree, handles)| {
+                for h in &handles {
+                    tree.remove_at(*h);
+                }
+                black_box(tree.len());
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("rb_tree_delete_best_by_key_10k", |b| {
+        b.iter_batched(
+            || {
+                let mut tree = RBTree::new();
+                for k in 0..10_000u32 {
+                    tree.insert(k, k);
+                }
+                tree
+            },
+            |mut tree| {
+                for k in 0..10_000u32 {
+                    tree.delete(&k);
+                }
+                black_box(tree.len());
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn bench_skiplist(c: &mut Criterion) {
+    let mut group = c.benchmark_group("SkipList Insert/Get");
+    group.sample_size(10).measurement_time(std::time::Duration::new(3, 0));
+
+    let data_100k = generate_random_pairs(100_000);
+    let data_50k = generate_random_pairs(50_000);
+
+    // 0. 10w条以上的数据 vs 10w条以下的数据的性能对比
+    group.bench_function("skiplist_insert_100k", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter() {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_insert_50k", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_50k.iter() {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    // 1. 高频 vs 低频
+    group.bench_function("skiplist_insert_high_freq", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter() {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_insert_low_freq", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter().step_by(10) {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    // 2. 小批量 vs 大批量
+    group.bench_function("skiplist_insert_small_batch", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter().take(100) {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_insert_large_batch", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter() {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    // 3. 逐笔交易 vs 批量交易
+    group.bench_function("skiplist_insert_single", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter() {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_insert_batch", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for chunk in data_100k.chunks(100) {
+                for (k, v) in chunk {
+                    list.insert(*k, *v);
+                }
+            }
+            black_box(list);
+        })
+    });
+
+    // 4. 批量 vs 单笔
+    group.bench_function("skiplist_insert_bulk", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter() {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_insert_single_op", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter() {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    // 5. 单条插入/删除/查询/区间查询的耗时对比
+    group.bench_function("skiplist_single_insert", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            list.insert(1, 1);
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_single_delete", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            list.insert(1, 1);
+            list.delete(&1);
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_single_query", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            list.insert(1, 1);
+            black_box(list.get(&1));
+        })
+    });
+
+    group.bench_function("skiplist_single_range_query", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            list.insert(1, 1);
+            black_box(list.range_query(&1, &10));
+        })
+    });
+
+    // 6. 批量插入/删除/查询/区间查询的耗时对比
+    group.bench_function("skiplist_bulk_insert", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter() {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_bulk_delete", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter() {
+                list.insert(*k, *v);
+            }
+            for (k, _) in data_100k.iter() {
+                list.delete(k);
+            }
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_bulk_query", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter() {
+                list.insert(*k, *v);
+            }
+            for (k, _) in data_100k.iter() {
+                black_box(list.get(k));
+            }
+        })
+    });
+
+    group.bench_function("skiplist_bulk_range_query", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter() {
+                list.insert(*k, *v);
+            }
+            black_box(list.range_query(&1, &10));
+        })
+    });
+
+    // 7. 区间插入/删除/查询/区间查询的耗时对比
+    group.bench_function("skiplist_range_insert", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter().take(10) {
+                list.insert(*k, *v);
+            }
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_range_delete", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter().take(10) {
+                list.insert(*k, *v);
+            }
+            for (k, _) in data_100k.iter().take(10) {
+                list.delete(k);
+            }
+            black_box(list);
+        })
+    });
+
+    group.bench_function("skiplist_range_query", |b| {
+        b.iter(|| {
+            let mut list = SkipList::new(42);
+            for (k, v) in data_100k.iter().take(10) {
+                list.insert(*k, *v);
+            }
+            black_box(list.range_query(&1, &10));
+        })
+    });
+
     group.finish();
 }
 
@@ -822,9 +1181,841 @@ fn bench_btree_map(c: &mut Criterion) {
         })
     });
 
+    // 撮合式扫单: 游标逐档吃掉 1000 个价位 vs 每次按 key 删除
+    group.bench_function("btree_map_cursor_sweep_1k", |b| {
+        b.iter(|| {
+            let mut btree = BTreeMap::new(3);
+            for (k, v) in data_100k.iter().take(10_000) {
+                btree.insert(*k, *v);
+            }
+            let mut cursor = btree.cursor_mut();
+            for _ in 0..1000 {
+                black_box(cursor.remove_current());
+            }
+        })
+    });
+    group.bench_function("btree_map_delete_sweep_1k", |b| {
+        b.iter(|| {
+            let mut btree = BTreeMap::new(3);
+            for (k, v) in data_100k.iter().take(10_000) {
+                btree.insert(*k, *v);
+            }
+            let mut keys: Vec<u32> = data_100k.iter().take(10_000).map(|(k, _)| *k).collect();
+            keys.sort_unstable();
+            keys.dedup();
+            for k in keys.iter().take(1000) {
+                black_box(btree.get(k));
+                btree.delete(k);
+            }
+        })
+    });
+
+    group.finish();
+}
+
+/// AVL 与 LLRB 对比: 插入/删除的旋转开销 vs 更矮的树带来的查询收益
+fn bench_avl_tree(c: &mut Criterion) {
+    let mut group = c.benchmark_group("AVLTree vs RBTree");
+    group.sample_size(10).measurement_time(std::time::Duration::new(3, 0));
+
+    let data_100k = generate_random_pairs(100_000);
+    // 顺序价格: 行情中常见的单边推进
+    let sorted_100k: Vec<(u32, u32)> = (0..100_000).map(|k| (k, k)).collect();
+
+    let mut avl = AVLTree::new();
+    let mut rb = RBTree::new();
+    for (k, v) in &data_100k {
+        avl.insert(*k, *v);
+        rb.insert(*k, *v);
+    }
+    println!(
+        "\n[random 100k] avl height={} rotations={} | rbtree height={}",
+        avl.height(),
+        avl.rotations(),
+        rb.memory_usage().height
+    );
+
+    for (name, data) in [("random", &data_100k), ("sorted", &sorted_100k)] {
+        group.bench_function(format!("avl_insert_{}_100k", name), |b| {
+            b.iter(|| {
+                let mut tree = AVLTree::new();
+                for (k, v) in data.iter() {
+                    tree.insert(*k, *v);
+                }
+                black_box(tree.len());
+            })
+        });
+        group.bench_function(format!("rb_tree_insert_{}_100k", name), |b| {
+            b.iter(|| {
+                let mut tree = RBTree::new();
+                for (k, v) in data.iter() {
+                    tree.insert(*k, *v);
+                }
+                black_box(tree.first().is_some());
+            })
+        });
+    }
+
+    // 读多写少: 查询与最优价
+    group.bench_function("avl_find_100k", |b| {
+        b.iter(|| {
+            for (k, _) in &data_100k {
+                black_box(avl.find(k));
+            }
+        })
+    });
+    group.bench_function("rb_tree_find_100k", |b| {
+        b.iter(|| {
+            for (k, _) in &data_100k {
+                black_box(rb.find(k));
+            }
+        })
+    });
+    group.bench_function("avl_best_price", |b| {
+        b.iter(|| black_box((avl.first(), avl.last())))
+    });
+    group.bench_function("rb_tree_best_price", |b| {
+        b.iter(|| black_box((rb.first(), rb.last())))
+    });
+    group.bench_function("avl_range_query", |b| {
+        b.iter(|| black_box(avl.range_query(&40_000, &41_000).len()))
+    });
+    group.bench_function("rb_tree_range_query", |b| {
+        b.iter(|| black_box(rb.range_query(&40_000, &41_000).len()))
+    });
+
+    // 插入后全部删除
+    group.bench_function("avl_insert_delete_100k", |b| {
+        b.iter(|| {
+            let mut tree = AVLTree::new();
+            for (k, v) in &data_100k {
+                tree.insert(*k, *v);
+            }
+            for (k, _) in &data_100k {
+                tree.delete(k);
+            }
+            black_box(tree.len());
+        })
+    });
+    group.bench_function("rb_tree_insert_delete_100k", |b| {
+        b.iter(|| {
+            let mut tree = RBTree::new();
+            for (k, v) in &data_100k {
+                tree.insert(*k, *v);
+            }
+            for (k, _) in &data_100k {
+                tree.delete(k);
+            }
+            black_box(tree.first().is_none());
+        })
+    });
+
+    group.finish();
+}
+
+/// CLRS 迭代红黑树 (arena + 父链接) 与递归 LLRB 对比
+fn bench_classic_rb_tree(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ClassicRBTree vs RBTree");
+    group.sample_size(10).measurement_time(std::time::Duration::new(3, 0));
+
+    let data_100k = generate_random_pairs(100_000);
+
+    let mut classic = ClassicRBTree::new();
+    let mut rb = RBTree::new();
+    for (k, v) in &data_100k {
+        classic.insert(*k, *v);
+        rb.insert(*k, *v);
+    }
+    println!(
+        "\n[random 100k] clrs height={} rotations={} | llrb height={}",
+        classic.height(),
+        classic.rotations(),
+        rb.memory_usage().height
+    );
+
+    group.bench_function("clrs_insert_100k", |b| {
+        b.iter(|| {
+            let mut tree = ClassicRBTree::new();
+            for (k, v) in &data_100k {
+                tree.insert(*k, *v);
+            }
+            black_box(tree.len());
+        })
+    });
+    group.bench_function("llrb_insert_100k", |b| {
+        b.iter(|| {
+            let mut tree = RBTree::new();
+            for (k, v) in &data_100k {
+                tree.insert(*k, *v);
+            }
+            black_box(tree.first().is_some());
+        })
+    });
+
+    group.bench_function("clrs_find_100k", |b| {
+        b.iter(|| {
+            for (k, _) in &data_100k {
+                black_box(classic.find(k));
+            }
+        })
+    });
+    group.bench_function("llrb_find_100k", |b| {
+        b.iter(|| {
+            for (k, _) in &data_100k {
+                black_box(rb.find(k));
+            }
+        })
+    });
+
+    group.bench_function("clrs_insert_delete_100k", |b| {
+        b.iter(|| {
+            let mut tree = ClassicRBTree::new();
+            for (k, v) in &data_100k {
+                tree.insert(*k, *v);
+            }
+            for (k, _) in &data_100k {
+                tree.delete(k);
+            }
+            black_box(tree.len());
+        })
+    });
+    group.bench_function("llrb_insert_delete_100k", |b| {
+        b.iter(|| {
+            let mut tree = RBTree::new();
+            for (k, v) in &data_100k {
+                tree.insert(*k, *v);
+            }
+            for (k, _) in &data_100k {
+                tree.delete(k);
+            }
+            black_box(tree.first().is_none());
+        })
+    });
+
+    // 按句柄删除 vs 按 key 删除: 最优价被吃光后移除该价位
+    group.bench_function("clrs_remove_best_by_handle", |b| {
+        b.iter(|| {
+            let mut tree = ClassicRBTree::new();
+            for (k, v) in data_100k.iter().take(10_000) {
+                tree.insert(*k, *v);
+            }
+            while let Some(id) = tree.first() {
+                tree.remove_at(id);
+            }
+            black_box(tree.len());
+        })
+    });
+    group.bench_function("llrb_remove_best_by_key", |b| {
+        b.iter(|| {
+            let mut tree = RBTree::new();
+            for (k, v) in data_100k.iter().take(10_000) {
+                tree.insert(*k, *v);
+            }
+            while let Some((k, _)) = tree.first() {
+                let k = *k;
+                tree.delete(&k);
+            }
+            black_box(tree.first().is_none());
+        })
+    });
+
+    // 全量中序遍历: 后继均摊 O(1)
+    group.bench_function("clrs_iter_successor", |b| {
+        b.iter(|| black_box(classic.iter().count()))
+    });
+    group.bench_function("llrb_range_query_all", |b| {
+        b.iter(|| black_box(rb.range_query(&0, &u32::MAX).len()))
+    });
+
     group.finish();
 }
 
-criterion_group!(benches, bench_b_plus_tree, bench_rb_tree, bench_btree_map);
+/// PriceLadder 与 BPTree 同数据对比 (价格区间 [0, 100000], tick = 1)
+fn bench_price_ladder(c: &mut Criterion) {
+    let mut group = c.benchmark_group("PriceLadder vs BPTree");
+    group.sample_size(10).measurement_time(std::time::Duration::new(3, 0));
+
+    let data_100k = generate_random_pairs(100_000);
+    let degree = 16;
+
+    let mut ladder = PriceLadder::new(0, 100_000, 1);
+    let mut bpt = BPTree::new(degree);
+    for (k, v) in &data_100k {
+        ladder.insert(*k as u64, *v);
+        bpt.insert(*k, *v);
+    }
+
+    // 1. 插入
+    group.bench_function("ladder_insert_100k", |b| {
+        b.iter(|| {
+            let mut ladder = PriceLadder::new(0, 100_000, 1);
+            for (k, v) in &data_100k {
+                ladder.insert(*k as u64, *v);
+            }
+            black_box(ladder.len());
+        })
+    });
+    group.bench_function("bptree_insert_100k", |b| {
+        b.iter(|| {
+            let mut bpt = BPTree::new(degree);
+            for (k, v) in &data_100k {
+                bpt.insert(*k, *v);
+            }
+            black_box(bpt.get(&1));
+        })
+    });
+
+    // 2. 查询
+    group.bench_function("ladder_get_100k", |b| {
+        b.iter(|| {
+            for (k, _) in &data_100k {
+                black_box(ladder.get(&(*k as u64)));
+            }
+        })
+    });
+    group.bench_function("bptree_get_100k", |b| {
+        b.iter(|| {
+            for (k, _) in &data_100k {
+                black_box(bpt.get(k));
+            }
+        })
+    });
+
+    // 3. 插入后全部删除
+    group.bench_function("ladder_insert_delete_100k", |b| {
+        b.iter(|| {
+            let mut ladder = PriceLadder::new(0, 100_000, 1);
+            for (k, v) in &data_100k {
+                ladder.insert(*k as u64, *v);
+            }
+            for (k, _) in &data_100k {
+                ladder.delete(&(*k as u64));
+            }
+            black_box(ladder.len());
+        })
+    });
+    group.bench_function("bptree_insert_delete_100k", |b| {
+        b.iter(|| {
+            let mut bpt = BPTree::new(degree);
+            for (k, v) in &data_100k {
+                bpt.insert(*k, *v);
+            }
+            for (k, _) in &data_100k {
+                bpt.delete(k);
+            }
+            black_box(bpt.get(&1));
+        })
+    });
+
+    // 4. 区间查询
+    group.bench_function("ladder_range_query", |b| {
+        b.iter(|| black_box(ladder.range_query(&40_000, &41_000).len()))
+    });
+    group.bench_function("bptree_range_query", |b| {
+        b.iter(|| black_box(bpt.range_query(&40_000, &41_000).len()))
+    });
+
+    // 5. 最优价: 撤掉当前最优价后再查下一档
+    group.bench_function("ladder_best_price_churn", |b| {
+        b.iter(|| {
+            let mut ladder = ladder.clone();
+            for _ in 0..1000 {
+                let (price, _) = ladder.first().unwrap();
+                ladder.delete(&price);
+            }
+            black_box(ladder.first());
+        })
+    });
+
+    group.finish();
+}
+
+/// 持久化版本: 每次修改产生一个新版本并全部保留, 对比可变 RBTree 和逐版本完整克隆
+fn bench_persistent_rb_tree(c: &mut Criterion) {
+    let mut group = c.benchmark_group("PersistentRBTree versions");
+    group.sample_size(10).measurement_time(Duration::new(3, 0));
+
+    let data_100k = generate_random_pairs(100_000);
+    let updates = generate_random_pairs(1_000);
+
+    let mut base = PersistentRBTree::new();
+    let mut std_base = std::collections::BTreeMap::new();
+    for (k, v) in &data_100k {
+        base = base.insert(*k, *v);
+        std_base.insert(*k, *v);
+    }
+
+    // 内存共享: 1000 个版本实际占用的节点数 vs 每个版本完整复制
+    let mut versions = vec![base.clone()];
+    for (k, v) in &updates {
+        versions.push(versions.last().unwrap().insert(*k, *v));
+    }
+    let shared = PersistentRBTree::distinct_nodes(&versions);
+    let full: usize = versions.iter().map(|v| v.node_count()).sum();
+    println!(
+        "PersistentRBTree: {} versions of {} entries use {} nodes ({} B), full copies would use {} nodes ({} B)",
+        versions.len(),
+        base.len(),
+        shared,
+        shared * PersistentRBTree::<u32, u32>::node_bytes(),
+        full,
+        full * PersistentRBTree::<u32, u32>::node_bytes()
+    );
+
+    // 1. 构建: 每次 insert 返回新版本 vs 原地修改
+    group.bench_function("persistent_insert_100k", |b| {
+        b.iter(|| {
+            let mut tree = PersistentRBTree::new();
+            for (k, v) in &data_100k {
+                tree = tree.insert(*k, *v);
+            }
+            black_box(tree.len());
+        })
+    });
+    group.bench_function("rb_tree_insert_100k", |b| {
+        b.iter(|| {
+            let mut tree = RBTree::new();
+            for (k, v) in &data_100k {
+                tree.insert(*k, *v);
+            }
+            black_box(tree.len());
+        })
+    });
+
+    // 2. 在 10w 条目的树上连续产生 100 个版本并全部保留 (完整克隆 1000 次太慢)
+    group.bench_function("persistent_keep_100_versions", |b| {
+        b.iter(|| {
+            let mut versions = Vec::with_capacity(101);
+            versions.push(base.clone());
+            for (i, (k, v)) in updates[..100].iter().enumerate() {
+                let prev = versions.last().unwrap();
+                let next = if i % 2 == 0 { prev.insert(*k, *v) } else { prev.delete(&data_100k[i].0) };
+                versions.push(next);
+            }
+            black_box(versions.len());
+        })
+    });
+    group.bench_function("std_clone_keep_100_versions", |b| {
+        b.iter(|| {
+            let mut versions = Vec::with_capacity(101);
+            versions.push(std_base.clone());
+            for (i, (k, v)) in updates[..100].iter().enumerate() {
+                let mut next = versions.last().unwrap().clone();
+                if i % 2 == 0 {
+                    next.insert(*k, *v);
+                } else {
+                    next.remove(&data_100k[i].0);
+                }
+                versions.push(next);
+            }
+            black_box(versions.len());
+        })
+    });
+
+    // 3. 查询旧版本与最新版本的开销相同
+    group.bench_function("persistent_get_oldest_version", |b| {
+        b.iter(|| {
+            for (k, _) in &data_100k[..10_000] {
+                black_box(versions[0].find(k));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+/// 多读者: 一个写者持续 insert/delete, N 个读者各做固定次数的 get, 统计读者全部完成的墙钟时间
+///
+/// CowBPTree 读者只 pin epoch 取快照; 对照组是 RwLock<BPTree>, 读者与写者互斥
+fn bench_cow_bptree_readers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("CowBPTree multi-reader");
+    group.sample_size(10).measurement_time(Duration::new(3, 0));
+
+    const READS: usize = 20_000;
+    let data = generate_random_pairs(100_000);
+    let degree = 16;
+
+    let cow = CowBPTree::new(degree);
+    let locked = RwLock::new(BPTree::new(degree));
+    for (k, v) in &data {
+        cow.insert(*k, *v);
+        locked.write().unwrap().insert(*k, *v);
+    }
+
+    for readers in [1usize, 2, 4, 8] {
+        group.throughput(criterion::Throughput::Elements((readers * READS) as u64));
+
+        group.bench_function(format!("cow_snapshot_get_{}r", readers), |b| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    let stop = AtomicBool::new(false);
+                    total += std::thread::scope(|s| {
+                        s.spawn(|| {
+                            let mut i = 0;
+                            while !stop.load(Ordering::Relaxed) {
+                                let (k, v) = data[i % data.len()];
+                                cow.delete(&k);
+                                cow.insert(k, v);
+                                i += 1;
+                            }
+                        });
+                        let start = Instant::now();
+                        let handles: Vec<_> = (0..readers)
+                            .map(|r| {
+                                let (cow, data) = (&cow, &data);
+                                s.spawn(move || {
+                                    for i in 0..READS {
+                                        let snap = cow.snapshot();
+                                        black_box(snap.get(&data[(i * 31 + r) % data.len()].0));
+                                    }
+                                })
+                            })
+                            .collect();
+                        handles.into_iter().for_each(|h| h.join().unwrap());
+                        let elapsed = start.elapsed();
+                        stop.store(true, Ordering::Relaxed);
+                        elapsed
+                    });
+                }
+                total
+            })
+        });
+
+        group.bench_function(format!("rwlock_bptree_get_{}r", readers), |b| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    let stop = AtomicBool::new(false);
+                    total += std::thread::scope(|s| {
+                        s.spawn(|| {
+                            let mut i = 0;
+                            while !stop.load(Ordering::Relaxed) {
+                                let (k, v) = data[i % data.len()];
+                                let mut bpt = locked.write().unwrap();
+                                bpt.delete(&k);
+                                bpt.insert(k, v);
+                                i += 1;
+                            }
+                        });
+                        let start = Instant::now();
+                        let handles: Vec<_> = (0..readers)
+                            .map(|r| {
+                                let (locked, data) = (&locked, &data);
+                                s.spawn(move || {
+                                    for i in 0..READS {
+                                        let bpt = locked.read().unwrap();
+                                        black_box(bpt.get(&data[(i * 31 + r) % data.len()].0));
+                                    }
+                                })
+                            })
+                            .collect();
+                        handles.into_iter().for_each(|h| h.join().unwrap());
+                        let elapsed = start.elapsed();
+                        stop.store(true, Ordering::Relaxed);
+                        elapsed
+                    });
+                }
+                total
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// 按线程切分 data 并发执行一种负载, 返回所有线程完成的墙钟时间
+///
+/// 负载与 runner 的场景一致: insert 从空表开始, query/delete 需预先填满, mixed 预填前一半后
+/// 按 50% 插入 / 30% 查询 / 20% 删除 执行, 每个线程只操作自己那一段 key
+fn run_threads<'a, M: Sync>(
+    map: &M,
+    data: &'a [(u32, u32)],
+    threads: usize,
+    workload: Workload,
+    insert: impl Fn(&M, u32, u32) + Sync,
+    get: impl Fn(&M, u32) + Sync,
+    delete: impl Fn(&M, u32) + Sync,
+) -> Duration {
+    // 每个线程的输入在计时前切好; 计时从所有线程就绪、同时越过 barrier 时开始
+    let half = data.len() / 2;
+    let chunk = |d: &'a [(u32, u32)], t: usize| d.chunks(d.len().div_ceil(threads)).nth(t).unwrap_or(&[]);
+    let inputs: Vec<_> = (0..threads)
+        .map(|t| {
+            let live: Vec<u32> = chunk(&data[..half], t).iter().map(|(k, _)| *k).collect();
+            (t, chunk(data, t), live, chunk(&data[half..], t))
+        })
+        .collect();
+    let barrier = Barrier::new(threads + 1);
+    let start = std::thread::scope(|s| {
+        for (t, own, mut live, pending) in inputs {
+            let (insert, get, delete, barrier) = (&insert, &get, &delete, &barrier);
+            s.spawn(move || {
+                let mut rng = StdRng::seed_from_u64(t as u64);
+                barrier.wait();
+                match workload {
+                    Workload::Insert => own.iter().for_each(|(k, v)| insert(map, *k, *v)),
+                    Workload::Query => own.iter().for_each(|(k, _)| get(map, *k)),
+                    Workload::Delete => own.iter().for_each(|(k, _)| delete(map, *k)),
+                    _ => {
+                        let mut pending = pending.iter();
+                        for _ in 0..own.len() {
+                            let roll = rng.gen_range(0..100);
+                            if roll < 50 {
+                                if let Some((k, v)) = pending.next() {
+                                    insert(map, *k, *v);
+                                    live.push(*k);
+                                    continue;
+                                }
+                            }
+                            if live.is_empty() {
+                                continue;
+                            }
+                            let idx = rng.gen_range(0..live.len());
+                            if roll < 80 {
+                                get(map, live[idx]);
+                            } else {
+                                delete(map, live.swap_remove(idx));
+                            }
+                        }
+                    }
+                }
+            });
+        }
+        barrier.wait();
+        Instant::now()
+    });
+    start.elapsed()
+}
+
+/// 吞吐随线程数的变化: 无锁 ConcurrentSkipList vs Mutex<SkipList>
+fn bench_concurrent_skiplist(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ConcurrentSkipList scaling");
+    group.sample_size(10).measurement_time(Duration::new(3, 0));
+
+    let data = generate_pairs(100_000, 42);
+    let prefill = |workload: Workload| match workload {
+        Workload::Insert => &data[..0],
+        Workload::Mixed => &data[..data.len() / 2],
+        _ => &data[..],
+    };
+
+    for workload in [Workload::Insert, Workload::Query, Workload::Delete, Workload::Mixed] {
+        for threads in [1usize, 2, 4, 8] {
+            group.throughput(criterion::Throughput::Elements(data.len() as u64));
+
+            group.bench_function(format!("lockfree_{}_{}t", workload.name(), threads), |b| {
+                b.iter_custom(|iters| {
+                    (0..iters)
+                        .map(|_| {
+                            let list = ConcurrentSkipList::new(42);
+                            for (k, v) in prefill(workload) {
+                                list.insert(*k, *v);
+                            }
+                            run_threads(
+                                &list,
+                                &data,
+                                threads,
+                                workload,
+                                |m, k, v| {
+                                    m.insert(k, v);
+                                },
+                                |m, k| {
+                                    black_box(m.get(&k));
+                                },
+                                |m, k| {
+                                    m.delete(&k);
+                                },
+                            )
+                        })
+                        .sum()
+                })
+            });
+
+            group.bench_function(format!("mutex_{}_{}t", workload.name(), threads), |b| {
+                b.iter_custom(|iters| {
+                    (0..iters)
+                        .map(|_| {
+                            let mut list = SkipList::new(42);
+                            for (k, v) in prefill(workload) {
+                                list.insert(*k, *v);
+                            }
+                            let list = Mutex::new(list);
+                            run_threads(
+                                &list,
+                                &data,
+                                threads,
+                                workload,
+                                |m, k, v| m.lock().unwrap().insert(k, v),
+                                |m, k| {
+                                    black_box(m.lock().unwrap().get(&k).copied());
+                                },
+                                |m, k| m.lock().unwrap().delete(&k),
+                            )
+                        })
+                        .sum()
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+/// 逐操作延迟: 每次 insert/get/delete/range_query 单独计时, 输出 p50/p90/p99/p99.9/max
+const LATENCY_SAMPLES: usize = 10;
+
+/// 只保留最近 capacity 次 iter_custom 调用的延迟.
+/// criterion 先反复调用闭包预热, 测量阶段恰好是最后 sample_size 次调用,
+/// 因此丢弃更早的记录即可把预热样本排除在直方图之外.
+struct MeasuredLatency {
+    capacity: usize,
+    calls: VecDeque<LatencyRecorder>,
+}
+
+impl MeasuredLatency {
+    fn new(capacity: usize) -> Self {
+        MeasuredLatency { capacity, calls: VecDeque::with_capacity(capacity) }
+    }
+
+    fn push(&mut self, recorder: LatencyRecorder) {
+        if self.calls.len() == self.capacity {
+            self.calls.pop_front();
+        }
+        self.calls.push_back(recorder);
+    }
+
+    fn drain_into(&mut self, report: &mut LatencyRecorder) {
+        for recorder in self.calls.drain(..) {
+            report.merge(&recorder);
+        }
+    }
+}
+
+fn bench_latency(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Latency");
+    group.sample_size(LATENCY_SAMPLES).measurement_time(Duration::new(3, 0));
+
+    let data_100k = generate_random_pairs(100_000);
+    let mut report = LatencyRecorder::new();
+    let mut samples = MeasuredLatency::new(LATENCY_SAMPLES);
+
+    group.bench_function("rb_tree_ops_latency", |b| {
+        b.iter_custom(|iters| {
+            let mut recorder = LatencyRecorder::new();
+            let start = Instant::now();
+            for _ in 0..iters {
+                let mut tree = RBTree::new();
+                for (k, v) in &data_100k {
+                    recorder.time("rbtree", "insert", || tree.insert(*k, *v));
+                }
+                for (k, _) in &data_100k {
+                    black_box(recorder.time("rbtree", "get", || tree.find(k).copied()));
+                }
+                for (k, _) in data_100k.iter().step_by(100) {
+                    black_box(recorder.time("rbtree", "range_query", || tree.range_query(k, &(k + 100)).len()));
+                }
+                for (k, _) in &data_100k {
+                    recorder.time("rbtree", "delete", || tree.delete(k));
+                }
+            }
+            let elapsed = start.elapsed();
+            samples.push(recorder);
+            elapsed
+        })
+    });
+    samples.drain_into(&mut report);
+
+    group.bench_function("btree_map_ops_latency", |b| {
+        b.iter_custom(|iters| {
+            let mut recorder = LatencyRecorder::new();
+            let start = Instant::now();
+            for _ in 0..iters {
+                let mut btree = BTreeMap::new(3);
+                for (k, v) in &data_100k {
+                    recorder.time("btree", "insert", || btree.insert(*k, *v));
+                }
+                for (k, _) in &data_100k {
+                    black_box(recorder.time("btree", "get", || btree.get(k).copied()));
+                }
+                for (k, _) in data_100k.iter().step_by(100) {
+                    black_box(recorder.time("btree", "range_query", || btree.range_query(k, &(k + 100)).len()));
+                }
+                for (k, _) in &data_100k {
+                    recorder.time("btree", "delete", || btree.delete(k));
+                }
+            }
+            let elapsed = start.elapsed();
+            samples.push(recorder);
+            elapsed
+        })
+    });
+    samples.drain_into(&mut report);
+
+    group.bench_function("skiplist_ops_latency", |b| {
+        b.iter_custom(|iters| {
+            let mut recorder = LatencyRecorder::new();
+            let start = Instant::now();
+            for _ in 0..iters {
+                let mut list = SkipList::new(42);
+                for (k, v) in &data_100k {
+                    recorder.time("skiplist", "insert", || list.insert(*k, *v));
+                }
+                for (k, _) in &data_100k {
+                    black_box(recorder.time("skiplist", "get", || list.get(k).copied()));
+                }
+                for (k, _) in data_100k.iter().step_by(100) {
+                    black_box(recorder.time("skiplist", "range_query", || list.range_query(k, &(k + 100)).len()));
+                }
+                for (k, _) in &data_100k {
+                    recorder.time("skiplist", "delete", || list.delete(k));
+                }
+            }
+            let elapsed = start.elapsed();
+            samples.push(recorder);
+            elapsed
+        })
+    });
+    samples.drain_into(&mut report);
+
+    group.bench_function("bptree_ops_latency", |b| {
+        b.iter_custom(|iters| {
+            let mut recorder = LatencyRecorder::new();
+            let start = Instant::now();
+            for _ in 0..iters {
+                let mut bpt = BPTree::new(3);
+                for (k, v) in &data_100k {
+                    recorder.time("bptree", "insert", || bpt.insert(*k, *v));
+                }
+                for (k, _) in &data_100k {
+                    black_box(recorder.time("bptree", "get", || bpt.get(k)));
+                }
+                for (k, _) in data_100k.iter().step_by(100) {
+                    black_box(recorder.time("bptree", "range_query", || bpt.range_query(k, &(k + 100)).len()));
+                }
+                for (k, _) in &data_100k {
+                    recorder.time("bptree", "delete", || bpt.delete(k));
+                }
+            }
+            let elapsed = start.elapsed();
+            samples.push(recorder);
+            elapsed
+        })
+    });
+    samples.drain_into(&mut report);
+
+    group.finish();
+
+    // 输出尾延迟报告
+    println!("\n{}", report.to_table());
+    let out_dir = std::path::Path::new("target").join("latency");
+    if std::fs::create_dir_all(&out_dir).is_ok() {
+        let _ = report.write_csv(out_dir.join("latency.csv").to_str().unwrap());
+        let _ = report.write_json(out_dir.join("latency.json").to_str().unwrap());
+        println!("Latency report written to {}", out_dir.display());
+    }
+}
+
+criterion_group!(benches, bench_b_plus_tree, bench_rb_tree, bench_avl_tree, bench_classic_rb_tree, bench_persistent_rb_tree, bench_btree_map, bench_skiplist, bench_price_ladder, bench_cow_bptree_readers, bench_concurrent_skiplist, bench_latency);
 // criterion_group!(benches, bench_rb_tree, bench_btree_map);
 criterion_main!(benches);
\ No newline at end of file
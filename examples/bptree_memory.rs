@@ -0,0 +1,26 @@
+//! 不同 min_degree 下 B+Tree 的内存占用, 用于按空间选择 degree
+//!
+//! ```text
+//! cargo run --release --example bptree_memory [N]
+//! ```
+
+use rust_orderbook_benchmark::bptree::bptree::BPTree;
+use rust_orderbook_benchmark::runner::runner::generate_pairs;
+
+fn main() {
+    let n = match std::env::args().nth(1) {
+        Some(arg) => arg.parse().unwrap_or_else(|_| {
+            eprintln!("error: invalid entry count `{}`", arg);
+            std::process::exit(2);
+        }),
+        None => 1_000_000,
+    };
+    let data = generate_pairs(n, 42);
+    for degree in [3, 4, 8, 16, 32, 64] {
+        let mut bpt = BPTree::new(degree);
+        for (k, v) in &data {
+            bpt.insert(*k, *v);
+        }
+        println!("bptree_degree_{:<3} {}", degree, bpt.memory_usage());
+    }
+}
@@ -0,0 +1,142 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// 计数开关, 关闭时分配路径只多一次 relaxed load
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// 分配次数 (alloc + realloc)
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static DEALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+/// 累计申请的字节数
+static BYTES_ALLOCATED: AtomicU64 = AtomicU64::new(0);
+/// 当前存活的堆字节数
+static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+/// 当前存活字节数的峰值
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// 计数分配器: 包装 `System`, 统计分配次数、字节数和峰值堆占用
+///
+/// 需要由二进制通过 `#[global_allocator]` 安装, 并调用 [`set_enabled`] 开启计数:
+///
+/// ```text
+/// #[global_allocator]
+/// static GLOBAL: CountingAllocator = CountingAllocator;
+/// ```
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() && ENABLED.load(Ordering::Relaxed) {
+            on_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() && ENABLED.load(Ordering::Relaxed) {
+            on_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        if ENABLED.load(Ordering::Relaxed) {
+            DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            sub_current(layout.size());
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() && ENABLED.load(Ordering::Relaxed) {
+            // realloc 视为一次新分配 + 一次释放
+            DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            sub_current(layout.size());
+            on_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+fn on_alloc(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    BYTES_ALLOCATED.fetch_add(size as u64, Ordering::Relaxed);
+    let current = CURRENT_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+}
+
+/// 计数开启之前分配的内存在释放时不能让 CURRENT_BYTES 下溢
+fn sub_current(size: usize) {
+    let _ = CURRENT_BYTES.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |c| {
+        Some(c.saturating_sub(size))
+    });
+}
+
+/// 开启 / 关闭计数
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// 计数是否真正生效: 已开启且 `CountingAllocator` 已安装为全局分配器
+pub fn is_active() -> bool {
+    if !is_enabled() {
+        return false;
+    }
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    std::hint::black_box(Box::new(0u64));
+    ALLOCATIONS.load(Ordering::Relaxed) != before
+}
+
+/// 把峰值重置为当前存活字节数, 用于单独测量某一阶段的峰值
+pub fn reset_peak() {
+    PEAK_BYTES.store(CURRENT_BYTES.load(Ordering::Relaxed), Ordering::Relaxed);
+}
+
+/// 清零累计的分配 / 释放次数和字节数, 峰值重置为当前存活字节数
+///
+/// 当前存活字节数保持不变, 否则之后释放已有内存时会被错误扣减
+pub fn reset() {
+    ALLOCATIONS.store(0, Ordering::Relaxed);
+    DEALLOCATIONS.store(0, Ordering::Relaxed);
+    BYTES_ALLOCATED.store(0, Ordering::Relaxed);
+    reset_peak();
+}
+
+/// 计数器快照
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocSnapshot {
+    pub allocations: u64,
+    pub deallocations: u64,
+    pub bytes_allocated: u64,
+    pub current_bytes: usize,
+    pub peak_bytes: usize,
+}
+
+impl AllocSnapshot {
+    pub fn take() -> Self {
+        AllocSnapshot {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+            bytes_allocated: BYTES_ALLOCATED.load(Ordering::Relaxed),
+            current_bytes: CURRENT_BYTES.load(Ordering::Relaxed),
+            peak_bytes: PEAK_BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 相对于更早快照的增量 (current / peak 取本快照的值)
+    pub fn since(&self, earlier: &AllocSnapshot) -> AllocSnapshot {
+        AllocSnapshot {
+            allocations: self.allocations - earlier.allocations,
+            deallocations: self.deallocations - earlier.deallocations,
+            bytes_allocated: self.bytes_allocated - earlier.bytes_allocated,
+            current_bytes: self.current_bytes,
+            peak_bytes: self.peak_bytes,
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use std::cmp::Ordering;
+use std::mem::size_of;
+
+use crate::memory::memory::MemoryReport;
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+#[derive(Debug)]
+pub struct Node<K, V> {
+    pub key: K,
+    pub value: V,
+    /// 以该节点为根的子树高度 (叶子为 1)
+    pub height: u8,
+    pub left: Link<K, V>,
+    pub right: Link<K, V>,
+}
+
+impl<K, V> Node<K, V> {
+    pub fn new(key: K, value: V) -> Self {
+        Node {
+            key,
+            value,
+            height: 1,
+            left: None,
+            right: None,
+        }
+    }
+}
+
+/// AVL 树: 任意节点左右子树高度差不超过 1
+///
+/// 比 LLRB 更矮 (高度 <= 1.44 * log2(n)), 查询路径更短; 接口与 `RBTree` 一致
+pub struct AVLTree<K, V> {
+    pub root: Link<K, V>,
+    len: usize,
+    /// 累计旋转次数 (双旋记 2 次)
+    rotations: u64,
+}
+
+impl<K: Ord, V> Default for AVLTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> AVLTree<K, V> {
+    pub fn new() -> Self {
+        AVLTree {
+            root: None,
+            len: 0,
+            rotations: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 树高 (空树为 0)
+    pub fn height(&self) -> usize {
+        Self::h(&self.root) as usize
+    }
+
+    /// 累计旋转次数
+    pub fn rotations(&self) -> u64 {
+        self.rotations
+    }
+
+    /// 查找
+    pub fn find(&self, key: &K) -> Option<&V> {
+        let mut curr = &self.root;
+        while let Some(node) = curr {
+            match key.cmp(&node.key) {
+                Ordering::Less => curr = &node.left,
+                Ordering::Greater => curr = &node.right,
+                Ordering::Equal => return Some(&node.value),
+            }
+        }
+        None
+    }
+
+    /// 最小键 (卖盘最优价)
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref left) = node.left {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// 最大键 (买盘最优价)
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref()?;
+        while let Some(ref right) = node.right {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    /// 插入, key 已存在时更新 value
+    pub fn insert(&mut self, key: K, value: V) {
+        let root = self.root.take();
+        self.root = Some(self.insert_node(root, key, value));
+    }
+
+    fn insert_node(&mut self, node: Link<K, V>, key: K, value: V) -> Box<Node<K, V>> {
+        let mut n = match node {
+            None => {
+                self.len += 1;
+                return Box::new(Node::new(key, value));
+            }
+            Some(n) => n,
+        };
+        match key.cmp(&n.key) {
+            Ordering::Less => n.left = Some(self.insert_node(n.left.take(), key, value)),
+            Ordering::Greater => n.right = Some(self.insert_node(n.right.take(), key, value)),
+            Ordering::Equal => {
+                // key 相等，更新 value, 结构不变
+                n.value = value;
+                return n;
+            }
+        }
+        self.rebalance(n)
+    }
+
+    /// 删除
+    pub fn delete(&mut self, key: &K) {
+        let root = self.root.take();
+        self.root = self.delete_node(root, key);
+    }
+
+    fn delete_node(&mut self, node: Link<K, V>, key: &K) -> Link<K, V> {
+        let mut n = node?;
+        match key.cmp(&n.key) {
+            Ordering::Less => n.left = self.delete_node(n.left.take(), key),
+            Ordering::Greater => n.right = self.delete_node(n.right.take(), key),
+            Ordering::Equal => {
+                self.len -= 1;
+                match (n.left.take(), n.right.take()) {
+                    (None, None) => return None,
+                    (Some(child), None) | (None, Some(child)) => return Some(child),
+                    (Some(left), Some(right)) => {
+                        // 摘下右子树的最小节点作为新的根, 无需克隆键值
+                        let (right, mut min) = self.detach_min(right);
+                        min.left = Some(left);
+                        min.right = right;
+                        return Some(self.rebalance(min));
+                    }
+                }
+            }
+        }
+        Some(self.rebalance(n))
+    }
+
+    /// 摘下子树中的最小节点, 返回 (剩余子树, 最小节点)
+    fn detach_min(&mut self, mut n: Box<Node<K, V>>) -> (Link<K, V>, Box<Node<K, V>>) {
+        match n.left.take() {
+            None => (n.right.take(), n),
+            Some(left) => {
+                let (rest, min) = self.detach_min(left);
+                n.left = rest;
+                (Some(self.rebalance(n)), min)
+            }
+        }
+    }
+
+    fn h(node: &Link<K, V>) -> u8 {
+        node.as_ref().map_or(0, |n| n.height)
+    }
+
+    fn update(n: &mut Node<K, V>) {
+        n.height = 1 + Self::h(&n.left).max(Self::h(&n.right));
+    }
+
+    /// 平衡因子 = 左高 - 右高
+    fn balance(n: &Node<K, V>) -> i16 {
+        Self::h(&n.left) as i16 - Self::h(&n.right) as i16
+    }
+
+    fn rebalance(&mut self, mut n: Box<Node<K, V>>) -> Box<Node<K, V>> {
+        Self::update(&mut n);
+        let bf = Self::balance(&n);
+        if bf > 1 {
+            // 左重: LR 情况先对左子左旋
+            if Self::balance(n.left.as_ref().unwrap()) < 0 {
+                let left = n.left.take().unwrap();
+                n.left = Some(self.rotate_left(left));
+            }
+            return self.rotate_right(n);
+        }
+        if bf < -1 {
+            // 右重: RL 情况先对右子右旋
+            if Self::balance(n.right.as_ref().unwrap()) > 0 {
+                let right = n.right.take().unwrap();
+                n.right = Some(self.rotate_right(right));
+            }
+            return self.rotate_left(n);
+        }
+        n
+    }
+
+    fn rotate_left(&mut self, mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+        self.rotations += 1;
+        let mut x = h.right.take().unwrap();
+        h.right = x.left.take();
+        Self::update(&mut h);
+        x.left = Some(h);
+        Self::update(&mut x);
+        x
+    }
+
+    fn rotate_right(&mut self, mut h: Box<Node<K, V>>) -> Box<Node<K, V>> {
+        self.rotations += 1;
+        let mut x = h.left.take().unwrap();
+        h.left = x.right.take();
+        Self::update(&mut h);
+        x.right = Some(h);
+        Self::update(&mut x);
+        x
+    }
+
+    /// 区间查询 [start..end], 按 key 升序
+    pub fn range_query(&self, start: &K, end: &K) -> Vec<&V> {
+        let mut result = Vec::new();
+        Self::range_query_node(&self.root, start, end, &mut result);
+        result
+    }
+
+    fn range_query_node<'a>(node: &'a Link<K, V>, start: &K, end: &K, result: &mut Vec<&'a V>) {
+        if let Some(n) = node {
+            if start < &n.key {
+                Self::range_query_node(&n.left, start, end, result);
+            }
+            if start <= &n.key && end >= &n.key {
+                result.push(&n.value);
+            }
+            if end > &n.key {
+                Self::range_query_node(&n.right, start, end, result);
+            }
+        }
+    }
+
+    /// 遍历统计内存占用, 每个节点是一次独立的 Box 分配
+    pub fn memory_usage(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+        let mut stack = Vec::new();
+        if let Some(ref root) = self.root {
+            stack.push((root, 1));
+        }
+        while let Some((node, depth)) = stack.pop() {
+            report.node_count += 1;
+            report.entry_count += 1;
+            report.key_bytes += size_of::<K>();
+            report.value_bytes += size_of::<V>();
+            // 左右子指针 + 高度 + 对齐填充
+            report.pointer_overhead_bytes += size_of::<Node<K, V>>() - size_of::<K>() - size_of::<V>();
+            report.height = report.height.max(depth);
+            for child in [&node.left, &node.right].into_iter().flatten() {
+                stack.push((child, depth + 1));
+            }
+        }
+        report.fill_factor = if report.node_count > 0 { 1.0 } else { 0.0 };
+        report.finish()
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod avl_tree;
+#[allow(clippy::module_inception)]
+pub mod tests;
@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod tests {
+    use crate::avl_tree::avl_tree::AVLTree;
+
+    #[test]
+    fn test_avl_tree_insert_find_delete() {
+        let mut tree = AVLTree::new();
+        tree.insert(10, "ten");
+        tree.insert(5, "five");
+        tree.insert(15, "fifteen");
+        tree.insert(10, "TEN");
+
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.find(&10), Some(&"TEN"));
+        assert_eq!(tree.find(&999), None);
+        assert_eq!(tree.first(), Some((&5, &"five")));
+        assert_eq!(tree.last(), Some((&15, &"fifteen")));
+        assert_eq!(tree.range_query(&6, &15), vec![&"TEN", &"fifteen"]);
+
+        tree.delete(&10);
+        tree.delete(&999);
+        assert_eq!(tree.find(&10), None);
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.range_query(&0, &100), vec![&"five", &"fifteen"]);
+    }
+
+    #[test]
+    fn test_avl_tree_stays_balanced() {
+        let mut tree = AVLTree::new();
+        let mut reference = std::collections::BTreeMap::new();
+        // 顺序插入是 BST 的最坏情况
+        for k in 0..10_000u32 {
+            tree.insert(k, k);
+            reference.insert(k, k);
+        }
+        // 1.44 * log2(10001) ≈ 19.1
+        assert!(tree.height() <= 19);
+        assert!(tree.rotations() > 0);
+        for i in 0..20_000u32 {
+            let k = (i * 7919) % 12_000;
+            if i % 2 == 0 {
+                tree.delete(&k);
+                reference.remove(&k);
+            } else {
+                tree.insert(k, i);
+                reference.insert(k, i);
+            }
+        }
+
+        assert_eq!(tree.len(), reference.len());
+        let got: Vec<u32> = tree.range_query(&0, &u32::MAX).into_iter().copied().collect();
+        let want: Vec<u32> = reference.values().copied().collect();
+        assert_eq!(got, want);
+        assert_eq!(tree.memory_usage().height, tree.height());
+        assert!((tree.height() as f64) <= 1.45 * ((tree.len() + 2) as f64).log2());
+    }
+}
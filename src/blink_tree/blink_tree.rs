@@ -0,0 +1,332 @@
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+
+type Latch<K, V> = RwLock<Node<K, V>>;
+
+/// 节点指针; 节点只在整棵树 drop 时释放, 树存活期间指针始终有效
+pub struct NodePtr<K, V>(NonNull<Latch<K, V>>);
+
+impl<K, V> Clone for NodePtr<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for NodePtr<K, V> {}
+
+impl<K, V> PartialEq for NodePtr<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+// 指向的节点自带读写闩, 跨线程共享与 RwLock<Node> 本身一样安全
+unsafe impl<K: Send + Sync, V: Send + Sync> Send for NodePtr<K, V> {}
+unsafe impl<K: Send + Sync, V: Send + Sync> Sync for NodePtr<K, V> {}
+
+/// B-link 树节点
+///
+/// 除普通 B+树字段外, 每个节点带右链 `right` 和上界 `high_key` (不含);
+/// key >= high_key 说明该节点已被分裂, key 落在右兄弟那边
+pub struct Node<K, V> {
+    /// 所在层, 叶子为 0
+    pub level: usize,
+    pub keys: Vec<K>,
+    /// 仅在叶子下使用
+    pub vals: Vec<V>,
+    /// 仅在内部节点下使用, children.len() = keys.len() + 1
+    pub children: Vec<NodePtr<K, V>>,
+    /// 同层右兄弟
+    pub right: Option<NodePtr<K, V>>,
+    /// 本节点 key 的上界, None 表示 +∞ (同层最右节点)
+    pub high_key: Option<K>,
+}
+
+impl<K: Ord, V> Node<K, V> {
+    /// key 是否仍归本节点管; 否则应沿右链前进
+    fn covers(&self, key: &K) -> bool {
+        self.high_key.as_ref().is_none_or(|h| key < h)
+    }
+
+    /// 内部节点中 key 所在子树的下标 (keys[i] 是 children[i + 1] 的最小键)
+    fn child_index(&self, key: &K) -> usize {
+        self.keys.partition_point(|k| k <= key)
+    }
+}
+
+/// Lehman–Yao B-link 树: 支持多线程并发插入与查询的 B+树变体
+///
+/// - 每个节点一把读写闩, 下降时闩耦合: 先拿到子节点 (或右兄弟) 的闩再放开当前节点
+/// - 分裂时先把右半边挂到右链上, 放开子节点后再去父节点插入分隔键;
+///   这段时间里到达左半边的查找通过 high_key 发现 key 越界, 沿右链走到新节点
+/// - 所有闩都按 "自上而下, 从左到右" 的顺序获取, 不会死锁
+///
+/// 删除只从叶子摘除条目, 不做合并 (节点允许下溢), 这也是 B-link 树的常见做法
+pub struct BLinkTree<K, V> {
+    root: RwLock<NodePtr<K, V>>,
+    /// 所有节点的所有权; 节点从不单独释放, 地址在树存活期间稳定
+    arena: Mutex<Vec<Box<Latch<K, V>>>>,
+    min_degree: usize,
+    len: AtomicUsize,
+}
+
+impl<K, V> BLinkTree<K, V>
+where
+    K: Ord + Clone + Send + Sync,
+    V: Clone + Send + Sync,
+{
+    pub fn new(min_degree: usize) -> Self {
+        assert!(min_degree >= 2, "B+Tree min_degree must >= 2");
+        let leaf = Box::new(RwLock::new(Node {
+            level: 0,
+            keys: Vec::new(),
+            vals: Vec::new(),
+            children: Vec::new(),
+            right: None,
+            high_key: None,
+        }));
+        let root = NodePtr(NonNull::from(&*leaf));
+        BLinkTree {
+            root: RwLock::new(root),
+            arena: Mutex::new(vec![leaf]),
+            min_degree,
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 树高 (只有一个叶子时为 1)
+    pub fn height(&self) -> usize {
+        let root = *self.root.read().unwrap();
+        self.node(root).read().unwrap().level + 1
+    }
+
+    fn node(&self, ptr: NodePtr<K, V>) -> &Latch<K, V> {
+        // 节点归 arena 所有, 与 &self 同寿命
+        unsafe { ptr.0.as_ref() }
+    }
+
+    fn alloc(&self, node: Node<K, V>) -> NodePtr<K, V> {
+        let boxed = Box::new(RwLock::new(node));
+        let ptr = NodePtr(NonNull::from(&*boxed));
+        self.arena.lock().unwrap().push(boxed);
+        ptr
+    }
+
+    /// 从根向下读闩耦合, 返回 level 层上覆盖 key 的节点; stack 记录沿途经过的更高层节点
+    fn descend(&self, key: &K, level: usize, stack: &mut Vec<NodePtr<K, V>>) -> NodePtr<K, V> {
+        let mut ptr = *self.root.read().unwrap();
+        let mut guard = self.node(ptr).read().unwrap();
+        loop {
+            while !guard.covers(key) {
+                ptr = guard.right.unwrap();
+                guard = self.node(ptr).read().unwrap();
+            }
+            if guard.level == level {
+                return ptr;
+            }
+            stack.push(ptr);
+            ptr = guard.children[guard.child_index(key)];
+            guard = self.node(ptr).read().unwrap();
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let root = *self.root.read().unwrap();
+        let mut guard = self.node(root).read().unwrap();
+        loop {
+            while !guard.covers(key) {
+                guard = self.node(guard.right.unwrap()).read().unwrap();
+            }
+            if guard.level == 0 {
+                return guard.keys.binary_search(key).ok().map(|i| guard.vals[i].clone());
+            }
+            guard = self.node(guard.children[guard.child_index(key)]).read().unwrap();
+        }
+    }
+
+    /// [start, end] 闭区间; 沿叶子右链扫描, 每次只持有一个叶子的读闩
+    pub fn range_query(&self, start: &K, end: &K) -> Vec<V> {
+        let mut out = Vec::new();
+        let leaf = self.descend(start, 0, &mut Vec::new());
+        let mut guard = self.node(leaf).read().unwrap();
+        loop {
+            let from = guard.keys.partition_point(|k| k < start);
+            for (k, v) in guard.keys[from..].iter().zip(&guard.vals[from..]) {
+                if k > end {
+                    return out;
+                }
+                out.push(v.clone());
+            }
+            match (&guard.high_key, guard.right) {
+                (Some(h), Some(right)) if h <= end => guard = self.node(right).read().unwrap(),
+                _ => return out,
+            }
+        }
+    }
+
+    /// 插入或覆盖; 返回是否新增了条目
+    pub fn insert(&self, key: K, value: V) -> bool {
+        let mut stack = Vec::new();
+        let mut ptr = self.descend(&key, 0, &mut stack);
+        let mut leaf = self.node(ptr).write().unwrap();
+        // 拿到写闩前叶子可能已被分裂
+        while !leaf.covers(&key) {
+            ptr = leaf.right.unwrap();
+            leaf = self.node(ptr).write().unwrap();
+        }
+        match leaf.keys.binary_search(&key) {
+            Ok(i) => {
+                leaf.vals[i] = value;
+                return false;
+            }
+            Err(i) => {
+                leaf.keys.insert(i, key);
+                leaf.vals.insert(i, value);
+            }
+        }
+        self.len.fetch_add(1, Ordering::Relaxed);
+        if leaf.keys.len() < 2 * self.min_degree {
+            return true;
+        }
+        let (sep, right) = self.split(&mut leaf);
+        drop(leaf);
+        self.insert_parent(stack, 1, sep, right);
+        true
+    }
+
+    /// 删除 (不合并节点)
+    pub fn delete(&self, key: &K) -> Option<V> {
+        let mut ptr = self.descend(key, 0, &mut Vec::new());
+        let mut leaf = self.node(ptr).write().unwrap();
+        while !leaf.covers(key) {
+            ptr = leaf.right.unwrap();
+            leaf = self.node(ptr).write().unwrap();
+        }
+        let i = leaf.keys.binary_search(key).ok()?;
+        leaf.keys.remove(i);
+        self.len.fetch_sub(1, Ordering::Relaxed);
+        Some(leaf.vals.remove(i))
+    }
+
+    /// 把持有写闩的节点一分为二, 右半边挂到右链上; 返回 (分隔键, 右节点)
+    fn split(&self, node: &mut Node<K, V>) -> (K, NodePtr<K, V>) {
+        let mid = node.keys.len() / 2;
+        let (keys, vals, children, sep) = if node.level == 0 {
+            let keys = node.keys.split_off(mid);
+            let sep = keys[0].clone();
+            (keys, node.vals.split_off(mid), Vec::new(), sep)
+        } else {
+            // 内部节点的中间键上提, 不留在任何一侧
+            let keys = node.keys.split_off(mid + 1);
+            let children = node.children.split_off(mid + 1);
+            (keys, Vec::new(), children, node.keys.pop().unwrap())
+        };
+        let right = self.alloc(Node {
+            level: node.level,
+            keys,
+            vals,
+            children,
+            right: node.right,
+            high_key: node.high_key.take(),
+        });
+        node.right = Some(right);
+        node.high_key = Some(sep.clone());
+        (sep, right)
+    }
+
+    /// 把 (sep, child) 插入 level 层; 父节点满了继续向上分裂
+    fn insert_parent(&self, mut stack: Vec<NodePtr<K, V>>, mut level: usize, mut sep: K, mut child: NodePtr<K, V>) {
+        loop {
+            let mut ptr = match stack.pop() {
+                Some(ptr) => ptr,
+                None => {
+                    let mut root = self.root.write().unwrap();
+                    if self.node(*root).read().unwrap().level == level - 1 {
+                        // 分裂的是根所在层: 根总是该层最左节点, 新根以它为第一个孩子
+                        *root = self.alloc(Node {
+                            level,
+                            keys: vec![sep],
+                            vals: Vec::new(),
+                            children: vec![*root, child],
+                            right: None,
+                            high_key: None,
+                        });
+                        return;
+                    }
+                    // 下降时根还在更低层, 别的线程已经长高了树, 重新定位这一层
+                    drop(root);
+                    self.descend(&sep, level, &mut Vec::new())
+                }
+            };
+            let mut parent = self.node(ptr).write().unwrap();
+            while !parent.covers(&sep) {
+                ptr = parent.right.unwrap();
+                parent = self.node(ptr).write().unwrap();
+            }
+            let idx = parent.keys.partition_point(|k| k < &sep);
+            parent.keys.insert(idx, sep);
+            parent.children.insert(idx + 1, child);
+            if parent.keys.len() < 2 * self.min_degree {
+                return;
+            }
+            (sep, child) = self.split(&mut parent);
+            drop(parent);
+            level += 1;
+        }
+    }
+
+    /// 检查每一层沿右链有序、high_key 与兄弟衔接; 返回叶子层的条目数
+    ///
+    /// 需要在没有并发写入时调用
+    pub fn validate(&self) -> Result<usize, String> {
+        let mut leftmost = *self.root.read().unwrap();
+        loop {
+            let (level, next_level) = {
+                let node = self.node(leftmost).read().unwrap();
+                (node.level, node.children.first().copied())
+            };
+            let mut count = 0;
+            let mut prev_high: Option<K> = None;
+            let mut curr = Some(leftmost);
+            while let Some(ptr) = curr {
+                let node = self.node(ptr).read().unwrap();
+                if node.level != level {
+                    return Err(format!("level {} links to a node at level {}", level, node.level));
+                }
+                if !node.keys.windows(2).all(|w| w[0] < w[1]) {
+                    return Err(format!("keys out of order at level {}", level));
+                }
+                if let (Some(low), Some(first)) = (&prev_high, node.keys.first()) {
+                    if first < low {
+                        return Err(format!("key below the left sibling's high key at level {}", level));
+                    }
+                }
+                if node.keys.last().is_some_and(|k| !node.covers(k)) {
+                    return Err(format!("key above high key at level {}", level));
+                }
+                if node.level > 0 && node.children.len() != node.keys.len() + 1 {
+                    return Err(format!("bad child count at level {}", level));
+                }
+                if node.right.is_none() != node.high_key.is_none() {
+                    return Err(format!("right link and high key disagree at level {}", level));
+                }
+                count += node.keys.len();
+                prev_high = node.high_key.clone();
+                curr = node.right;
+            }
+            match next_level {
+                Some(child) => leftmost = child,
+                None => return Ok(count),
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod blink_tree;
+#[allow(clippy::module_inception)]
+pub mod tests;
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use crate::blink_tree::blink_tree::BLinkTree;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_blink_tree_matches_reference() {
+        let tree = BLinkTree::new(2);
+        let mut reference = BTreeMap::new();
+        for i in 0..20_000u32 {
+            let k = (i * 7919) % 5_000;
+            if i % 4 == 0 {
+                assert_eq!(tree.delete(&k), reference.remove(&k));
+            } else {
+                assert_eq!(tree.insert(k, i), reference.insert(k, i).is_none());
+            }
+        }
+
+        assert_eq!(tree.validate(), Ok(reference.len()));
+        assert_eq!(tree.len(), reference.len());
+        assert!(tree.height() > 2);
+        let want: Vec<u32> = reference.range(100..=4_000).map(|(_, v)| *v).collect();
+        assert_eq!(tree.range_query(&100, &4_000), want);
+        for k in 0..5_000 {
+            assert_eq!(tree.get(&k), reference.get(&k).copied());
+        }
+    }
+
+    #[test]
+    fn test_blink_tree_concurrent_stress() {
+        const THREADS: u32 = 8;
+        const OPS: u32 = 20_000;
+        // 线程 t 的第 i 个操作; 值只由 key 决定且随 key 递增, 读者可以校验读到的值和顺序
+        let op = |t: u32, i: u32| ((i.wrapping_mul(2_654_435_761) ^ t.wrapping_mul(40_503)) % 50_000, i % 5 == 4);
+        let value = |k: u32| k * 2 + 1;
+
+        for degree in [2, 3, 16] {
+            let tree = BLinkTree::new(degree);
+            std::thread::scope(|s| {
+                for t in 0..THREADS {
+                    let tree = &tree;
+                    s.spawn(move || {
+                        for i in 0..OPS {
+                            // 每个线程只删除自己插入过的 key 段, 保证最终结果与执行顺序无关
+                            let (k, del) = op(t, i);
+                            let k = k * THREADS + t;
+                            if del {
+                                tree.delete(&k);
+                            } else {
+                                tree.insert(k, value(k));
+                            }
+                        }
+                    });
+                }
+                for _ in 0..2 {
+                    let tree = &tree;
+                    s.spawn(move || {
+                        for k in (0..50_000 * THREADS).step_by(7) {
+                            if let Some(v) = tree.get(&k) {
+                                assert_eq!(v, value(k));
+                            }
+                        }
+                        // 扫描期间叶子在分裂, 右链保证结果不重复也不乱序
+                        let vals = tree.range_query(&0, &u32::MAX);
+                        assert!(vals.windows(2).all(|w| w[0] < w[1]));
+                    });
+                }
+            });
+
+            // 顺序重放每个线程的操作得到参考结果
+            let mut reference = BTreeMap::new();
+            for t in 0..THREADS {
+                for i in 0..OPS {
+                    let (k, del) = op(t, i);
+                    let k = k * THREADS + t;
+                    if del {
+                        reference.remove(&k);
+                    } else {
+                        reference.insert(k, value(k));
+                    }
+                }
+            }
+            assert_eq!(tree.validate(), Ok(reference.len()));
+            assert_eq!(tree.len(), reference.len());
+            let want: Vec<u32> = reference.values().copied().collect();
+            assert_eq!(tree.range_query(&0, &u32::MAX), want);
+        }
+    }
+}
@@ -1,6 +1,11 @@
 #![allow(non_snake_case)]
 
 use std::fmt::Debug;
+use std::mem::size_of;
+use std::ptr::NonNull;
+
+use crate::handle::handle::{Handle, HandleTable};
+use crate::memory::memory::MemoryReport;
 
 /// 节点类型 (内部节点 or 叶子节点)
 #[derive(Clone, Debug, PartialEq)]
@@ -18,6 +23,8 @@ pub struct Node<K: Ord + Clone + Debug, V: Clone + Debug> {
     /// 对 Internal 节点：只存 key (vals 对内部节点无实际用处)
     pub keys: Vec<K>,
     pub vals: Vec<V>,  // 仅在 Leaf 下使用
+    /// 与 vals 一一对应的句柄槽位, 仅在 Leaf 下使用
+    pub slots: Vec<usize>,
     /// 对 Internal 节点： children.len() = keys.len() + 1
     /// 对 Leaf 节点： children 为空
     pub children: Vec<Node<K, V>>,
@@ -30,6 +37,8 @@ pub struct Node<K: Ord + Clone + Debug, V: Clone + Debug> {
 pub struct BPTree<K: Ord + Clone + Debug, V: Clone + Debug> {
     pub root: Box<Node<K, V>>,
     pub min_degree: usize,
+    /// 条目句柄; 按句柄删除只打墓碑, 条目在 purge 时摘除
+    slots: HandleTable,
 }
 
 impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
@@ -40,34 +49,88 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
             node_type: NodeType::Leaf,
             keys: Vec::with_capacity(2 * min_degree - 1),  // 预分配容量
             vals: Vec::with_capacity(2 * min_degree - 1),
+            slots: Vec::new(),
             children: Vec::new(),
             next_leaf: None,  // 叶子节点链表
         };
         BPTree {
             root: Box::new(leaf_node),
             min_degree,
+            slots: HandleTable::new(),
         }
     }
 
-    /// 粗略估算内存使用
-    pub fn approximate_memory_usage(&self) -> usize {
-        let mut total = 0;
-        let mut stack = vec![&*self.root];
-        
-        while let Some(node) = stack.pop() {
-            total += std::mem::size_of::<Node<K, V>>();
-            total += node.keys.capacity() * std::mem::size_of::<K>();
-            total += node.vals.capacity() * std::mem::size_of::<V>();
-            
+    /// 遍历统计内存占用
+    ///
+    /// 包括 `children` 的空闲容量, 以及叶子 `next_leaf` 链表中克隆出的节点 (计入 duplicated_bytes);
+    /// 墓碑条目的键值计为浪费, 槽位和句柄表计入指针开销
+    pub fn memory_usage(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+        let mut used_slots = 0;
+        let mut stack = vec![(&*self.root, 1)];
+
+        while let Some((node, depth)) = stack.pop() {
+            let dead = node.slots.iter().filter(|s| !self.slots.is_live(**s)).count();
+            report.node_count += 1;
+            report.key_bytes += (node.keys.len() - dead) * size_of::<K>();
+            report.value_bytes += (node.vals.len() - dead) * size_of::<V>();
+            report.wasted_capacity_bytes += Self::spare_capacity_bytes(node) + dead * (size_of::<K>() + size_of::<V>());
+            report.pointer_overhead_bytes += node.slots.len() * size_of::<usize>();
+            // 节点头: 3 个 Vec 头 + 节点类型 + next_leaf 指针
+            report.pointer_overhead_bytes += size_of::<Node<K, V>>();
+            report.height = report.height.max(depth);
+            used_slots += node.keys.len();
+
+            if node.node_type == NodeType::Leaf {
+                report.entry_count += node.keys.len() - dead;
+                let mut next = &node.next_leaf;
+                while let Some(copy) = next {
+                    report.duplicated_bytes += size_of::<Node<K, V>>()
+                        + copy.keys.capacity() * size_of::<K>()
+                        + copy.vals.capacity() * size_of::<V>()
+                        + copy.slots.capacity() * size_of::<usize>()
+                        + copy.children.capacity() * size_of::<Node<K, V>>();
+                    next = &copy.next_leaf;
+                }
+            }
+
             for child in &node.children {
-                stack.push(child);
+                stack.push((child, depth + 1));
             }
         }
-        total
+        report.pointer_overhead_bytes += self.slots.heap_bytes();
+        report.fill_factor = used_slots as f64 / (report.node_count * (2 * self.min_degree - 1)) as f64;
+        report.finish()
+    }
+
+    fn spare_capacity_bytes(node: &Node<K, V>) -> usize {
+        (node.keys.capacity() - node.keys.len()) * size_of::<K>()
+            + (node.vals.capacity() - node.vals.len()) * size_of::<V>()
+            + (node.slots.capacity() - node.slots.len()) * size_of::<usize>()
+            + (node.children.capacity() - node.children.len()) * size_of::<Node<K, V>>()
+    }
+
+    /// 存活条目数
+    pub fn len(&self) -> usize {
+        self.slots.live()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.live() == 0
+    }
+
+    /// 已按句柄删除、尚未摘除的条目数
+    pub fn tombstones(&self) -> usize {
+        self.slots.tombstones()
     }
 
-    /// 插入 (key, value)
-    pub fn insert(&mut self, key: K, value: V) {
+    /// 句柄是否仍指向存活条目
+    pub fn is_valid(&self, handle: Handle) -> bool {
+        self.slots.resolve(handle).is_some()
+    }
+
+    /// 插入 (key, value), 返回该条目的句柄
+    pub fn insert(&mut self, key: K, value: V) -> Handle {
         let root = &mut self.root;
         if Self::is_full(self.min_degree, root) {
             // 创建新的根节点
@@ -75,19 +138,96 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
                 node_type: NodeType::Internal,
                 keys: Vec::with_capacity(2 * self.min_degree),
                 vals: Vec::new(),
+                slots: Vec::new(),
                 children: Vec::with_capacity(2 * self.min_degree + 1),
                 next_leaf: None,
             });
             // 交换新旧根节点
             std::mem::swap(root, &mut new_root);
             // 将旧根节点作为新根节点的第一个子节点
-            (*root).children.push(*new_root);
+            root.children.push(*new_root);
             // 分裂子节点
             Self::split_child(self.min_degree, root, 0);
         }
         
         // 直接在根节点上操作，避免克隆
-        Self::insert_non_full(self.min_degree, root, key, value);
+        Self::insert_non_full(self.min_degree, root, key, value, &mut self.slots)
+    }
+
+    /// 按句柄删除, 不从根按 key 查找: 只打墓碑, 再沿最左和最右路径摘掉落在两端的墓碑
+    ///
+    /// 墓碑比例超过阈值时由这里触发 `purge` 整体重建; 句柄失效时返回 false
+    pub fn remove_at(&mut self, handle: Handle) -> bool {
+        if !self.slots.tombstone(handle) {
+            return false;
+        }
+        if !self.purge_if_needed() {
+            self.trim_ends();
+        }
+        true
+    }
+
+    /// 从两端叶子摘除连续的墓碑, 使最小和最大的物理条目总是存活条目, `first`/`last` 不必跳过墓碑
+    ///
+    /// 只沿最左 (右) 路径下降, 不比较 key; 叶子下溢时像游标删除一样借位或合并
+    fn trim_ends(&mut self) {
+        if self.slots.tombstones() == 0 {
+            return;
+        }
+        let mut cursor = CursorMut::new(self);
+        for right in [false, true] {
+            loop {
+                cursor.pos.clear();
+                if right {
+                    cursor.pos.step_prev();
+                } else {
+                    cursor.pos.step_next();
+                }
+                if cursor.is_live(&cursor.pos) {
+                    break;
+                }
+                cursor.unlink_current();
+            }
+        }
+    }
+
+    /// 墓碑过多时重建, 返回是否重建
+    fn purge_if_needed(&mut self) -> bool {
+        let needed = self.slots.needs_purge();
+        if needed {
+            self.purge();
+        }
+        needed
+    }
+
+    /// 摘除所有墓碑条目: 按序取出存活条目, 用 `from_sorted` 的方式自底向上重建
+    ///
+    /// O(n), 不逐个插入; 存活条目的槽位 (句柄) 保持不变
+    pub fn purge(&mut self) {
+        if self.slots.tombstones() == 0 {
+            return;
+        }
+        let mut entries = Vec::with_capacity(self.slots.live());
+        let old_root = std::mem::replace(&mut self.root, Self::new(self.min_degree).root);
+        Self::drain_entries(*old_root, &mut self.slots, &mut entries);
+        *self.root = Self::build_sorted(self.min_degree, entries);
+    }
+
+    /// 按 key 顺序取出存活的叶子条目 (不经过 next_leaf 副本), 墓碑条目的槽位直接回收
+    fn drain_entries(node: Node<K, V>, slots: &mut HandleTable, out: &mut Vec<(K, V, usize)>) {
+        if node.node_type == NodeType::Leaf {
+            for ((k, v), slot) in node.keys.into_iter().zip(node.vals).zip(node.slots) {
+                if slots.is_live(slot) {
+                    out.push((k, v, slot));
+                } else {
+                    slots.release(slot);
+                }
+            }
+        } else {
+            for child in node.children {
+                Self::drain_entries(child, slots, out);
+            }
+        }
     }
 
     /// 查询
@@ -111,55 +251,58 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
         }
         
         match current.keys.binary_search(key) {
-            Ok(i) => Some(current.vals[i].clone()),
-            Err(_) => None,
+            Ok(i) if self.slots.is_live(current.slots[i]) => Some(current.vals[i].clone()),
+            _ => None,
         }
     }
 
     /// 删除
     pub fn delete(&mut self, key: &K) {
-        Self::delete_recur(&mut self.root, key, self.min_degree);
+        let mut removed = Vec::new();
+        Self::delete_recur(&mut self.root, key, self.min_degree, &mut removed);
+        for slot in removed {
+            self.slots.release(slot);
+        }
         
         // 如果根节点是内部节点且为空，提升其唯一的子节点为新根
-        if self.root.node_type == NodeType::Internal && self.root.keys.is_empty() {
-            if !self.root.children.is_empty() {
-                let new_root = self.root.children.remove(0);
-                self.root = Box::new(new_root);
-            }
+        if self.root.node_type == NodeType::Internal
+            && self.root.keys.is_empty()
+            && !self.root.children.is_empty()
+        {
+            let new_root = self.root.children.remove(0);
+            *self.root = new_root;
         }
+        // 删掉的若是端点, 紧邻的墓碑成了新的端点
+        self.trim_ends();
     }
 
-    /// 范围查询 [start..end] - B+树的独特优势
+    /// 范围查询 [start..end]
+    ///
+    /// 只下降与区间相交的子树; 叶子的 `next_leaf` 是分裂时的副本, 不能用来扫描
     pub fn range_query(&self, start: &K, end: &K) -> Vec<V> {
         let mut result = Vec::new();
-        let mut current = &*self.root;
-        
-        // 1. 快速定位起始叶子节点
-        while current.node_type == NodeType::Internal {
-            let i = match current.keys.binary_search(start) {
-                Ok(i) => i + 1,
-                Err(i) => i,
-            };
-            current = &current.children[i];
-        }
-        
-        // 2. 利用叶子节点链表进行高效的范围扫描
-        loop {
-            for (i, k) in current.keys.iter().enumerate() {
-                if k >= start && k <= end {
-                    result.push(current.vals[i].clone());
-                }
-                if k > end {
-                    return result;
+        self.range_query_node(&self.root, start, end, &mut result);
+        result
+    }
+
+    fn range_query_node(&self, node: &Node<K, V>, start: &K, end: &K, result: &mut Vec<V>) {
+        if node.node_type == NodeType::Leaf {
+            for (i, k) in node.keys.iter().enumerate() {
+                if k >= start && k <= end && self.slots.is_live(node.slots[i]) {
+                    result.push(node.vals[i].clone());
                 }
             }
-            
-            match &current.next_leaf {
-                Some(next) => current = next,
-                None => break,
-            }
+            return;
+        }
+        if node.children.is_empty() {
+            return;
+        }
+        // children[i] 的 key 落在 [keys[i - 1], keys[i]) 内
+        let from = node.keys.partition_point(|k| k <= start);
+        let to = node.keys.partition_point(|k| k <= end).min(node.children.len() - 1);
+        for child in &node.children[from.min(to)..=to] {
+            self.range_query_node(child, start, end, result);
         }
-        result
     }
 
     /// 批量插入操作 - 针对大数据量优化
@@ -175,6 +318,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
                     node_type: NodeType::Internal,
                     keys: Vec::with_capacity(2 * self.min_degree),
                     vals: Vec::new(),
+                    slots: Vec::new(),
                     children: Vec::with_capacity(2 * self.min_degree + 1),
                     next_leaf: None,
                 });
@@ -214,6 +358,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
             };
             current.keys.insert(i, key);
             current.vals.insert(i, value);
+            current.slots.insert(i, self.slots.alloc());
         }
     }
 
@@ -227,7 +372,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
                   self.root.keys.is_empty() && 
                   self.root.children.len() == 1 {  // 修改：明确检查只有一个子节点的情况
                 let new_root = self.root.children.remove(0);
-                self.root = Box::new(new_root);
+                *self.root = new_root;
             }
         }
     }
@@ -243,15 +388,33 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
     }
 
     /// 在非满节点插入
-    fn insert_non_full(min_degree: usize, node: &mut Node<K, V>, key: K, value: V) {
+    fn insert_non_full(
+        min_degree: usize,
+        node: &mut Node<K, V>,
+        key: K,
+        value: V,
+        slots: &mut HandleTable,
+    ) -> Handle {
         match node.node_type {
             NodeType::Leaf => {
                 let i = match node.keys.binary_search(&key) {
-                    Ok(i) => i,
+                    Ok(i) if !slots.is_live(node.slots[i]) => {
+                        // 同 key 的墓碑条目直接复活
+                        node.vals[i] = value;
+                        return slots.revive(node.slots[i]);
+                    }
+                    Ok(i) => {
+                        // 已存在的 key 覆盖 value, 句柄不变
+                        node.vals[i] = value;
+                        return slots.handle(node.slots[i]);
+                    }
                     Err(i) => i,
                 };
+                let slot = slots.alloc();
                 node.keys.insert(i, key);
                 node.vals.insert(i, value);
+                node.slots.insert(i, slot);
+                slots.handle(slot)
             }
             NodeType::Internal => {
                 let mut i = match node.keys.binary_search(&key) {
@@ -268,23 +431,28 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
                 if !node.children.is_empty() {
                     if Self::is_full(min_degree, &node.children[i]) {
                         Self::split_child(min_degree, node, i);
-                        if key > node.keys[i] {
+                        // 与分隔键相等的 key 属于右半边, 与查找的走向一致
+                        if key >= node.keys[i] {
                             i += 1;
                         }
                     }
-                    Self::insert_non_full(min_degree, &mut node.children[i], key, value);
+                    Self::insert_non_full(min_degree, &mut node.children[i], key, value, slots)
                 } else {
                     // 如果没有子节点，创建一个新的叶子节点
                     let mut leaf = Node {
                         node_type: NodeType::Leaf,
                         keys: Vec::with_capacity(2 * min_degree - 1),
                         vals: Vec::with_capacity(2 * min_degree - 1),
+                        slots: Vec::new(),
                         children: Vec::new(),
                         next_leaf: None,
                     };
+                    let slot = slots.alloc();
                     leaf.keys.push(key);
                     leaf.vals.push(value);
+                    leaf.slots.push(slot);
                     node.children.push(leaf);
+                    slots.handle(slot)
                 }
             }
         }
@@ -299,6 +467,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
             node_type: child.node_type.clone(),
             keys: Vec::with_capacity(2 * t - 1),  // 预分配容量
             vals: Vec::with_capacity(2 * t - 1),
+            slots: Vec::new(),
             children: Vec::with_capacity(if child.node_type == NodeType::Internal { 2 * t } else { 0 }),
             next_leaf: None,
         };
@@ -308,6 +477,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
             let mid = t - 1;
             new_node.keys = child.keys.split_off(mid);
             new_node.vals = child.vals.split_off(mid);
+            new_node.slots = child.slots.split_off(mid);
             
             new_node.next_leaf = child.next_leaf.take();
             child.next_leaf = Some(Box::new(new_node.clone()));
@@ -327,13 +497,14 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
         }
     }
 
-    /// 删除递归
-    fn delete_recur(node: &mut Node<K, V>, key: &K, min_degree: usize) {
+    /// 删除递归, removed 收集被摘除条目的槽位
+    fn delete_recur(node: &mut Node<K, V>, key: &K, min_degree: usize, removed: &mut Vec<usize>) {
         match node.node_type {
             NodeType::Leaf => {
                 if let Ok(pos) = node.keys.binary_search(key) {
                     node.keys.remove(pos);
                     node.vals.remove(pos);
+                    removed.push(node.slots.remove(pos));
                 }
             }
             NodeType::Internal => {
@@ -352,17 +523,17 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
                             if has_left {
                                 if let Some(pred) = Self::get_predecessor(node, i) {
                                     node.keys[i] = pred.0.clone();
-                                    Self::delete_recur(&mut node.children[i], &pred.0, min_degree);
+                                    Self::delete_recur(&mut node.children[i], &pred.0, min_degree, removed);
                                 }
                             } else if has_right {
                                 if let Some(succ) = Self::get_successor(node, i) {
                                     node.keys[i] = succ.0.clone();
-                                    Self::delete_recur(&mut node.children[i + 1], &succ.0, min_degree);
+                                    Self::delete_recur(&mut node.children[i + 1], &succ.0, min_degree, removed);
                                 }
                             } else if i < node.children.len() - 1 {
                                 Self::merge_child(node, i);
                                 if !node.children.is_empty() {
-                                    Self::delete_recur(&mut node.children[i], key, min_degree);
+                                    Self::delete_recur(&mut node.children[i], key, min_degree, removed);
                                 }
                             }
                         }
@@ -372,7 +543,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
                 };
 
                 if i < node.children.len() {
-                    Self::delete_recur(&mut node.children[i], key, min_degree);
+                    Self::delete_recur(&mut node.children[i], key, min_degree, removed);
                     
                     if !node.children.is_empty() && 
                        i < node.children.len() && 
@@ -406,7 +577,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
 
     fn borrow_from_left(parent: &mut Node<K, V>, idx: usize) {
         // 首先检查边界条件
-        if idx == 0 || idx - 1 >= parent.keys.len() {
+        if idx == 0 || idx > parent.keys.len() {
             return;
         }
     
@@ -419,6 +590,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
             if !left.keys.is_empty() && !cur.keys.is_empty() {
                 let borrow_k = left.keys.pop().unwrap();
                 let borrow_v = left.vals.pop().unwrap();
+                let borrow_s = left.slots.pop().unwrap();
                 
                 // 更新父节点的键，确保当前节点有键可用
                 if idx - 1 < parent.keys.len() && !cur.keys.is_empty() {
@@ -427,6 +599,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
                 
                 cur.keys.insert(0, borrow_k);
                 cur.vals.insert(0, borrow_v);
+                cur.slots.insert(0, borrow_s);
             }
         } else {
             // 内部节点的情况
@@ -463,6 +636,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
             if !right.keys.is_empty() {
                 let borrow_k = right.keys.remove(0);
                 let borrow_v = right.vals.remove(0);
+                let borrow_s = right.slots.remove(0);
                 
                 // 更新父节点的键
                 if idx < parent.keys.len() {
@@ -471,6 +645,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
                 
                 cur.keys.push(borrow_k);
                 cur.vals.push(borrow_v);
+                cur.slots.push(borrow_s);
             }
         } else {
             // 内部节点的情况
@@ -504,6 +679,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
         if left.node_type == NodeType::Leaf {
             left.keys.extend(right.keys);
             left.vals.extend(right.vals);
+            left.slots.extend(right.slots);
             left.next_leaf = right.next_leaf;
         } else if idx < parent.keys.len() {
             if let Some(up_key) = parent.keys.get(idx).cloned() {
@@ -557,4 +733,632 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
             Some((current.keys[0].clone(), current.vals[0].clone()))
         }
     }
+
+    /// 最小的存活条目; 两端的墓碑在删除时已摘除, 不会扫过墓碑
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.walk(1, false).pop()
+    }
+
+    /// 最大的存活条目
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.walk(1, true).pop()
+    }
+
+    /// 第一个 key >= 给定 key 的存活条目, 跳过墓碑
+    pub fn lower_bound(&self, key: &K) -> Option<(&K, &V)> {
+        fn seek<'a, K: Ord + Clone + Debug, V: Clone + Debug>(
+            node: &'a Node<K, V>,
+            slots: &HandleTable,
+            key: &K,
+        ) -> Option<(&'a K, &'a V)> {
+            if node.node_type == NodeType::Leaf {
+                let from = node.keys.partition_point(|k| k < key);
+                let i = (from..node.keys.len()).find(|&i| slots.is_live(node.slots[i]))?;
+                return Some((&node.keys[i], &node.vals[i]));
+            }
+            // children[i] 的 key 落在 [keys[i - 1], keys[i]) 内; 从 key 所在的孩子往右找
+            let from = node.keys.partition_point(|k| k <= key).min(node.children.len().saturating_sub(1));
+            node.children.get(from..)?.iter().find_map(|child| seek(child, slots, key))
+        }
+        seek(&self.root, &self.slots, key)
+    }
+
+    /// 按 key 升序的全部存活条目
+    pub fn entries(&self) -> Vec<(&K, &V)> {
+        self.walk(self.len(), false)
+    }
+
+    /// 按 key 升序的前 n 个存活条目, 走到第 n 个即停止
+    pub fn first_n(&self, n: usize) -> Vec<(&K, &V)> {
+        self.walk(n, false)
+    }
+
+    /// 按 key 降序的前 n 个存活条目
+    pub fn last_n(&self, n: usize) -> Vec<(&K, &V)> {
+        self.walk(n, true)
+    }
+
+    /// 深度优先遍历叶子 (rev 时从右往左), 收集 n 个存活条目后停止
+    fn walk(&self, n: usize, rev: bool) -> Vec<(&K, &V)> {
+        fn visit<'a, K: Ord + Clone + Debug, V: Clone + Debug>(
+            node: &'a Node<K, V>,
+            slots: &HandleTable,
+            n: usize,
+            rev: bool,
+            out: &mut Vec<(&'a K, &'a V)>,
+        ) {
+            let at = |k: usize, len: usize| if rev { len - 1 - k } else { k };
+            if node.node_type == NodeType::Leaf {
+                for k in 0..node.keys.len() {
+                    if out.len() >= n {
+                        break;
+                    }
+                    let i = at(k, node.keys.len());
+                    if slots.is_live(node.slots[i]) {
+                        out.push((&node.keys[i], &node.vals[i]));
+                    }
+                }
+                return;
+            }
+            for k in 0..node.children.len() {
+                if out.len() >= n {
+                    break;
+                }
+                visit(&node.children[at(k, node.children.len())], slots, n, rev, out);
+            }
+        }
+        let mut out = Vec::with_capacity(n.min(self.len()));
+        visit(&self.root, &self.slots, n, rev, &mut out);
+        out
+    }
+
+    /// 由按 key 严格递增的条目自底向上建树, O(n)
+    ///
+    /// 先取能放下全部条目的最小高度, 每个内部节点用尽量少的孩子 (非根节点至少 t 个), 条目在叶子间平均分配;
+    /// 叶子不建 `next_leaf` 副本
+    pub fn from_sorted(min_degree: usize, entries: Vec<(K, V)>) -> Self {
+        assert!(min_degree >= 2, "B+Tree min_degree must >= 2");
+        debug_assert!(entries.windows(2).all(|w| w[0].0 < w[1].0), "entries must be strictly increasing");
+        let mut slots = HandleTable::new();
+        let entries = entries.into_iter().map(|(k, v)| (k, v, slots.alloc())).collect();
+        let root = Self::build_sorted(min_degree, entries);
+        BPTree { root: Box::new(root), min_degree, slots }
+    }
+
+    /// 由按 key 严格递增的 (key, value, 槽位) 建树, 返回根节点
+    fn build_sorted(min_degree: usize, entries: Vec<(K, V, usize)>) -> Node<K, V> {
+        let n = entries.len();
+        let mut height = 1;
+        while Self::max_entries(min_degree, height) < n {
+            height += 1;
+        }
+        Self::build(min_degree, &mut entries.into_iter(), n, height, true)
+    }
+
+    /// 高为 height 的子树最多容纳的条目数: (2t)^(h-1) * (2t - 1)
+    fn max_entries(min_degree: usize, height: u32) -> usize {
+        (2 * min_degree).saturating_pow(height - 1).saturating_mul(2 * min_degree - 1)
+    }
+
+    fn build(
+        min_degree: usize,
+        entries: &mut impl Iterator<Item = (K, V, usize)>,
+        n: usize,
+        height: u32,
+        is_root: bool,
+    ) -> Node<K, V> {
+        let mut node = Node {
+            node_type: if height == 1 { NodeType::Leaf } else { NodeType::Internal },
+            keys: Vec::new(),
+            vals: Vec::new(),
+            slots: Vec::new(),
+            children: Vec::new(),
+            next_leaf: None,
+        };
+        if height == 1 {
+            for (key, value, slot) in entries.take(n) {
+                node.keys.push(key);
+                node.vals.push(value);
+                node.slots.push(slot);
+            }
+            return node;
+        }
+        let c = n.div_ceil(Self::max_entries(min_degree, height - 1)).max(if is_root { 2 } else { min_degree });
+        for i in 0..c {
+            let size = n / c + usize::from(i < n % c);
+            let child = Self::build(min_degree, entries, size, height - 1, false);
+            if i > 0 {
+                // 分隔键是右侧子树的最小 key
+                let mut first = &child;
+                while first.node_type == NodeType::Internal {
+                    first = &first.children[0];
+                }
+                node.keys.push(first.keys[0].clone());
+            }
+            node.children.push(child);
+        }
+        node
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone + Debug> BPTree<K, V> {
+    /// 定位在第一个存活条目上的可变游标
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut::new(self);
+        cursor.move_next();
+        cursor
+    }
+
+    /// 定位在第一个 key >= 给定 key 的存活条目上的可变游标
+    pub fn lower_bound_mut(&mut self, key: &K) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut::new(self);
+        cursor.lower_bound(key);
+        cursor
+    }
+}
+
+/// 游标位置: 从根到叶子每层的节点指针和下标 (内部节点是子节点下标, 叶子是条目下标); 为空表示空位
+///
+/// 指针只在游标持有树的可变借用期间使用。分裂、合并会移动子节点,
+/// 改动第 d 层之后要调用 `relink(d)`, 从父节点重新取第 d 层及以下的指针。
+#[derive(Clone)]
+struct Position<K: Ord + Clone + Debug, V: Clone + Debug> {
+    /// 根节点在 Box 中, 地址在游标存续期间不变
+    root: NonNull<Node<K, V>>,
+    nodes: Vec<NonNull<Node<K, V>>>,
+    path: Vec<usize>,
+}
+
+impl<K: Ord + Clone + Debug, V: Clone + Debug> Position<K, V> {
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.path.clear();
+    }
+
+    fn node(&self, d: usize) -> &Node<K, V> {
+        // SAFETY: nodes 中的指针由 relink/descend 从父节点取得, 游标独占整棵树, 期间没有其它引用
+        unsafe { self.nodes[d].as_ref() }
+    }
+
+    fn node_mut(&mut self, d: usize) -> &mut Node<K, V> {
+        // SAFETY: 同 node; 每次只通过一个指针改动节点
+        unsafe { self.nodes[d].as_mut() }
+    }
+
+    fn leaf(&self) -> Option<(&Node<K, V>, usize)> {
+        let pos = *self.path.last()?;
+        Some((self.node(self.path.len() - 1), pos))
+    }
+
+    /// 重新取第 from 层 (from >= 1) 及以下的节点指针
+    fn relink(&mut self, from: usize) {
+        for d in from..self.path.len() {
+            let c = self.path[d - 1];
+            let child = &mut self.node_mut(d - 1).children[c];
+            self.nodes[d] = NonNull::from(child);
+        }
+    }
+
+    /// 从最后一层节点下降到最左或最右的叶子, 叶子下标取 0 或条目数; 遇到无子节点的内部节点时清空
+    fn descend(&mut self, leftmost: bool) {
+        loop {
+            let d = self.nodes.len() - 1;
+            let node = self.node_mut(d);
+            if node.node_type == NodeType::Leaf {
+                let pos = if leftmost { 0 } else { node.keys.len() };
+                self.path.push(pos);
+                return;
+            }
+            if node.children.is_empty() {
+                self.clear();
+                return;
+            }
+            let c = if leftmost { 0 } else { node.children.len() - 1 };
+            let child = NonNull::from(&mut node.children[c]);
+            self.path.push(c);
+            self.nodes.push(child);
+        }
+    }
+
+    fn start(&mut self, leftmost: bool) {
+        self.clear();
+        self.nodes.push(self.root);
+        self.descend(leftmost);
+    }
+
+    /// 从当前叶子移到右侧 (或左侧) 相邻叶子, 没有时清空
+    fn adjacent_leaf(&mut self, right: bool) -> bool {
+        self.nodes.pop();
+        self.path.pop();
+        while let Some(&c) = self.path.last() {
+            let d = self.path.len() - 1;
+            let len = self.node(d).children.len();
+            if (right && c + 1 < len) || (!right && c > 0) {
+                let c = if right { c + 1 } else { c - 1 };
+                self.path[d] = c;
+                let child = NonNull::from(&mut self.node_mut(d).children[c]);
+                self.nodes.push(child);
+                self.descend(right);
+                return !self.path.is_empty();
+            }
+            self.nodes.pop();
+            self.path.pop();
+        }
+        self.clear();
+        false
+    }
+
+    /// 若叶子内下标越界, 向右跨叶子直到落在条目上, 否则停在空位
+    fn settle_forward(&mut self) {
+        while let Some((leaf, pos)) = self.leaf() {
+            if pos < leaf.keys.len() || !self.adjacent_leaf(true) {
+                return;
+            }
+        }
+    }
+
+    /// 移到下一个条目 (不区分存活与墓碑)
+    fn step_next(&mut self) {
+        match self.path.last_mut() {
+            Some(pos) => *pos += 1,
+            None => self.start(true),
+        }
+        self.settle_forward();
+    }
+
+    /// 移到上一个条目 (不区分存活与墓碑)
+    fn step_prev(&mut self) {
+        if self.path.is_empty() {
+            self.start(false);
+        }
+        while let Some(pos) = self.path.last_mut() {
+            if *pos > 0 {
+                *pos -= 1;
+                return;
+            }
+            self.adjacent_leaf(false);
+        }
+    }
+
+    /// 当前条目是否是整棵树的第一个 (right 时为最后一个) 物理条目, 不区分存活与墓碑
+    fn at_edge(&self, right: bool) -> bool {
+        self.path.iter().enumerate().all(|(d, &c)| {
+            let node = self.node(d);
+            let len = if node.node_type == NodeType::Leaf { node.keys.len() } else { node.children.len() };
+            if right { c + 1 == len } else { c == 0 }
+        })
+    }
+
+    /// 当前位置之后最近的右侧祖先分隔键, 即当前叶子 key 的上界
+    fn upper_bound(&self) -> Option<&K> {
+        (0..self.path.len() - 1)
+            .rev()
+            .find(|&d| self.path[d] < self.node(d).keys.len())
+            .map(|d| &self.node(d).keys[self.path[d]])
+    }
+}
+
+/// 可变游标: 停在某个存活条目上, 或停在首尾之间的空位
+///
+/// 游标保存从根到当前叶子的节点栈, 叶子内和跨叶子移动只在栈上进出, 不回到根, 也不比较 key。
+/// 删除和插入直接改当前叶子, 只有下溢或溢出时才沿栈向上借位、合并或分裂。墓碑条目会被跳过。
+pub struct CursorMut<'a, K: Ord + Clone + Debug, V: Clone + Debug> {
+    tree: &'a mut BPTree<K, V>,
+    pos: Position<K, V>,
+}
+
+impl<'a, K: Ord + Clone + Debug, V: Clone + Debug> CursorMut<'a, K, V> {
+    fn new(tree: &'a mut BPTree<K, V>) -> Self {
+        let root = NonNull::from(&mut *tree.root);
+        CursorMut { tree, pos: Position { root, nodes: Vec::new(), path: Vec::new() } }
+    }
+
+    fn is_live(&self, pos: &Position<K, V>) -> bool {
+        match pos.leaf() {
+            Some((leaf, i)) => self.tree.slots.is_live(leaf.slots[i]),
+            None => true,
+        }
+    }
+
+    fn skip_dead_forward(&mut self) {
+        while !self.is_live(&self.pos) {
+            self.pos.step_next();
+        }
+    }
+
+    pub fn key(&self) -> Option<&K> {
+        self.pos.leaf().map(|(leaf, i)| &leaf.keys[i])
+    }
+
+    pub fn value(&self) -> Option<&V> {
+        self.pos.leaf().map(|(leaf, i)| &leaf.vals[i])
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        let i = *self.pos.path.last()?;
+        let d = self.pos.path.len() - 1;
+        Some(&mut self.pos.node_mut(d).vals[i])
+    }
+
+    /// 当前条目的句柄
+    pub fn handle(&self) -> Option<Handle> {
+        self.pos.leaf().map(|(leaf, i)| self.tree.slots.handle(leaf.slots[i]))
+    }
+
+    /// 后移一个存活条目, 越过末尾后停在空位; 返回是否停在条目上
+    pub fn move_next(&mut self) -> bool {
+        self.pos.step_next();
+        self.skip_dead_forward();
+        !self.pos.path.is_empty()
+    }
+
+    /// 前移一个存活条目, 越过开头后停在空位; 返回是否停在条目上
+    pub fn move_prev(&mut self) -> bool {
+        loop {
+            self.pos.step_prev();
+            if self.is_live(&self.pos) {
+                return !self.pos.path.is_empty();
+            }
+        }
+    }
+
+    /// 从根重新定位到第一个 key >= 给定 key 的存活条目
+    pub fn lower_bound(&mut self, key: &K) {
+        self.pos.clear();
+        self.pos.nodes.push(self.pos.root);
+        loop {
+            let d = self.pos.nodes.len() - 1;
+            let node = self.pos.node_mut(d);
+            if node.node_type == NodeType::Leaf {
+                let i = node.keys.partition_point(|k| k < key);
+                self.pos.path.push(i);
+                break;
+            }
+            if node.children.is_empty() {
+                self.pos.clear();
+                return;
+            }
+            let c = node.keys.partition_point(|k| k <= key).min(node.children.len() - 1);
+            let child = NonNull::from(&mut node.children[c]);
+            self.pos.path.push(c);
+            self.pos.nodes.push(child);
+        }
+        self.pos.settle_forward();
+        self.skip_dead_forward();
+    }
+
+    /// 删除当前条目, 游标移到其后继
+    ///
+    /// 直接从当前叶子摘除; 叶子下溢时沿节点栈向上借位或合并, 根只剩一个子节点时降低一层
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        let edge = !self.pos.path.is_empty() && (self.pos.at_edge(false) || self.pos.at_edge(true));
+        let entry = self.unlink_current()?;
+        self.pos.settle_forward();
+        self.skip_dead_forward();
+        if edge && self.tree.slots.tombstones() > 0 {
+            // 删的是端点时紧邻的墓碑成了新的端点, 摘掉后按后继的 key 重新定位
+            let next = self.key().cloned();
+            self.tree.trim_ends();
+            match next {
+                Some(key) => self.lower_bound(&key),
+                None => self.pos.clear(),
+            }
+        }
+        Some(entry)
+    }
+
+    /// 从当前叶子摘除条目并修复下溢, 游标下标停在原处 (可能越过叶子末尾)
+    fn unlink_current(&mut self) -> Option<(K, V)> {
+        let i = *self.pos.path.last()?;
+        let d = self.pos.path.len() - 1;
+        let leaf = self.pos.node_mut(d);
+        let entry = (leaf.keys.remove(i), leaf.vals.remove(i));
+        let slot = leaf.slots.remove(i);
+        self.tree.slots.release(slot);
+        self.rebalance();
+        Some(entry)
+    }
+
+    /// 自当前叶子向上修复下溢, 游标下标随借位与合并调整
+    fn rebalance(&mut self) {
+        let min_keys = self.tree.min_degree - 1;
+        let pos = &mut self.pos;
+        for d in (1..pos.path.len()).rev() {
+            let c = pos.path[d - 1];
+            let parent = pos.node_mut(d - 1);
+            if parent.children[c].keys.len() >= min_keys {
+                break;
+            }
+            if c > 0 && parent.children[c - 1].keys.len() > min_keys {
+                Self::borrow_from_left(parent, c);
+                pos.path[d] += 1;
+            } else if c + 1 < parent.children.len() && parent.children[c + 1].keys.len() > min_keys {
+                Self::borrow_from_right(parent, c);
+            } else if c > 0 {
+                let left = &parent.children[c - 1];
+                let offset = if left.node_type == NodeType::Leaf { left.keys.len() } else { left.children.len() };
+                Self::merge(parent, c - 1);
+                pos.path[d - 1] = c - 1;
+                pos.path[d] += offset;
+            } else if c + 1 < parent.children.len() {
+                Self::merge(parent, c);
+            }
+            pos.relink(d);
+        }
+
+        // 根只剩一个子节点时把它提上来; 根节点的地址不变
+        let root = pos.node_mut(0);
+        if root.node_type == NodeType::Internal && root.children.len() == 1 {
+            *root = root.children.pop().unwrap();
+            pos.path.remove(0);
+            pos.nodes.remove(1);
+            pos.relink(1);
+        }
+    }
+
+    fn borrow_from_left(parent: &mut Node<K, V>, c: usize) {
+        let (left, cur) = parent.children.split_at_mut(c);
+        let (left, cur) = (&mut left[c - 1], &mut cur[0]);
+        if cur.node_type == NodeType::Leaf {
+            cur.keys.insert(0, left.keys.pop().unwrap());
+            cur.vals.insert(0, left.vals.pop().unwrap());
+            cur.slots.insert(0, left.slots.pop().unwrap());
+            parent.keys[c - 1] = cur.keys[0].clone();
+        } else {
+            let up = left.keys.pop().unwrap();
+            cur.keys.insert(0, std::mem::replace(&mut parent.keys[c - 1], up));
+            cur.children.insert(0, left.children.pop().unwrap());
+        }
+    }
+
+    fn borrow_from_right(parent: &mut Node<K, V>, c: usize) {
+        let (cur, right) = parent.children.split_at_mut(c + 1);
+        let (cur, right) = (&mut cur[c], &mut right[0]);
+        if cur.node_type == NodeType::Leaf {
+            cur.keys.push(right.keys.remove(0));
+            cur.vals.push(right.vals.remove(0));
+            cur.slots.push(right.slots.remove(0));
+            parent.keys[c] = right.keys[0].clone();
+        } else {
+            let up = right.keys.remove(0);
+            cur.keys.push(std::mem::replace(&mut parent.keys[c], up));
+            cur.children.push(right.children.remove(0));
+        }
+    }
+
+    /// 把第 c + 1 个子节点并入第 c 个
+    fn merge(parent: &mut Node<K, V>, c: usize) {
+        let right = parent.children.remove(c + 1);
+        let separator = parent.keys.remove(c);
+        let left = &mut parent.children[c];
+        if left.node_type == NodeType::Leaf {
+            left.keys.extend(right.keys);
+            left.vals.extend(right.vals);
+            left.slots.extend(right.slots);
+            left.next_leaf = right.next_leaf;
+        } else {
+            left.keys.push(separator);
+            left.keys.extend(right.keys);
+            left.children.extend(right.children);
+        }
+    }
+
+    /// 在当前条目之后插入, key 必须严格介于当前条目与下一个存活条目之间; 游标位置不变
+    ///
+    /// 写入当前叶子 (key 越过右侧分隔键时写入其后的叶子), 同 key 的墓碑直接复活;
+    /// 叶子溢出时沿节点栈向上分裂, 不回到根重新查找
+    pub fn insert_after(&mut self, key: K, value: V) -> Result<Handle, String> {
+        let (leaf, i) = self.pos.leaf().ok_or("cursor is not on an entry")?;
+        if key <= leaf.keys[i] {
+            return Err(format!("key {:?} must be greater than the current key {:?}", key, leaf.keys[i]));
+        }
+        let mut next = self.pos.clone();
+        loop {
+            next.step_next();
+            if self.is_live(&next) {
+                break;
+            }
+        }
+        if let Some((leaf, j)) = next.leaf() {
+            if key >= leaf.keys[j] {
+                return Err(format!("key {:?} must be less than the next key {:?}", key, leaf.keys[j]));
+            }
+        }
+
+        // 当前与后继之间只有墓碑; 越过叶子末尾且 key 不小于上界时换到下一个叶子
+        let mut at = self.pos.clone();
+        *at.path.last_mut().unwrap() += 1;
+        loop {
+            let (leaf, from) = at.leaf().unwrap();
+            let j = from + leaf.keys[from..].partition_point(|k| k < &key);
+            let past_end = j == leaf.keys.len();
+            *at.path.last_mut().unwrap() = j;
+            if !past_end || at.upper_bound().is_none_or(|bound| &key < bound) {
+                break;
+            }
+            at.adjacent_leaf(true);
+        }
+
+        let (leaf, j) = at.leaf().unwrap();
+        if j < leaf.keys.len() && leaf.keys[j] == key {
+            // 介于当前与后继之间的同 key 条目只可能是墓碑
+            let d = at.path.len() - 1;
+            let leaf = at.node_mut(d);
+            leaf.vals[j] = value;
+            return Ok(self.tree.slots.revive(leaf.slots[j]));
+        }
+        let slot = self.tree.slots.alloc();
+        let d = at.path.len() - 1;
+        let leaf = at.node_mut(d);
+        leaf.keys.insert(j, key);
+        leaf.vals.insert(j, value);
+        leaf.slots.insert(j, slot);
+        self.split_overflow(&mut at);
+
+        // at 停在新条目上, 它的前一个存活条目就是原来的当前条目
+        self.pos = at;
+        self.move_prev();
+        Ok(self.tree.slots.handle(slot))
+    }
+
+    /// 自 at 所在叶子向上分裂溢出的节点, at 的下标随分裂调整
+    fn split_overflow(&mut self, at: &mut Position<K, V>) {
+        let t = self.tree.min_degree;
+        let mut d = at.path.len() - 1;
+        loop {
+            let node = at.node(d);
+            let overflow = match node.node_type {
+                NodeType::Leaf => node.keys.len() > 2 * t - 1,
+                NodeType::Internal => node.children.len() > 2 * t,
+            };
+            if !overflow {
+                return;
+            }
+            if d == 0 {
+                // 根溢出: 旧根成为新根的唯一子节点, 再按普通节点分裂
+                let root = at.node_mut(0);
+                let old = std::mem::replace(root, Node {
+                    node_type: NodeType::Internal,
+                    keys: Vec::new(),
+                    vals: Vec::new(),
+                    slots: Vec::new(),
+                    children: Vec::new(),
+                    next_leaf: None,
+                });
+                root.children.push(old);
+                at.path.insert(0, 0);
+                at.nodes.insert(1, at.root);
+                at.relink(1);
+                d = 1;
+            }
+
+            let c = at.path[d - 1];
+            let parent = at.node_mut(d - 1);
+            let node = &mut parent.children[c];
+            let mut right = Node {
+                node_type: node.node_type.clone(),
+                keys: node.keys.split_off(t),
+                vals: Vec::new(),
+                slots: Vec::new(),
+                children: Vec::new(),
+                next_leaf: None,
+            };
+            let separator = if node.node_type == NodeType::Leaf {
+                right.vals = node.vals.split_off(t);
+                right.slots = node.slots.split_off(t);
+                right.keys[0].clone()
+            } else {
+                right.children = node.children.split_off(t);
+                node.keys.pop().unwrap()
+            };
+            parent.keys.insert(c, separator);
+            parent.children.insert(c + 1, right);
+            if at.path[d] >= t {
+                at.path[d - 1] += 1;
+                at.path[d] -= t;
+            }
+            at.relink(d);
+            d -= 1;
+        }
+    }
 }
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
-    use crate::bptree::bptree::BPTree;
+    use crate::bptree::bptree::{BPTree, Node, NodeType};
+    use crate::snapshot::snapshot::Snapshot;
 
     #[test]
     fn test_bptree_insert_find() {
@@ -15,6 +16,30 @@ mod tests {
         assert_eq!(bpt.get(&999), None);
     }
 
+    #[test]
+    fn test_bptree_insert_overwrites_live_key() {
+        // 已存在的 key 覆盖 value 并返回同一句柄, 不留下重复条目
+        let mut bpt = BPTree::new(2);
+        let h = bpt.insert(1, 10);
+        assert_eq!(bpt.insert(1, 20), h);
+        assert_eq!((bpt.len(), bpt.get(&1)), (1, Some(20)));
+
+        let handles: Vec<_> = (0..200u32).map(|k| bpt.insert(k, k)).collect();
+        assert_eq!(handles[1], h);
+        for k in (0..200u32).rev() {
+            assert_eq!(bpt.insert(k, k + 1000), handles[k as usize]);
+        }
+        assert_eq!(bpt.len(), 200);
+        let want: Vec<(u32, u32)> = (0..200).map(|k| (k, k + 1000)).collect();
+        let entries: Vec<(u32, u32)> = bpt.entries().into_iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(entries, want);
+
+        // 快照要求 key 严格递增
+        let mut buf = Vec::new();
+        bpt.save(&mut buf).unwrap();
+        assert_eq!(BPTree::<u32, u32>::load(&buf[..], 2).unwrap().len(), 200);
+    }
+
     #[test]
     fn test_bptree_delete() {
         let mut bpt = BPTree::new(3);
@@ -34,4 +59,340 @@ mod tests {
         assert_eq!(bpt.get(&20), None);
         assert_eq!(bpt.get(&10), Some("ten".to_string()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_bptree_memory_usage() {
+        let mut bpt = BPTree::new(3);
+        let report = bpt.memory_usage();
+        assert_eq!(report.node_count, 1);
+        // 新建叶子预分配了 2t - 1 个槽位
+        assert_eq!(report.wasted_capacity_bytes, 5 * 4 + 5 * 4);
+
+        for k in 0..1000u32 {
+            bpt.insert(k, k);
+        }
+        let report = bpt.memory_usage();
+        assert_eq!(report.entry_count, 1000);
+        assert_eq!(report.value_bytes, 4000);
+        // 内部节点的分隔键也计入 key_bytes
+        assert!(report.key_bytes > 4000);
+        // 分裂时克隆的 next_leaf 链表
+        assert!(report.duplicated_bytes > 0);
+        assert!(report.height >= 2);
+    }
+
+    #[test]
+    fn test_bptree_remove_at() {
+        let mut bpt = BPTree::new(3);
+        let handles: Vec<_> = (0..200u32).map(|k| bpt.insert(k, k)).collect();
+
+        assert!(bpt.remove_at(handles[7]));
+        assert!(!bpt.remove_at(handles[7]));
+        assert_eq!(bpt.get(&7), None);
+        assert!(!bpt.range_query(&5, &9).contains(&7));
+        assert_eq!((bpt.len(), bpt.tombstones()), (199, 1));
+
+        // 墓碑 key 重新插入得到新句柄
+        let h = bpt.insert(7, 70);
+        assert_ne!(h, handles[7]);
+        assert_eq!(bpt.get(&7), Some(70));
+
+        // 大量按句柄删除由 remove_at 触发重建, 墓碑比例始终有界, 存活句柄在重建后仍有效
+        let mut purged = false;
+        for h in handles.iter().skip(99) {
+            let before = bpt.tombstones();
+            assert!(bpt.remove_at(*h));
+            purged |= bpt.tombstones() < before;
+            assert!(bpt.tombstones() < 32 || bpt.tombstones() * 2 <= bpt.len());
+        }
+        assert!(purged);
+        bpt.insert(1000, 1000);
+        bpt.purge();
+        assert_eq!(bpt.tombstones(), 0);
+        assert_eq!(bpt.memory_usage().entry_count, bpt.len());
+        assert_eq!(bpt.len(), 100);
+        assert!(bpt.is_valid(handles[50]));
+        assert!(bpt.remove_at(handles[50]));
+        assert_eq!(bpt.get(&50), None);
+        assert_eq!(bpt.get(&150), None);
+        assert_eq!((0..200u32).filter(|k| bpt.get(k).is_some()).count(), 98);
+        // 重建出的树可以继续插入和分裂
+        for k in 2000..2300u32 {
+            bpt.insert(k, k);
+        }
+        assert_eq!(bpt.range_query(&2000, &2299).len(), 300);
+    }
+
+    #[test]
+    fn test_bptree_range_query_after_splits() {
+        // 叶子分裂后继续插入: 结果必须包含所有在区间内的 key, 与叶子链表无关
+        let mut bpt = BPTree::new(2);
+        let keys: Vec<u32> = (0..500).map(|i| (i * 7919) % 500).collect();
+        for &k in &keys {
+            bpt.insert(k, k);
+        }
+        assert_eq!(bpt.range_query(&0, &499), (0..500).collect::<Vec<_>>());
+        assert_eq!(bpt.range_query(&123, &321), (123..=321).collect::<Vec<_>>());
+        assert!(bpt.range_query(&600, &700).is_empty());
+    }
+
+    #[test]
+    fn test_bptree_first_last_n() {
+        let mut bpt = BPTree::new(2);
+        let handles: Vec<_> = (0..300u32).map(|k| bpt.insert(k, k * 10)).collect();
+        // 中间打墓碑, 有界遍历必须跳过它们
+        for k in (1..299u32).filter(|k| k % 3 == 0) {
+            assert!(bpt.remove_at(handles[k as usize]));
+        }
+        let live: Vec<u32> = (0..300).filter(|k| k % 3 != 0 || *k == 0 || *k == 299).collect();
+        let want = |ks: &[u32]| ks.iter().map(|k| (*k, k * 10)).collect::<Vec<_>>();
+        let got = |es: Vec<(&u32, &u32)>| es.into_iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+        assert_eq!(got(bpt.first_n(50)), want(&live[..50]));
+        let rev: Vec<u32> = live.iter().rev().copied().collect();
+        assert_eq!(got(bpt.last_n(50)), want(&rev[..50]));
+        assert_eq!(got(bpt.last_n(1000)), want(&rev));
+        assert!(bpt.first_n(0).is_empty());
+
+        // 不可变的 first / last / lower_bound 同样跳过墓碑, 两端被删后也是如此
+        assert!(bpt.remove_at(handles[0]) && bpt.remove_at(handles[299]));
+        assert_eq!((bpt.first(), bpt.last()), (Some((&1, &10)), Some((&298, &2980))));
+        for k in 0..301u32 {
+            let want = live.iter().copied().find(|&x| x >= k && x != 0 && x != 299);
+            assert_eq!(bpt.lower_bound(&k).map(|(k, _)| *k), want, "key {}", k);
+        }
+    }
+
+    #[test]
+    fn test_bptree_trim_tombstones_at_ends() {
+        // 端点被删时, 紧邻的一段墓碑随之摘除, first / last 直接落在存活条目上
+        let mut bpt = BPTree::new(3);
+        let handles: Vec<_> = (0..1000u32).map(|k| bpt.insert(k, k)).collect();
+        for k in (1..=100).chain(899..999) {
+            assert!(bpt.remove_at(handles[k]));
+        }
+        assert_eq!(bpt.tombstones(), 200);
+        assert!(bpt.remove_at(handles[0]));
+        assert_eq!(bpt.tombstones(), 100);
+        assert_eq!(bpt.first(), Some((&101, &101)));
+        bpt.delete(&999);
+        assert_eq!((bpt.tombstones(), bpt.last()), (0, Some((&898, &898))));
+        check(&bpt.root, None, None, bpt.min_degree, true);
+
+        // 游标删掉第一个条目时同样摘除其后的墓碑, 并停在后继上
+        for h in &handles[102..110] {
+            assert!(bpt.remove_at(*h));
+        }
+        let mut cursor = bpt.cursor_mut();
+        assert_eq!(cursor.remove_current(), Some((101, 101)));
+        assert_eq!(cursor.key(), Some(&110));
+        drop(cursor);
+        assert_eq!(bpt.tombstones(), 0);
+        assert_eq!(bpt.len(), 789);
+        check(&bpt.root, None, None, bpt.min_degree, true);
+    }
+
+    #[test]
+    fn test_bptree_reinsert_separator_key() {
+        // 分隔键被打墓碑后重新插入: 新条目必须落在查找会走到的右侧叶子
+        let mut bpt = BPTree::new(2);
+        let handles: Vec<_> = (0..200u32).map(|k| bpt.insert(k, k)).collect();
+        for k in 0..200u32 {
+            assert!(bpt.remove_at(handles[k as usize]));
+            bpt.insert(k, k + 1000);
+            assert_eq!(bpt.get(&k), Some(k + 1000), "key {}", k);
+        }
+        assert_eq!(bpt.len(), 200);
+    }
+
+    /// 校验 B+Tree 结构: 叶子同深度, 非根节点不下溢也不溢出, 分隔键界定子树; 返回高度
+    fn check(node: &Node<u32, u32>, lo: Option<u32>, hi: Option<u32>, t: usize, is_root: bool) -> usize {
+        assert!(node.keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(node.keys.len() < 2 * t);
+        if !is_root {
+            assert!(node.keys.len() + 1 >= t, "underflow: {:?}", node.keys);
+        }
+        assert!(node.keys.iter().all(|&k| lo.is_none_or(|lo| k >= lo) && hi.is_none_or(|hi| k < hi)));
+        if node.node_type == NodeType::Leaf {
+            assert_eq!(node.keys.len(), node.slots.len());
+            return 1;
+        }
+        assert_eq!(node.children.len(), node.keys.len() + 1);
+        let heights: Vec<usize> = (0..node.children.len())
+            .map(|i| {
+                let lo = if i == 0 { lo } else { Some(node.keys[i - 1]) };
+                let hi = node.keys.get(i).copied().or(hi);
+                check(&node.children[i], lo, hi, t, false)
+            })
+            .collect();
+        assert!(heights.windows(2).all(|w| w[0] == w[1]));
+        heights[0] + 1
+    }
+
+    fn keys_of(bpt: &mut BPTree<u32, u32>) -> Vec<u32> {
+        let mut cursor = bpt.cursor_mut();
+        let mut keys = Vec::new();
+        while let Some(&k) = cursor.key() {
+            keys.push(k);
+            cursor.move_next();
+        }
+        keys
+    }
+
+    #[test]
+    fn test_bptree_cursor_mut() {
+        let mut bpt = BPTree::new(2);
+        for k in (0..100u32).step_by(2) {
+            bpt.insert(k, k);
+        }
+
+        let mut cursor = bpt.lower_bound_mut(&31);
+        assert_eq!(cursor.key(), Some(&32));
+        *cursor.value_mut().unwrap() = 320;
+        assert!(cursor.insert_after(32, 0).is_err());
+        assert!(cursor.insert_after(34, 0).is_err());
+        assert!(cursor.insert_after(33, 33).is_ok());
+        assert_eq!(cursor.key(), Some(&32));
+        assert_eq!(cursor.remove_current(), Some((32, 320)));
+        assert_eq!(cursor.key(), Some(&33));
+
+        // 叶子最多 3 个条目, 逐个后退会跨过每一个叶子边界
+        cursor.lower_bound(&1000);
+        assert_eq!(cursor.key(), None);
+        let mut got = Vec::new();
+        while cursor.move_prev() {
+            got.push(*cursor.key().unwrap());
+        }
+        let mut want: Vec<u32> = (0..100).step_by(2).filter(|&k| k != 32).chain([33]).collect();
+        want.sort_unstable();
+        assert_eq!(got, want.iter().rev().copied().collect::<Vec<_>>());
+        // 越过开头后 move_next 回到第一个条目
+        assert!(cursor.move_next());
+        assert_eq!(cursor.key(), Some(&0));
+    }
+
+    #[test]
+    fn test_bptree_cursor_insert_splits_up_to_root() {
+        // 只用 insert_after 从单个条目长出整棵树: 叶子末尾和中间的分裂一路传到根
+        let mut bpt = BPTree::new(2);
+        bpt.insert(0u32, 0);
+        let mut cursor = bpt.cursor_mut();
+        for k in 1..300u32 {
+            cursor.insert_after(k * 10, k).unwrap();
+            assert_eq!(cursor.key(), Some(&((k - 1) * 10)));
+            assert!(cursor.move_next());
+        }
+        // 回到开头, 在每对相邻条目之间插入, 落在已满叶子的中间
+        cursor.lower_bound(&0);
+        while let Some(&k) = cursor.key() {
+            if k % 10 == 0 && k < 2990 {
+                cursor.insert_after(k + 5, k).unwrap();
+                assert_eq!(cursor.key(), Some(&k));
+                cursor.move_next();
+            }
+            cursor.move_next();
+        }
+
+        let t = bpt.min_degree;
+        assert!(check(&bpt.root, None, None, t, true) >= 5);
+        let want: Vec<u32> = (0..599).map(|i| i * 5).collect();
+        assert_eq!(keys_of(&mut bpt), want);
+        assert!(want.iter().all(|k| bpt.get(k).is_some()));
+        assert_eq!(bpt.len(), 599);
+    }
+
+    #[test]
+    fn test_bptree_cursor_remove_merges_down_to_leaf() {
+        let mut bpt = BPTree::new(2);
+        for k in 0..200u32 {
+            bpt.insert(k, k);
+        }
+        let t = bpt.min_degree;
+
+        // 从中间往后删: 叶子下溢时向兄弟借位或合并, 游标始终落在后继上
+        let mut cursor = bpt.lower_bound_mut(&100);
+        for k in 100..150u32 {
+            assert_eq!(cursor.remove_current(), Some((k, k)));
+            assert_eq!(cursor.key(), Some(&(k + 1)));
+        }
+        drop(cursor);
+        check(&bpt.root, None, None, t, true);
+
+        // 从末尾往前删到空, 根逐层降低直到只剩一个空叶子
+        let mut cursor = bpt.lower_bound_mut(&u32::MAX);
+        while cursor.move_prev() {
+            let k = *cursor.key().unwrap();
+            assert_eq!(cursor.remove_current().map(|(k, _)| k), Some(k));
+            assert!(cursor.key().is_none_or(|&next| next > k));
+            // 删掉最后一个条目后游标停在空位, move_prev 回到新的末尾
+        }
+        assert_eq!(cursor.key(), None);
+        drop(cursor);
+        assert_eq!(bpt.len(), 0);
+        assert_eq!(bpt.root.node_type, NodeType::Leaf);
+        assert!(bpt.root.keys.is_empty());
+    }
+
+    #[test]
+    fn test_bptree_cursor_insert_past_tombstones() {
+        // 当前条目与后继之间隔着一段墓碑并跨过叶子边界, 新 key 要写进查找会走到的叶子
+        let mut bpt = BPTree::new(2);
+        let handles: Vec<_> = (0..20u32).map(|k| bpt.insert(k * 10, k)).collect();
+        for h in &handles[2..7] {
+            assert!(bpt.remove_at(*h));
+        }
+        assert_eq!(bpt.tombstones(), 5);
+
+        let mut cursor = bpt.lower_bound_mut(&10);
+        assert!(cursor.insert_after(70, 0).is_err());
+        let h = cursor.insert_after(55, 55).unwrap();
+        assert_eq!(cursor.key(), Some(&10));
+        // 同 key 的墓碑直接复活, 旧句柄仍然失效
+        let revived = cursor.insert_after(40, 40).unwrap();
+        assert_ne!(revived, handles[4]);
+        assert!(cursor.move_next());
+        assert_eq!(cursor.key(), Some(&40));
+        assert!(cursor.move_next());
+        assert_eq!(cursor.handle(), Some(h));
+        assert!(cursor.move_next());
+        assert_eq!(cursor.key(), Some(&70));
+        drop(cursor);
+
+        check(&bpt.root, None, None, bpt.min_degree, true);
+        assert_eq!(bpt.get(&55), Some(55));
+        assert_eq!(bpt.get(&40), Some(40));
+        assert_eq!(bpt.get(&30), None);
+        assert!(bpt.is_valid(revived) && !bpt.is_valid(handles[4]));
+        assert_eq!(bpt.range_query(&0, &70), vec![0, 1, 40, 55, 7]);
+    }
+
+    #[test]
+    fn test_bptree_cursor_matches_reference() {
+        // 撮合式遍历: 边走边删除和插入, 每一轮后校验结构并与 BTreeMap 对照
+        let mut bpt = BPTree::new(3);
+        let mut reference = std::collections::BTreeMap::new();
+        for k in (0..3000u32).step_by(3) {
+            bpt.insert(k, k);
+            reference.insert(k, k);
+        }
+        for round in 1..4u32 {
+            let mut cursor = bpt.cursor_mut();
+            let mut step = 0;
+            while let Some(&k) = cursor.key() {
+                step += 1;
+                if step % (round + 1) == 0 {
+                    assert_eq!(cursor.remove_current(), reference.remove_entry(&k));
+                    continue;
+                }
+                if reference.range(k + 1..k + 2).next().is_none() && step % 2 == 0 {
+                    cursor.insert_after(k + 1, round).unwrap();
+                    reference.insert(k + 1, round);
+                }
+                cursor.move_next();
+            }
+            drop(cursor);
+            check(&bpt.root, None, None, bpt.min_degree, true);
+            assert_eq!(keys_of(&mut bpt), reference.keys().copied().collect::<Vec<_>>());
+            assert!(reference.iter().all(|(k, v)| bpt.get(k) == Some(*v)));
+        }
+    }
+}
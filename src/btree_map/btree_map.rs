@@ -1,4 +1,9 @@
 use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ptr::NonNull;
+
+use crate::memory::memory::MemoryReport;
 
 #[derive(Debug, Clone)]
 pub struct BTreeMap<K: Ord + Clone + Debug, V: Clone + Debug> {
@@ -34,6 +39,36 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BTreeMap<K, V> {
         }
     }
 
+    /// 遍历统计内存占用, 子节点以 `Option<BTreeNode>` 内联存放在父节点的 Vec 中
+    pub fn memory_usage(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+        let mut used_slots = 0;
+        let mut stack = Vec::new();
+        if let Some(ref root) = self.root {
+            stack.push((root, 1));
+        }
+        while let Some((node, depth)) = stack.pop() {
+            report.node_count += 1;
+            report.entry_count += node.keys.len();
+            report.key_bytes += node.keys.len() * size_of::<K>();
+            report.value_bytes += node.vals.len() * size_of::<V>();
+            report.wasted_capacity_bytes += (node.keys.capacity() - node.keys.len()) * size_of::<K>()
+                + (node.vals.capacity() - node.vals.len()) * size_of::<V>()
+                + (node.children.capacity() - node.children.len()) * size_of::<Option<BTreeNode<K, V>>>();
+            // 节点头 (3 个 Vec 头 + leaf 标记) 所在的槽位
+            report.pointer_overhead_bytes += size_of::<Option<BTreeNode<K, V>>>();
+            report.height = report.height.max(depth);
+            used_slots += node.keys.len();
+            for child in node.children.iter().flatten() {
+                stack.push((child, depth + 1));
+            }
+        }
+        if report.node_count > 0 {
+            report.fill_factor = used_slots as f64 / (report.node_count * (2 * self.min_degree - 1)) as f64;
+        }
+        report.finish()
+    }
+
     /// 调试日志
     fn debug_log(&self, _msg: &str) {
         // println!("[BTreeMap DEBUG] {}", msg);
@@ -253,7 +288,7 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BTreeMap<K, V> {
         let val = node.vals.remove(idx);
 
         let left_child = node.children[idx].take().unwrap();
-        let right_child = node.children.remove(idx + 1).take().unwrap();
+        let right_child = node.children.remove(idx + 1).unwrap();
 
         let mut merged = left_child;
         merged.keys.push(key);
@@ -365,4 +400,585 @@ impl<K: Ord + Clone + Debug, V: Clone + Debug> BTreeMap<K, V> {
             self.range_query_node(node.children[i].as_ref().unwrap(), start, end, output);
         }
     }
-}
\ No newline at end of file
+
+    /// 按 key 升序的全部条目
+    pub fn entries(&self) -> Vec<(&K, &V)> {
+        fn walk<'a, K: Ord + Clone + Debug, V: Clone + Debug>(node: &'a BTreeNode<K, V>, out: &mut Vec<(&'a K, &'a V)>) {
+            for i in 0..node.keys.len() {
+                if let Some(Some(child)) = node.children.get(i) {
+                    walk(child, out);
+                }
+                out.push((&node.keys[i], &node.vals[i]));
+            }
+            if let Some(Some(child)) = node.children.get(node.keys.len()) {
+                walk(child, out);
+            }
+        }
+        let mut out = Vec::new();
+        if let Some(root) = &self.root {
+            walk(root, &mut out);
+        }
+        out
+    }
+
+    /// 由按 key 严格递增的条目自底向上建树, O(n)
+    ///
+    /// 先取能放下全部条目的最小高度, 每个节点用尽量少的孩子 (非根节点至少 t 个), 条目在孩子间平均分配
+    pub fn from_sorted(min_degree: usize, entries: Vec<(K, V)>) -> Self {
+        assert!(min_degree >= 2, "[BTreeMap] min_degree must be >= 2");
+        debug_assert!(entries.windows(2).all(|w| w[0].0 < w[1].0), "entries must be strictly increasing");
+        let n = entries.len();
+        let mut height = 1;
+        while Self::max_entries(min_degree, height) < n {
+            height += 1;
+        }
+        let root = Self::build(min_degree, &mut entries.into_iter(), n, height, true);
+        BTreeMap { root: Some(root), min_degree }
+    }
+
+    /// 高为 height 的子树最多容纳的条目数: (2t)^h - 1
+    fn max_entries(min_degree: usize, height: u32) -> usize {
+        (2 * min_degree).saturating_pow(height) - 1
+    }
+
+    fn build(
+        min_degree: usize,
+        entries: &mut impl Iterator<Item = (K, V)>,
+        n: usize,
+        height: u32,
+        is_root: bool,
+    ) -> BTreeNode<K, V> {
+        let mut node = BTreeNode::new(height == 1);
+        if height == 1 {
+            (node.keys, node.vals) = entries.take(n).unzip();
+            return node;
+        }
+        let child_max = Self::max_entries(min_degree, height - 1);
+        let mut c = (n + 1).div_ceil(child_max + 1);
+        if !is_root {
+            c = c.max(min_degree);
+        }
+        // c 个孩子之间夹着 c - 1 个条目
+        let in_children = n + 1 - c;
+        for i in 0..c {
+            let size = in_children / c + usize::from(i < in_children % c);
+            node.children.push(Some(Self::build(min_degree, entries, size, height - 1, false)));
+            if i + 1 < c {
+                let (key, value) = entries.next().unwrap();
+                node.keys.push(key);
+                node.vals.push(value);
+            }
+        }
+        node
+    }
+}
+impl<K: Ord + Clone + Debug, V: Clone + Debug> BTreeMap<K, V> {
+    /// 定位在第一个条目上的可变游标
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut::new(self);
+        cursor.move_next();
+        cursor
+    }
+
+    /// 定位在第一个 key >= 给定 key 的条目上的可变游标
+    pub fn lower_bound_mut(&mut self, key: &K) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut::new(self);
+        cursor.lower_bound(key);
+        cursor
+    }
+}
+
+/// 游标位置: 从根出发每层的节点指针和下标; 末尾是当前节点内的 key 下标, 其余是各层的子节点下标。为空表示空位
+///
+/// 指针只在游标持有树的可变借用期间使用。分裂、合并会移动子节点, 改动第 d 层之后要调用 `relink(d)`。
+#[derive(Clone)]
+struct Position<K: Ord + Clone + Debug, V: Clone + Debug> {
+    /// 根节点内联在树中, 地址在游标存续期间不变
+    root: NonNull<BTreeNode<K, V>>,
+    nodes: Vec<NonNull<BTreeNode<K, V>>>,
+    path: Vec<usize>,
+}
+
+impl<K: Ord + Clone + Debug, V: Clone + Debug> Position<K, V> {
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.path.clear();
+    }
+
+    fn node(&self, d: usize) -> &BTreeNode<K, V> {
+        // SAFETY: nodes 中的指针由 relink/descend 从父节点取得, 游标独占整棵树, 期间没有其它引用
+        unsafe { self.nodes[d].as_ref() }
+    }
+
+    fn node_mut(&mut self, d: usize) -> &mut BTreeNode<K, V> {
+        // SAFETY: 同 node; 每次只通过一个指针改动节点
+        unsafe { self.nodes[d].as_mut() }
+    }
+
+    fn top(&self) -> Option<(&BTreeNode<K, V>, usize)> {
+        let i = *self.path.last()?;
+        Some((self.node(self.path.len() - 1), i))
+    }
+
+    /// 重新取第 from 层 (from >= 1) 及以下的节点指针
+    fn relink(&mut self, from: usize) {
+        self.nodes.resize(self.path.len(), self.root);
+        for d in from..self.path.len() {
+            let c = self.path[d - 1];
+            let child = self.node_mut(d - 1).children[c].as_mut().unwrap();
+            self.nodes[d] = NonNull::from(child);
+        }
+    }
+
+    /// 从最后一层节点下降到最左或最右的叶子条目
+    fn descend(&mut self, leftmost: bool) {
+        loop {
+            let d = self.nodes.len() - 1;
+            let node = self.node_mut(d);
+            if node.leaf {
+                let i = if leftmost { 0 } else { node.keys.len() - 1 };
+                self.path.push(i);
+                return;
+            }
+            let c = if leftmost { 0 } else { node.children.len() - 1 };
+            let child = NonNull::from(node.children[c].as_mut().unwrap());
+            self.path.push(c);
+            self.nodes.push(child);
+        }
+    }
+
+    fn start(&mut self, leftmost: bool) {
+        self.clear();
+        self.nodes.push(self.root);
+        if self.node(0).keys.is_empty() {
+            self.clear();
+        } else {
+            self.descend(leftmost);
+        }
+    }
+
+    /// 叶子末尾: 向上找到第一个从左侧子树返回的祖先条目, 没有时清空
+    fn climb_forward(&mut self) {
+        loop {
+            self.nodes.pop();
+            self.path.pop();
+            let Some(&c) = self.path.last() else {
+                return;
+            };
+            if c < self.node(self.path.len() - 1).keys.len() {
+                return;
+            }
+        }
+    }
+
+    fn step_next(&mut self) {
+        let Some((node, i)) = self.top() else {
+            // 空位 -> 第一个条目
+            self.start(true);
+            return;
+        };
+        let d = self.path.len() - 1;
+        if !node.leaf {
+            // 右侧子树的最左条目
+            self.path[d] = i + 1;
+            let child = NonNull::from(self.node_mut(d).children[i + 1].as_mut().unwrap());
+            self.nodes.push(child);
+            self.descend(true);
+        } else if i + 1 < node.keys.len() {
+            self.path[d] = i + 1;
+        } else {
+            self.climb_forward();
+        }
+    }
+
+    fn step_prev(&mut self) {
+        let Some((node, i)) = self.top() else {
+            // 空位 -> 最后一个条目
+            self.start(false);
+            return;
+        };
+        let d = self.path.len() - 1;
+        if !node.leaf {
+            let child = NonNull::from(self.node_mut(d).children[i].as_mut().unwrap());
+            self.nodes.push(child);
+            self.descend(false);
+        } else if i > 0 {
+            self.path[d] = i - 1;
+        } else {
+            loop {
+                self.nodes.pop();
+                self.path.pop();
+                match self.path.last_mut() {
+                    Some(c) if *c > 0 => {
+                        *c -= 1;
+                        return;
+                    }
+                    Some(_) => {}
+                    None => return,
+                }
+            }
+        }
+    }
+
+    /// 删除后下标可能落在叶子末尾, 此时移到后继
+    fn settle_forward(&mut self) {
+        if let Some((node, i)) = self.top() {
+            if i == node.keys.len() {
+                self.climb_forward();
+            }
+        }
+    }
+}
+
+/// 可变游标: 停在某个条目上, 或停在首尾之间的空位
+///
+/// 游标保存从根到当前条目的节点栈, 前后移动只在栈上进出, 不回到根, 也不比较 key。
+/// 删除和插入直接改当前节点所在的叶子, 只有下溢或溢出时才沿栈向上借位、合并或分裂。
+pub struct CursorMut<'a, K: Ord + Clone + Debug, V: Clone + Debug> {
+    min_degree: usize,
+    pos: Position<K, V>,
+    _tree: PhantomData<&'a mut BTreeMap<K, V>>,
+}
+
+impl<'a, K: Ord + Clone + Debug, V: Clone + Debug> CursorMut<'a, K, V> {
+    fn new(tree: &'a mut BTreeMap<K, V>) -> Self {
+        let min_degree = tree.min_degree;
+        let root = NonNull::from(tree.root.get_or_insert_with(|| BTreeNode::new(true)));
+        CursorMut { min_degree, pos: Position { root, nodes: Vec::new(), path: Vec::new() }, _tree: PhantomData }
+    }
+
+    pub fn key(&self) -> Option<&K> {
+        self.pos.top().map(|(node, i)| &node.keys[i])
+    }
+
+    pub fn value(&self) -> Option<&V> {
+        self.pos.top().map(|(node, i)| &node.vals[i])
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        let i = *self.pos.path.last()?;
+        let d = self.pos.path.len() - 1;
+        Some(&mut self.pos.node_mut(d).vals[i])
+    }
+
+    /// 后移一个条目, 越过末尾后停在空位; 返回是否停在条目上
+    pub fn move_next(&mut self) -> bool {
+        self.pos.step_next();
+        !self.pos.path.is_empty()
+    }
+
+    /// 前移一个条目, 越过开头后停在空位; 返回是否停在条目上
+    pub fn move_prev(&mut self) -> bool {
+        self.pos.step_prev();
+        !self.pos.path.is_empty()
+    }
+
+    /// 从根重新定位到第一个 key >= 给定 key 的条目
+    pub fn lower_bound(&mut self, key: &K) {
+        let pos = &mut self.pos;
+        pos.clear();
+        pos.nodes.push(pos.root);
+        // 候选位置: (路径深度, key 下标), 越深越接近
+        let mut candidate = None;
+        loop {
+            let d = pos.nodes.len() - 1;
+            let node = pos.node_mut(d);
+            let i = node.keys.partition_point(|k| k < key);
+            if i < node.keys.len() {
+                if &node.keys[i] == key {
+                    pos.path.push(i);
+                    return;
+                }
+                candidate = Some((d, i));
+            }
+            if node.leaf {
+                break;
+            }
+            let child = NonNull::from(node.children[i].as_mut().unwrap());
+            pos.path.push(i);
+            pos.nodes.push(child);
+        }
+        match candidate {
+            Some((d, i)) => {
+                pos.path.truncate(d);
+                pos.path.push(i);
+                pos.nodes.truncate(d + 1);
+            }
+            None => pos.clear(),
+        }
+    }
+
+    /// 删除当前条目, 游标移到其后继
+    ///
+    /// 叶子条目直接摘除; 内部节点条目由右侧子树的最左条目顶替, 再从那个叶子摘除。
+    /// 叶子下溢时沿节点栈向上借位或合并, 根变空时降低一层
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        let i = *self.pos.path.last()?;
+        let d = self.pos.path.len() - 1;
+        // chain 是被摘除条目所在叶子的节点栈; 游标留在原位, 删除后这里就是后继
+        let mut chain = self.pos.clone();
+        let entry = if self.pos.node(d).leaf {
+            let node = self.pos.node_mut(d);
+            (node.keys.remove(i), node.vals.remove(i))
+        } else {
+            chain.path[d] = i + 1;
+            let child = NonNull::from(chain.node_mut(d).children[i + 1].as_mut().unwrap());
+            chain.nodes.push(child);
+            chain.descend(true);
+            let leaf = chain.node_mut(chain.nodes.len() - 1);
+            let (key, val) = (leaf.keys.remove(0), leaf.vals.remove(0));
+            let node = self.pos.node_mut(d);
+            (std::mem::replace(&mut node.keys[i], key), std::mem::replace(&mut node.vals[i], val))
+        };
+        chain.path.pop();
+        self.rebalance(&chain);
+        self.pos.settle_forward();
+        Some(entry)
+    }
+
+    /// 沿 chain (从根到刚删除条目的叶子的子节点下标路径) 向上修复下溢, 游标随借位与合并调整
+    fn rebalance(&mut self, chain: &Position<K, V>) {
+        let min_keys = self.min_degree - 1;
+        for d in (1..chain.nodes.len()).rev() {
+            let c = chain.path[d - 1];
+            // SAFETY: 第 d - 1 层及以上的节点不因下层的借位与合并移动
+            let parent = unsafe { &mut *chain.nodes[d - 1].as_ptr() };
+            if parent.children[c].as_ref().unwrap().keys.len() >= min_keys {
+                break;
+            }
+            let sibling_keys = |j: usize| parent.children.get(j).map_or(0, |s| s.as_ref().unwrap().keys.len());
+            let on_parent = self.pos.path.len() >= d && self.pos.nodes[d - 1] == chain.nodes[d - 1];
+            if c > 0 && sibling_keys(c - 1) > min_keys {
+                let left = parent.children[c - 1].as_ref().unwrap();
+                let (left_keys, left_children) = (left.keys.len(), left.children.len());
+                Self::borrow_from_prev(parent, c);
+                if on_parent {
+                    self.pos.shift_borrow_from_prev(d - 1, c, left_keys, left_children);
+                }
+            } else if c + 1 < parent.children.len() && sibling_keys(c + 1) > min_keys {
+                let cur = parent.children[c].as_ref().unwrap();
+                let (cur_keys, cur_children) = (cur.keys.len(), cur.children.len());
+                Self::borrow_from_next(parent, c);
+                if on_parent {
+                    self.pos.shift_borrow_from_next(d - 1, c, cur_keys, cur_children);
+                }
+            } else {
+                let m = if c > 0 { c - 1 } else { c };
+                let left_keys = parent.children[m].as_ref().unwrap().keys.len();
+                Self::merge(parent, m);
+                if on_parent {
+                    self.pos.shift_merge(d - 1, m, left_keys);
+                }
+            }
+        }
+
+        // 根的 key 被合并下去时, 唯一的子节点成为新根; 根节点的地址不变
+        // SAFETY: 根节点指针在游标存续期间有效
+        let root = unsafe { &mut *self.pos.root.as_ptr() };
+        if root.keys.is_empty() && !root.leaf {
+            *root = root.children.pop().unwrap().unwrap();
+            self.pos.path.remove(0);
+            self.pos.nodes.remove(0);
+        }
+        if !self.pos.path.is_empty() {
+            self.pos.nodes[0] = self.pos.root;
+            self.pos.relink(1);
+        }
+    }
+
+    fn borrow_from_prev(parent: &mut BTreeNode<K, V>, c: usize) {
+        let left = parent.children[c - 1].as_mut().unwrap();
+        let (key, val) = (left.keys.pop().unwrap(), left.vals.pop().unwrap());
+        let child = if left.leaf { None } else { left.children.pop() };
+        let key = std::mem::replace(&mut parent.keys[c - 1], key);
+        let val = std::mem::replace(&mut parent.vals[c - 1], val);
+        let cur = parent.children[c].as_mut().unwrap();
+        cur.keys.insert(0, key);
+        cur.vals.insert(0, val);
+        if let Some(child) = child {
+            cur.children.insert(0, child);
+        }
+    }
+
+    fn borrow_from_next(parent: &mut BTreeNode<K, V>, c: usize) {
+        let right = parent.children[c + 1].as_mut().unwrap();
+        let (key, val) = (right.keys.remove(0), right.vals.remove(0));
+        let child = if right.leaf { None } else { Some(right.children.remove(0)) };
+        let key = std::mem::replace(&mut parent.keys[c], key);
+        let val = std::mem::replace(&mut parent.vals[c], val);
+        let cur = parent.children[c].as_mut().unwrap();
+        cur.keys.push(key);
+        cur.vals.push(val);
+        if let Some(child) = child {
+            cur.children.push(child);
+        }
+    }
+
+    /// 把第 c 个 key 和第 c + 1 个子节点并入第 c 个子节点
+    fn merge(parent: &mut BTreeNode<K, V>, c: usize) {
+        let (key, val) = (parent.keys.remove(c), parent.vals.remove(c));
+        let right = parent.children.remove(c + 1).unwrap();
+        let left = parent.children[c].as_mut().unwrap();
+        left.keys.push(key);
+        left.vals.push(val);
+        left.keys.extend(right.keys);
+        left.vals.extend(right.vals);
+        left.children.extend(right.children);
+    }
+
+    /// 在当前条目之后插入, key 必须严格介于当前条目与其后继之间; 游标位置不变
+    ///
+    /// 新条目写在当前条目所在的叶子, 或右侧子树的最左叶子; 溢出时沿节点栈向上分裂, 不回到根重新查找
+    pub fn insert_after(&mut self, key: K, value: V) -> Result<(), String> {
+        let (node, i) = self.pos.top().ok_or("cursor is not on an entry")?;
+        if key <= node.keys[i] {
+            return Err(format!("key {:?} must be greater than the current key {:?}", key, node.keys[i]));
+        }
+        let mut next = self.pos.clone();
+        next.step_next();
+        if let Some((node, j)) = next.top() {
+            if key >= node.keys[j] {
+                return Err(format!("key {:?} must be less than the next key {:?}", key, node.keys[j]));
+            }
+        }
+
+        // 叶子条目之后的位置, 或内部条目右侧子树的最左位置
+        let mut at = self.pos.clone();
+        let d = at.path.len() - 1;
+        if at.node(d).leaf {
+            at.path[d] = i + 1;
+        } else {
+            at.path[d] = i + 1;
+            let child = NonNull::from(at.node_mut(d).children[i + 1].as_mut().unwrap());
+            at.nodes.push(child);
+            at.descend(true);
+        }
+        let d = at.path.len() - 1;
+        let j = at.path[d];
+        let leaf = at.node_mut(d);
+        leaf.keys.insert(j, key);
+        leaf.vals.insert(j, value);
+        self.split_overflow(&mut at);
+
+        // at 停在新条目上, 它的前一个条目就是原来的当前条目
+        self.pos = at;
+        self.pos.step_prev();
+        Ok(())
+    }
+
+    /// 自 at 所在节点向上分裂溢出的节点, at 随分裂调整
+    fn split_overflow(&mut self, at: &mut Position<K, V>) {
+        let t = self.min_degree;
+        let mut d = at.path.len() - 1;
+        while at.node(d).keys.len() > 2 * t - 1 {
+            if d == 0 {
+                // 根溢出: 旧根成为新根的唯一子节点, 再按普通节点分裂
+                let root = at.node_mut(0);
+                let old = std::mem::replace(root, BTreeNode::new(false));
+                root.children.push(Some(old));
+                at.path.insert(0, 0);
+                at.relink(1);
+                d = 1;
+            }
+
+            // 节点有 2t 个 key: 前 t 个留下, 第 t 个上提, 其余 t - 1 个进新节点
+            let c = at.path[d - 1];
+            let parent = at.node_mut(d - 1);
+            let node = parent.children[c].as_mut().unwrap();
+            let mut right = BTreeNode::new(node.leaf);
+            right.keys = node.keys.split_off(t + 1);
+            right.vals = node.vals.split_off(t + 1);
+            if !node.leaf {
+                right.children = node.children.split_off(t + 1);
+            }
+            let (key, val) = (node.keys.pop().unwrap(), node.vals.pop().unwrap());
+            parent.keys.insert(c, key);
+            parent.vals.insert(c, val);
+            parent.children.insert(c + 1, Some(right));
+
+            let is_key = at.path.len() == d + 1;
+            let e = at.path[d];
+            if is_key && e == t {
+                at.path.truncate(d);
+            } else if e > t {
+                at.path[d - 1] = c + 1;
+                at.path[d] = e - t - 1;
+            }
+            at.relink(d);
+            d -= 1;
+        }
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone + Debug> Position<K, V> {
+    /// 第 d 层节点从左兄弟借位后, 调整经过该父节点的位置
+    fn shift_borrow_from_prev(&mut self, d: usize, c: usize, left_keys: usize, left_children: usize) {
+        let is_key = self.path.len() == d + 1;
+        let e = self.path[d];
+        if is_key {
+            if e == c - 1 {
+                // 父节点的 key 下移到 cur 的开头
+                self.path[d] = c;
+                self.path.push(0);
+            }
+        } else if e == c {
+            self.path[d + 1] += 1;
+        } else if e == c - 1 {
+            let below_key = self.path.len() == d + 2;
+            let f = self.path[d + 1];
+            if below_key && f == left_keys - 1 {
+                // 左兄弟的最后一个 key 上移到父节点
+                self.path.truncate(d + 1);
+            } else if !below_key && f == left_children - 1 {
+                // 左兄弟的最后一个子节点移到 cur 的开头
+                self.path[d] = c;
+                self.path[d + 1] = 0;
+            }
+        }
+    }
+
+    /// 第 d 层节点从右兄弟借位后, 调整经过该父节点的位置
+    fn shift_borrow_from_next(&mut self, d: usize, c: usize, cur_keys: usize, cur_children: usize) {
+        let is_key = self.path.len() == d + 1;
+        let e = self.path[d];
+        if is_key {
+            if e == c {
+                // 父节点的 key 下移到 cur 的末尾
+                self.path.push(cur_keys);
+            }
+        } else if e == c + 1 {
+            let below_key = self.path.len() == d + 2;
+            let f = self.path[d + 1];
+            if f > 0 {
+                self.path[d + 1] -= 1;
+            } else if below_key {
+                // 右兄弟的第一个 key 上移到父节点
+                self.path[d] = c;
+                self.path.truncate(d + 1);
+            } else {
+                // 右兄弟的第一个子节点移到 cur 的末尾
+                self.path[d] = c;
+                self.path[d + 1] = cur_children;
+            }
+        }
+    }
+
+    /// 父节点第 c 个 key 与两侧子节点合并后, 调整经过该父节点的位置
+    fn shift_merge(&mut self, d: usize, c: usize, left_keys: usize) {
+        let is_key = self.path.len() == d + 1;
+        let e = self.path[d];
+        if is_key {
+            if e == c {
+                self.path.push(left_keys);
+            } else if e > c {
+                self.path[d] -= 1;
+            }
+        } else if e == c + 1 {
+            // 右侧子节点的 key 与子节点下标都后移 left_keys + 1
+            self.path[d] = c;
+            self.path[d + 1] += left_keys + 1;
+        } else if e > c + 1 {
+            self.path[d] -= 1;
+        }
+    }
+}
@@ -1,2 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod btree_map;
+#[allow(clippy::module_inception)]
 pub mod tests;
\ No newline at end of file
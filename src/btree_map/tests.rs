@@ -14,4 +14,151 @@ mod tests {
         assert_eq!(btree.get(&15), Some(&"fifteen"));
         assert_eq!(btree.get(&999), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_btree_memory_usage() {
+        let mut btree = BTreeMap::new(4);
+        for k in 0..1000u32 {
+            btree.insert(k, k);
+        }
+
+        let report = btree.memory_usage();
+        assert_eq!(report.entry_count, 1000);
+        assert_eq!(report.key_bytes, 4000);
+        assert_eq!(report.value_bytes, 4000);
+        assert!(report.node_count > 1000 / 7);
+        assert!(report.fill_factor > 0.4 && report.fill_factor <= 1.0);
+        assert_eq!(
+            report.total_bytes,
+            report.key_bytes + report.value_bytes + report.wasted_capacity_bytes + report.pointer_overhead_bytes
+        );
+    }
+
+    /// 校验有序性与占用: 非根节点至少 t - 1 个 key, 且每个 key 都能查到
+    fn check(btree: &BTreeMap<u32, u32>, min_degree: usize) -> Vec<u32> {
+        let keys: Vec<u32> = btree.entries().into_iter().map(|(k, _)| *k).collect();
+        assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        assert!(keys.iter().all(|k| btree.get(k) == Some(k)));
+        let report = btree.memory_usage();
+        assert!((report.node_count - 1) * (min_degree - 1) <= report.entry_count);
+        assert!(report.fill_factor <= 1.0);
+        keys
+    }
+
+    #[test]
+    fn test_btree_cursor_mut() {
+        let mut btree = BTreeMap::new(2);
+        for k in (0..100u32).step_by(2) {
+            btree.insert(k, k);
+        }
+
+        let mut cursor = btree.lower_bound_mut(&31);
+        assert_eq!(cursor.key(), Some(&32));
+        *cursor.value_mut().unwrap() = 32;
+        assert!(cursor.insert_after(32, 0).is_err());
+        assert!(cursor.insert_after(34, 0).is_err());
+        assert!(cursor.insert_after(33, 33).is_ok());
+        assert_eq!(cursor.key(), Some(&32));
+        assert!(cursor.move_next());
+        assert_eq!(cursor.key(), Some(&33));
+
+        // 逐个后退, 在叶子与内部节点的条目之间来回穿过
+        cursor.lower_bound(&1000);
+        assert_eq!(cursor.key(), None);
+        let mut got = Vec::new();
+        while cursor.move_prev() {
+            got.push(*cursor.key().unwrap());
+        }
+        got.reverse();
+        drop(cursor);
+        assert_eq!(got, check(&btree, 2));
+        assert_eq!(got.len(), 51);
+    }
+
+    #[test]
+    fn test_btree_cursor_insert_after_internal_entries() {
+        // 在每个条目之后插入: 当前条目在内部节点时, 新条目写入右侧子树的最左叶子
+        let mut btree = BTreeMap::new(2);
+        btree.insert(0u32, 0);
+        let mut cursor = btree.cursor_mut();
+        for k in 1..200u32 {
+            cursor.insert_after(k * 4, k * 4).unwrap();
+            assert!(cursor.move_next());
+        }
+        let height = btree.memory_usage().height;
+        assert!(height >= 5);
+
+        for step in [2u32, 1] {
+            let mut cursor = btree.cursor_mut();
+            while let Some(&k) = cursor.key() {
+                if k % 4 == 0 && k < 796 {
+                    cursor.insert_after(k + step, k + step).unwrap();
+                    assert_eq!(cursor.key(), Some(&k));
+                }
+                cursor.move_next();
+            }
+        }
+        let want: Vec<u32> = (0..797).filter(|k| k % 4 != 3).collect();
+        assert_eq!(check(&btree, 2), want);
+        assert!(btree.memory_usage().height > height);
+    }
+
+    #[test]
+    fn test_btree_cursor_remove_collapses_root() {
+        let mut btree = BTreeMap::new(2);
+        for k in 0..300u32 {
+            btree.insert(k, k);
+        }
+
+        // 从头删到尾: 内部节点的条目由后继顶替, 叶子下溢时借位或合并, 游标始终落在后继上
+        let mut cursor = btree.cursor_mut();
+        for k in 0..150u32 {
+            assert_eq!(cursor.remove_current(), Some((k, k)));
+            assert_eq!(cursor.key(), Some(&(k + 1)));
+        }
+        drop(cursor);
+        assert_eq!(check(&btree, 2), (150..300).collect::<Vec<_>>());
+
+        // 从尾删到头, 根逐层降低直到只剩一个空叶子
+        let mut cursor = btree.lower_bound_mut(&299);
+        for k in (150..300u32).rev() {
+            assert_eq!(cursor.key(), Some(&k));
+            assert_eq!(cursor.remove_current(), Some((k, k)));
+            assert_eq!(cursor.key(), None);
+            cursor.move_prev();
+        }
+        assert_eq!(cursor.key(), None);
+        drop(cursor);
+        let report = btree.memory_usage();
+        assert_eq!((report.height, report.node_count, report.entry_count), (1, 1, 0));
+    }
+
+    #[test]
+    fn test_btree_cursor_matches_reference() {
+        // 撮合式遍历: 边走边删除和插入, 每一轮后与标准库 BTreeMap 对照
+        let mut btree = BTreeMap::new(3);
+        let mut reference = std::collections::BTreeMap::new();
+        for k in (0..3000u32).step_by(3) {
+            btree.insert(k, k);
+            reference.insert(k, k);
+        }
+        for round in 1..4u32 {
+            let mut cursor = btree.cursor_mut();
+            let mut step = 0;
+            while let Some(&k) = cursor.key() {
+                step += 1;
+                if step % (round + 1) == 0 {
+                    assert_eq!(cursor.remove_current(), reference.remove_entry(&k));
+                    continue;
+                }
+                if !reference.contains_key(&(k + 1)) && step % 2 == 0 {
+                    cursor.insert_after(k + 1, k + 1).unwrap();
+                    reference.insert(k + 1, k + 1);
+                }
+                cursor.move_next();
+            }
+            drop(cursor);
+            assert_eq!(check(&btree, 3), reference.keys().copied().collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// 页号, 即页在文件中的下标 (偏移 = 页号 * 页大小)
+pub type PageId = u32;
+/// 空页号 (空指针)
+pub const NO_PAGE: PageId = u32::MAX;
+
+/// 缓冲池命中率等统计
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PoolStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// 写回磁盘的页数
+    pub writes: u64,
+}
+
+struct Frame {
+    page: PageId,
+    data: Box<[u8]>,
+    dirty: bool,
+    /// clock 算法的访问位
+    referenced: bool,
+}
+
+/// 定长页的缓冲池, clock (二次机会) 淘汰
+///
+/// 最多缓存 `capacity` 个页; 淘汰脏页时先写回文件. 调用方每次访问都拿到页的切片,
+/// 不跨调用持有, 因此不需要 pin 计数.
+pub struct BufferPool {
+    file: File,
+    page_size: usize,
+    capacity: usize,
+    frames: Vec<Frame>,
+    /// 页号 -> 帧下标
+    table: HashMap<PageId, usize>,
+    /// clock 指针
+    hand: usize,
+    /// 已分配的页数 (含尚未写回文件的新页)
+    pages: u32,
+    stats: PoolStats,
+    /// 故障注入: 还允许写回的页数, 用完后模拟崩溃
+    crash_after: Option<u64>,
+    crashed: bool,
+}
+
+impl BufferPool {
+    /// 在已打开的文件上建立缓冲池; 文件长度必须是页大小的整数倍
+    pub fn new(file: File, page_size: usize, capacity: usize) -> io::Result<Self> {
+        assert!(page_size > 0 && capacity > 0, "page_size and capacity must > 0");
+        let len = file.metadata()?.len();
+        if !len.is_multiple_of(page_size as u64) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("file length {} is not a multiple of page size {}", len, page_size),
+            ));
+        }
+        Ok(BufferPool {
+            file,
+            page_size,
+            capacity,
+            frames: Vec::with_capacity(capacity),
+            table: HashMap::with_capacity(capacity),
+            hand: 0,
+            pages: (len / page_size as u64) as u32,
+            stats: PoolStats::default(),
+            crash_after: None,
+            crashed: false,
+        })
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 已分配的页数
+    pub fn page_count(&self) -> u32 {
+        self.pages
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.stats
+    }
+
+    /// 在文件末尾分配一个全零的新页
+    pub fn allocate(&mut self) -> io::Result<PageId> {
+        let page = self.pages;
+        self.pages += 1;
+        let idx = self.slot_for(page)?;
+        let frame = &mut self.frames[idx];
+        frame.data.fill(0);
+        frame.dirty = true;
+        Ok(page)
+    }
+
+    /// 只读访问一个页
+    pub fn read(&mut self, page: PageId) -> io::Result<&[u8]> {
+        let idx = self.fetch(page)?;
+        Ok(&self.frames[idx].data)
+    }
+
+    /// 可写访问一个页, 该页被标记为脏
+    pub fn write(&mut self, page: PageId) -> io::Result<&mut [u8]> {
+        let idx = self.fetch(page)?;
+        let frame = &mut self.frames[idx];
+        frame.dirty = true;
+        Ok(&mut frame.data)
+    }
+
+    /// 把所有脏页写回文件 (不 fsync)
+    pub fn flush(&mut self) -> io::Result<()> {
+        for idx in 0..self.frames.len() {
+            self.write_back(idx)?;
+        }
+        self.file.flush()
+    }
+
+    /// 写回并 fsync
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.flush()?;
+        self.check_crashed()?;
+        self.file.sync_data()
+    }
+
+    /// 测试用的故障注入: 再写回 writes 页后模拟进程崩溃
+    ///
+    /// 第 writes + 1 页只写入前一半 (撕裂写), 之后的写回和 fsync 全部返回错误; None 取消注入
+    pub fn inject_crash_after(&mut self, writes: Option<u64>) {
+        self.crash_after = writes;
+    }
+
+    fn check_crashed(&self) -> io::Result<()> {
+        if self.crashed {
+            return Err(io::Error::other("injected crash"));
+        }
+        Ok(())
+    }
+
+    fn fetch(&mut self, page: PageId) -> io::Result<usize> {
+        if page >= self.pages {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("page {} out of range ({} pages)", page, self.pages),
+            ));
+        }
+        if let Some(&idx) = self.table.get(&page) {
+            self.stats.hits += 1;
+            self.frames[idx].referenced = true;
+            return Ok(idx);
+        }
+        self.stats.misses += 1;
+        let idx = self.slot_for(page)?;
+        let offset = page as u64 * self.page_size as u64;
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut self.frames[idx].data)?;
+        Ok(idx)
+    }
+
+    /// 为 page 找一个空帧 (必要时淘汰), 内容未初始化
+    fn slot_for(&mut self, page: PageId) -> io::Result<usize> {
+        let idx = if self.frames.len() < self.capacity {
+            self.frames.push(Frame {
+                page,
+                data: vec![0; self.page_size].into_boxed_slice(),
+                dirty: false,
+                referenced: true,
+            });
+            self.frames.len() - 1
+        } else {
+            // clock: 访问位为 1 的帧清零后跳过, 第一个访问位为 0 的帧被淘汰
+            loop {
+                let idx = self.hand;
+                self.hand = (self.hand + 1) % self.capacity;
+                if self.frames[idx].referenced {
+                    self.frames[idx].referenced = false;
+                    continue;
+                }
+                self.write_back(idx)?;
+                self.stats.evictions += 1;
+                self.table.remove(&self.frames[idx].page);
+                let frame = &mut self.frames[idx];
+                frame.page = page;
+                frame.referenced = true;
+                break idx;
+            }
+        };
+        self.table.insert(page, idx);
+        Ok(idx)
+    }
+
+    fn write_back(&mut self, idx: usize) -> io::Result<()> {
+        if !self.frames[idx].dirty {
+            return Ok(());
+        }
+        self.check_crashed()?;
+        let frame = &mut self.frames[idx];
+        let offset = frame.page as u64 * self.page_size as u64;
+        if let Some(left) = self.crash_after.as_mut() {
+            if *left == 0 {
+                self.crashed = true;
+                self.file.seek(SeekFrom::Start(offset))?;
+                self.file.write_all(&frame.data[..self.page_size / 2])?;
+                return Err(io::Error::other("injected crash"));
+            }
+            *left -= 1;
+        }
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&frame.data)?;
+        frame.dirty = false;
+        self.stats.writes += 1;
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod buffer_pool;
+#[allow(clippy::module_inception)]
+pub mod tests;
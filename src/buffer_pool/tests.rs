@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod tests {
+    use crate::buffer_pool::buffer_pool::BufferPool;
+    use std::fs::OpenOptions;
+
+    #[test]
+    fn test_buffer_pool_clock_eviction() {
+        let path = std::env::temp_dir().join(format!("buffer_pool_{}.db", std::process::id()));
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+        let mut pool = BufferPool::new(file, 64, 3).unwrap();
+        for i in 0..10u8 {
+            let page = pool.allocate().unwrap();
+            pool.write(page).unwrap()[0] = i;
+        }
+        // 10 个页只能驻留 3 个, 其余被淘汰时写回文件
+        let stats = pool.stats();
+        assert_eq!(pool.page_count(), 10);
+        assert_eq!(stats.evictions, 7);
+        assert_eq!(stats.writes, 7);
+
+        for i in 0..10u8 {
+            assert_eq!(pool.read(i as u32).unwrap()[0], i);
+        }
+        // 热页在 clock 扫过时因访问位得到第二次机会
+        let before = pool.stats();
+        for _ in 0..5 {
+            pool.read(9).unwrap();
+            pool.read(0).unwrap();
+        }
+        assert!(pool.stats().hits >= before.hits + 8);
+        assert!(pool.read(10).is_err());
+
+        pool.sync().unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 640);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
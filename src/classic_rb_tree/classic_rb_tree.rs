@@ -0,0 +1,600 @@
+use std::cmp::Ordering;
+use std::mem::size_of;
+
+use crate::memory::memory::MemoryReport;
+use crate::rb_tree::rb_tree::Color;
+
+/// 哨兵节点 (CLRS 中的 T.nil), 固定在下标 0, 永远是黑色
+const NIL: usize = 0;
+
+/// 节点句柄: arena 下标 + 代数, 节点被删除后旧句柄失效
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Links {
+    parent: usize,
+    left: usize,
+    right: usize,
+    color: Color,
+}
+
+impl Links {
+    const EMPTY: Links = Links {
+        parent: NIL,
+        left: NIL,
+        right: NIL,
+        color: Color::Black,
+    };
+}
+
+/// 经典 (非左倾) 红黑树, 按 CLRS 迭代实现
+///
+/// 节点存放在 arena 中, 父/子链接均为下标; 插入/删除自底向上修正, 不重建路径。
+/// 删除时移动节点而不是拷贝键值, 其他节点的句柄始终有效。
+pub struct ClassicRBTree<K, V> {
+    links: Vec<Links>,
+    entries: Vec<Option<(K, V)>>,
+    generations: Vec<u32>,
+    free: Vec<usize>,
+    root: usize,
+    len: usize,
+    rotations: u64,
+}
+
+impl<K: Ord, V> Default for ClassicRBTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> ClassicRBTree<K, V> {
+    pub fn new() -> Self {
+        ClassicRBTree {
+            links: vec![Links::EMPTY],
+            entries: vec![None],
+            generations: vec![0],
+            free: Vec::new(),
+            root: NIL,
+            len: 0,
+            rotations: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 累计旋转次数
+    pub fn rotations(&self) -> u64 {
+        self.rotations
+    }
+
+    /// 树高 (空树为 0)
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack = Vec::new();
+        if self.root != NIL {
+            stack.push((self.root, 1));
+        }
+        while let Some((x, depth)) = stack.pop() {
+            height = height.max(depth);
+            for child in [self.links[x].left, self.links[x].right] {
+                if child != NIL {
+                    stack.push((child, depth + 1));
+                }
+            }
+        }
+        height
+    }
+
+    fn key_of(&self, x: usize) -> &K {
+        &self.entries[x].as_ref().unwrap().0
+    }
+
+    fn id(&self, index: usize) -> NodeId {
+        NodeId {
+            index,
+            generation: self.generations[index],
+        }
+    }
+
+    /// 句柄仍指向存活节点时返回其下标
+    fn index_of(&self, id: NodeId) -> Option<usize> {
+        let live = id.index != NIL
+            && id.index < self.entries.len()
+            && self.generations[id.index] == id.generation
+            && self.entries[id.index].is_some();
+        live.then_some(id.index)
+    }
+
+    /// 查找
+    pub fn find(&self, key: &K) -> Option<&V> {
+        self.find_id(key).and_then(|id| self.value(id))
+    }
+
+    /// 查找 key 对应的句柄
+    pub fn find_id(&self, key: &K) -> Option<NodeId> {
+        let mut x = self.root;
+        while x != NIL {
+            match key.cmp(self.key_of(x)) {
+                Ordering::Less => x = self.links[x].left,
+                Ordering::Greater => x = self.links[x].right,
+                Ordering::Equal => return Some(self.id(x)),
+            }
+        }
+        None
+    }
+
+    /// 第一个 key >= 给定 key 的节点
+    pub fn lower_bound(&self, key: &K) -> Option<NodeId> {
+        let mut x = self.root;
+        let mut candidate = NIL;
+        while x != NIL {
+            if self.key_of(x) >= key {
+                candidate = x;
+                x = self.links[x].left;
+            } else {
+                x = self.links[x].right;
+            }
+        }
+        (candidate != NIL).then(|| self.id(candidate))
+    }
+
+    pub fn key(&self, id: NodeId) -> Option<&K> {
+        self.get_at(id).map(|(k, _)| k)
+    }
+
+    pub fn value(&self, id: NodeId) -> Option<&V> {
+        self.get_at(id).map(|(_, v)| v)
+    }
+
+    pub fn value_mut(&mut self, id: NodeId) -> Option<&mut V> {
+        let x = self.index_of(id)?;
+        self.entries[x].as_mut().map(|(_, v)| v)
+    }
+
+    /// 句柄对应的键值, 句柄失效时返回 None
+    pub fn get_at(&self, id: NodeId) -> Option<(&K, &V)> {
+        let x = self.index_of(id)?;
+        self.entries[x].as_ref().map(|(k, v)| (k, v))
+    }
+
+    /// 最小节点 (卖盘最优价)
+    pub fn first(&self) -> Option<NodeId> {
+        (self.root != NIL).then(|| self.id(self.minimum(self.root)))
+    }
+
+    /// 最大节点 (买盘最优价)
+    pub fn last(&self) -> Option<NodeId> {
+        (self.root != NIL).then(|| self.id(self.maximum(self.root)))
+    }
+
+    /// 中序后继, 完整遍历时均摊 O(1)
+    pub fn successor(&self, id: NodeId) -> Option<NodeId> {
+        let mut x = self.index_of(id)?;
+        if self.links[x].right != NIL {
+            return Some(self.id(self.minimum(self.links[x].right)));
+        }
+        let mut y = self.links[x].parent;
+        while y != NIL && x == self.links[y].right {
+            x = y;
+            y = self.links[y].parent;
+        }
+        (y != NIL).then(|| self.id(y))
+    }
+
+    /// 中序前驱
+    pub fn predecessor(&self, id: NodeId) -> Option<NodeId> {
+        let mut x = self.index_of(id)?;
+        if self.links[x].left != NIL {
+            return Some(self.id(self.maximum(self.links[x].left)));
+        }
+        let mut y = self.links[x].parent;
+        while y != NIL && x == self.links[y].left {
+            x = y;
+            y = self.links[y].parent;
+        }
+        (y != NIL).then(|| self.id(y))
+    }
+
+    fn minimum(&self, mut x: usize) -> usize {
+        while self.links[x].left != NIL {
+            x = self.links[x].left;
+        }
+        x
+    }
+
+    fn maximum(&self, mut x: usize) -> usize {
+        while self.links[x].right != NIL {
+            x = self.links[x].right;
+        }
+        x
+    }
+
+    /// 按 key 升序遍历
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            tree: self,
+            next: self.first(),
+        }
+    }
+
+    /// 区间查询 [start..end], 从 lower_bound 沿后继遍历
+    pub fn range_query(&self, start: &K, end: &K) -> Vec<&V> {
+        let mut result = Vec::new();
+        let mut cur = self.lower_bound(start);
+        while let Some(id) = cur {
+            let (k, v) = self.get_at(id).unwrap();
+            if k > end {
+                break;
+            }
+            result.push(v);
+            cur = self.successor(id);
+        }
+        result
+    }
+
+    fn alloc(&mut self, key: K, value: V, parent: usize) -> usize {
+        let links = Links {
+            parent,
+            left: NIL,
+            right: NIL,
+            color: Color::Red,
+        };
+        match self.free.pop() {
+            Some(x) => {
+                self.links[x] = links;
+                self.entries[x] = Some((key, value));
+                x
+            }
+            None => {
+                self.links.push(links);
+                self.entries.push(Some((key, value)));
+                self.generations.push(0);
+                self.links.len() - 1
+            }
+        }
+    }
+
+    /// 插入, key 已存在时更新 value; 返回该 key 的句柄
+    pub fn insert(&mut self, key: K, value: V) -> NodeId {
+        let mut y = NIL;
+        let mut x = self.root;
+        let mut go_left = false;
+        while x != NIL {
+            y = x;
+            match key.cmp(self.key_of(x)) {
+                Ordering::Less => {
+                    go_left = true;
+                    x = self.links[x].left;
+                }
+                Ordering::Greater => {
+                    go_left = false;
+                    x = self.links[x].right;
+                }
+                Ordering::Equal => {
+                    self.entries[x].as_mut().unwrap().1 = value;
+                    return self.id(x);
+                }
+            }
+        }
+
+        let z = self.alloc(key, value, y);
+        if y == NIL {
+            self.root = z;
+        } else if go_left {
+            self.links[y].left = z;
+        } else {
+            self.links[y].right = z;
+        }
+        self.len += 1;
+        self.insert_fixup(z);
+        self.id(z)
+    }
+
+    fn insert_fixup(&mut self, mut z: usize) {
+        while self.links[self.links[z].parent].color == Color::Red {
+            let p = self.links[z].parent;
+            let g = self.links[p].parent;
+            if p == self.links[g].left {
+                let u = self.links[g].right;
+                if self.links[u].color == Color::Red {
+                    // 情况 1: 叔节点为红, 变色后上移
+                    self.links[p].color = Color::Black;
+                    self.links[u].color = Color::Black;
+                    self.links[g].color = Color::Red;
+                    z = g;
+                } else {
+                    if z == self.links[p].right {
+                        // 情况 2: 转为情况 3
+                        z = p;
+                        self.rotate_left(z);
+                    }
+                    // 情况 3
+                    let p = self.links[z].parent;
+                    let g = self.links[p].parent;
+                    self.links[p].color = Color::Black;
+                    self.links[g].color = Color::Red;
+                    self.rotate_right(g);
+                }
+            } else {
+                let u = self.links[g].left;
+                if self.links[u].color == Color::Red {
+                    self.links[p].color = Color::Black;
+                    self.links[u].color = Color::Black;
+                    self.links[g].color = Color::Red;
+                    z = g;
+                } else {
+                    if z == self.links[p].left {
+                        z = p;
+                        self.rotate_right(z);
+                    }
+                    let p = self.links[z].parent;
+                    let g = self.links[p].parent;
+                    self.links[p].color = Color::Black;
+                    self.links[g].color = Color::Red;
+                    self.rotate_left(g);
+                }
+            }
+        }
+        let root = self.root;
+        self.links[root].color = Color::Black;
+    }
+
+    /// 删除
+    pub fn delete(&mut self, key: &K) {
+        if let Some(id) = self.find_id(key) {
+            self.remove_at(id);
+        }
+    }
+
+    /// 按句柄删除, 无需从根重新查找; 句柄失效时返回 None
+    pub fn remove_at(&mut self, id: NodeId) -> Option<(K, V)> {
+        let z = self.index_of(id)?;
+        let mut y = z;
+        let mut y_color = self.links[y].color;
+        let x;
+        if self.links[z].left == NIL {
+            x = self.links[z].right;
+            self.transplant(z, x);
+        } else if self.links[z].right == NIL {
+            x = self.links[z].left;
+            self.transplant(z, x);
+        } else {
+            // 用后继节点 y 顶替 z 的位置
+            y = self.minimum(self.links[z].right);
+            y_color = self.links[y].color;
+            x = self.links[y].right;
+            if self.links[y].parent == z {
+                // x 可能是哨兵, 修正时需要它的父节点
+                self.links[x].parent = y;
+            } else {
+                self.transplant(y, x);
+                self.links[y].right = self.links[z].right;
+                let r = self.links[y].right;
+                self.links[r].parent = y;
+            }
+            self.transplant(z, y);
+            self.links[y].left = self.links[z].left;
+            let l = self.links[y].left;
+            self.links[l].parent = y;
+            self.links[y].color = self.links[z].color;
+        }
+        if y_color == Color::Black {
+            self.delete_fixup(x);
+        }
+        // 哨兵的链接在修正过程中可能被改写, 复位
+        self.links[NIL] = Links::EMPTY;
+
+        self.len -= 1;
+        self.links[z] = Links::EMPTY;
+        self.generations[z] = self.generations[z].wrapping_add(1);
+        self.free.push(z);
+        self.entries[z].take()
+    }
+
+    fn delete_fixup(&mut self, mut x: usize) {
+        while x != self.root && self.links[x].color == Color::Black {
+            let p = self.links[x].parent;
+            if x == self.links[p].left {
+                let mut w = self.links[p].right;
+                if self.links[w].color == Color::Red {
+                    // 情况 1: 兄弟为红
+                    self.links[w].color = Color::Black;
+                    self.links[p].color = Color::Red;
+                    self.rotate_left(p);
+                    w = self.links[p].right;
+                }
+                let (wl, wr) = (self.links[w].left, self.links[w].right);
+                if self.links[wl].color == Color::Black && self.links[wr].color == Color::Black {
+                    // 情况 2: 兄弟的两个子节点都为黑
+                    self.links[w].color = Color::Red;
+                    x = p;
+                } else {
+                    if self.links[wr].color == Color::Black {
+                        // 情况 3: 转为情况 4
+                        self.links[wl].color = Color::Black;
+                        self.links[w].color = Color::Red;
+                        self.rotate_right(w);
+                        w = self.links[p].right;
+                    }
+                    // 情况 4
+                    self.links[w].color = self.links[p].color;
+                    self.links[p].color = Color::Black;
+                    let wr = self.links[w].right;
+                    self.links[wr].color = Color::Black;
+                    self.rotate_left(p);
+                    x = self.root;
+                }
+            } else {
+                let mut w = self.links[p].left;
+                if self.links[w].color == Color::Red {
+                    self.links[w].color = Color::Black;
+                    self.links[p].color = Color::Red;
+                    self.rotate_right(p);
+                    w = self.links[p].left;
+                }
+                let (wl, wr) = (self.links[w].left, self.links[w].right);
+                if self.links[wl].color == Color::Black && self.links[wr].color == Color::Black {
+                    self.links[w].color = Color::Red;
+                    x = p;
+                } else {
+                    if self.links[wl].color == Color::Black {
+                        self.links[wr].color = Color::Black;
+                        self.links[w].color = Color::Red;
+                        self.rotate_left(w);
+                        w = self.links[p].left;
+                    }
+                    self.links[w].color = self.links[p].color;
+                    self.links[p].color = Color::Black;
+                    let wl = self.links[w].left;
+                    self.links[wl].color = Color::Black;
+                    self.rotate_right(p);
+                    x = self.root;
+                }
+            }
+        }
+        self.links[x].color = Color::Black;
+    }
+
+    /// 用以 v 为根的子树替换以 u 为根的子树
+    fn transplant(&mut self, u: usize, v: usize) {
+        let p = self.links[u].parent;
+        if p == NIL {
+            self.root = v;
+        } else if u == self.links[p].left {
+            self.links[p].left = v;
+        } else {
+            self.links[p].right = v;
+        }
+        self.links[v].parent = p;
+    }
+
+    fn rotate_left(&mut self, x: usize) {
+        self.rotations += 1;
+        let y = self.links[x].right;
+        let yl = self.links[y].left;
+        self.links[x].right = yl;
+        if yl != NIL {
+            self.links[yl].parent = x;
+        }
+        self.replace_child(x, y);
+        self.links[y].left = x;
+        self.links[x].parent = y;
+    }
+
+    fn rotate_right(&mut self, x: usize) {
+        self.rotations += 1;
+        let y = self.links[x].left;
+        let yr = self.links[y].right;
+        self.links[x].left = yr;
+        if yr != NIL {
+            self.links[yr].parent = x;
+        }
+        self.replace_child(x, y);
+        self.links[y].right = x;
+        self.links[x].parent = y;
+    }
+
+    /// 旋转时让 x 的父节点改为指向 y
+    fn replace_child(&mut self, x: usize, y: usize) {
+        let p = self.links[x].parent;
+        self.links[y].parent = p;
+        if p == NIL {
+            self.root = y;
+        } else if x == self.links[p].left {
+            self.links[p].left = y;
+        } else {
+            self.links[p].right = y;
+        }
+    }
+
+    /// 校验红黑性质与父链接, 返回黑高
+    pub fn validate(&self) -> Result<usize, String> {
+        if self.links[self.root].color != Color::Black {
+            return Err("root is red".to_string());
+        }
+        if self.root != NIL && self.links[self.root].parent != NIL {
+            return Err("root has a parent".to_string());
+        }
+        let mut count = 0;
+        let black_height = self.validate_node(self.root, &mut count)?;
+        if count != self.len {
+            return Err(format!("reachable nodes {} != len {}", count, self.len));
+        }
+        Ok(black_height)
+    }
+
+    fn validate_node(&self, x: usize, count: &mut usize) -> Result<usize, String> {
+        if x == NIL {
+            return Ok(1);
+        }
+        *count += 1;
+        let Links { left, right, color, .. } = self.links[x];
+        for child in [left, right] {
+            if child != NIL && self.links[child].parent != x {
+                return Err(format!("bad parent link at node {}", child));
+            }
+            if color == Color::Red && self.links[child].color == Color::Red {
+                return Err(format!("red node {} has a red child", x));
+            }
+        }
+        if left != NIL && self.key_of(left) >= self.key_of(x) {
+            return Err(format!("left child of {} out of order", x));
+        }
+        if right != NIL && self.key_of(right) <= self.key_of(x) {
+            return Err(format!("right child of {} out of order", x));
+        }
+        let lh = self.validate_node(left, count)?;
+        let rh = self.validate_node(right, count)?;
+        if lh != rh {
+            return Err(format!("black height mismatch at node {}", x));
+        }
+        Ok(lh + (color == Color::Black) as usize)
+    }
+
+    /// 内存占用: 链接 / 键值 / 代数三个 arena, 空闲槽位计为浪费
+    pub fn memory_usage(&self) -> MemoryReport {
+        let slot = size_of::<Links>() + size_of::<Option<(K, V)>>() + size_of::<u32>();
+        let payload = size_of::<K>() + size_of::<V>();
+        let capacity = self.links.capacity().min(self.entries.capacity()).min(self.generations.capacity());
+        let report = MemoryReport {
+            node_count: self.len,
+            entry_count: self.len,
+            key_bytes: self.len * size_of::<K>(),
+            value_bytes: self.len * size_of::<V>(),
+            // 空闲槽位 + 预留容量 (含哨兵)
+            wasted_capacity_bytes: (capacity - self.len) * slot,
+            pointer_overhead_bytes: self.len * (slot - payload) + self.free.capacity() * size_of::<usize>(),
+            duplicated_bytes: 0,
+            total_bytes: 0,
+            fill_factor: if self.links.len() > 1 { self.len as f64 / (self.links.len() - 1) as f64 } else { 0.0 },
+            height: self.height(),
+        };
+        report.finish()
+    }
+}
+
+/// 中序迭代器, 逐个沿后继前进
+pub struct Iter<'a, K, V> {
+    tree: &'a ClassicRBTree<K, V>,
+    next: Option<NodeId>,
+}
+
+impl<'a, K: Ord, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next?;
+        self.next = self.tree.successor(id);
+        self.tree.get_at(id)
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+    use crate::classic_rb_tree::classic_rb_tree::ClassicRBTree;
+
+    #[test]
+    fn test_classic_rb_tree_handles() {
+        let mut tree = ClassicRBTree::new();
+        let ten = tree.insert(10, "ten");
+        let five = tree.insert(5, "five");
+        let fifteen = tree.insert(15, "fifteen");
+        assert_eq!(tree.insert(10, "TEN"), ten);
+
+        assert_eq!(tree.find(&10), Some(&"TEN"));
+        assert_eq!(tree.find(&999), None);
+        assert_eq!(tree.first(), Some(five));
+        assert_eq!(tree.last(), Some(fifteen));
+        assert_eq!(tree.successor(five), Some(ten));
+        assert_eq!(tree.predecessor(ten), Some(five));
+        assert_eq!(tree.successor(fifteen), None);
+        assert_eq!(tree.lower_bound(&11), Some(fifteen));
+        assert_eq!(tree.range_query(&6, &15), vec![&"TEN", &"fifteen"]);
+
+        assert_eq!(tree.remove_at(ten), Some((10, "TEN")));
+        // 被删除节点的句柄失效, 即使槽位被复用
+        assert_eq!(tree.remove_at(ten), None);
+        let twenty = tree.insert(20, "twenty");
+        assert_eq!(tree.get_at(ten), None);
+        assert_eq!(tree.get_at(twenty), Some((&20, &"twenty")));
+        assert_eq!(tree.successor(five), Some(fifteen));
+        assert_eq!(tree.len(), 3);
+        tree.validate().unwrap();
+    }
+
+    #[test]
+    fn test_classic_rb_tree_matches_std() {
+        let mut tree = ClassicRBTree::new();
+        let mut reference = std::collections::BTreeMap::new();
+        let mut handles = std::collections::HashMap::new();
+        for i in 0..20_000u32 {
+            let k = (i * 7919) % 5000;
+            if i % 3 == 0 {
+                // 交替使用按 key 删除与按句柄删除
+                match handles.remove(&k) {
+                    Some(id) if i % 2 == 0 => assert_eq!(tree.remove_at(id).map(|(k, _)| k), Some(k)),
+                    _ => tree.delete(&k),
+                }
+                reference.remove(&k);
+            } else {
+                handles.insert(k, tree.insert(k, i));
+                reference.insert(k, i);
+            }
+            if i % 1000 == 0 {
+                tree.validate().unwrap();
+            }
+        }
+
+        tree.validate().unwrap();
+        // 其他节点的删除不影响存活句柄
+        for (k, id) in &handles {
+            assert_eq!(tree.key(*id), Some(k));
+        }
+        let got: Vec<_> = tree.iter().map(|(k, v)| (*k, *v)).collect();
+        let want: Vec<_> = reference.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(got, want);
+        assert_eq!(tree.range_query(&100, &200).len(), reference.range(100..=200).count());
+        assert!(tree.height() <= 2 * ((tree.len() + 1) as f64).log2().ceil() as usize);
+    }
+}
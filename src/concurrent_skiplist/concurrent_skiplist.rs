@@ -0,0 +1,348 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned, Shared};
+
+/// 最大层数, 与单线程 `SkipList` 一致
+pub const MAX_LEVEL: usize = 16;
+
+/// 跳表节点; `tower[i]` 为第 i 层后继, 指针 tag = 1 表示本节点在该层已被逻辑删除
+struct Node<K, V> {
+    key: K,
+    /// 覆盖写时整体替换, 旧值交给 epoch 回收
+    value: Atomic<V>,
+    /// 仍链接在表中的层数 + 插入线程持有的 1; 归零时回收节点
+    refs: AtomicUsize,
+    tower: Box<[Atomic<Node<K, V>>]>,
+}
+
+impl<K, V> Drop for Node<K, V> {
+    fn drop(&mut self) {
+        // 节点被回收时已没有任何线程能看到它
+        unsafe {
+            let value = self.value.load(Ordering::Relaxed, epoch::unprotected());
+            if !value.is_null() {
+                drop(value.into_owned());
+            }
+        }
+    }
+}
+
+/// 无锁并发跳表 (Herlihy-Shavit 算法, CAS + 指针标记)
+///
+/// 删除先自顶向下标记各层后继指针, 第 0 层标记成功者即为删除者; 之后的任何遍历遇到
+/// 已标记节点都会顺手把它从该层摘除. 节点在所有层都被摘除后经 crossbeam-epoch 延迟释放.
+///
+/// 接口与单线程结构一致, 但全部方法只需 `&self`, 可在线程间共享;
+/// `get`/`range_query` 返回值的拷贝, `len` 与区间查询在并发写入下只保证弱一致.
+pub struct ConcurrentSkipList<K, V> {
+    head: Box<[Atomic<Node<K, V>>]>,
+    len: AtomicUsize,
+    /// 随机层数的种子 (splitmix64 状态)
+    seed: AtomicU64,
+}
+
+/// 一次查找得到的各层前驱 (所在的 tower) 与后继
+struct Position<'g, K, V> {
+    preds: [&'g [Atomic<Node<K, V>>]; MAX_LEVEL],
+    succs: [Shared<'g, Node<K, V>>; MAX_LEVEL],
+}
+
+impl<K, V> ConcurrentSkipList<K, V>
+where
+    K: Ord + Clone + Send + Sync,
+    V: Clone + Send + Sync,
+{
+    pub fn new(seed: u64) -> Self {
+        ConcurrentSkipList {
+            head: (0..MAX_LEVEL).map(|_| Atomic::null()).collect(),
+            len: AtomicUsize::new(0),
+            seed: AtomicU64::new(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 随机层数: 每层以 1/4 的概率继续升高
+    fn random_level(&self) -> usize {
+        let mut z = self
+            .seed
+            .fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed)
+            .wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (1 + z.trailing_zeros() as usize / 2).min(MAX_LEVEL)
+    }
+
+    /// 定位 key 在每一层的前驱与后继, 途中摘除已标记的节点; 返回第 0 层后继是否等于 key
+    fn find<'g>(&'g self, key: &K, pos: &mut Position<'g, K, V>, guard: &'g Guard) -> bool {
+        'retry: loop {
+            let mut pred: &'g [Atomic<Node<K, V>>] = &self.head;
+            for level in (0..MAX_LEVEL).rev() {
+                let mut curr = pred[level].load(Ordering::Acquire, guard);
+                // 前驱本身在这一层被删除了, 从头再来
+                if curr.tag() == 1 {
+                    continue 'retry;
+                }
+                while let Some(c) = unsafe { curr.as_ref() } {
+                    let succ = c.tower[level].load(Ordering::Acquire, guard);
+                    if succ.tag() == 1 {
+                        match pred[level].compare_exchange(
+                            curr,
+                            succ.with_tag(0),
+                            Ordering::AcqRel,
+                            Ordering::Acquire,
+                            guard,
+                        ) {
+                            Ok(_) => {
+                                unsafe { Self::release(curr, guard) };
+                                curr = succ.with_tag(0);
+                                continue;
+                            }
+                            Err(_) => continue 'retry,
+                        }
+                    }
+                    if c.key < *key {
+                        pred = &c.tower;
+                        curr = succ;
+                    } else {
+                        break;
+                    }
+                }
+                pos.preds[level] = pred;
+                pos.succs[level] = curr;
+            }
+            return unsafe { pos.succs[0].as_ref() }.is_some_and(|c| c.key == *key);
+        }
+    }
+
+    /// 节点引用计数减一, 归零后延迟释放
+    unsafe fn release(node: Shared<'_, Node<K, V>>, guard: &Guard) {
+        if node.deref().refs.fetch_sub(1, Ordering::AcqRel) == 1 {
+            guard.defer_destroy(node);
+        }
+    }
+
+    fn position<'g>(&self) -> Position<'g, K, V> {
+        Position {
+            preds: [&[]; MAX_LEVEL],
+            succs: [Shared::null(); MAX_LEVEL],
+        }
+    }
+
+    /// 插入或覆盖; 返回是否新增了条目
+    pub fn insert(&self, key: K, value: V) -> bool {
+        let guard = &epoch::pin();
+        let mut pos = self.position();
+        let height = self.random_level();
+        let mut value = Some(Owned::new(value));
+
+        // 1. 第 0 层链接成功即插入生效
+        let mut owned: Option<Owned<Node<K, V>>> = None;
+        let node = loop {
+            if self.find(&key, &mut pos, guard) {
+                // 值可能已经放进了上一轮没链接成功的节点里
+                let value = value.take().unwrap_or_else(|| unsafe {
+                    let n = owned.as_ref().unwrap();
+                    n.value.swap(Shared::null(), Ordering::Relaxed, guard).into_owned()
+                });
+                let existing = unsafe { pos.succs[0].deref() };
+                let old = existing.value.swap(value, Ordering::AcqRel, guard);
+                unsafe { guard.defer_destroy(old) };
+                return false;
+            }
+            let new = owned.take().unwrap_or_else(|| {
+                Owned::new(Node {
+                    key: key.clone(),
+                    value: Atomic::from(value.take().unwrap()),
+                    // 插入线程持有 1 + 第 0 层链接 1
+                    refs: AtomicUsize::new(2),
+                    tower: (0..height).map(|_| Atomic::null()).collect(),
+                })
+            });
+            new.tower[0].store(pos.succs[0], Ordering::Relaxed);
+            match pos.preds[0][0].compare_exchange(
+                pos.succs[0],
+                new,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+                guard,
+            ) {
+                Ok(node) => break node,
+                Err(e) => owned = Some(e.new),
+            }
+        };
+        let n = unsafe { node.deref() };
+        self.len.fetch_add(1, Ordering::Relaxed);
+
+        // 2. 逐层向上链接; 中途被删除就停下
+        'levels: for level in 1..height {
+            loop {
+                let next = n.tower[level].load(Ordering::Acquire, guard);
+                if next.tag() == 1 {
+                    break 'levels;
+                }
+                if next != pos.succs[level]
+                    && n.tower[level]
+                        .compare_exchange(next, pos.succs[level], Ordering::AcqRel, Ordering::Acquire, guard)
+                        .is_err()
+                {
+                    break 'levels;
+                }
+                n.refs.fetch_add(1, Ordering::AcqRel);
+                if pos.preds[level][level]
+                    .compare_exchange(pos.succs[level], node, Ordering::AcqRel, Ordering::Acquire, guard)
+                    .is_ok()
+                {
+                    break;
+                }
+                n.refs.fetch_sub(1, Ordering::AcqRel);
+                // 重新定位; 节点已不在第 0 层说明被删除了
+                if !self.find(&key, &mut pos, guard) || pos.succs[0] != node {
+                    break 'levels;
+                }
+            }
+        }
+        // 链接期间被删除的话, 把可能刚挂上的高层摘掉
+        if n.tower[0].load(Ordering::Acquire, guard).tag() == 1 {
+            self.find(&key, &mut pos, guard);
+        }
+        unsafe { Self::release(node, guard) };
+        true
+    }
+
+    /// 删除; 并发删除同一个 key 时只有一个线程拿到旧值
+    pub fn delete(&self, key: &K) -> Option<V> {
+        let guard = &epoch::pin();
+        let mut pos = self.position();
+        if !self.find(key, &mut pos, guard) {
+            return None;
+        }
+        let n = unsafe { pos.succs[0].deref() };
+        // 自顶向下标记, 高层标记后插入线程不会再往上链接
+        for level in (1..n.tower.len()).rev() {
+            let mut next = n.tower[level].load(Ordering::Acquire, guard);
+            while next.tag() == 0 {
+                match n.tower[level].compare_exchange(
+                    next,
+                    next.with_tag(1),
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                    guard,
+                ) {
+                    Ok(_) => break,
+                    Err(e) => next = e.current,
+                }
+            }
+        }
+        // 第 0 层标记是删除的线性化点
+        let mut next = n.tower[0].load(Ordering::Acquire, guard);
+        loop {
+            if next.tag() == 1 {
+                return None;
+            }
+            match n.tower[0].compare_exchange(next, next.with_tag(1), Ordering::AcqRel, Ordering::Acquire, guard) {
+                Ok(_) => break,
+                Err(e) => next = e.current,
+            }
+        }
+        self.len.fetch_sub(1, Ordering::Relaxed);
+        let value = unsafe { n.value.load(Ordering::Acquire, guard).deref() }.clone();
+        // 物理摘除
+        self.find(key, &mut pos, guard);
+        Some(value)
+    }
+
+    /// 查找 (只读, 不摘除已标记节点)
+    pub fn get(&self, key: &K) -> Option<V> {
+        let guard = &epoch::pin();
+        let node = self.lower_bound(key, guard)?;
+        (node.key == *key).then(|| unsafe { node.value.load(Ordering::Acquire, guard).deref() }.clone())
+    }
+
+    /// [start, end] 闭区间, 按键升序; 与并发写入之间只保证弱一致
+    pub fn range_query(&self, start: &K, end: &K) -> Vec<V> {
+        let guard = &epoch::pin();
+        let mut out = Vec::new();
+        let mut curr = self.lower_bound(start, guard);
+        while let Some(node) = curr {
+            if node.key > *end {
+                break;
+            }
+            out.push(unsafe { node.value.load(Ordering::Acquire, guard).deref() }.clone());
+            curr = Self::next_live(node.tower[0].load(Ordering::Acquire, guard), guard);
+        }
+        out
+    }
+
+    /// 最小的键值对
+    pub fn first(&self) -> Option<(K, V)> {
+        let guard = &epoch::pin();
+        let node = Self::next_live(self.head[0].load(Ordering::Acquire, guard), guard)?;
+        let value = unsafe { node.value.load(Ordering::Acquire, guard).deref() }.clone();
+        Some((node.key.clone(), value))
+    }
+
+    /// 第 0 层上第一个 key >= key 的未删除节点
+    fn lower_bound<'g>(&'g self, key: &K, guard: &'g Guard) -> Option<&'g Node<K, V>> {
+        let mut pred: &'g [Atomic<Node<K, V>>] = &self.head;
+        let mut curr = Shared::null();
+        for level in (0..MAX_LEVEL).rev() {
+            curr = pred[level].load(Ordering::Acquire, guard).with_tag(0);
+            while let Some(c) = unsafe { curr.as_ref() } {
+                let succ = c.tower[level].load(Ordering::Acquire, guard);
+                // 跳过已删除的节点
+                if succ.tag() == 1 {
+                    curr = succ.with_tag(0);
+                    continue;
+                }
+                if c.key < *key {
+                    pred = &c.tower;
+                    curr = succ;
+                } else {
+                    break;
+                }
+            }
+        }
+        unsafe { curr.as_ref() }
+    }
+
+    /// 从 curr 起第 0 层上第一个未删除的节点
+    fn next_live<'g>(mut curr: Shared<'g, Node<K, V>>, guard: &'g Guard) -> Option<&'g Node<K, V>> {
+        while let Some(c) = unsafe { curr.with_tag(0).as_ref() } {
+            let next = c.tower[0].load(Ordering::Acquire, guard);
+            if next.tag() == 0 {
+                return Some(c);
+            }
+            curr = next;
+        }
+        None
+    }
+}
+
+impl<K, V> Drop for ConcurrentSkipList<K, V> {
+    fn drop(&mut self) {
+        // &mut self 保证没有并发访问; 仍链接在任意一层的节点都还没被回收, 去重后释放
+        unsafe {
+            let guard = epoch::unprotected();
+            let mut nodes = HashSet::new();
+            for level in 0..MAX_LEVEL {
+                let mut curr = self.head[level].load(Ordering::Relaxed, guard);
+                while let Some(c) = curr.with_tag(0).as_ref() {
+                    nodes.insert(curr.with_tag(0).as_raw() as usize);
+                    curr = c.tower[level].load(Ordering::Relaxed, guard);
+                }
+            }
+            for ptr in nodes {
+                drop(Box::from_raw(ptr as *mut Node<K, V>));
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod concurrent_skiplist;
+#[allow(clippy::module_inception)]
+pub mod tests;
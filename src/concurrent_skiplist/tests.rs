@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+    use crate::concurrent_skiplist::concurrent_skiplist::ConcurrentSkipList;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_concurrent_skiplist_matches_reference() {
+        let list = ConcurrentSkipList::new(7);
+        let mut reference = std::collections::BTreeMap::new();
+        for i in 0..20_000u32 {
+            let k = (i * 7919) % 3_000;
+            if i % 3 == 0 {
+                assert_eq!(list.delete(&k), reference.remove(&k));
+            } else {
+                assert_eq!(list.insert(k, i), reference.insert(k, i).is_none());
+            }
+        }
+
+        assert_eq!(list.len(), reference.len());
+        assert_eq!(list.first(), reference.iter().next().map(|(k, v)| (*k, *v)));
+        let want: Vec<u32> = reference.range(100..=2_000).map(|(_, v)| *v).collect();
+        assert_eq!(list.range_query(&100, &2_000), want);
+        for k in 0..3_000 {
+            assert_eq!(list.get(&k), reference.get(&k).copied());
+        }
+    }
+
+    #[test]
+    fn test_concurrent_skiplist_threads() {
+        let list = ConcurrentSkipList::new(1);
+        let deleted = AtomicUsize::new(0);
+        std::thread::scope(|s| {
+            // 每个线程插入自己的 key 段, 再删掉其中的偶数
+            for t in 0..4u32 {
+                let list = &list;
+                s.spawn(move || {
+                    for k in (t * 10_000)..(t * 10_000 + 5_000) {
+                        assert!(list.insert(k, k));
+                    }
+                    for k in ((t * 10_000)..(t * 10_000 + 5_000)).step_by(2) {
+                        assert_eq!(list.delete(&k), Some(k));
+                    }
+                });
+            }
+            // 另外几个线程争抢同一批 key, 每个 key 只能被删一次
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for k in 100_000..101_000u32 {
+                        list.insert(k, k);
+                        if list.delete(&k).is_some() {
+                            deleted.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+
+        let inserted_contended = (100_000..101_000u32).filter(|k| list.get(k).is_some()).count();
+        assert!(deleted.load(Ordering::Relaxed) >= 1_000);
+        assert_eq!(list.len(), 4 * 2_500 + inserted_contended);
+        let all = list.range_query(&0, &99_999);
+        assert_eq!(all.len(), 10_000);
+        assert!(all.iter().all(|k| k % 2 == 1));
+        assert!(all.windows(2).all(|w| w[0] < w[1]));
+    }
+}
@@ -0,0 +1,372 @@
+use std::marker::PhantomData;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+use crossbeam_epoch::{self as epoch, Atomic, Guard, Owned};
+
+/// 不可变节点; 子节点用 `Arc` 在多个版本之间共享
+///
+/// 叶子节点 `children` 为空, 内部节点 `vals` 为空且 `children.len() = keys.len() + 1`
+#[derive(Clone, Debug)]
+pub struct Node<K, V> {
+    pub keys: Vec<K>,
+    pub vals: Vec<V>,
+    pub children: Vec<Arc<Node<K, V>>>,
+}
+
+impl<K, V> Node<K, V> {
+    fn leaf() -> Self {
+        Node {
+            keys: Vec::new(),
+            vals: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+/// 一次写入发布的版本: 根节点 + 条目数 + 版本号
+struct Version<K, V> {
+    root: Arc<Node<K, V>>,
+    len: usize,
+    seq: u64,
+}
+
+/// 插入后子节点的结果: 未分裂 or 分裂为 (左, 分隔键, 右)
+enum Inserted<K, V> {
+    One(Node<K, V>),
+    Split(Node<K, V>, K, Node<K, V>),
+}
+
+/// 写时复制 (路径复制) 的 B+Tree
+///
+/// 写者复制根到叶子的一条路径, 其余子树与旧版本共享, 然后原子地发布新根;
+/// 读者 `snapshot()` 只需 pin 一个 epoch, 不加锁也不改引用计数, 看到的是某个完整版本.
+/// 旧版本交给 crossbeam-epoch 延迟回收, 等所有可能持有它的读者离开后才释放.
+///
+/// 路径复制无法维护叶子兄弟指针, 区间查询走自顶向下的剪枝遍历.
+pub struct CowBPTree<K, V> {
+    current: Atomic<Version<K, V>>,
+    /// 写者之间互斥; 读者不碰这把锁
+    writer: Mutex<()>,
+    min_degree: usize,
+}
+
+impl<K, V> CowBPTree<K, V>
+where
+    K: Ord + Clone + Send + Sync,
+    V: Clone + Send + Sync,
+{
+    pub fn new(min_degree: usize) -> Self {
+        assert!(min_degree >= 2, "B+Tree min_degree must >= 2");
+        CowBPTree {
+            current: Atomic::new(Version {
+                root: Arc::new(Node::leaf()),
+                len: 0,
+                seq: 0,
+            }),
+            writer: Mutex::new(()),
+            min_degree,
+        }
+    }
+
+    pub fn min_degree(&self) -> usize {
+        self.min_degree
+    }
+
+    /// 获取当前版本的一致快照 (无锁)
+    pub fn snapshot(&self) -> Snapshot<'_, K, V> {
+        let guard = epoch::pin();
+        let version = self.current.load(Ordering::Acquire, &guard).as_raw();
+        Snapshot {
+            guard,
+            version,
+            _tree: PhantomData,
+        }
+    }
+
+    /// 插入或覆盖; 返回新发布的版本号
+    pub fn insert(&self, key: K, value: V) -> u64 {
+        let _w = self.writer.lock().unwrap();
+        let guard = epoch::pin();
+        // 持有写锁, 当前版本不会被其他写者替换
+        let cur = unsafe { self.current.load(Ordering::Acquire, &guard).deref() };
+
+        let t = self.min_degree;
+        let (inserted, added) = Self::insert_rec(&cur.root, key, value, t);
+        let root = match inserted {
+            Inserted::One(node) => node,
+            Inserted::Split(left, sep, right) => Node {
+                keys: vec![sep],
+                vals: Vec::new(),
+                children: vec![Arc::new(left), Arc::new(right)],
+            },
+        };
+        let len = cur.len + added as usize;
+        self.publish(Arc::new(root), len, cur.seq + 1, &guard)
+    }
+
+    /// 删除; key 不存在时不发布新版本, 返回 None
+    pub fn delete(&self, key: &K) -> Option<V> {
+        let _w = self.writer.lock().unwrap();
+        let guard = epoch::pin();
+        let cur = unsafe { self.current.load(Ordering::Acquire, &guard).deref() };
+
+        let (mut root, value) = Self::delete_rec(&cur.root, key, self.min_degree)?;
+        // 根只剩一个孩子时树高减一
+        let root = if !root.is_leaf() && root.keys.is_empty() {
+            root.children.pop().unwrap()
+        } else {
+            Arc::new(root)
+        };
+        self.publish(root, cur.len - 1, cur.seq + 1, &guard);
+        Some(value)
+    }
+
+    /// 原子替换根, 旧版本延迟到所有读者离开后释放
+    fn publish(&self, root: Arc<Node<K, V>>, len: usize, seq: u64, guard: &Guard) -> u64 {
+        let old = self
+            .current
+            .swap(Owned::new(Version { root, len, seq }), Ordering::AcqRel, guard);
+        // old 已不可达, 只可能被 pin 住的读者引用
+        unsafe { guard.defer_destroy(old) };
+        seq
+    }
+
+    /// 复制从 node 到目标叶子的路径; 返回 (新节点, 是否新增了条目)
+    fn insert_rec(node: &Node<K, V>, key: K, value: V, t: usize) -> (Inserted<K, V>, bool) {
+        let max_keys = 2 * t - 1;
+        if node.is_leaf() {
+            let mut leaf = node.clone();
+            let added = match leaf.keys.binary_search(&key) {
+                Ok(i) => {
+                    leaf.vals[i] = value;
+                    false
+                }
+                Err(i) => {
+                    leaf.keys.insert(i, key);
+                    leaf.vals.insert(i, value);
+                    true
+                }
+            };
+            if leaf.keys.len() <= max_keys {
+                return (Inserted::One(leaf), added);
+            }
+            // 叶子分裂: 右半边的首键上提
+            let mid = leaf.keys.len() / 2;
+            let right = Node {
+                keys: leaf.keys.split_off(mid),
+                vals: leaf.vals.split_off(mid),
+                children: Vec::new(),
+            };
+            let sep = right.keys[0].clone();
+            return (Inserted::Split(leaf, sep, right), added);
+        }
+
+        let idx = Self::child_index(node, &key);
+        let (child, added) = Self::insert_rec(&node.children[idx], key, value, t);
+        // 只复制 keys 和子指针, 兄弟子树仍然共享
+        let mut inner = node.clone();
+        match child {
+            Inserted::One(c) => inner.children[idx] = Arc::new(c),
+            Inserted::Split(l, sep, r) => {
+                inner.children[idx] = Arc::new(l);
+                inner.keys.insert(idx, sep);
+                inner.children.insert(idx + 1, Arc::new(r));
+            }
+        }
+        if inner.keys.len() <= max_keys {
+            return (Inserted::One(inner), added);
+        }
+        // 内部节点分裂: 中间键上提, 不留在任何一侧
+        let mid = inner.keys.len() / 2;
+        let right = Node {
+            keys: inner.keys.split_off(mid + 1),
+            vals: Vec::new(),
+            children: inner.children.split_off(mid + 1),
+        };
+        let sep = inner.keys.pop().unwrap();
+        (Inserted::Split(inner, sep, right), added)
+    }
+
+    /// 复制删除路径; key 不存在返回 None (整棵树保持共享)
+    fn delete_rec(node: &Node<K, V>, key: &K, t: usize) -> Option<(Node<K, V>, V)> {
+        if node.is_leaf() {
+            let i = node.keys.binary_search(key).ok()?;
+            let mut leaf = node.clone();
+            leaf.keys.remove(i);
+            let value = leaf.vals.remove(i);
+            return Some((leaf, value));
+        }
+
+        let idx = Self::child_index(node, key);
+        let (child, value) = Self::delete_rec(&node.children[idx], key, t)?;
+        let mut inner = node.clone();
+        let underflow = child.keys.len() < t - 1;
+        inner.children[idx] = Arc::new(child);
+        if underflow {
+            Self::rebalance(&mut inner, idx, t);
+        }
+        Some((inner, value))
+    }
+
+    /// 修复下溢的 children[idx]: 先向左/右兄弟借, 借不到就合并
+    fn rebalance(parent: &mut Node<K, V>, idx: usize, t: usize) {
+        if idx > 0 && parent.children[idx - 1].keys.len() > t - 1 {
+            let mut left = parent.children[idx - 1].as_ref().clone();
+            let mut child = parent.children[idx].as_ref().clone();
+            if child.is_leaf() {
+                child.keys.insert(0, left.keys.pop().unwrap());
+                child.vals.insert(0, left.vals.pop().unwrap());
+                parent.keys[idx - 1] = child.keys[0].clone();
+            } else {
+                let sep = std::mem::replace(&mut parent.keys[idx - 1], left.keys.pop().unwrap());
+                child.keys.insert(0, sep);
+                child.children.insert(0, left.children.pop().unwrap());
+            }
+            parent.children[idx - 1] = Arc::new(left);
+            parent.children[idx] = Arc::new(child);
+        } else if idx + 1 < parent.children.len() && parent.children[idx + 1].keys.len() > t - 1 {
+            let mut right = parent.children[idx + 1].as_ref().clone();
+            let mut child = parent.children[idx].as_ref().clone();
+            if child.is_leaf() {
+                child.keys.push(right.keys.remove(0));
+                child.vals.push(right.vals.remove(0));
+                parent.keys[idx] = right.keys[0].clone();
+            } else {
+                let sep = std::mem::replace(&mut parent.keys[idx], right.keys.remove(0));
+                child.keys.push(sep);
+                child.children.push(right.children.remove(0));
+            }
+            parent.children[idx] = Arc::new(child);
+            parent.children[idx + 1] = Arc::new(right);
+        } else {
+            // 与兄弟合并 (总是把右边并入左边)
+            let i = if idx > 0 { idx - 1 } else { idx };
+            let sep = parent.keys.remove(i);
+            let right = parent.children.remove(i + 1);
+            let mut left = parent.children[i].as_ref().clone();
+            if !left.is_leaf() {
+                left.keys.push(sep);
+            }
+            left.keys.extend(right.keys.iter().cloned());
+            left.vals.extend(right.vals.iter().cloned());
+            left.children.extend(right.children.iter().cloned());
+            parent.children[i] = Arc::new(left);
+        }
+    }
+
+    /// 内部节点中 key 所在子树的下标 (keys[i] 是 children[i + 1] 的最小键)
+    fn child_index(node: &Node<K, V>, key: &K) -> usize {
+        node.keys.partition_point(|k| k <= key)
+    }
+}
+
+impl<K, V> Drop for CowBPTree<K, V> {
+    fn drop(&mut self) {
+        // &mut self 保证没有读者, 可以直接释放当前版本
+        unsafe {
+            let guard = epoch::unprotected();
+            let cur = self.current.load(Ordering::Relaxed, guard);
+            if !cur.is_null() {
+                drop(cur.into_owned());
+            }
+        }
+    }
+}
+
+/// 某一版本的只读视图; 存活期间该版本的所有节点都不会被回收
+///
+/// 内含 epoch guard, 不能跨线程传递; 长期持有会推迟旧版本的回收, 读完应尽快 drop
+pub struct Snapshot<'a, K, V> {
+    guard: Guard,
+    version: *const Version<K, V>,
+    _tree: PhantomData<&'a CowBPTree<K, V>>,
+}
+
+impl<K: Ord, V> Snapshot<'_, K, V> {
+    fn version(&self) -> &Version<K, V> {
+        // guard 一直 pin 着, 版本在 snapshot 存活期间不会被释放
+        let _ = &self.guard;
+        unsafe { &*self.version }
+    }
+
+    /// 快照对应的版本号 (每次成功写入 +1)
+    pub fn seq(&self) -> u64 {
+        self.version().seq
+    }
+
+    pub fn len(&self) -> usize {
+        self.version().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.version().len == 0
+    }
+
+    /// 树高 (只有一个叶子时为 1)
+    pub fn height(&self) -> usize {
+        let mut node = self.version().root.as_ref();
+        let mut height = 1;
+        while !node.is_leaf() {
+            node = &node.children[0];
+            height += 1;
+        }
+        height
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut node = self.version().root.as_ref();
+        while !node.is_leaf() {
+            node = &node.children[node.keys.partition_point(|k| k <= key)];
+        }
+        node.keys.binary_search(key).ok().map(|i| &node.vals[i])
+    }
+
+    /// [start, end] 闭区间, 按键升序
+    pub fn range_query(&self, start: &K, end: &K) -> Vec<&V> {
+        let mut out = Vec::new();
+        Self::collect(&self.version().root, start, end, &mut out);
+        out
+    }
+
+    /// 最小的键值对
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.version().root.as_ref();
+        while !node.is_leaf() {
+            node = &node.children[0];
+        }
+        node.keys.first().zip(node.vals.first())
+    }
+
+    /// 最大的键值对
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.version().root.as_ref();
+        while !node.is_leaf() {
+            node = node.children.last().unwrap();
+        }
+        node.keys.last().zip(node.vals.last())
+    }
+
+    fn collect<'s>(node: &'s Node<K, V>, start: &K, end: &K, out: &mut Vec<&'s V>) {
+        if node.is_leaf() {
+            let from = node.keys.partition_point(|k| k < start);
+            for (k, v) in node.keys[from..].iter().zip(&node.vals[from..]) {
+                if k > end {
+                    break;
+                }
+                out.push(v);
+            }
+            return;
+        }
+        // 只下降到与区间相交的子树
+        let lo = node.keys.partition_point(|k| k <= start);
+        let hi = node.keys.partition_point(|k| k <= end);
+        for child in &node.children[lo..=hi] {
+            Self::collect(child, start, end, out);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod tests {
+    use crate::cow_bptree::cow_bptree::CowBPTree;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_cow_bptree_matches_reference() {
+        let tree = CowBPTree::new(2);
+        let mut reference = std::collections::BTreeMap::new();
+        for i in 0..20_000u32 {
+            let k = (i * 7919) % 3_000;
+            if i % 3 == 0 {
+                assert_eq!(tree.delete(&k), reference.remove(&k));
+            } else {
+                tree.insert(k, i);
+                reference.insert(k, i);
+            }
+        }
+
+        let snap = tree.snapshot();
+        assert_eq!(snap.len(), reference.len());
+        assert_eq!(snap.first(), reference.iter().next());
+        assert_eq!(snap.last(), reference.iter().next_back());
+        let got: Vec<u32> = snap.range_query(&100, &2_000).into_iter().copied().collect();
+        let want: Vec<u32> = reference.range(100..=2_000).map(|(_, v)| *v).collect();
+        assert_eq!(got, want);
+        for k in 0..3_000 {
+            assert_eq!(snap.get(&k), reference.get(&k));
+        }
+    }
+
+    #[test]
+    fn test_cow_bptree_snapshot_is_frozen() {
+        let tree = CowBPTree::new(3);
+        for k in 0..100u32 {
+            tree.insert(k, k);
+        }
+        let old = tree.snapshot();
+        for k in 0..50u32 {
+            tree.delete(&k);
+        }
+        tree.insert(1_000, 1_000);
+        assert_eq!(tree.delete(&5), None);
+
+        // 旧快照看不到之后的写入
+        assert_eq!(old.seq(), 100);
+        assert_eq!(old.len(), 100);
+        assert_eq!(old.get(&0), Some(&0));
+        assert_eq!(old.get(&1_000), None);
+
+        let new = tree.snapshot();
+        assert_eq!(new.seq(), 151);
+        assert_eq!(new.len(), 51);
+        assert_eq!(new.get(&0), None);
+        assert_eq!(new.range_query(&0, &u32::MAX).len(), 51);
+    }
+
+    #[test]
+    fn test_cow_bptree_concurrent_readers() {
+        let tree = CowBPTree::new(4);
+        let done = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    while !done.load(Ordering::Relaxed) {
+                        // 写者按顺序插入 0..n, 任何快照都必须恰好是 0..len
+                        let snap = tree.snapshot();
+                        let vals = snap.range_query(&0, &u32::MAX);
+                        assert_eq!(vals.len(), snap.len());
+                        assert!(vals.iter().enumerate().all(|(i, v)| **v == i as u32));
+                        assert_eq!(snap.seq() as usize, snap.len());
+                    }
+                });
+            }
+            for k in 0..5_000u32 {
+                tree.insert(k, k);
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+        assert_eq!(tree.snapshot().len(), 5_000);
+    }
+}
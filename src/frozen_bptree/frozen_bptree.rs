@@ -0,0 +1,217 @@
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::bptree::bptree::BPTree;
+use crate::paged_bptree::paged_bptree::FixedCodec;
+
+const MAGIC: &[u8; 4] = b"OBFZ";
+const FORMAT_VERSION: u32 = 1;
+/// MAGIC | 版本 u32 | key 大小 u32 | value 大小 u32 | 扇出 u32 | 层数 u32 | 条目数 u64
+const HEADER_LEN: usize = 32;
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// 每层的 key 数: 第 0 层是全部条目, 第 l 层是第 l - 1 层每个节点 (fanout 个 key 一组) 的首 key,
+/// 直到某一层不超过一个节点
+fn level_sizes(len: usize, fanout: usize) -> Vec<usize> {
+    let mut sizes = vec![len];
+    while sizes[sizes.len() - 1] > fanout {
+        sizes.push(sizes[sizes.len() - 1].div_ceil(fanout));
+    }
+    sizes
+}
+
+/// 把按 key 严格递增的条目写成冻结 B+Tree 文件
+///
+/// 布局 (小端): `文件头 | 第 0 层 key | 全部 value | 第 1 层 key | 第 2 层 key | ...`。
+/// 每层都是连续的定长 key 数组, 节点是其中每 fanout 个 key 一组, 第 j 个节点的孩子就是下一层的第 j 个节点,
+/// 因此文件里没有任何指针, 偏移全部由条目数和扇出算出
+pub struct FrozenBuilder {
+    fanout: usize,
+}
+
+impl FrozenBuilder {
+    pub fn new(fanout: usize) -> Self {
+        assert!(fanout >= 2, "fanout must be at least 2");
+        FrozenBuilder { fanout }
+    }
+
+    /// 写出条目, key 不是严格递增时返回 InvalidInput
+    pub fn write<K: FixedCodec + Ord, V: FixedCodec, W: Write>(&self, entries: &[(&K, &V)], w: W) -> io::Result<()> {
+        if !entries.windows(2).all(|e| e[0].0 < e[1].0) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "keys must be strictly increasing"));
+        }
+        let sizes = level_sizes(entries.len(), self.fanout);
+        let mut w = BufWriter::new(w);
+        w.write_all(MAGIC)?;
+        for field in [FORMAT_VERSION, K::SIZE as u32, V::SIZE as u32, self.fanout as u32, sizes.len() as u32] {
+            w.write_all(&field.to_le_bytes())?;
+        }
+        w.write_all(&(entries.len() as u64).to_le_bytes())?;
+
+        let mut key = vec![0u8; K::SIZE];
+        for (k, _) in entries {
+            k.encode(&mut key);
+            w.write_all(&key)?;
+        }
+        let mut value = vec![0u8; V::SIZE];
+        for (_, v) in entries {
+            v.encode(&mut value);
+            w.write_all(&value)?;
+        }
+        // 第 l 层的第 j 个 key 是第 0 层的第 j * fanout^l 个 key
+        let mut stride = 1;
+        for _ in 1..sizes.len() {
+            stride *= self.fanout;
+            for (k, _) in entries.iter().step_by(stride) {
+                k.encode(&mut key);
+                w.write_all(&key)?;
+            }
+        }
+        w.flush()
+    }
+
+    /// 冻结一棵 `BPTree`: 先写临时文件, fsync 后原子改名
+    pub fn write_bptree<K, V>(&self, tree: &BPTree<K, V>, path: impl AsRef<Path>) -> io::Result<()>
+    where
+        K: FixedCodec + Ord + Clone + Debug,
+        V: FixedCodec + Clone + Debug,
+    {
+        let path = path.as_ref();
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        self.write(&tree.entries(), &mut file)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)
+    }
+}
+
+/// 内存映射的只读冻结 B+Tree
+///
+/// 查询直接在映射的字节上二分, 只解码比较到的 key 和返回的 value, 打开时不读入也不重建任何节点;
+/// 冷数据由操作系统按页调入, 多个进程打开同一个文件时共享页缓存。
+/// 映射期间文件不能被修改或截断 (`FrozenBuilder::write_bptree` 总是写新文件再改名, 不会影响已打开的映射)
+pub struct FrozenBPTree<K, V> {
+    map: Mmap,
+    len: usize,
+    fanout: usize,
+    /// 每层 key 数组的 (起始偏移, key 数), 下标为层号
+    levels: Vec<(usize, usize)>,
+    values: usize,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K: FixedCodec + Ord, V: FixedCodec> FrozenBPTree<K, V> {
+    /// 映射文件并校验文件头与总长度; 不做全文件校验和, 以免打开时读遍所有页
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: 冻结文件只由 FrozenBuilder 整体写出后改名发布, 之后不再修改
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < HEADER_LEN || &map[..4] != MAGIC {
+            return Err(invalid("not a frozen tree"));
+        }
+        let field = |i: usize| u32::from_le_bytes(map[4 + i * 4..8 + i * 4].try_into().unwrap()) as usize;
+        if field(0) != FORMAT_VERSION as usize {
+            return Err(invalid(format!("unsupported format version {}", field(0))));
+        }
+        if (field(1), field(2)) != (K::SIZE, V::SIZE) {
+            return Err(invalid(format!("entry is {}+{} bytes, expected {}+{}", field(1), field(2), K::SIZE, V::SIZE)));
+        }
+        let (fanout, depth) = (field(3), field(4));
+        let len = u64::from_le_bytes(map[24..32].try_into().unwrap()) as usize;
+        if fanout < 2 || len > map.len() {
+            return Err(invalid("corrupt frozen tree header"));
+        }
+        let sizes = level_sizes(len, fanout);
+        if sizes.len() != depth {
+            return Err(invalid("corrupt frozen tree header"));
+        }
+        let mut levels = vec![(HEADER_LEN, len)];
+        let values = HEADER_LEN + len * K::SIZE;
+        let mut offset = values + len * V::SIZE;
+        for &size in &sizes[1..] {
+            levels.push((offset, size));
+            offset += size * K::SIZE;
+        }
+        if offset != map.len() {
+            return Err(invalid(format!("frozen tree is {} bytes, expected {}", map.len(), offset)));
+        }
+        Ok(FrozenBPTree { map, len, fanout, levels, values, _marker: PhantomData })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 层数 (只有叶子层时为 1)
+    pub fn height(&self) -> usize {
+        self.levels.len()
+    }
+
+    fn key_at(&self, level: usize, i: usize) -> K {
+        let at = self.levels[level].0 + i * K::SIZE;
+        K::decode(&self.map[at..at + K::SIZE])
+    }
+
+    fn value_at(&self, i: usize) -> V {
+        let at = self.values + i * V::SIZE;
+        V::decode(&self.map[at..at + V::SIZE])
+    }
+
+    /// 满足 pred 的 key 的个数 (pred 对升序 key 先真后假)
+    ///
+    /// 每层在一个节点内二分: 节点里满足 pred 的最后一个 key 是下一层对应节点的首 key
+    fn partition_point(&self, pred: impl Fn(&K) -> bool) -> usize {
+        let mut node = 0;
+        for level in (0..self.levels.len()).rev() {
+            let start = node * self.fanout;
+            let (mut lo, mut hi) = (start, (start + self.fanout).min(self.levels[level].1));
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                if pred(&self.key_at(level, mid)) {
+                    lo = mid + 1;
+                } else {
+                    hi = mid;
+                }
+            }
+            if level == 0 {
+                return lo;
+            }
+            if lo == start {
+                // 只会发生在最左的节点: 所有 key 都不满足
+                return 0;
+            }
+            node = lo - 1;
+        }
+        unreachable!()
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let i = self.partition_point(|k| k <= key).checked_sub(1)?;
+        (self.key_at(0, i) == *key).then(|| self.value_at(i))
+    }
+
+    /// 不大于 key 的最大条目
+    pub fn floor(&self, key: &K) -> Option<(K, V)> {
+        let i = self.partition_point(|k| k <= key).checked_sub(1)?;
+        Some((self.key_at(0, i), self.value_at(i)))
+    }
+
+    /// [start, end] 闭区间; 定位两端后顺序读取连续的 value 数组
+    pub fn range_query(&self, start: &K, end: &K) -> Vec<V> {
+        let from = self.partition_point(|k| k < start);
+        let to = self.partition_point(|k| k <= end);
+        (from..to.max(from)).map(|i| self.value_at(i)).collect()
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod frozen_bptree;
+#[allow(clippy::module_inception)]
+pub mod tests;
@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use crate::bptree::bptree::BPTree;
+    use crate::frozen_bptree::frozen_bptree::{FrozenBPTree, FrozenBuilder};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeMap;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("frozen_bptree_{}_{}.db", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_frozen_bptree_matches_source() {
+        let path = temp_path("match");
+        let mut rng = StdRng::seed_from_u64(46);
+        for (n, fanout) in [(0, 4), (1, 2), (5, 2), (16, 4), (17, 4), (1_000, 3), (20_000, 64)] {
+            let mut reference = BTreeMap::new();
+            while reference.len() < n {
+                reference.insert(rng.gen_range(0..n as u64 * 10 + 1), rng.gen::<u32>());
+            }
+            let tree = BPTree::from_sorted(3, reference.iter().map(|(k, v)| (*k, *v)).collect());
+            FrozenBuilder::new(fanout).write_bptree(&tree, &path).unwrap();
+            let frozen = FrozenBPTree::<u64, u32>::open(&path).unwrap();
+            assert_eq!(frozen.len(), n);
+            // 文件只有文件头和定长数组, 索引层约占叶子层的 1 / (fanout - 1)
+            let size = std::fs::metadata(&path).unwrap().len() as usize;
+            assert!(size <= 32 + n * 12 + (n / (fanout - 1) + frozen.height()) * 8);
+
+            for _ in 0..2_000 {
+                let key = rng.gen_range(0..n as u64 * 10 + 2);
+                assert_eq!(frozen.get(&key), reference.get(&key).copied());
+                assert_eq!(frozen.floor(&key), reference.range(..=key).next_back().map(|(k, v)| (*k, *v)));
+                let end = key + rng.gen_range(0..200);
+                let want: Vec<u32> = reference.range(key..=end).map(|(_, v)| *v).collect();
+                assert_eq!(frozen.range_query(&key, &end), want);
+            }
+            assert_eq!(frozen.range_query(&u64::MAX, &0), Vec::<u32>::new());
+            assert_eq!(frozen.range_query(&0, &u64::MAX), reference.values().copied().collect::<Vec<_>>());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_frozen_bptree_rejects_bad_files() {
+        let path = temp_path("bad");
+        let tree = BPTree::from_sorted(3, (0..100u64).map(|k| (k, k)).collect());
+        FrozenBuilder::new(8).write_bptree(&tree, &path).unwrap();
+        assert!(FrozenBPTree::<u64, u64>::open(&path).is_ok());
+        assert!(FrozenBPTree::<u32, u64>::open(&path).is_err());
+
+        // 截断的文件
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 8]).unwrap();
+        assert!(FrozenBPTree::<u64, u64>::open(&path).is_err());
+        std::fs::write(&path, b"garbage").unwrap();
+        assert!(FrozenBPTree::<u64, u64>::open(&path).is_err());
+
+        // 乱序的条目不能冻结
+        let (a, b) = (2u64, 1u64);
+        assert!(FrozenBuilder::new(8).write(&[(&a, &a), (&b, &b)], Vec::new()).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+use std::mem::size_of;
+
+/// 条目句柄: 槽位下标 + 代数
+///
+/// 条目在树中移动 (旋转、分裂、合并) 时槽位随之移动, 句柄保持有效;
+/// 条目被删除后代数递增, 旧句柄失效
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    slot: usize,
+    generation: u32,
+}
+
+/// 墓碑少于这个数时不值得重建
+const MIN_PURGE: usize = 32;
+
+#[derive(Clone, Copy, Debug)]
+struct SlotState {
+    generation: u32,
+    live: bool,
+}
+
+/// 槽位表: 记录每个槽位的代数和存活状态, 供树实现 O(1) 的按句柄删除
+///
+/// 槽位生命周期: alloc (存活) -> tombstone (已逻辑删除, 条目仍在树中) -> release (已从树中摘除, 可复用)
+#[derive(Clone, Debug, Default)]
+pub struct HandleTable {
+    slots: Vec<SlotState>,
+    free: Vec<usize>,
+    live: usize,
+    tombstones: usize,
+}
+
+impl HandleTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 存活条目数
+    pub fn live(&self) -> usize {
+        self.live
+    }
+
+    /// 已逻辑删除、尚未从树中摘除的条目数
+    pub fn tombstones(&self) -> usize {
+        self.tombstones
+    }
+
+    /// 墓碑占全部条目的比例超过 1/3 (且不少于 MIN_PURGE 个) 时应当清理
+    ///
+    /// 清理是 O(n) 重建, 触发前至少积累了 n/3 次按句柄删除, 每次删除的均摊代价是 O(1)
+    pub fn needs_purge(&self) -> bool {
+        self.tombstones >= MIN_PURGE && self.tombstones * 2 > self.live
+    }
+
+    /// 分配一个存活槽位
+    pub fn alloc(&mut self) -> usize {
+        self.live += 1;
+        match self.free.pop() {
+            Some(slot) => {
+                self.slots[slot].live = true;
+                slot
+            }
+            None => {
+                self.slots.push(SlotState { generation: 0, live: true });
+                self.slots.len() - 1
+            }
+        }
+    }
+
+    pub fn handle(&self, slot: usize) -> Handle {
+        Handle {
+            slot,
+            generation: self.slots[slot].generation,
+        }
+    }
+
+    pub fn is_live(&self, slot: usize) -> bool {
+        self.slots[slot].live
+    }
+
+    /// 句柄仍有效时返回槽位
+    pub fn resolve(&self, handle: Handle) -> Option<usize> {
+        let state = self.slots.get(handle.slot)?;
+        (state.live && state.generation == handle.generation).then_some(handle.slot)
+    }
+
+    /// 逻辑删除, 句柄失效时返回 false
+    pub fn tombstone(&mut self, handle: Handle) -> bool {
+        let Some(slot) = self.resolve(handle) else {
+            return false;
+        };
+        let state = &mut self.slots[slot];
+        state.live = false;
+        state.generation = state.generation.wrapping_add(1);
+        self.live -= 1;
+        self.tombstones += 1;
+        true
+    }
+
+    /// 同一 key 再次插入时复用墓碑槽位, 返回新句柄
+    pub fn revive(&mut self, slot: usize) -> Handle {
+        debug_assert!(!self.slots[slot].live);
+        self.slots[slot].live = true;
+        self.tombstones -= 1;
+        self.live += 1;
+        self.handle(slot)
+    }
+
+    /// 条目已从树中摘除, 回收槽位
+    pub fn release(&mut self, slot: usize) {
+        let state = &mut self.slots[slot];
+        if state.live {
+            state.live = false;
+            state.generation = state.generation.wrapping_add(1);
+            self.live -= 1;
+        } else {
+            self.tombstones -= 1;
+        }
+        self.free.push(slot);
+    }
+
+    /// 槽位表自身的堆内存
+    pub fn heap_bytes(&self) -> usize {
+        self.slots.capacity() * size_of::<SlotState>() + self.free.capacity() * size_of::<usize>()
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod handle;
+#[allow(clippy::module_inception)]
+pub mod tests;
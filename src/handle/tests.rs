@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod tests {
+    use crate::handle::handle::HandleTable;
+
+    #[test]
+    fn test_handle_table_lifecycle() {
+        let mut table = HandleTable::new();
+        let a = table.alloc();
+        let b = table.alloc();
+        let ha = table.handle(a);
+        assert_eq!(table.resolve(ha), Some(a));
+        assert_eq!(table.live(), 2);
+
+        // 逻辑删除后句柄失效, 槽位仍被占用
+        assert!(table.tombstone(ha));
+        assert!(!table.tombstone(ha));
+        assert_eq!(table.resolve(ha), None);
+        assert_eq!((table.live(), table.tombstones()), (1, 1));
+
+        // 复用墓碑得到新句柄
+        let ha2 = table.revive(a);
+        assert_ne!(ha, ha2);
+        assert_eq!(table.resolve(ha2), Some(a));
+
+        // 摘除后槽位被复用, 旧句柄不会指向新条目
+        let hb = table.handle(b);
+        table.release(b);
+        let c = table.alloc();
+        assert_eq!(c, b);
+        assert_eq!(table.resolve(hb), None);
+        assert_eq!((table.live(), table.tombstones()), (2, 0));
+    }
+}
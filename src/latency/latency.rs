@@ -0,0 +1,302 @@
+use std::fmt::Write as _;
+use std::time::Instant;
+
+/// 每个 2 的幂区间内的子桶位数 (2^7 = 128 个子桶, 相对误差 < 1%)
+const SUB_BUCKET_BITS: u32 = 7;
+const SUB_BUCKET_COUNT: usize = 1 << SUB_BUCKET_BITS;
+/// u64 全范围所需的桶数量
+const BUCKET_COUNT: usize = (64 - SUB_BUCKET_BITS as usize + 1) * SUB_BUCKET_COUNT;
+
+/// 报告中输出的分位点
+pub const REPORT_PERCENTILES: [f64; 4] = [50.0, 90.0, 99.0, 99.9];
+
+/// HDR 风格的对数-线性延迟直方图 (单位: 纳秒)
+///
+/// 小于 128ns 的值精确记录, 更大的值按 2 的幂分段, 每段再线性切分为 128 个子桶,
+/// 内存占用固定, 记录开销为 O(1)。
+#[derive(Clone, Debug)]
+pub struct LatencyHistogram {
+    counts: Vec<u64>,
+    total_count: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        LatencyHistogram {
+            counts: vec![0; BUCKET_COUNT],
+            total_count: 0,
+            sum: 0,
+            min: u64::MAX,
+            max: 0,
+        }
+    }
+
+    /// 记录一次延迟 (纳秒)
+    pub fn record(&mut self, value_ns: u64) {
+        self.counts[Self::bucket_index(value_ns)] += 1;
+        self.total_count += 1;
+        self.sum += value_ns as u128;
+        self.min = self.min.min(value_ns);
+        self.max = self.max.max(value_ns);
+    }
+
+    /// 合并另一个直方图
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        for (c, o) in self.counts.iter_mut().zip(&other.counts) {
+            *c += *o;
+        }
+        self.total_count += other.total_count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.total_count
+    }
+
+    pub fn min(&self) -> u64 {
+        if self.total_count == 0 {
+            0
+        } else {
+            self.min
+        }
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.total_count == 0 {
+            0.0
+        } else {
+            self.sum as f64 / self.total_count as f64
+        }
+    }
+
+    /// 分位数 (percentile 取值 0..=100), 返回所在桶的上界 (不超过记录到的最大值)
+    pub fn percentile(&self, percentile: f64) -> u64 {
+        if self.total_count == 0 {
+            return 0;
+        }
+        let percentile = percentile.clamp(0.0, 100.0);
+        let rank = ((percentile / 100.0) * self.total_count as f64).ceil() as u64;
+        let rank = rank.max(1);
+
+        let mut seen = 0;
+        for (idx, c) in self.counts.iter().enumerate() {
+            seen += *c;
+            if seen >= rank {
+                return Self::bucket_upper(idx).min(self.max).max(self.min);
+            }
+        }
+        self.max
+    }
+
+    /// 值 -> 桶下标
+    fn bucket_index(value: u64) -> usize {
+        if value < SUB_BUCKET_COUNT as u64 {
+            return value as usize;
+        }
+        let exp = 63 - value.leading_zeros();
+        let shift = exp - SUB_BUCKET_BITS;
+        let mantissa = (value >> shift) as usize;
+        (shift as usize + 1) * SUB_BUCKET_COUNT + (mantissa - SUB_BUCKET_COUNT)
+    }
+
+    /// 桶下标 -> 桶内最大值
+    fn bucket_upper(idx: usize) -> u64 {
+        if idx < SUB_BUCKET_COUNT {
+            return idx as u64;
+        }
+        let shift = (idx / SUB_BUCKET_COUNT - 1) as u32;
+        let mantissa = (idx % SUB_BUCKET_COUNT + SUB_BUCKET_COUNT) as u64;
+        let lower = mantissa << shift;
+        lower + ((1u64 << shift) - 1)
+    }
+}
+
+/// CSV 字段: 含逗号、引号或换行时加引号, 内部引号写两次 (RFC 4180)
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// JSON 字符串字面量 (含两侧引号)
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// 单个 (数据结构, 操作类型) 的延迟摘要
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatencySummary {
+    pub structure: String,
+    pub op: String,
+    pub count: u64,
+    pub min: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub p999: u64,
+    pub max: u64,
+}
+
+impl LatencySummary {
+    pub fn from_histogram(structure: &str, op: &str, hist: &LatencyHistogram) -> Self {
+        LatencySummary {
+            structure: structure.to_string(),
+            op: op.to_string(),
+            count: hist.count(),
+            min: hist.min(),
+            mean: hist.mean(),
+            p50: hist.percentile(REPORT_PERCENTILES[0]),
+            p90: hist.percentile(REPORT_PERCENTILES[1]),
+            p99: hist.percentile(REPORT_PERCENTILES[2]),
+            p999: hist.percentile(REPORT_PERCENTILES[3]),
+            max: hist.max(),
+        }
+    }
+}
+
+/// 按 (数据结构, 操作类型) 分组的逐操作延迟记录器
+#[derive(Clone, Debug, Default)]
+pub struct LatencyRecorder {
+    entries: Vec<(String, String, LatencyHistogram)>,
+}
+
+impl LatencyRecorder {
+    pub fn new() -> Self {
+        LatencyRecorder { entries: Vec::new() }
+    }
+
+    /// 获取 (structure, op) 对应的直方图, 不存在则创建
+    ///
+    /// 热循环中可先取出直方图再调用 `record`, 避免每次查表
+    pub fn histogram_mut(&mut self, structure: &str, op: &str) -> &mut LatencyHistogram {
+        let pos = match self
+            .entries
+            .iter()
+            .position(|(s, o, _)| s == structure && o == op)
+        {
+            Some(pos) => pos,
+            None => {
+                self.entries
+                    .push((structure.to_string(), op.to_string(), LatencyHistogram::new()));
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[pos].2
+    }
+
+    pub fn histogram(&self, structure: &str, op: &str) -> Option<&LatencyHistogram> {
+        self.entries
+            .iter()
+            .find(|(s, o, _)| s == structure && o == op)
+            .map(|(_, _, h)| h)
+    }
+
+    /// 计时执行一次操作并记录其延迟
+    pub fn time<R, F: FnOnce() -> R>(&mut self, structure: &str, op: &str, f: F) -> R {
+        let start = Instant::now();
+        let result = f();
+        let elapsed = start.elapsed().as_nanos() as u64;
+        self.histogram_mut(structure, op).record(elapsed);
+        result
+    }
+
+    /// 合并另一个记录器
+    pub fn merge(&mut self, other: &LatencyRecorder) {
+        for (s, o, h) in &other.entries {
+            self.histogram_mut(s, o).merge(h);
+        }
+    }
+
+    pub fn summaries(&self) -> Vec<LatencySummary> {
+        self.entries
+            .iter()
+            .map(|(s, o, h)| LatencySummary::from_histogram(s, o, h))
+            .collect()
+    }
+
+    /// 导出 CSV (单位: 纳秒)
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("structure,op,count,min_ns,mean_ns,p50_ns,p90_ns,p99_ns,p999_ns,max_ns\n");
+        for s in self.summaries() {
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{:.1},{},{},{},{},{}",
+                csv_field(&s.structure), csv_field(&s.op), s.count, s.min, s.mean, s.p50, s.p90, s.p99, s.p999, s.max
+            );
+        }
+        out
+    }
+
+    /// 导出 JSON 数组 (单位: 纳秒)
+    pub fn to_json(&self) -> String {
+        let rows: Vec<String> = self
+            .summaries()
+            .iter()
+            .map(|s| {
+                format!(
+                    "  {{\"structure\": {}, \"op\": {}, \"count\": {}, \"min_ns\": {}, \"mean_ns\": {:.1}, \"p50_ns\": {}, \"p90_ns\": {}, \"p99_ns\": {}, \"p999_ns\": {}, \"max_ns\": {}}}",
+                    json_string(&s.structure), json_string(&s.op), s.count, s.min, s.mean, s.p50, s.p90, s.p99, s.p999, s.max
+                )
+            })
+            .collect();
+        format!("[\n{}\n]\n", rows.join(",\n"))
+    }
+
+    /// 打印对齐的文本表格
+    pub fn to_table(&self) -> String {
+        let mut out = format!(
+            "{:<12} {:<14} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}\n",
+            "structure", "op", "count", "p50(ns)", "p90(ns)", "p99(ns)", "p99.9(ns)", "max(ns)"
+        );
+        for s in self.summaries() {
+            let _ = writeln!(
+                out,
+                "{:<12} {:<14} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                s.structure, s.op, s.count, s.p50, s.p90, s.p99, s.p999, s.max
+            );
+        }
+        out
+    }
+
+    pub fn write_csv(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_csv())
+    }
+
+    pub fn write_json(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+}
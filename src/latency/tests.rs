@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use crate::latency::latency::{LatencyHistogram, LatencyRecorder};
+
+    #[test]
+    fn test_histogram_percentiles() {
+        let mut hist = LatencyHistogram::new();
+        for v in 1..=1000u64 {
+            hist.record(v * 100);
+        }
+
+        assert_eq!(hist.count(), 1000);
+        assert_eq!(hist.min(), 100);
+        assert_eq!(hist.max(), 100_000);
+        // 相对误差 < 1%
+        let p50 = hist.percentile(50.0) as f64;
+        let p99 = hist.percentile(99.0) as f64;
+        assert!((p50 - 50_000.0).abs() / 50_000.0 < 0.01, "p50 = {}", p50);
+        assert!((p99 - 99_000.0).abs() / 99_000.0 < 0.01, "p99 = {}", p99);
+        assert_eq!(hist.percentile(100.0), 100_000);
+    }
+
+    #[test]
+    fn test_recorder_export() {
+        let mut recorder = LatencyRecorder::new();
+        recorder.histogram_mut("rbtree", "insert").record(120);
+        recorder.histogram_mut("rbtree", "insert").record(80);
+        recorder.time("bptree", "get", || 1 + 1);
+
+        let summaries = recorder.summaries();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].structure, "rbtree");
+        assert_eq!(summaries[0].count, 2);
+        assert_eq!(summaries[0].max, 120);
+
+        let csv = recorder.to_csv();
+        assert!(csv.starts_with("structure,op,count"));
+        assert!(csv.contains("rbtree,insert,2,80"));
+        assert!(recorder.to_json().contains("\"op\": \"get\""));
+    }
+
+    #[test]
+    fn test_export_escapes_names() {
+        let mut recorder = LatencyRecorder::new();
+        recorder.histogram_mut("tree,\"v2\"", "get\nall\\").record(5);
+
+        let csv = recorder.to_csv();
+        assert!(csv.contains("\"tree,\"\"v2\"\"\",\"get\nall\\\",1,5"), "{}", csv);
+        let json: serde_json::Value = serde_json::from_str(&recorder.to_json()).unwrap();
+        assert_eq!(json[0]["structure"], "tree,\"v2\"");
+        assert_eq!(json[0]["op"], "get\nall\\");
+    }
+}
@@ -1,5 +1,28 @@
 pub mod rb_tree;
+pub mod avl_tree;
+pub mod classic_rb_tree;
+pub mod persistent_rb_tree;
 pub mod btree_map;
 pub mod bptree;
+pub mod cow_bptree;
+pub mod blink_tree;
+pub mod paged_bptree;
+pub mod shadow_bptree;
+pub mod frozen_bptree;
+pub mod buffer_pool;
+pub mod handle;
+pub mod skiplist;
+pub mod concurrent_skiplist;
+pub mod price_ladder;
+pub mod order_book;
+pub mod wal;
+pub mod snapshot;
+#[cfg(feature = "serde")]
+pub mod serde_support;
+pub mod latency;
+pub mod runner;
+pub mod report;
+pub mod alloc_counter;
+pub mod memory;
 
-pub mod benchmark;
\ No newline at end of file
+pub mod benchmark;
@@ -1,8 +1,86 @@
+use rust_orderbook_benchmark::alloc_counter::alloc_counter::{self, CountingAllocator};
+use rust_orderbook_benchmark::report::report::{
+    compare, format_comparison, has_regression, load_results, records_from_results,
+    write_results, CompareArgs,
+};
+use rust_orderbook_benchmark::runner::runner::{format_table, run_scenario, RunnerArgs, USAGE};
+
+/// 计数关闭时几乎没有额外开销, 由 `--alloc-stats` 开启
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
 fn main() {
-    println!("Rust Orderbook Benchmark - main()");
-    println!("Run `cargo bench` to execute the benchmarks.");
-    println!("Manual test:");
-    rust_orderbook_benchmark::benchmark::simple_test_rb_tree();
-    rust_orderbook_benchmark::benchmark::simple_test_btree();
-    println!("Now run `cargo bench` to see performance results.");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("compare") {
+        run_compare(&args[1..]);
+        return;
+    }
+
+    let args = match RunnerArgs::parse(&args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    alloc_counter::set_enabled(args.alloc_stats);
+
+    println!("Rust Orderbook Benchmark");
+    println!(
+        "size={} degree={} seed={} iterations={} alloc_stats={}\n",
+        args.size, args.degree, args.seed, args.iterations, args.alloc_stats
+    );
+
+    let mut results = Vec::new();
+    for scenario in args.scenarios() {
+        eprintln!(
+            "running {} / {} ...",
+            scenario.structure.name(),
+            scenario.workload.name()
+        );
+        results.push(run_scenario(&scenario));
+    }
+    println!("{}", format_table(&results));
+
+    let records = records_from_results(&results);
+    for path in &args.outputs {
+        match write_results(path, &records) {
+            Ok(()) => println!("results written to {}", path),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// 对比两份结果文件, 存在显著回归时以状态码 1 退出
+fn run_compare(args: &[String]) {
+    let args = match CompareArgs::parse(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let load = |path: &str| {
+        load_results(path).unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            std::process::exit(2);
+        })
+    };
+    let baseline = load(&args.baseline);
+    let candidate = load(&args.candidate);
+
+    let comparisons = compare(&baseline, &candidate, args.threshold_pct);
+    println!("{}", format_comparison(&comparisons));
+    if has_regression(&comparisons) {
+        eprintln!("significant regressions detected (threshold {}%)", args.threshold_pct);
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,78 @@
+use std::fmt;
+
+/// 遍历树结构得到的内存占用明细 (字节)
+///
+/// 只统计 K/V 的内联大小 (`size_of`), 不含 K/V 自身持有的堆内存 (如 `String` 的缓冲区)。
+/// `total_bytes` = key + value + 空闲容量 + 指针/节点头开销 + 重复拷贝。
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryReport {
+    /// 节点数 (不含 B+Tree 叶子链表中的拷贝)
+    pub node_count: usize,
+    /// 键值对数量
+    pub entry_count: usize,
+    /// 已使用的 key 槽位 (含 B+Tree 内部节点的分隔键)
+    pub key_bytes: usize,
+    /// 已使用的 value 槽位
+    pub value_bytes: usize,
+    /// Vec 预留但未使用的容量
+    pub wasted_capacity_bytes: usize,
+    /// 子节点指针、Vec 头、颜色/类型标记及对齐填充
+    pub pointer_overhead_bytes: usize,
+    /// 冗余拷贝 (B+Tree `next_leaf` 链表中克隆出的叶子)
+    pub duplicated_bytes: usize,
+    pub total_bytes: usize,
+    /// 节点槽位利用率: 已用 key 槽位 / 节点可容纳的最大 key 数
+    pub fill_factor: f64,
+    /// 树高 (空树为 0)
+    pub height: usize,
+}
+
+impl MemoryReport {
+    /// 汇总各项得到 total_bytes
+    pub fn finish(mut self) -> Self {
+        self.total_bytes = self.key_bytes
+            + self.value_bytes
+            + self.wasted_capacity_bytes
+            + self.pointer_overhead_bytes
+            + self.duplicated_bytes;
+        self
+    }
+
+    /// 平均每个键值对占用的字节数
+    pub fn bytes_per_entry(&self) -> f64 {
+        if self.entry_count == 0 {
+            0.0
+        } else {
+            self.total_bytes as f64 / self.entry_count as f64
+        }
+    }
+
+    /// 有效载荷 (key + value) 占总内存的比例
+    pub fn payload_ratio(&self) -> f64 {
+        if self.total_bytes == 0 {
+            0.0
+        } else {
+            (self.key_bytes + self.value_bytes) as f64 / self.total_bytes as f64
+        }
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "nodes={} entries={} height={} total={}B ({:.1}B/entry) keys={}B values={}B wasted={}B pointers={}B duplicated={}B fill={:.1}%",
+            self.node_count,
+            self.entry_count,
+            self.height,
+            self.total_bytes,
+            self.bytes_per_entry(),
+            self.key_bytes,
+            self.value_bytes,
+            self.wasted_capacity_bytes,
+            self.pointer_overhead_bytes,
+            self.duplicated_bytes,
+            self.fill_factor * 100.0
+        )
+    }
+}
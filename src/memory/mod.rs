@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod memory;
+#[allow(clippy::module_inception)]
+pub mod tests;
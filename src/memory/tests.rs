@@ -0,0 +1,23 @@
+#[cfg(test)]
+mod tests {
+    use crate::memory::memory::MemoryReport;
+
+    #[test]
+    fn test_memory_report_totals() {
+        let report = MemoryReport {
+            entry_count: 4,
+            key_bytes: 16,
+            value_bytes: 16,
+            wasted_capacity_bytes: 8,
+            pointer_overhead_bytes: 20,
+            duplicated_bytes: 4,
+            ..Default::default()
+        }
+        .finish();
+
+        assert_eq!(report.total_bytes, 64);
+        assert_eq!(report.bytes_per_entry(), 16.0);
+        assert_eq!(report.payload_ratio(), 0.5);
+        assert_eq!(MemoryReport::default().finish().bytes_per_entry(), 0.0);
+    }
+}
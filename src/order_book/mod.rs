@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod order_book;
+#[allow(clippy::module_inception)]
+pub mod tests;
@@ -0,0 +1,1114 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::bptree::bptree::BPTree;
+use crate::handle::handle::Handle;
+use crate::rb_tree::rb_tree::RBTree;
+
+pub type OrderId = u64;
+pub type Price = u64;
+pub type Qty = u64;
+pub type AccountId = u64;
+/// 交易组: 同一组的账户之间适用自成交防护
+pub type GroupId = u64;
+/// 账户净持仓: 买入为正, 卖出为负
+pub type Position = i64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+impl Side {
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        }
+    }
+}
+
+/// 只挂单 (post-only) 的订单到达时会与对手盘成交的处理方式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PostOnly {
+    /// 拒绝订单
+    Reject,
+    /// 改价到对手最优价外一个最小价位 (价格以最小价位为单位)
+    Slide,
+}
+
+/// 自成交防护 (STP): 吃单与同一账户或同一交易组的挂单相遇时的处理方式, 由吃单方的设置决定
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelfTrade {
+    /// 撤掉吃单的剩余部分
+    CancelNewest,
+    /// 撤掉挂单, 吃单继续撮合
+    CancelOldest,
+    /// 两边都撤掉
+    CancelBoth,
+    /// 两边都减去较小的数量, 减到 0 的一方被撤掉
+    DecrementAndCancel,
+}
+
+/// 新订单
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NewOrder {
+    pub id: OrderId,
+    pub side: Side,
+    pub price: Price,
+    /// 总数量 (冰山单含隐藏部分)
+    pub qty: Qty,
+    /// 冰山单每次显示的数量; None 为普通订单
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub display: Option<Qty>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub account: AccountId,
+    /// 只做 maker, 不主动成交
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub post_only: Option<PostOnly>,
+    /// 只减仓: 成交不能让账户持仓的绝对值变大, 也不能反向开仓
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reduce_only: bool,
+    /// 自成交防护; None 允许自成交, 账户 0 的订单不做防护
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub self_trade: Option<SelfTrade>,
+}
+
+impl NewOrder {
+    /// 账户 0 的普通限价单, 其余字段用结构体更新语法设置
+    pub fn limit(id: OrderId, side: Side, price: Price, qty: Qty) -> Self {
+        NewOrder { id, side, price, qty, display: None, account: 0, post_only: None, reduce_only: false, self_trade: None }
+    }
+}
+
+/// 止损单: 最新成交价触及 trigger 后释放进撮合 (买单在价格 >= trigger 时, 卖单在价格 <= trigger 时)
+///
+/// limit 为 None 是止损市价单, 触发后不限价成交, 未成交部分作废; 否则是止损限价单, 触发后按限价单处理
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StopOrder {
+    pub id: OrderId,
+    pub side: Side,
+    pub trigger: Price,
+    pub limit: Option<Price>,
+    pub qty: Qty,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub account: AccountId,
+    /// 触发后的订单沿用的自成交防护
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub self_trade: Option<SelfTrade>,
+}
+
+impl StopOrder {
+    /// 最新成交价为 last 时是否已触发
+    pub fn triggered_at(&self, last: Price) -> bool {
+        match self.side {
+            Side::Buy => last >= self.trigger,
+            Side::Sell => last <= self.trigger,
+        }
+    }
+}
+
+/// 订单簿命令: 日志记录与回放的单位
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Command {
+    /// 限价单: 先与对手盘撮合, 剩余部分挂单
+    Limit(NewOrder),
+    /// 止损单: 进入触发簿, 触发条件已满足时立即释放
+    Stop(StopOrder),
+    /// 撤销挂单或未触发的止损单
+    Cancel { id: OrderId },
+    /// 把账户加入交易组, group 为 0 时退出; 不在任何组的账户自成一组
+    Group { account: AccountId, group: GroupId },
+}
+
+/// 一笔成交, 按 maker 的价格成交
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trade {
+    pub maker: OrderId,
+    pub taker: OrderId,
+    pub price: Price,
+    pub qty: Qty,
+}
+
+/// 订单被撮合引擎撤掉或减少的原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CancelReason {
+    /// 与 counterparty 自成交, 按吃单方的 mode 处理
+    SelfTrade { mode: SelfTrade, counterparty: OrderId },
+    /// 只减仓订单的持仓已经不够
+    ReduceOnly,
+}
+
+/// 撮合中撤掉的数量; 订单还有剩余时 (`DecrementAndCancel` 的较大一方、被截断的只减仓单) 只是减少
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CancelEvent {
+    pub id: OrderId,
+    pub qty: Qty,
+    pub reason: CancelReason,
+}
+
+/// 一条命令的执行结果, 按发生顺序
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Execution {
+    pub trades: Vec<Trade>,
+    pub cancels: Vec<CancelEvent>,
+}
+
+/// 挂单中的订单, qty 为显示中的剩余数量
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Order {
+    pub id: OrderId,
+    pub side: Side,
+    pub price: Price,
+    pub qty: Qty,
+    /// 冰山单的隐藏数量, 显示部分成交完后补充
+    pub hidden: Qty,
+    /// 冰山单每次显示的数量
+    pub display: Option<Qty>,
+    pub account: AccountId,
+    pub reduce_only: bool,
+}
+
+impl Order {
+    /// 显示 + 隐藏的剩余数量
+    pub fn total(&self) -> Qty {
+        self.qty + self.hidden
+    }
+
+    /// 显示部分成交完后从隐藏数量补充, 返回是否补充了
+    fn replenish(&mut self) -> bool {
+        let refill = self.hidden.min(self.display.unwrap_or(0));
+        self.qty += refill;
+        self.hidden -= refill;
+        refill > 0
+    }
+}
+
+/// 价格档位索引: 价格 -> 档位在订单簿 slab 中的下标
+///
+/// 档位的订单队列由订单簿自己保存, 树只负责价格有序; 档位清空时按句柄 O(1) 删除。
+/// 查询都不修改索引, 只读的订单簿方法因此可以取 `&self`。
+pub trait PriceIndex {
+    fn insert(&mut self, price: Price, level: usize) -> Handle;
+    fn get(&self, price: Price) -> Option<usize>;
+    fn remove(&mut self, handle: Handle);
+    /// 价格最低的档位
+    fn first(&self) -> Option<(Price, usize)>;
+    /// 价格最高的档位
+    fn last(&self) -> Option<(Price, usize)>;
+    /// 最小的 >= price 的档位
+    fn lower_bound(&self, price: Price) -> Option<(Price, usize)>;
+    /// 按价格升序的全部档位
+    fn entries(&self) -> Vec<(Price, usize)>;
+    /// 价格最低的 n 个档位, 升序; 只遍历到第 n 个
+    fn first_n(&self, n: usize) -> Vec<(Price, usize)>;
+    /// 价格最高的 n 个档位, 降序
+    fn last_n(&self, n: usize) -> Vec<(Price, usize)>;
+    /// 在空索引上自底向上批量建立 (价格严格递增), 返回各档位的句柄
+    fn bulk_load(&mut self, levels: Vec<(Price, usize)>) -> Vec<Handle>;
+    /// 配置相同 (如最小度数) 的空索引, 用于触发簿
+    fn new_empty(&self) -> Self
+    where
+        Self: Sized;
+}
+
+impl PriceIndex for RBTree<Price, usize> {
+    fn insert(&mut self, price: Price, level: usize) -> Handle {
+        RBTree::insert(self, price, level)
+    }
+    fn get(&self, price: Price) -> Option<usize> {
+        self.find(&price).copied()
+    }
+    fn remove(&mut self, handle: Handle) {
+        self.remove_at(handle);
+    }
+    fn first(&self) -> Option<(Price, usize)> {
+        RBTree::first(self).map(|(p, l)| (*p, *l))
+    }
+    fn last(&self) -> Option<(Price, usize)> {
+        RBTree::last(self).map(|(p, l)| (*p, *l))
+    }
+    fn lower_bound(&self, price: Price) -> Option<(Price, usize)> {
+        RBTree::lower_bound(self, &price).map(|(p, l)| (*p, *l))
+    }
+    fn entries(&self) -> Vec<(Price, usize)> {
+        RBTree::entries(self).into_iter().map(|(p, l)| (*p, *l)).collect()
+    }
+    fn first_n(&self, n: usize) -> Vec<(Price, usize)> {
+        RBTree::first_n(self, n).into_iter().map(|(p, l)| (*p, *l)).collect()
+    }
+    fn last_n(&self, n: usize) -> Vec<(Price, usize)> {
+        RBTree::last_n(self, n).into_iter().map(|(p, l)| (*p, *l)).collect()
+    }
+    fn bulk_load(&mut self, levels: Vec<(Price, usize)>) -> Vec<Handle> {
+        assert!(self.is_empty(), "bulk_load needs an empty index");
+        *self = RBTree::from_sorted(levels);
+        let mut handles = Vec::with_capacity(self.len());
+        let mut cursor = self.cursor_mut();
+        while let Some(handle) = cursor.handle() {
+            handles.push(handle);
+            cursor.move_next();
+        }
+        handles
+    }
+    fn new_empty(&self) -> Self {
+        RBTree::new()
+    }
+}
+
+impl PriceIndex for BPTree<Price, usize> {
+    fn insert(&mut self, price: Price, level: usize) -> Handle {
+        BPTree::insert(self, price, level)
+    }
+    fn get(&self, price: Price) -> Option<usize> {
+        BPTree::get(self, &price)
+    }
+    fn remove(&mut self, handle: Handle) {
+        self.remove_at(handle);
+    }
+    fn first(&self) -> Option<(Price, usize)> {
+        BPTree::first(self).map(|(p, l)| (*p, *l))
+    }
+    fn last(&self) -> Option<(Price, usize)> {
+        BPTree::last(self).map(|(p, l)| (*p, *l))
+    }
+    fn lower_bound(&self, price: Price) -> Option<(Price, usize)> {
+        BPTree::lower_bound(self, &price).map(|(p, l)| (*p, *l))
+    }
+    fn entries(&self) -> Vec<(Price, usize)> {
+        BPTree::entries(self).into_iter().map(|(p, l)| (*p, *l)).collect()
+    }
+    fn first_n(&self, n: usize) -> Vec<(Price, usize)> {
+        BPTree::first_n(self, n).into_iter().map(|(p, l)| (*p, *l)).collect()
+    }
+    fn last_n(&self, n: usize) -> Vec<(Price, usize)> {
+        BPTree::last_n(self, n).into_iter().map(|(p, l)| (*p, *l)).collect()
+    }
+    fn bulk_load(&mut self, levels: Vec<(Price, usize)>) -> Vec<Handle> {
+        assert!(self.is_empty(), "bulk_load needs an empty index");
+        *self = BPTree::from_sorted(self.min_degree, levels);
+        let mut handles = Vec::with_capacity(self.len());
+        let mut cursor = self.cursor_mut();
+        while let Some(handle) = cursor.handle() {
+            handles.push(handle);
+            cursor.move_next();
+        }
+        handles
+    }
+    fn new_empty(&self) -> Self {
+        BPTree::new(self.min_degree)
+    }
+}
+
+/// 一个价位上按时间排队的订单
+#[derive(Debug)]
+struct Level {
+    price: Price,
+    orders: VecDeque<OrderId>,
+    /// 档位在价格索引中的句柄
+    handle: Handle,
+}
+
+/// 一侧的盘口: 价格索引 + 档位 slab; 触发簿也用它, 价格为触发价
+struct BookSide<I> {
+    index: I,
+    levels: Vec<Option<Level>>,
+    free: Vec<usize>,
+}
+
+impl<I: PriceIndex> BookSide<I> {
+    fn new(index: I) -> Self {
+        BookSide { index, levels: Vec::new(), free: Vec::new() }
+    }
+
+    fn level_mut(&mut self, idx: usize) -> &mut Level {
+        self.levels[idx].as_mut().unwrap()
+    }
+
+    /// price 档位的下标, 不存在时新建
+    fn level_for(&mut self, price: Price) -> usize {
+        if let Some(idx) = self.index.get(price) {
+            return idx;
+        }
+        let idx = self.free.pop().unwrap_or_else(|| {
+            self.levels.push(None);
+            self.levels.len() - 1
+        });
+        let handle = self.index.insert(price, idx);
+        self.levels[idx] = Some(Level { price, orders: VecDeque::new(), handle });
+        idx
+    }
+
+    /// 档位空了就从索引和 slab 中移除
+    fn release_if_empty(&mut self, idx: usize) {
+        if self.levels[idx].as_ref().is_some_and(|l| l.orders.is_empty()) {
+            let level = self.levels[idx].take().unwrap();
+            self.index.remove(level.handle);
+            self.free.push(idx);
+        }
+    }
+
+    /// 从 price 档位的队列中摘掉 id
+    fn remove_order(&mut self, price: Price, id: OrderId) {
+        let idx = self.index.get(price).unwrap();
+        let level = self.level_mut(idx);
+        let pos = level.orders.iter().position(|&o| o == id).unwrap();
+        level.orders.remove(pos);
+        self.release_if_empty(idx);
+    }
+}
+
+/// 账户在 side 方向上还能减仓的数量 (买入只能减空头, 卖出只能减多头)
+fn reducible(positions: &HashMap<AccountId, Position>, account: AccountId, side: Side) -> Qty {
+    let position = positions.get(&account).copied().unwrap_or(0);
+    match side {
+        Side::Buy => (-position).max(0) as Qty,
+        Side::Sell => position.max(0) as Qty,
+    }
+}
+
+fn add_position(positions: &mut HashMap<AccountId, Position>, account: AccountId, delta: Position) {
+    let position = positions.entry(account).or_insert(0);
+    *position += delta;
+    if *position == 0 {
+        positions.remove(&account);
+    }
+}
+
+/// 自成交防护比较的身份: 组内账户用 (true, 组号), 其余用 (false, 账户), 组号与账户号互不冲突
+fn stp_identity(groups: &HashMap<AccountId, GroupId>, account: AccountId) -> (bool, u64) {
+    groups.get(&account).map_or((false, account), |&g| (true, g))
+}
+
+/// 触发簿中的止损单, seq 为接受时的命令序号, 用于两侧同时触发时按到达顺序释放
+struct PendingStop {
+    order: StopOrder,
+    seq: u64,
+}
+
+/// 按价格从优到劣的档位快照: (价格, [(订单号, 剩余数量)])
+pub type SideState = Vec<(Price, Vec<(OrderId, Qty)>)>;
+
+/// 冰山单的隐藏部分: (订单号, 每次显示的数量, 隐藏数量)
+pub type IcebergState = Vec<(OrderId, Qty, Qty)>;
+
+/// 挂单的所属账户和只减仓标志: (订单号, 账户, 只减仓); 只列出账户非 0 或只减仓的挂单
+pub type AccountState = Vec<(OrderId, AccountId, bool)>;
+
+/// 订单簿的完整可比较状态, 用于校验恢复结果
+///
+/// 档位中的数量只含显示部分, 冰山单的隐藏部分单独列出
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookState {
+    pub bids: SideState,
+    pub asks: SideState,
+    /// 按订单号升序
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub icebergs: IcebergState,
+    /// 按订单号升序
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub accounts: AccountState,
+    /// 非零的账户持仓, 按账户升序
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub positions: Vec<(AccountId, Position)>,
+    /// 账户所属的交易组, 按账户升序
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub groups: Vec<(AccountId, GroupId)>,
+    /// 未触发的止损单, 按到达顺序
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stops: Vec<StopOrder>,
+    pub last_price: Option<Price>,
+    pub seq: u64,
+}
+
+/// 价格优先、时间优先的限价订单簿, 价格档位由可替换的树 (`PriceIndex`) 索引
+///
+/// 止损单放在独立的触发簿里, 每侧一棵按触发价排序的同类索引树; 每条命令执行完后,
+/// 从最新成交价一侧的端点顺序扫描, 把已触发的止损单逐个释放进撮合, 直到没有新的触发。
+///
+/// 每笔成交都计入双方账户的净持仓, 只减仓订单在接受时和每次撮合时都对照持仓检查。
+/// 自成交防护在撮合循环里逐个挂单检查, 撤单和减量作为 `CancelEvent` 随成交一起返回。
+pub struct OrderBook<I> {
+    bids: BookSide<I>,
+    asks: BookSide<I>,
+    orders: HashMap<OrderId, Order>,
+    positions: HashMap<AccountId, Position>,
+    groups: HashMap<AccountId, GroupId>,
+    /// 买入止损单按触发价升序最先触发, 卖出止损单按降序
+    buy_stops: BookSide<I>,
+    sell_stops: BookSide<I>,
+    stops: HashMap<OrderId, PendingStop>,
+    last_price: Option<Price>,
+    /// 已接受的命令数
+    seq: u64,
+}
+
+/// 以 `RBTree` 为价格索引的订单簿
+pub type RBOrderBook = OrderBook<RBTree<Price, usize>>;
+/// 以 `BPTree` 为价格索引的订单簿
+pub type BPOrderBook = OrderBook<BPTree<Price, usize>>;
+
+impl RBOrderBook {
+    pub fn with_rb_tree() -> Self {
+        OrderBook::new(RBTree::new(), RBTree::new())
+    }
+}
+
+impl BPOrderBook {
+    pub fn with_bptree(min_degree: usize) -> Self {
+        OrderBook::new(BPTree::new(min_degree), BPTree::new(min_degree))
+    }
+}
+
+impl<I: PriceIndex> OrderBook<I> {
+    /// 用两棵空索引树建立订单簿, 触发簿的索引由 `PriceIndex::new_empty` 建立
+    pub fn new(bid_index: I, ask_index: I) -> Self {
+        OrderBook {
+            buy_stops: BookSide::new(bid_index.new_empty()),
+            sell_stops: BookSide::new(ask_index.new_empty()),
+            bids: BookSide::new(bid_index),
+            asks: BookSide::new(ask_index),
+            orders: HashMap::new(),
+            positions: HashMap::new(),
+            groups: HashMap::new(),
+            stops: HashMap::new(),
+            last_price: None,
+            seq: 0,
+        }
+    }
+
+    /// 挂单数
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    pub fn last_price(&self) -> Option<Price> {
+        self.last_price
+    }
+
+    pub fn order(&self, id: OrderId) -> Option<&Order> {
+        self.orders.get(&id)
+    }
+
+    /// 账户的净持仓
+    pub fn position(&self, account: AccountId) -> Position {
+        self.positions.get(&account).copied().unwrap_or(0)
+    }
+
+    /// 账户所属的交易组
+    pub fn group(&self, account: AccountId) -> Option<GroupId> {
+        self.groups.get(&account).copied()
+    }
+
+    /// 未触发的止损单数
+    pub fn stop_count(&self) -> usize {
+        self.stops.len()
+    }
+
+    pub fn stop(&self, id: OrderId) -> Option<&StopOrder> {
+        self.stops.get(&id).map(|s| &s.order)
+    }
+
+    fn side(&mut self, side: Side) -> &mut BookSide<I> {
+        self.side_and_orders(side).0
+    }
+
+    /// 同时借出一侧盘口和订单表
+    fn side_and_orders(&mut self, side: Side) -> (&mut BookSide<I>, &mut HashMap<OrderId, Order>) {
+        match side {
+            Side::Buy => (&mut self.bids, &mut self.orders),
+            Side::Sell => (&mut self.asks, &mut self.orders),
+        }
+    }
+
+    /// 买方最高价
+    pub fn best_bid(&self) -> Option<Price> {
+        self.bids.index.last().map(|(p, _)| p)
+    }
+
+    /// 卖方最低价
+    pub fn best_ask(&self) -> Option<Price> {
+        self.asks.index.first().map(|(p, _)| p)
+    }
+
+    /// side 一侧最优的档位
+    fn best_level(&self, side: Side) -> Option<(Price, usize)> {
+        match side {
+            Side::Buy => self.bids.index.last(),
+            Side::Sell => self.asks.index.first(),
+        }
+    }
+
+    fn stop_side(&mut self, side: Side) -> &mut BookSide<I> {
+        match side {
+            Side::Buy => &mut self.buy_stops,
+            Side::Sell => &mut self.sell_stops,
+        }
+    }
+
+    fn check_new_id(&self, id: OrderId, qty: Qty) -> Result<(), String> {
+        if qty == 0 {
+            return Err(format!("order {}: quantity must > 0", id));
+        }
+        if self.orders.contains_key(&id) || self.stops.contains_key(&id) {
+            return Err(format!("order {}: duplicate id", id));
+        }
+        Ok(())
+    }
+
+    /// 检查命令能否被接受, 不修改订单簿
+    pub fn validate(&self, cmd: &Command) -> Result<(), String> {
+        match cmd {
+            Command::Limit(o) => {
+                self.check_new_id(o.id, o.qty)?;
+                if o.display == Some(0) {
+                    return Err(format!("order {}: display quantity must > 0", o.id));
+                }
+                if o.reduce_only && reducible(&self.positions, o.account, o.side) == 0 {
+                    return Err(format!("order {}: reduce-only would not reduce the position of account {}", o.id, o.account));
+                }
+                self.post_only_price(o)?;
+            }
+            Command::Stop(s) => self.check_new_id(s.id, s.qty)?,
+            Command::Cancel { id } => {
+                if !self.orders.contains_key(id) && !self.stops.contains_key(id) {
+                    return Err(format!("order {}: not found", id));
+                }
+            }
+            Command::Group { account, .. } => {
+                if *account == 0 {
+                    return Err("account 0 cannot join a group".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 执行一条命令, 返回产生的成交 (含被它触发的止损单的成交); 被拒绝的命令不改变任何状态
+    pub fn apply(&mut self, cmd: &Command) -> Result<Vec<Trade>, String> {
+        self.execute(cmd).map(|e| e.trades)
+    }
+
+    /// 同 `apply`, 另外返回撮合中撤掉的订单
+    pub fn execute(&mut self, cmd: &Command) -> Result<Execution, String> {
+        self.validate(cmd)?;
+        self.seq += 1;
+        let mut out = Execution::default();
+        match cmd {
+            Command::Limit(o) => self.limit(o, &mut out),
+            Command::Stop(s) => self.add_stop(s.clone(), self.seq),
+            Command::Cancel { id } => self.cancel(*id),
+            Command::Group { account, group: 0 } => {
+                self.groups.remove(account);
+            }
+            Command::Group { account, group } => {
+                self.groups.insert(*account, *group);
+            }
+        }
+        self.release_stops(&mut out);
+        Ok(out)
+    }
+
+    /// 只挂单订单的实际挂单价: 不会成交时保持原价, 会成交时按 `PostOnly` 拒绝或改到对手最优价外一个价位
+    fn post_only_price(&self, o: &NewOrder) -> Result<Price, String> {
+        let Some(mode) = o.post_only else { return Ok(o.price) };
+        let best = match o.side {
+            Side::Buy => self.best_ask().filter(|&ask| ask <= o.price),
+            Side::Sell => self.best_bid().filter(|&bid| bid >= o.price),
+        };
+        let Some(best) = best else { return Ok(o.price) };
+        let slid = match o.side {
+            Side::Buy => best.checked_sub(1),
+            Side::Sell => best.checked_add(1),
+        };
+        match (mode, slid) {
+            (PostOnly::Slide, Some(price)) => Ok(price),
+            _ => Err(format!("order {}: post-only would take liquidity at {}", o.id, best)),
+        }
+    }
+
+    /// 主动成交时冰山单按全部数量吃单; 剩余部分挂单, 冰山单只显示 display 的数量
+    fn limit(&mut self, o: &NewOrder, out: &mut Execution) {
+        let price = self.post_only_price(o).unwrap();
+        let mut remaining = self.take(o, Some(price), out);
+        if o.reduce_only {
+            // 挂单部分不能超过剩余可减的持仓
+            let cut = remaining - remaining.min(reducible(&self.positions, o.account, o.side));
+            if cut > 0 {
+                remaining -= cut;
+                out.cancels.push(CancelEvent { id: o.id, qty: cut, reason: CancelReason::ReduceOnly });
+            }
+        }
+        if remaining > 0 {
+            let book = self.side(o.side);
+            let idx = book.level_for(price);
+            book.level_mut(idx).orders.push_back(o.id);
+            let qty = remaining.min(o.display.unwrap_or(remaining));
+            let order = Order {
+                id: o.id,
+                side: o.side,
+                price,
+                qty,
+                hidden: remaining - qty,
+                display: o.display,
+                account: o.account,
+                reduce_only: o.reduce_only,
+            };
+            self.orders.insert(o.id, order);
+        }
+    }
+
+    /// 吃对手盘直到数量用完或价格越过 limit (None 为不限价), 返回未成交数量
+    ///
+    /// 只和显示数量成交; 冰山单的显示部分成交完后从隐藏数量补充并排到本档位队尾, 失去时间优先,
+    /// 同一档位上的其他订单先于它的下一次显示成交。
+    /// 只减仓的一方每笔成交都以当时的持仓为上限: 吃单方的剩余数量被截断, 挂单方持仓已平时被撤掉。
+    /// 吃单设置了自成交防护时, 每个挂单成交前先比较双方的交易组, 相同则按 `SelfTrade` 撤单或减量
+    fn take(&mut self, o: &NewOrder, limit: Option<Price>, out: &mut Execution) -> Qty {
+        let (side, mut remaining) = (o.side, o.qty);
+        let opposite = side.opposite();
+        let crosses = |price: Price| match (side, limit) {
+            (_, None) => true,
+            (Side::Buy, Some(limit)) => price <= limit,
+            (Side::Sell, Some(limit)) => price >= limit,
+        };
+        while remaining > 0 {
+            let Some((price, idx)) = self.best_level(opposite) else { break };
+            if !crosses(price) {
+                break;
+            }
+            let (book, orders, positions) = match opposite {
+                Side::Buy => (&mut self.bids, &mut self.orders, &mut self.positions),
+                Side::Sell => (&mut self.asks, &mut self.orders, &mut self.positions),
+            };
+            let taker_identity = o.self_trade.filter(|_| o.account != 0).map(|mode| (mode, stp_identity(&self.groups, o.account)));
+            let level = book.level_mut(idx);
+            let mut traded = false;
+            while remaining > 0 {
+                if o.reduce_only {
+                    let cut = remaining - remaining.min(reducible(positions, o.account, side));
+                    if cut > 0 {
+                        remaining -= cut;
+                        out.cancels.push(CancelEvent { id: o.id, qty: cut, reason: CancelReason::ReduceOnly });
+                    }
+                    if remaining == 0 {
+                        break;
+                    }
+                }
+                let Some(&maker_id) = level.orders.front() else { break };
+                let maker = orders.get_mut(&maker_id).unwrap();
+                if let Some((mode, identity)) = taker_identity {
+                    if maker.account != 0 && stp_identity(&self.groups, maker.account) == identity {
+                        let reason = CancelReason::SelfTrade { mode, counterparty: maker_id };
+                        let (cancel_taker, cancel_maker) = match mode {
+                            SelfTrade::CancelNewest => (remaining, 0),
+                            SelfTrade::CancelOldest => (0, maker.total()),
+                            SelfTrade::CancelBoth => (remaining, maker.total()),
+                            SelfTrade::DecrementAndCancel => (remaining.min(maker.total()), remaining.min(maker.total())),
+                        };
+                        if cancel_maker > 0 {
+                            out.cancels.push(CancelEvent {
+                                id: maker_id,
+                                qty: cancel_maker,
+                                reason: CancelReason::SelfTrade { mode, counterparty: o.id },
+                            });
+                            // 先减隐藏部分, 显示中的数量保留在原来的队列位置
+                            let from_hidden = cancel_maker.min(maker.hidden);
+                            maker.hidden -= from_hidden;
+                            maker.qty -= cancel_maker - from_hidden;
+                            if maker.qty == 0 {
+                                level.orders.pop_front();
+                                orders.remove(&maker_id);
+                            }
+                        }
+                        if cancel_taker > 0 {
+                            out.cancels.push(CancelEvent { id: o.id, qty: cancel_taker, reason });
+                            remaining -= cancel_taker;
+                        }
+                        continue;
+                    }
+                }
+                let mut qty = remaining.min(maker.qty);
+                if maker.reduce_only {
+                    qty = qty.min(reducible(positions, maker.account, opposite));
+                    if qty == 0 {
+                        // 持仓已平, 再成交就会反向开仓
+                        out.cancels.push(CancelEvent { id: maker_id, qty: maker.total(), reason: CancelReason::ReduceOnly });
+                        level.orders.pop_front();
+                        orders.remove(&maker_id);
+                        continue;
+                    }
+                }
+                maker.qty -= qty;
+                remaining -= qty;
+                traded = true;
+                let delta = qty as Position;
+                let (buyer, seller) = match side {
+                    Side::Buy => (o.account, maker.account),
+                    Side::Sell => (maker.account, o.account),
+                };
+                add_position(positions, buyer, delta);
+                add_position(positions, seller, -delta);
+                out.trades.push(Trade { maker: maker_id, taker: o.id, price, qty });
+                if maker.qty == 0 {
+                    level.orders.pop_front();
+                    if maker.replenish() {
+                        level.orders.push_back(maker_id);
+                    } else {
+                        orders.remove(&maker_id);
+                    }
+                }
+            }
+            book.release_if_empty(idx);
+            // 档位上可能只有被撤掉的订单
+            if traded {
+                self.last_price = Some(price);
+            }
+        }
+        remaining
+    }
+
+    /// 放入触发簿, 同一触发价按到达顺序排队
+    fn add_stop(&mut self, order: StopOrder, seq: u64) {
+        let book = self.stop_side(order.side);
+        let idx = book.level_for(order.trigger);
+        book.level_mut(idx).orders.push_back(order.id);
+        self.stops.insert(order.id, PendingStop { order, seq });
+    }
+
+    /// 当前成交价下优先级最高的已触发止损单
+    ///
+    /// 同一侧按触发价 (价格移动方向上先被触及的先释放) 再按时间; 两侧都有时先到达的优先
+    fn next_triggered(&self) -> Option<OrderId> {
+        let last = self.last_price?;
+        let buy = self.buy_stops.index.first().filter(|&(trigger, _)| last >= trigger);
+        let sell = self.sell_stops.index.last().filter(|&(trigger, _)| last <= trigger);
+        let front = |book: &BookSide<I>, idx: usize| book.levels[idx].as_ref().unwrap().orders[0];
+        let buy = buy.map(|(_, idx)| front(&self.buy_stops, idx));
+        let sell = sell.map(|(_, idx)| front(&self.sell_stops, idx));
+        match (buy, sell) {
+            (Some(b), Some(s)) => Some(if self.stops[&b].seq < self.stops[&s].seq { b } else { s }),
+            (b, s) => b.or(s),
+        }
+    }
+
+    /// 逐个释放已触发的止损单; 每次释放都可能产生新的成交价, 因此每次都重新查找
+    fn release_stops(&mut self, out: &mut Execution) {
+        while let Some(id) = self.next_triggered() {
+            let stop = self.stops.remove(&id).unwrap().order;
+            self.stop_side(stop.side).remove_order(stop.trigger, id);
+            let order = NewOrder {
+                account: stop.account,
+                self_trade: stop.self_trade,
+                ..NewOrder::limit(id, stop.side, stop.limit.unwrap_or(0), stop.qty)
+            };
+            match stop.limit {
+                Some(_) => self.limit(&order, out),
+                None => {
+                    self.take(&order, None, out);
+                }
+            }
+        }
+    }
+
+    fn cancel(&mut self, id: OrderId) {
+        if let Some(stop) = self.stops.remove(&id) {
+            self.stop_side(stop.order.side).remove_order(stop.order.trigger, id);
+            return;
+        }
+        let order = self.orders.remove(&id).unwrap();
+        self.side(order.side).remove_order(order.price, id);
+    }
+
+    /// side 一侧前 n 个档位的 (价格, 总数量), 从优到劣; 只遍历价格索引的前 n 个档位
+    pub fn depth(&self, side: Side, n: usize) -> Vec<(Price, Qty)> {
+        let (book, levels) = match side {
+            Side::Buy => (&self.bids, self.bids.index.last_n(n)),
+            Side::Sell => (&self.asks, self.asks.index.first_n(n)),
+        };
+        levels
+            .into_iter()
+            .map(|(price, idx)| {
+                let level = book.levels[idx].as_ref().unwrap();
+                (price, level.orders.iter().map(|id| self.orders[id].qty).sum())
+            })
+            .collect()
+    }
+
+    fn side_state(&self, side: Side) -> SideState {
+        let book = match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        };
+        let mut levels = book.index.entries();
+        if side == Side::Buy {
+            levels.reverse();
+        }
+        levels
+            .into_iter()
+            .map(|(price, idx)| {
+                let level = book.levels[idx].as_ref().unwrap();
+                debug_assert_eq!(level.price, price);
+                (price, level.orders.iter().map(|id| (*id, self.orders[id].qty)).collect())
+            })
+            .collect()
+    }
+
+    /// 由状态重建订单簿, 两侧价格索引自底向上批量建立; bid_index/ask_index 须为空树
+    ///
+    /// 止损单按到达顺序重新放入触发簿; 已满足触发条件的止损单说明状态不一致, 会被拒绝
+    pub fn restore(state: BookState, bid_index: I, ask_index: I) -> Result<Self, String> {
+        let mut book = OrderBook::new(bid_index, ask_index);
+        book.seq = state.seq;
+        book.last_price = state.last_price;
+        for (side, mut levels) in [(Side::Buy, state.bids), (Side::Sell, state.asks)] {
+            // 状态按从优到劣排列, 索引需要升序
+            if side == Side::Buy {
+                levels.reverse();
+            }
+            if !levels.windows(2).all(|w| w[0].0 < w[1].0) {
+                return Err(format!("{:?} levels out of order", side));
+            }
+            let (book_side, orders) = book.side_and_orders(side);
+            let handles = book_side.index.bulk_load(levels.iter().enumerate().map(|(i, l)| (l.0, i)).collect());
+            for ((price, queue), handle) in levels.into_iter().zip(handles) {
+                if queue.is_empty() {
+                    return Err(format!("empty level at {}", price));
+                }
+                let mut ids = VecDeque::with_capacity(queue.len());
+                for (id, qty) in queue {
+                    let order = Order { id, side, price, qty, hidden: 0, display: None, account: 0, reduce_only: false };
+                    if qty == 0 || orders.insert(id, order).is_some() {
+                        return Err(format!("order {}: zero quantity or duplicate id", id));
+                    }
+                    ids.push_back(id);
+                }
+                book_side.levels.push(Some(Level { price, orders: ids, handle }));
+            }
+        }
+        if let (Some(bid), Some(ask)) = (book.best_bid(), book.best_ask()) {
+            if bid >= ask {
+                return Err(format!("crossed book: bid {} >= ask {}", bid, ask));
+            }
+        }
+        for (id, display, hidden) in state.icebergs {
+            let order = book.orders.get_mut(&id).ok_or_else(|| format!("iceberg {}: not resting", id))?;
+            if display == 0 || order.qty > display {
+                return Err(format!("iceberg {}: shows {} of display {}", id, order.qty, display));
+            }
+            order.display = Some(display);
+            order.hidden = hidden;
+        }
+        for (id, account, reduce_only) in state.accounts {
+            let order = book.orders.get_mut(&id).ok_or_else(|| format!("order {}: not resting", id))?;
+            order.account = account;
+            order.reduce_only = reduce_only;
+        }
+        for (account, position) in state.positions {
+            if position == 0 || book.positions.insert(account, position).is_some() {
+                return Err(format!("account {}: zero or duplicate position", account));
+            }
+        }
+        for (account, group) in state.groups {
+            if account == 0 || group == 0 || book.groups.insert(account, group).is_some() {
+                return Err(format!("account {}: bad or duplicate group", account));
+            }
+        }
+        // 只需要保持相对顺序, 序号都不超过 state.seq, 之后到达的止损单一定排在后面
+        for (seq, stop) in state.stops.into_iter().enumerate() {
+            book.check_new_id(stop.id, stop.qty)?;
+            if book.last_price.is_some_and(|last| stop.triggered_at(last)) {
+                return Err(format!("stop {}: already triggered", stop.id));
+            }
+            book.add_stop(stop, seq as u64);
+        }
+        Ok(book)
+    }
+
+    /// 完整状态 (按价格-时间优先级排列), 与价格索引的实现无关
+    pub fn state(&self) -> BookState {
+        BookState {
+            bids: self.side_state(Side::Buy),
+            asks: self.side_state(Side::Sell),
+            icebergs: {
+                let mut icebergs: Vec<_> = self.orders.values().filter_map(|o| Some((o.id, o.display?, o.hidden))).collect();
+                icebergs.sort_unstable();
+                icebergs
+            },
+            accounts: {
+                let mut accounts: Vec<_> = self
+                    .orders
+                    .values()
+                    .filter(|o| o.account != 0 || o.reduce_only)
+                    .map(|o| (o.id, o.account, o.reduce_only))
+                    .collect();
+                accounts.sort_unstable();
+                accounts
+            },
+            positions: {
+                let mut positions: Vec<_> = self.positions.iter().map(|(&a, &p)| (a, p)).collect();
+                positions.sort_unstable();
+                positions
+            },
+            groups: {
+                let mut groups: Vec<_> = self.groups.iter().map(|(&a, &g)| (a, g)).collect();
+                groups.sort_unstable();
+                groups
+            },
+            stops: {
+                let mut stops: Vec<&PendingStop> = self.stops.values().collect();
+                stops.sort_by_key(|s| s.seq);
+                stops.into_iter().map(|s| s.order.clone()).collect()
+            },
+            last_price: self.last_price,
+            seq: self.seq,
+        }
+    }
+}
+
+/// 限价单的标志位
+const FLAG_POST_ONLY_REJECT: u8 = 1;
+const FLAG_POST_ONLY_SLIDE: u8 = 2;
+const FLAG_REDUCE_ONLY: u8 = 4;
+/// 第 3..6 位是自成交防护, 取值同 `SelfTrade::encode`
+const SELF_TRADE_SHIFT: u8 = 3;
+const SELF_TRADE_MODES: [SelfTrade; 4] =
+    [SelfTrade::CancelNewest, SelfTrade::CancelOldest, SelfTrade::CancelBoth, SelfTrade::DecrementAndCancel];
+
+impl SelfTrade {
+    /// 日志和快照中的编码: 0 为不防护, 否则为 `SELF_TRADE_MODES` 的下标 + 1
+    pub fn encode(mode: Option<SelfTrade>) -> u8 {
+        mode.map_or(0, |m| SELF_TRADE_MODES.iter().position(|&x| x == m).unwrap() as u8 + 1)
+    }
+
+    pub fn decode(code: u8) -> Result<Option<SelfTrade>, String> {
+        match code as usize {
+            0 => Ok(None),
+            i if i <= SELF_TRADE_MODES.len() => Ok(Some(SELF_TRADE_MODES[i - 1])),
+            _ => Err(format!("bad self-trade mode {}", code)),
+        }
+    }
+}
+
+const TAG_LIMIT: u8 = 1;
+const TAG_CANCEL: u8 = 2;
+const TAG_STOP: u8 = 3;
+const TAG_GROUP: u8 = 4;
+
+impl Command {
+    /// 定长小端编码, 第一个字节是命令类型, 每种命令只有一种布局:
+    ///
+    /// - 限价单 1: `订单号 | 方向 u8 | 价格 | 数量 | 显示数量 (0 为普通订单) | 账户 | 标志 u8`
+    /// - 撤单 2: `订单号`
+    /// - 止损单 3: `订单号 | 方向 u8 | 触发价 | 有无限价 u8 | 限价 | 数量 | 账户 | 自成交防护 u8`
+    /// - 交易组 4: `账户 | 组号`
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Command::Limit(o) => {
+                out.push(TAG_LIMIT);
+                out.extend_from_slice(&o.id.to_le_bytes());
+                out.push(o.side as u8);
+                out.extend_from_slice(&o.price.to_le_bytes());
+                out.extend_from_slice(&o.qty.to_le_bytes());
+                // 显示数量不能为 0, 用 0 表示普通订单
+                out.extend_from_slice(&o.display.unwrap_or(0).to_le_bytes());
+                out.extend_from_slice(&o.account.to_le_bytes());
+                let flags = match o.post_only {
+                    Some(PostOnly::Reject) => FLAG_POST_ONLY_REJECT,
+                    Some(PostOnly::Slide) => FLAG_POST_ONLY_SLIDE,
+                    None => 0,
+                } | if o.reduce_only { FLAG_REDUCE_ONLY } else { 0 }
+                    | SelfTrade::encode(o.self_trade) << SELF_TRADE_SHIFT;
+                out.push(flags);
+            }
+            Command::Cancel { id } => {
+                out.push(TAG_CANCEL);
+                out.extend_from_slice(&id.to_le_bytes());
+            }
+            Command::Stop(s) => {
+                out.push(TAG_STOP);
+                out.extend_from_slice(&s.id.to_le_bytes());
+                out.push(s.side as u8);
+                out.extend_from_slice(&s.trigger.to_le_bytes());
+                out.push(s.limit.is_some() as u8);
+                out.extend_from_slice(&s.limit.unwrap_or(0).to_le_bytes());
+                out.extend_from_slice(&s.qty.to_le_bytes());
+                out.extend_from_slice(&s.account.to_le_bytes());
+                out.push(SelfTrade::encode(s.self_trade));
+            }
+            Command::Group { account, group } => {
+                out.push(TAG_GROUP);
+                out.extend_from_slice(&account.to_le_bytes());
+                out.extend_from_slice(&group.to_le_bytes());
+            }
+        }
+    }
+
+    pub fn decode(buf: &[u8]) -> Result<Command, String> {
+        let u64_at = |at: usize| -> Result<u64, String> {
+            buf.get(at..at + 8)
+                .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+                .ok_or_else(|| "command truncated".to_string())
+        };
+        let side = || match buf.get(9) {
+            Some(0) => Ok(Side::Buy),
+            Some(1) => Ok(Side::Sell),
+            other => Err(format!("bad side {:?}", other)),
+        };
+        let (cmd, len) = match buf.first() {
+            Some(&TAG_LIMIT) => {
+                let mut order = NewOrder::limit(u64_at(1)?, side()?, u64_at(10)?, u64_at(18)?);
+                order.display = Some(u64_at(26)?).filter(|&d| d > 0);
+                order.account = u64_at(34)?;
+                let flags = *buf.get(42).ok_or("command truncated")?;
+                order.self_trade = SelfTrade::decode(flags >> SELF_TRADE_SHIFT).map_err(|_| format!("bad flags {:#x}", flags))?;
+                order.post_only = match flags & (FLAG_POST_ONLY_REJECT | FLAG_POST_ONLY_SLIDE) {
+                    0 => None,
+                    FLAG_POST_ONLY_REJECT => Some(PostOnly::Reject),
+                    FLAG_POST_ONLY_SLIDE => Some(PostOnly::Slide),
+                    _ => return Err(format!("bad flags {:#x}", flags)),
+                };
+                order.reduce_only = flags & FLAG_REDUCE_ONLY != 0;
+                (Command::Limit(order), 43)
+            }
+            Some(&TAG_CANCEL) => (Command::Cancel { id: u64_at(1)? }, 9),
+            Some(&TAG_STOP) => {
+                let limit = match buf.get(18) {
+                    Some(0) => None,
+                    Some(1) => Some(u64_at(19)?),
+                    other => return Err(format!("bad limit flag {:?}", other)),
+                };
+                let account = u64_at(35)?;
+                let self_trade = SelfTrade::decode(*buf.get(43).ok_or("command truncated")?)?;
+                let stop = StopOrder { id: u64_at(1)?, side: side()?, trigger: u64_at(10)?, limit, qty: u64_at(27)?, account, self_trade };
+                (Command::Stop(stop), 44)
+            }
+            Some(&TAG_GROUP) => (Command::Group { account: u64_at(1)?, group: u64_at(9)? }, 17),
+            other => return Err(format!("bad command tag {:?}", other)),
+        };
+        if buf.len() != len {
+            return Err(format!("command length {} != {}", buf.len(), len));
+        }
+        Ok(cmd)
+    }
+}
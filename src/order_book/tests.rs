@@ -0,0 +1,368 @@
+#[cfg(test)]
+mod tests {
+    use crate::order_book::order_book::{
+        BPOrderBook, CancelEvent, CancelReason, Command, NewOrder, OrderBook, PostOnly, RBOrderBook, SelfTrade, Side, StopOrder, Trade,
+    };
+    use crate::rb_tree::rb_tree::RBTree;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn limit(id: u64, side: Side, price: u64, qty: u64) -> Command {
+        Command::Limit(NewOrder::limit(id, side, price, qty))
+    }
+
+    fn stop(id: u64, side: Side, trigger: u64, limit: Option<u64>, qty: u64) -> Command {
+        Command::Stop(StopOrder { id, side, trigger, limit, qty, account: 0, self_trade: None })
+    }
+
+    #[test]
+    fn test_order_book_matching() {
+        let mut book = RBOrderBook::with_rb_tree();
+        assert!(book.apply(&limit(1, Side::Sell, 101, 5)).unwrap().is_empty());
+        assert!(book.apply(&limit(2, Side::Sell, 101, 5)).unwrap().is_empty());
+        assert!(book.apply(&limit(3, Side::Sell, 103, 5)).unwrap().is_empty());
+        assert!(book.apply(&limit(4, Side::Buy, 99, 5)).unwrap().is_empty());
+        assert_eq!((book.best_bid(), book.best_ask()), (Some(99), Some(101)));
+
+        // 吃掉 101 档 (时间优先) 和 103 档的一部分, 剩余挂在 103
+        let trades = book.apply(&limit(5, Side::Buy, 103, 12)).unwrap();
+        let want = [(1, 101, 5), (2, 101, 5), (3, 103, 2)]
+            .map(|(maker, price, qty)| Trade { maker, taker: 5, price, qty });
+        assert_eq!(trades, want);
+        assert_eq!(book.depth(Side::Sell, 5), vec![(103, 3)]);
+        assert_eq!(book.depth(Side::Buy, 5), vec![(99, 5)]);
+        assert_eq!(book.last_price(), Some(103));
+
+        assert!(book.apply(&limit(4, Side::Buy, 50, 1)).is_err());
+        assert!(book.apply(&limit(6, Side::Buy, 50, 0)).is_err());
+        assert!(book.apply(&Command::Cancel { id: 1 }).is_err());
+        book.apply(&Command::Cancel { id: 4 }).unwrap();
+        assert_eq!(book.best_bid(), None);
+        assert_eq!(book.len(), 1);
+        assert_eq!(book.seq(), 6);
+    }
+
+    #[test]
+    fn test_order_book_backends_agree() {
+        let mut rb = RBOrderBook::with_rb_tree();
+        let mut bp = BPOrderBook::with_bptree(3);
+        let mut rng = StdRng::seed_from_u64(42);
+        let mut live = Vec::new();
+        for id in 0..20_000u64 {
+            let cmd = if !live.is_empty() && rng.gen_bool(0.3) {
+                Command::Cancel { id: live.swap_remove(rng.gen_range(0..live.len())) }
+            } else if rng.gen_bool(0.01) {
+                Command::Group { account: rng.gen_range(1..4), group: rng.gen_range(0..2) }
+            } else if rng.gen_bool(0.1) {
+                let side = if rng.gen_bool(0.5) { Side::Buy } else { Side::Sell };
+                let limit = rng.gen_bool(0.5).then(|| rng.gen_range(950..1050));
+                live.push(id);
+                stop(id, side, rng.gen_range(970..1030), limit, rng.gen_range(1..50))
+            } else {
+                // 买卖价只在 990..1010 重叠, 盘口两侧保持足够深度
+                let (side, price) = if rng.gen_bool(0.5) {
+                    (Side::Buy, rng.gen_range(900..1010))
+                } else {
+                    (Side::Sell, rng.gen_range(990..1100))
+                };
+                live.push(id);
+                let qty = rng.gen_range(1..50);
+                // 混入冰山单、只挂单和只减仓单, 分属几个账户
+                let mut order = NewOrder::limit(id, side, price, qty);
+                if rng.gen_bool(0.2) {
+                    order = NewOrder { qty: qty * 4, display: Some(qty), ..order };
+                }
+                order.account = rng.gen_range(0..4);
+                order.post_only = rng.gen_bool(0.1).then_some(PostOnly::Slide);
+                order.reduce_only = rng.gen_bool(0.1);
+                let modes = [SelfTrade::CancelNewest, SelfTrade::CancelOldest, SelfTrade::CancelBoth, SelfTrade::DecrementAndCancel];
+                order.self_trade = rng.gen_bool(0.3).then(|| modes[rng.gen_range(0..4)]);
+                Command::Limit(order)
+            };
+            // 已完全成交的订单撤单会被拒绝, 两边也必须一致
+            let a = rb.execute(&cmd);
+            assert_eq!(a, bp.execute(&cmd));
+            if id % 1000 == 0 {
+                // 有界的 depth 与完整状态一致; 只读查询取 &self
+                let state = rb.state();
+                let (rb, bp) = (&rb, &bp);
+                assert_eq!(rb.best_bid(), state.bids.first().map(|l| l.0));
+                assert_eq!(bp.best_ask(), state.asks.first().map(|l| l.0));
+                assert_eq!(rb.validate(&cmd).is_ok(), bp.validate(&cmd).is_ok());
+                for (side, levels) in [(Side::Buy, &state.bids), (Side::Sell, &state.asks)] {
+                    let want: Vec<_> = levels.iter().take(7).map(|(p, q)| (*p, q.iter().map(|(_, q)| q).sum())).collect();
+                    assert_eq!(rb.depth(side, 7), want);
+                    assert_eq!(bp.depth(side, 7), want);
+                }
+            }
+        }
+        assert!(rb.len() > 100 && rb.stop_count() > 10);
+        let state = rb.state();
+        assert!(state.icebergs.iter().any(|&(_, _, hidden)| hidden > 0));
+        assert!(!state.positions.is_empty() && !state.groups.is_empty());
+        assert_eq!(state, bp.state());
+    }
+
+    #[test]
+    fn test_stop_orders_trigger_in_priority() {
+        let mut book = BPOrderBook::with_bptree(3);
+        for (id, price) in [(1, 101), (2, 102), (3, 103), (4, 104), (5, 105)] {
+            book.apply(&limit(id, Side::Sell, price, 5)).unwrap();
+        }
+        book.apply(&limit(6, Side::Buy, 95, 10)).unwrap();
+        // 没有成交价之前不会触发
+        assert!(book.apply(&stop(10, Side::Buy, 103, Some(104), 4)).unwrap().is_empty());
+        assert!(book.apply(&stop(11, Side::Buy, 102, None, 6)).unwrap().is_empty());
+        assert!(book.apply(&stop(12, Side::Sell, 96, None, 3)).unwrap().is_empty());
+        assert!(book.apply(&stop(13, Side::Buy, 110, Some(120), 1)).unwrap().is_empty());
+        assert_eq!(book.stop_count(), 4);
+        assert!(book.apply(&stop(10, Side::Sell, 90, None, 1)).is_err());
+        assert!(book.apply(&stop(14, Side::Sell, 90, None, 0)).is_err());
+
+        // 成交价到 102: 先释放触发价更低的 11 (市价), 它把价格推到 103, 连锁触发 10 (限价 104)
+        let trades = book.apply(&limit(7, Side::Buy, 102, 6)).unwrap();
+        let want = [(1, 7, 101, 5), (2, 7, 102, 1), (2, 11, 102, 4), (3, 11, 103, 2), (3, 10, 103, 3), (4, 10, 104, 1)]
+            .map(|(maker, taker, price, qty)| Trade { maker, taker, price, qty });
+        assert_eq!(trades, want);
+        assert_eq!(book.last_price(), Some(104));
+        assert_eq!(book.depth(Side::Sell, 5), vec![(104, 4), (105, 5)]);
+        assert_eq!(book.stop_count(), 2);
+        assert!(book.stop(13).is_some());
+
+        // 撤销未触发的止损单
+        book.apply(&Command::Cancel { id: 13 }).unwrap();
+        assert_eq!(book.stop(13), None);
+
+        // 已满足条件的止损单立即释放; 市价止损单没成交完的部分作废
+        let trades = book.apply(&stop(15, Side::Sell, 200, None, 20)).unwrap();
+        assert_eq!(trades, vec![Trade { maker: 6, taker: 15, price: 95, qty: 10 }]);
+        // 成交价跌到 95 又触发了卖出止损单 12, 但买盘已空
+        assert_eq!(book.stop_count(), 0);
+        assert_eq!(book.order(15), None);
+        assert_eq!(book.best_bid(), None);
+
+        let cmd = stop(16, Side::Buy, 7, Some(9), 3);
+        let mut buf = Vec::new();
+        cmd.encode(&mut buf);
+        assert_eq!(Command::decode(&buf).unwrap(), cmd);
+    }
+
+    #[test]
+    fn test_iceberg_orders() {
+        let mut book = RBOrderBook::with_rb_tree();
+        let iceberg = |id, side, price, qty, display| Command::Limit(NewOrder { display: Some(display), ..NewOrder::limit(id, side, price, qty) });
+        book.apply(&iceberg(1, Side::Sell, 100, 30, 10)).unwrap();
+        book.apply(&limit(2, Side::Sell, 100, 5)).unwrap();
+        // 深度只显示冰山单的显示部分
+        assert_eq!(book.depth(Side::Sell, 5), vec![(100, 15)]);
+        assert_eq!((book.order(1).unwrap().hidden, book.order(1).unwrap().total()), (20, 30));
+        assert!(book.apply(&iceberg(3, Side::Buy, 90, 10, 0)).is_err());
+
+        // 显示部分吃完后补充并排到队尾, 订单 2 先成交
+        let trades = book.apply(&limit(3, Side::Buy, 100, 12)).unwrap();
+        let want = [(1, 3, 10), (2, 3, 2)].map(|(maker, taker, qty)| Trade { maker, taker, price: 100, qty });
+        assert_eq!(trades, want);
+        assert_eq!(book.state().asks, vec![(100, vec![(2, 3), (1, 10)])]);
+
+        // 同一笔吃单可以连续吃到补充后的显示部分
+        let trades = book.apply(&limit(4, Side::Buy, 100, 20)).unwrap();
+        let want = [(2, 4, 3), (1, 4, 10), (1, 4, 7)].map(|(maker, taker, qty)| Trade { maker, taker, price: 100, qty });
+        assert_eq!(trades, want);
+        assert_eq!(book.depth(Side::Sell, 5), vec![(100, 3)]);
+        assert_eq!(book.order(1).unwrap().hidden, 0);
+
+        // 主动成交的冰山单按全部数量吃单, 剩余部分只显示 5
+        let trades = book.apply(&iceberg(5, Side::Buy, 101, 25, 5)).unwrap();
+        assert_eq!(trades, vec![Trade { maker: 1, taker: 5, price: 100, qty: 3 }]);
+        assert_eq!(book.depth(Side::Buy, 5), vec![(101, 5)]);
+        assert_eq!(book.state().icebergs, vec![(5, 5, 17)]);
+
+        let restored = OrderBook::restore(book.state(), RBTree::new(), RBTree::new()).unwrap();
+        assert_eq!(restored.order(5), book.order(5));
+        let cmd = iceberg(6, Side::Sell, 7, 9, 3);
+        let mut buf = Vec::new();
+        cmd.encode(&mut buf);
+        assert_eq!(Command::decode(&buf).unwrap(), cmd);
+    }
+
+    #[test]
+    fn test_post_only_and_reduce_only() {
+        let order = |id, side, price, qty, account| NewOrder { account, ..NewOrder::limit(id, side, price, qty) };
+        let mut book = RBOrderBook::with_rb_tree();
+        book.apply(&Command::Limit(order(1, Side::Sell, 101, 10, 1))).unwrap();
+        book.apply(&Command::Limit(order(2, Side::Buy, 99, 10, 1))).unwrap();
+
+        // 只挂单: 会成交时拒绝, 或移到对手价内侧一档
+        let post = |id, side, price, mode| Command::Limit(NewOrder { post_only: Some(mode), ..order(id, side, price, 5, 2) });
+        assert!(book.validate(&post(3, Side::Buy, 101, PostOnly::Reject)).is_err());
+        assert!(book.apply(&post(3, Side::Buy, 101, PostOnly::Reject)).is_err());
+        assert!(book.apply(&post(3, Side::Buy, 105, PostOnly::Slide)).unwrap().is_empty());
+        assert_eq!(book.order(3).unwrap().price, 100);
+        assert!(book.apply(&post(4, Side::Sell, 90, PostOnly::Slide)).unwrap().is_empty());
+        assert_eq!(book.order(4).unwrap().price, 101);
+        assert!(book.apply(&post(5, Side::Buy, 100, PostOnly::Reject)).unwrap().is_empty());
+
+        // 持仓: 账户 3 买入 4, 卖方账户 1 持仓 -4
+        assert_eq!(book.apply(&Command::Limit(order(6, Side::Buy, 101, 4, 3))).unwrap().len(), 1);
+        assert_eq!((book.position(3), book.position(1)), (4, -4));
+
+        // 只减仓: 空仓时拒绝; 吃单数量被截断到持仓, 不会反向开仓
+        let reduce = |id, side, price, qty, account| Command::Limit(NewOrder { reduce_only: true, ..order(id, side, price, qty, account) });
+        assert!(book.apply(&reduce(7, Side::Sell, 100, 1, 9)).is_err());
+        assert!(book.apply(&reduce(7, Side::Buy, 100, 1, 3)).is_err());
+        let trades = book.apply(&reduce(8, Side::Sell, 100, 10, 3)).unwrap();
+        assert_eq!(trades.iter().map(|t| t.qty).sum::<u64>(), 4);
+        assert!(book.order(8).is_none());
+        assert_eq!(book.position(3), 0);
+
+        // 挂单方只减仓: 持仓平掉后剩余部分被撤掉, 后面的订单接着成交
+        book.apply(&Command::Limit(order(9, Side::Sell, 99, 3, 4))).unwrap();
+        assert_eq!(book.position(4), -3);
+        book.apply(&reduce(10, Side::Buy, 97, 8, 4)).unwrap();
+        assert_eq!(book.order(10).unwrap().qty, 3);
+        book.apply(&Command::Limit(order(11, Side::Buy, 101, 3, 4))).unwrap();
+        assert_eq!(book.position(4), 0);
+        book.apply(&Command::Limit(order(12, Side::Buy, 97, 2, 5))).unwrap();
+        let trades = book.apply(&Command::Limit(order(13, Side::Sell, 97, 15, 6))).unwrap();
+        assert!(trades.iter().all(|t| t.maker != 10));
+        assert_eq!(trades.last(), Some(&Trade { maker: 12, taker: 13, price: 97, qty: 2 }));
+        assert!(book.order(10).is_none());
+        assert_eq!(book.position(4), 0);
+
+        // 编码和快照都保留账户与标志
+        let cmd = Command::Limit(NewOrder { display: Some(2), post_only: Some(PostOnly::Slide), ..order(14, Side::Sell, 120, 6, 7) });
+        let mut buf = Vec::new();
+        cmd.encode(&mut buf);
+        assert_eq!(Command::decode(&buf).unwrap(), cmd);
+        let stop = Command::Stop(StopOrder { id: 15, side: Side::Buy, trigger: 130, limit: None, qty: 1, account: 7, self_trade: None });
+        buf.clear();
+        stop.encode(&mut buf);
+        assert_eq!(Command::decode(&buf).unwrap(), stop);
+        let restored = OrderBook::restore(book.state(), RBTree::new(), RBTree::new()).unwrap();
+        assert_eq!(restored.state(), book.state());
+        assert_eq!(restored.position(1), book.position(1));
+    }
+
+    #[test]
+    fn test_self_trade_prevention() {
+        let order = |id, side, price, qty, account| NewOrder { account, ..NewOrder::limit(id, side, price, qty) };
+        let stp = |id, side, price, qty, account, mode| Command::Limit(NewOrder { self_trade: Some(mode), ..order(id, side, price, qty, account) });
+        let stp_cancel = |id, qty, mode, counterparty| CancelEvent { id, qty, reason: CancelReason::SelfTrade { mode, counterparty } };
+        let mut book = RBOrderBook::with_rb_tree();
+        assert!(book.apply(&Command::Group { account: 0, group: 10 }).is_err());
+        book.apply(&Command::Group { account: 1, group: 10 }).unwrap();
+        book.apply(&Command::Group { account: 2, group: 10 }).unwrap();
+        book.apply(&Command::Limit(order(1, Side::Sell, 101, 5, 1))).unwrap();
+        book.apply(&Command::Limit(order(2, Side::Sell, 101, 5, 3))).unwrap();
+
+        // 同组的挂单被撤掉, 吃单继续和后面的订单成交
+        let e = book.execute(&stp(3, Side::Buy, 101, 4, 2, SelfTrade::CancelOldest)).unwrap();
+        assert_eq!(e.cancels, vec![stp_cancel(1, 5, SelfTrade::CancelOldest, 3)]);
+        assert_eq!(e.trades, vec![Trade { maker: 2, taker: 3, price: 101, qty: 4 }]);
+
+        // 同一账户: 撤掉吃单, 挂单不动
+        let e = book.execute(&stp(4, Side::Buy, 101, 3, 3, SelfTrade::CancelNewest)).unwrap();
+        assert!(e.trades.is_empty() && book.order(4).is_none());
+        assert_eq!(e.cancels, vec![stp_cancel(4, 3, SelfTrade::CancelNewest, 2)]);
+        assert_eq!(book.order(2).unwrap().qty, 1);
+
+        // 减量: 冰山挂单先减隐藏部分, 吃单减到 0
+        let iceberg = NewOrder { display: Some(2), ..order(5, Side::Sell, 102, 6, 1) };
+        book.apply(&Command::Limit(iceberg)).unwrap();
+        let e = book.execute(&stp(6, Side::Buy, 102, 3, 2, SelfTrade::DecrementAndCancel)).unwrap();
+        assert_eq!(e.trades, vec![Trade { maker: 2, taker: 6, price: 101, qty: 1 }]);
+        let mode = SelfTrade::DecrementAndCancel;
+        assert_eq!(e.cancels, vec![stp_cancel(5, 2, mode, 6), stp_cancel(6, 2, mode, 5)]);
+        assert_eq!((book.order(5).unwrap().qty, book.order(5).unwrap().hidden), (2, 2));
+
+        // 两边都撤
+        let e = book.execute(&stp(7, Side::Buy, 102, 1, 1, SelfTrade::CancelBoth)).unwrap();
+        assert_eq!(e.cancels, vec![stp_cancel(5, 4, SelfTrade::CancelBoth, 7), stp_cancel(7, 1, SelfTrade::CancelBoth, 5)]);
+        assert!(book.best_ask().is_none() && book.is_empty());
+
+        // 退出交易组后不再防护; 交易组随快照状态和日志编码保存
+        book.apply(&Command::Group { account: 2, group: 0 }).unwrap();
+        book.apply(&Command::Limit(order(8, Side::Sell, 103, 2, 1))).unwrap();
+        assert_eq!(book.apply(&stp(9, Side::Buy, 103, 2, 2, SelfTrade::CancelBoth)).unwrap().len(), 1);
+        assert_eq!((book.group(1), book.group(2)), (Some(10), None));
+        let restored = OrderBook::restore(book.state(), RBTree::new(), RBTree::new()).unwrap();
+        assert_eq!(restored.state(), book.state());
+        for cmd in [Command::Group { account: 1, group: 10 }, stp(10, Side::Sell, 99, 1, 4, SelfTrade::DecrementAndCancel)] {
+            let mut buf = Vec::new();
+            cmd.encode(&mut buf);
+            assert_eq!(Command::decode(&buf).unwrap(), cmd);
+        }
+    }
+
+    #[test]
+    fn test_stop_keeps_self_trade_prevention() {
+        // 触发后的止损单沿用自己的 STP: 遇到同账户的挂单时按防护处理, 不与之成交
+        let order = |id, side, price, qty, account| Command::Limit(NewOrder { account, ..NewOrder::limit(id, side, price, qty) });
+        let mut book = RBOrderBook::with_rb_tree();
+        book.apply(&order(1, Side::Sell, 100, 2, 1)).unwrap();
+        book.apply(&order(2, Side::Sell, 101, 3, 2)).unwrap();
+        let stop = StopOrder {
+            id: 3,
+            side: Side::Buy,
+            trigger: 100,
+            limit: Some(101),
+            qty: 4,
+            account: 1,
+            self_trade: Some(SelfTrade::CancelOldest),
+        };
+        book.apply(&Command::Stop(stop)).unwrap();
+        // 快照状态恢复出的止损单同样带着 STP
+        let mut book = OrderBook::restore(book.state(), RBTree::new(), RBTree::new()).unwrap();
+
+        let e = book.execute(&order(4, Side::Buy, 100, 1, 3)).unwrap();
+        let cancel = CancelEvent { id: 1, qty: 1, reason: CancelReason::SelfTrade { mode: SelfTrade::CancelOldest, counterparty: 3 } };
+        assert_eq!(e.cancels, vec![cancel]);
+        let want = [(1, 4, 100, 1), (2, 3, 101, 3)].map(|(maker, taker, price, qty)| Trade { maker, taker, price, qty });
+        assert_eq!(e.trades, want);
+        assert_eq!((book.best_bid(), book.order(3).unwrap().qty), (Some(101), 1));
+        assert_eq!(book.position(1), 2);
+    }
+
+    #[test]
+    fn test_command_encoding_fixture() {
+        // 日志中的字节布局是固定的: 每种命令只有一个类型字节和一种长度
+        let le = |v: u64| v.to_le_bytes().to_vec();
+        let order = NewOrder {
+            display: Some(4),
+            account: 7,
+            post_only: Some(PostOnly::Slide),
+            reduce_only: true,
+            self_trade: Some(SelfTrade::CancelBoth),
+            ..NewOrder::limit(11, Side::Sell, 105, 12)
+        };
+        let fixtures = [
+            (Command::Limit(order), [vec![1], le(11), vec![1], le(105), le(12), le(4), le(7), vec![2 | 4 | (3 << 3)]].concat()),
+            (limit(12, Side::Buy, 99, 5), [vec![1], le(12), vec![0], le(99), le(5), le(0), le(0), vec![0]].concat()),
+            (Command::Cancel { id: 13 }, [vec![2], le(13)].concat()),
+            (
+                Command::Stop(StopOrder {
+                    id: 14,
+                    side: Side::Buy,
+                    trigger: 101,
+                    limit: Some(102),
+                    qty: 3,
+                    account: 7,
+                    self_trade: Some(SelfTrade::CancelOldest),
+                }),
+                [vec![3], le(14), vec![0], le(101), vec![1], le(102), le(3), le(7), vec![2]].concat(),
+            ),
+            (stop(15, Side::Sell, 98, None, 2), [vec![3], le(15), vec![1], le(98), vec![0], le(0), le(2), le(0), vec![0]].concat()),
+            (Command::Group { account: 7, group: 9 }, [vec![4], le(7), le(9)].concat()),
+        ];
+        for (cmd, bytes) in fixtures {
+            let mut buf = Vec::new();
+            cmd.encode(&mut buf);
+            assert_eq!(buf, bytes, "{:?}", cmd);
+            assert_eq!(Command::decode(&bytes).unwrap(), cmd);
+            assert!(Command::decode(&bytes[..bytes.len() - 1]).is_err());
+        }
+        for tag in [0, 5, 6, 7] {
+            assert!(Command::decode(&[vec![tag], le(1), le(2)].concat()).is_err());
+        }
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod paged_bptree;
+#[allow(clippy::module_inception)]
+pub mod tests;
@@ -0,0 +1,516 @@
+use std::fs::OpenOptions;
+use std::io::{self, Read};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::buffer_pool::buffer_pool::{BufferPool, PageId, PoolStats, NO_PAGE};
+
+/// 定长二进制编码, 页内按固定槽位存放 key/value
+pub trait FixedCodec: Sized {
+    /// 编码后的字节数
+    const SIZE: usize;
+    fn encode(&self, buf: &mut [u8]);
+    fn decode(buf: &[u8]) -> Self;
+}
+
+macro_rules! impl_fixed_codec {
+    ($($t:ty),*) => {$(
+        impl FixedCodec for $t {
+            const SIZE: usize = std::mem::size_of::<$t>();
+            fn encode(&self, buf: &mut [u8]) {
+                buf[..Self::SIZE].copy_from_slice(&self.to_le_bytes());
+            }
+            fn decode(buf: &[u8]) -> Self {
+                <$t>::from_le_bytes(buf[..Self::SIZE].try_into().unwrap())
+            }
+        }
+    )*};
+}
+
+impl_fixed_codec!(u16, u32, u64, i32, i64);
+
+/// 元数据页 (第 0 页) 的魔数
+const MAGIC: &[u8; 4] = b"OBPT";
+const FORMAT_VERSION: u32 = 1;
+const META_PAGE: PageId = 0;
+/// 元数据页的有效长度
+const META_LEN: usize = 36;
+
+/// 节点页头: 类型 u8 | 保留 u8 | 条目数 u16 | 叶子右兄弟/空闲链表下一页 u32
+const HEADER: usize = 8;
+const LEAF: u8 = 0;
+const INTERNAL: u8 = 1;
+const FREE: u8 = 2;
+
+/// 解码后的节点; 修改后整页重新编码写回
+struct Node<K, V> {
+    leaf: bool,
+    keys: Vec<K>,
+    /// 仅叶子
+    vals: Vec<V>,
+    /// 仅内部节点, children.len() = keys.len() + 1
+    children: Vec<PageId>,
+    /// 叶子右兄弟
+    next: PageId,
+}
+
+impl<K, V> Node<K, V> {
+    fn leaf() -> Self {
+        Node {
+            leaf: true,
+            keys: Vec::new(),
+            vals: Vec::new(),
+            children: Vec::new(),
+            next: NO_PAGE,
+        }
+    }
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(buf[at..at + 4].try_into().unwrap())
+}
+
+fn write_u32(buf: &mut [u8], at: usize, v: u32) {
+    buf[at..at + 4].copy_from_slice(&v.to_le_bytes());
+}
+
+/// 磁盘分页 B+Tree
+///
+/// 节点是文件中的定长页, 子指针和叶子右兄弟都是页号; 页通过 `BufferPool` 缓存,
+/// 内存中最多驻留 `pool_pages` 个页. 第 0 页为元数据 (根页号、条目数、空闲页链表),
+/// 合并释放的页进入空闲链表复用.
+///
+/// 修改先写进缓冲池, `flush` (或 drop) 时写回文件; 没有日志, 中途崩溃不保证文件一致.
+pub struct PagedBPTree<K: FixedCodec, V: FixedCodec> {
+    pool: BufferPool,
+    root: PageId,
+    len: u64,
+    free_head: PageId,
+    max_leaf: usize,
+    max_internal: usize,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K: FixedCodec + Ord + Clone, V: FixedCodec + Clone> PagedBPTree<K, V> {
+    /// 新建 (覆盖) 文件
+    pub fn create<P: AsRef<Path>>(path: P, page_size: usize, pool_pages: usize) -> io::Result<Self> {
+        let (max_leaf, max_internal) = Self::capacities(page_size)?;
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        let mut pool = BufferPool::new(file, page_size, pool_pages)?;
+        pool.allocate()?;
+        let root = pool.allocate()?;
+        let mut tree = PagedBPTree {
+            pool,
+            root,
+            len: 0,
+            free_head: NO_PAGE,
+            max_leaf,
+            max_internal,
+            _marker: PhantomData,
+        };
+        tree.write_node(root, &Node::leaf())?;
+        tree.write_meta()?;
+        Ok(tree)
+    }
+
+    /// 打开已有文件, 页大小从元数据页读取
+    pub fn open<P: AsRef<Path>>(path: P, pool_pages: usize) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mut meta = [0u8; META_LEN];
+        file.read_exact(&mut meta)?;
+        if &meta[0..4] != MAGIC {
+            return Err(invalid("bad magic".to_string()));
+        }
+        if read_u32(&meta, 4) != FORMAT_VERSION {
+            return Err(invalid(format!("unsupported format version {}", read_u32(&meta, 4))));
+        }
+        if read_u32(&meta, 12) as usize != K::SIZE || read_u32(&meta, 16) as usize != V::SIZE {
+            return Err(invalid("key/value size mismatch".to_string()));
+        }
+        let page_size = read_u32(&meta, 8) as usize;
+        let (max_leaf, max_internal) = Self::capacities(page_size)?;
+        Ok(PagedBPTree {
+            pool: BufferPool::new(file, page_size, pool_pages)?,
+            root: read_u32(&meta, 20),
+            free_head: read_u32(&meta, 24),
+            len: u64::from_le_bytes(meta[28..36].try_into().unwrap()),
+            max_leaf,
+            max_internal,
+            _marker: PhantomData,
+        })
+    }
+
+    /// 每页最多容纳的 (叶子条目数, 内部节点 key 数)
+    fn capacities(page_size: usize) -> io::Result<(usize, usize)> {
+        let max_leaf = page_size.saturating_sub(HEADER) / (K::SIZE + V::SIZE);
+        let max_internal = page_size.saturating_sub(HEADER + 4) / (K::SIZE + 4);
+        if page_size < META_LEN || max_leaf < 3 || max_internal < 3 || max_leaf > u16::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("page size {} cannot hold 3 entries per node", page_size),
+            ));
+        }
+        Ok((max_leaf, max_internal))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 文件中的页数 (含元数据页和空闲页)
+    pub fn page_count(&self) -> u32 {
+        self.pool.page_count()
+    }
+
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+
+    /// 树高 (只有一个叶子时为 1)
+    pub fn height(&mut self) -> io::Result<usize> {
+        let mut node = self.read_node(self.root)?;
+        let mut height = 1;
+        while !node.leaf {
+            node = self.read_node(node.children[0])?;
+            height += 1;
+        }
+        Ok(height)
+    }
+
+    /// 写回元数据和所有脏页并 fsync
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write_meta()?;
+        self.pool.sync()
+    }
+
+    pub fn get(&mut self, key: &K) -> io::Result<Option<V>> {
+        let leaf = self.find_leaf(key)?;
+        Ok(leaf.keys.binary_search(key).ok().map(|i| leaf.vals[i].clone()))
+    }
+
+    /// [start, end] 闭区间, 沿磁盘上的叶子右兄弟链扫描
+    pub fn range_query(&mut self, start: &K, end: &K) -> io::Result<Vec<V>> {
+        let mut out = Vec::new();
+        let mut leaf = self.find_leaf(start)?;
+        loop {
+            let from = leaf.keys.partition_point(|k| k < start);
+            for (k, v) in leaf.keys[from..].iter().zip(&leaf.vals[from..]) {
+                if k > end {
+                    return Ok(out);
+                }
+                out.push(v.clone());
+            }
+            if leaf.next == NO_PAGE {
+                return Ok(out);
+            }
+            leaf = self.read_node(leaf.next)?;
+        }
+    }
+
+    /// 插入或覆盖; 返回是否新增了条目
+    pub fn insert(&mut self, key: K, value: V) -> io::Result<bool> {
+        let mut added = false;
+        if let Some((sep, right)) = self.insert_rec(self.root, key, value, &mut added)? {
+            // 根分裂, 树长高一层
+            let new_root = self.alloc_page()?;
+            let node = Node {
+                leaf: false,
+                keys: vec![sep],
+                vals: Vec::new(),
+                children: vec![self.root, right],
+                next: NO_PAGE,
+            };
+            self.write_node(new_root, &node)?;
+            self.root = new_root;
+        }
+        if added {
+            self.len += 1;
+        }
+        Ok(added)
+    }
+
+    pub fn delete(&mut self, key: &K) -> io::Result<Option<V>> {
+        let removed = self.delete_rec(self.root, key)?;
+        if removed.is_some() {
+            self.len -= 1;
+            // 根只剩一个孩子时树高减一
+            let root = self.read_node(self.root)?;
+            if !root.leaf && root.keys.is_empty() {
+                self.free_page(self.root)?;
+                self.root = root.children[0];
+            }
+        }
+        Ok(removed)
+    }
+
+    fn find_leaf(&mut self, key: &K) -> io::Result<Node<K, V>> {
+        let mut node = self.read_node(self.root)?;
+        while !node.leaf {
+            node = self.read_node(node.children[Self::child_index(&node, key)])?;
+        }
+        Ok(node)
+    }
+
+    /// 内部节点中 key 所在子树的下标 (keys[i] 是 children[i + 1] 的最小键)
+    fn child_index(node: &Node<K, V>, key: &K) -> usize {
+        node.keys.partition_point(|k| k <= key)
+    }
+
+    /// 递归插入; 节点分裂时返回 (分隔键, 新右节点页号)
+    fn insert_rec(&mut self, page: PageId, key: K, value: V, added: &mut bool) -> io::Result<Option<(K, PageId)>> {
+        let mut node = self.read_node(page)?;
+        if node.leaf {
+            match node.keys.binary_search(&key) {
+                Ok(i) => node.vals[i] = value,
+                Err(i) => {
+                    node.keys.insert(i, key);
+                    node.vals.insert(i, value);
+                    *added = true;
+                }
+            }
+            if node.keys.len() <= self.max_leaf {
+                self.write_node(page, &node)?;
+                return Ok(None);
+            }
+            // 叶子分裂, 新页接到右兄弟链上
+            let mid = node.keys.len() / 2;
+            let right_page = self.alloc_page()?;
+            let right = Node {
+                leaf: true,
+                keys: node.keys.split_off(mid),
+                vals: node.vals.split_off(mid),
+                children: Vec::new(),
+                next: node.next,
+            };
+            node.next = right_page;
+            let sep = right.keys[0].clone();
+            self.write_node(page, &node)?;
+            self.write_node(right_page, &right)?;
+            return Ok(Some((sep, right_page)));
+        }
+
+        let idx = Self::child_index(&node, &key);
+        let Some((sep, child)) = self.insert_rec(node.children[idx], key, value, added)? else {
+            return Ok(None);
+        };
+        node.keys.insert(idx, sep);
+        node.children.insert(idx + 1, child);
+        if node.keys.len() <= self.max_internal {
+            self.write_node(page, &node)?;
+            return Ok(None);
+        }
+        // 内部节点分裂: 中间键上提
+        let mid = node.keys.len() / 2;
+        let right_page = self.alloc_page()?;
+        let right = Node {
+            leaf: false,
+            keys: node.keys.split_off(mid + 1),
+            vals: Vec::new(),
+            children: node.children.split_off(mid + 1),
+            next: NO_PAGE,
+        };
+        let sep = node.keys.pop().unwrap();
+        self.write_node(page, &node)?;
+        self.write_node(right_page, &right)?;
+        Ok(Some((sep, right_page)))
+    }
+
+    fn delete_rec(&mut self, page: PageId, key: &K) -> io::Result<Option<V>> {
+        let mut node = self.read_node(page)?;
+        if node.leaf {
+            let Ok(i) = node.keys.binary_search(key) else {
+                return Ok(None);
+            };
+            node.keys.remove(i);
+            let value = node.vals.remove(i);
+            self.write_node(page, &node)?;
+            return Ok(Some(value));
+        }
+
+        let idx = Self::child_index(&node, key);
+        let removed = self.delete_rec(node.children[idx], key)?;
+        if removed.is_some() {
+            let child = self.read_node(node.children[idx])?;
+            if child.keys.len() < self.min_keys(&child) {
+                self.rebalance(&mut node, idx, child)?;
+                self.write_node(page, &node)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    fn min_keys(&self, node: &Node<K, V>) -> usize {
+        if node.leaf {
+            self.max_leaf / 2
+        } else {
+            self.max_internal / 2
+        }
+    }
+
+    /// 修复下溢的 children[idx]: 先向左/右兄弟借, 借不到就合并
+    fn rebalance(&mut self, parent: &mut Node<K, V>, idx: usize, mut child: Node<K, V>) -> io::Result<()> {
+        let child_page = parent.children[idx];
+        let left = match idx {
+            0 => None,
+            _ => Some(self.read_node(parent.children[idx - 1])?),
+        };
+        if let Some(mut left) = left.filter(|l| l.keys.len() > self.min_keys(l)) {
+            if child.leaf {
+                child.keys.insert(0, left.keys.pop().unwrap());
+                child.vals.insert(0, left.vals.pop().unwrap());
+                parent.keys[idx - 1] = child.keys[0].clone();
+            } else {
+                let sep = std::mem::replace(&mut parent.keys[idx - 1], left.keys.pop().unwrap());
+                child.keys.insert(0, sep);
+                child.children.insert(0, left.children.pop().unwrap());
+            }
+            self.write_node(parent.children[idx - 1], &left)?;
+            return self.write_node(child_page, &child);
+        }
+
+        if idx + 1 < parent.children.len() {
+            let mut right = self.read_node(parent.children[idx + 1])?;
+            if right.keys.len() > self.min_keys(&right) {
+                if child.leaf {
+                    child.keys.push(right.keys.remove(0));
+                    child.vals.push(right.vals.remove(0));
+                    parent.keys[idx] = right.keys[0].clone();
+                } else {
+                    let sep = std::mem::replace(&mut parent.keys[idx], right.keys.remove(0));
+                    child.keys.push(sep);
+                    child.children.push(right.children.remove(0));
+                }
+                self.write_node(parent.children[idx + 1], &right)?;
+                return self.write_node(child_page, &child);
+            }
+            // 右兄弟并入 child
+            return self.merge(parent, idx, child, right);
+        }
+        // 最右的孩子: 并入左兄弟
+        let left = self.read_node(parent.children[idx - 1])?;
+        self.merge(parent, idx - 1, left, child)
+    }
+
+    /// 把 children[i + 1] 并入 children[i], 释放右边的页
+    fn merge(&mut self, parent: &mut Node<K, V>, i: usize, mut left: Node<K, V>, right: Node<K, V>) -> io::Result<()> {
+        let sep = parent.keys.remove(i);
+        let right_page = parent.children.remove(i + 1);
+        if left.leaf {
+            left.next = right.next;
+        } else {
+            left.keys.push(sep);
+        }
+        left.keys.extend(right.keys);
+        left.vals.extend(right.vals);
+        left.children.extend(right.children);
+        self.write_node(parent.children[i], &left)?;
+        self.free_page(right_page)
+    }
+
+    /// 优先复用空闲链表中的页
+    fn alloc_page(&mut self) -> io::Result<PageId> {
+        if self.free_head == NO_PAGE {
+            return self.pool.allocate();
+        }
+        let page = self.free_head;
+        self.free_head = read_u32(self.pool.read(page)?, 4);
+        Ok(page)
+    }
+
+    fn free_page(&mut self, page: PageId) -> io::Result<()> {
+        let buf = self.pool.write(page)?;
+        buf.fill(0);
+        buf[0] = FREE;
+        write_u32(buf, 4, self.free_head);
+        self.free_head = page;
+        Ok(())
+    }
+
+    fn read_node(&mut self, page: PageId) -> io::Result<Node<K, V>> {
+        let buf = self.pool.read(page)?;
+        let count = u16::from_le_bytes([buf[2], buf[3]]) as usize;
+        let mut node = Node::leaf();
+        match buf[0] {
+            LEAF => {
+                node.next = read_u32(buf, 4);
+                let entry = K::SIZE + V::SIZE;
+                for i in 0..count {
+                    let at = HEADER + i * entry;
+                    node.keys.push(K::decode(&buf[at..]));
+                    node.vals.push(V::decode(&buf[at + K::SIZE..]));
+                }
+            }
+            INTERNAL => {
+                node.leaf = false;
+                node.children.push(read_u32(buf, HEADER));
+                let entry = K::SIZE + 4;
+                for i in 0..count {
+                    let at = HEADER + 4 + i * entry;
+                    node.keys.push(K::decode(&buf[at..]));
+                    node.children.push(read_u32(buf, at + K::SIZE));
+                }
+            }
+            kind => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("page {} is not a tree node (kind {})", page, kind),
+                ))
+            }
+        }
+        Ok(node)
+    }
+
+    fn write_node(&mut self, page: PageId, node: &Node<K, V>) -> io::Result<()> {
+        let buf = self.pool.write(page)?;
+        buf.fill(0);
+        buf[0] = if node.leaf { LEAF } else { INTERNAL };
+        buf[2..4].copy_from_slice(&(node.keys.len() as u16).to_le_bytes());
+        if node.leaf {
+            write_u32(buf, 4, node.next);
+            let entry = K::SIZE + V::SIZE;
+            for (i, (k, v)) in node.keys.iter().zip(&node.vals).enumerate() {
+                let at = HEADER + i * entry;
+                k.encode(&mut buf[at..]);
+                v.encode(&mut buf[at + K::SIZE..]);
+            }
+        } else {
+            write_u32(buf, HEADER, node.children[0]);
+            let entry = K::SIZE + 4;
+            for (i, (k, c)) in node.keys.iter().zip(&node.children[1..]).enumerate() {
+                let at = HEADER + 4 + i * entry;
+                k.encode(&mut buf[at..]);
+                write_u32(buf, at + K::SIZE, *c);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<K: FixedCodec, V: FixedCodec> PagedBPTree<K, V> {
+    /// 元数据写进第 0 页 (仍在缓冲池中)
+    fn write_meta(&mut self) -> io::Result<()> {
+        let page_size = self.pool.page_size() as u32;
+        let buf = self.pool.write(META_PAGE)?;
+        buf[0..4].copy_from_slice(MAGIC);
+        write_u32(buf, 4, FORMAT_VERSION);
+        write_u32(buf, 8, page_size);
+        write_u32(buf, 12, K::SIZE as u32);
+        write_u32(buf, 16, V::SIZE as u32);
+        write_u32(buf, 20, self.root);
+        write_u32(buf, 24, self.free_head);
+        buf[28..36].copy_from_slice(&self.len.to_le_bytes());
+        Ok(())
+    }
+}
+
+impl<K: FixedCodec, V: FixedCodec> Drop for PagedBPTree<K, V> {
+    fn drop(&mut self) {
+        // 析构时无法上报错误; 需要确认落盘时应显式调用 flush
+        let _ = self.write_meta().and_then(|_| self.pool.flush());
+    }
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use crate::paged_bptree::paged_bptree::PagedBPTree;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_paged_bptree_matches_reference() {
+        let path = std::env::temp_dir().join(format!("paged_bptree_{}.db", std::process::id()));
+        // 128 字节的页每个叶子只放 15 个条目, 缓冲池只有 8 页, 逼出频繁的淘汰与写回
+        let mut tree = PagedBPTree::create(&path, 128, 8).unwrap();
+        let mut reference = BTreeMap::new();
+        for i in 0..30_000u32 {
+            let k = (i * 7919) % 5_000;
+            if i % 3 == 0 {
+                assert_eq!(tree.delete(&k).unwrap(), reference.remove(&k));
+            } else {
+                assert_eq!(tree.insert(k, i as u64).unwrap(), reference.insert(k, i as u64).is_none());
+            }
+        }
+        assert_eq!(tree.len(), reference.len());
+        assert!(tree.height().unwrap() >= 3);
+        assert!(tree.pool_stats().evictions > 0);
+        let want: Vec<u64> = reference.range(100..=4_000).map(|(_, v)| *v).collect();
+        assert_eq!(tree.range_query(&100, &4_000).unwrap(), want);
+
+        // 重新打开后内容不变
+        tree.flush().unwrap();
+        drop(tree);
+        let mut tree = PagedBPTree::<u32, u64>::open(&path, 4).unwrap();
+        assert_eq!(tree.len(), reference.len());
+        for k in 0..5_000 {
+            assert_eq!(tree.get(&k).unwrap(), reference.get(&k).copied());
+        }
+        let want: Vec<u64> = reference.values().copied().collect();
+        assert_eq!(tree.range_query(&0, &u32::MAX).unwrap(), want);
+
+        // 删除释放的页被后续插入复用
+        for k in 0..5_000 {
+            tree.delete(&k).unwrap();
+        }
+        assert!(tree.is_empty());
+        let pages = tree.page_count();
+        for k in 0..1_000 {
+            tree.insert(k, 0).unwrap();
+        }
+        assert_eq!(tree.page_count(), pages);
+        drop(tree);
+
+        assert!(PagedBPTree::<u64, u64>::open(&path, 4).is_err());
+        assert!(PagedBPTree::<u32, u64>::create(&path, 16, 4).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
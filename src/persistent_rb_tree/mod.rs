@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod persistent_rb_tree;
+#[allow(clippy::module_inception)]
+pub mod tests;
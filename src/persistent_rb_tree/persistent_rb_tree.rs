@@ -0,0 +1,358 @@
+use std::collections::HashSet;
+use std::mem::size_of;
+use std::sync::Arc;
+
+use crate::memory::memory::MemoryReport;
+use crate::rb_tree::rb_tree::Color;
+
+type Link<K, V> = Option<Arc<Node<K, V>>>;
+
+#[derive(Clone, Debug)]
+pub struct Node<K, V> {
+    pub key: K,
+    pub value: V,
+    pub color: Color,
+    pub left: Link<K, V>,
+    pub right: Link<K, V>,
+}
+
+/// 持久化 (不可变) 左倾红黑树
+///
+/// 算法与 `RBTree` 相同, 但子节点是 `Arc`: `insert`/`delete` 不修改原树,
+/// 而是复制根到目标节点的路径 (加上旋转/变色碰到的兄弟) 并返回新版本, 其余子树与旧版本共享.
+/// 克隆一个版本只是增加根的引用计数, 可以廉价地保留任意多个历史版本.
+#[derive(Debug)]
+pub struct PersistentRBTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K, V> Clone for PersistentRBTree<K, V> {
+    fn clone(&self) -> Self {
+        PersistentRBTree {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Default for PersistentRBTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> PersistentRBTree<K, V> {
+    pub fn new() -> Self {
+        PersistentRBTree { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 树高 (空树为 0)
+    pub fn height(&self) -> usize {
+        fn h<K, V>(link: &Link<K, V>) -> usize {
+            link.as_ref().map_or(0, |n| 1 + h(&n.left).max(h(&n.right)))
+        }
+        h(&self.root)
+    }
+
+    pub fn find(&self, key: &K) -> Option<&V> {
+        let mut curr = &self.root;
+        while let Some(n) = curr {
+            if key < &n.key {
+                curr = &n.left;
+            } else if key > &n.key {
+                curr = &n.right;
+            } else {
+                return Some(&n.value);
+            }
+        }
+        None
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut n = self.root.as_deref()?;
+        while let Some(left) = n.left.as_deref() {
+            n = left;
+        }
+        Some((&n.key, &n.value))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut n = self.root.as_deref()?;
+        while let Some(right) = n.right.as_deref() {
+            n = right;
+        }
+        Some((&n.key, &n.value))
+    }
+
+    /// [start, end] 闭区间, 按键升序
+    pub fn range_query(&self, start: &K, end: &K) -> Vec<&V> {
+        let mut result = Vec::new();
+        Self::range_query_node(&self.root, start, end, &mut result);
+        result
+    }
+
+    fn range_query_node<'a>(node: &'a Link<K, V>, start: &K, end: &K, result: &mut Vec<&'a V>) {
+        if let Some(n) = node {
+            if start < &n.key {
+                Self::range_query_node(&n.left, start, end, result);
+            }
+            if start <= &n.key && end >= &n.key {
+                result.push(&n.value);
+            }
+            if end > &n.key {
+                Self::range_query_node(&n.right, start, end, result);
+            }
+        }
+    }
+
+    /// 返回插入 (或覆盖) 后的新版本, self 不变
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let mut added = false;
+        let mut root = Self::insert_node(self.root.clone(), key, value, &mut added);
+        // 根节点必须是黑色
+        root.color = Color::Black;
+        PersistentRBTree {
+            root: Some(Arc::new(root)),
+            len: self.len + added as usize,
+        }
+    }
+
+    fn insert_node(node: Link<K, V>, key: K, value: V, added: &mut bool) -> Node<K, V> {
+        // 标准 BST 插入, 沿途节点都是副本
+        let mut n = match node {
+            None => {
+                *added = true;
+                return Node {
+                    key,
+                    value,
+                    color: Color::Red,
+                    left: None,
+                    right: None,
+                };
+            }
+            Some(n) => {
+                let mut n = Self::own(n);
+                if key < n.key {
+                    n.left = Some(Arc::new(Self::insert_node(n.left.take(), key, value, added)));
+                } else if key > n.key {
+                    n.right = Some(Arc::new(Self::insert_node(n.right.take(), key, value, added)));
+                } else {
+                    n.value = value;
+                }
+                n
+            }
+        };
+
+        // 红黑树修正, 与 RBTree 相同
+        if Self::is_red(&n.right) && !Self::is_red(&n.left) {
+            n = Self::rotate_left(n);
+        }
+        if Self::is_red(&n.left) && Self::is_red(&n.left.as_ref().unwrap().left) {
+            n = Self::rotate_right(n);
+        }
+        if Self::is_red(&n.left) && Self::is_red(&n.right) {
+            Self::flip_colors(&mut n);
+        }
+        n
+    }
+
+    /// 返回删除后的新版本; key 不存在时直接共享整棵树
+    pub fn delete(&self, key: &K) -> Self {
+        if self.find(key).is_none() {
+            return self.clone();
+        }
+        let mut root = Self::own(self.root.clone().unwrap());
+        // 如果根的两个子节点都是黑色，将根设为红色（LLRB 逻辑）
+        if !Self::is_red(&root.left) && !Self::is_red(&root.right) {
+            root.color = Color::Red;
+        }
+        let root = Self::delete_node(root, key).map(|mut root| {
+            root.color = Color::Black;
+            Arc::new(root)
+        });
+        PersistentRBTree {
+            root,
+            len: self.len - 1,
+        }
+    }
+
+    /// 内部删除逻辑 (Left-Leaning Red-Black Tree), 要求 key 存在
+    fn delete_node(mut h: Node<K, V>, key: &K) -> Option<Node<K, V>> {
+        if key < &h.key {
+            if !Self::is_red(&h.left) && !Self::is_red(&h.left.as_ref().unwrap().left) {
+                h = Self::move_red_left(h);
+            }
+            h.left = Self::delete_node(Self::own(h.left.take().unwrap()), key).map(Arc::new);
+        } else {
+            if Self::is_red(&h.left) {
+                h = Self::rotate_right(h);
+            }
+            if key == &h.key && h.right.is_none() {
+                return None;
+            }
+            if !Self::is_red(&h.right) && !Self::is_red(&h.right.as_ref().unwrap().left) {
+                h = Self::move_red_right(h);
+            }
+            let right = Self::own(h.right.take().unwrap());
+            if key == &h.key {
+                // 用右子树中的最小节点替换
+                let (min_key, min_value) = Self::min(&right);
+                h.key = min_key.clone();
+                h.value = min_value.clone();
+                h.right = Self::delete_min(right).map(Arc::new);
+            } else {
+                h.right = Self::delete_node(right, key).map(Arc::new);
+            }
+        }
+        Some(Self::fix_up(h))
+    }
+
+    fn delete_min(mut h: Node<K, V>) -> Option<Node<K, V>> {
+        h.left.as_ref()?;
+        if !Self::is_red(&h.left) && !Self::is_red(&h.left.as_ref().unwrap().left) {
+            h = Self::move_red_left(h);
+        }
+        h.left = Self::delete_min(Self::own(h.left.take().unwrap())).map(Arc::new);
+        Some(Self::fix_up(h))
+    }
+
+    fn move_red_left(mut h: Node<K, V>) -> Node<K, V> {
+        Self::flip_colors(&mut h);
+        if Self::is_red(&h.right.as_ref().unwrap().left) {
+            let right = Self::own(h.right.take().unwrap());
+            h.right = Some(Arc::new(Self::rotate_right(right)));
+            h = Self::rotate_left(h);
+            Self::flip_colors(&mut h);
+        }
+        h
+    }
+
+    fn move_red_right(mut h: Node<K, V>) -> Node<K, V> {
+        Self::flip_colors(&mut h);
+        if Self::is_red(&h.left.as_ref().unwrap().left) {
+            h = Self::rotate_right(h);
+            Self::flip_colors(&mut h);
+        }
+        h
+    }
+
+    fn fix_up(mut h: Node<K, V>) -> Node<K, V> {
+        if Self::is_red(&h.right) {
+            h = Self::rotate_left(h);
+        }
+        if Self::is_red(&h.left) && Self::is_red(&h.left.as_ref().unwrap().left) {
+            h = Self::rotate_right(h);
+        }
+        if Self::is_red(&h.left) && Self::is_red(&h.right) {
+            Self::flip_colors(&mut h);
+        }
+        h
+    }
+
+    fn rotate_left(mut h: Node<K, V>) -> Node<K, V> {
+        let mut x = Self::own(h.right.take().unwrap());
+        h.right = x.left.take();
+        x.color = h.color;
+        h.color = Color::Red;
+        x.left = Some(Arc::new(h));
+        x
+    }
+
+    fn rotate_right(mut h: Node<K, V>) -> Node<K, V> {
+        let mut x = Self::own(h.left.take().unwrap());
+        h.left = x.right.take();
+        x.color = h.color;
+        h.color = Color::Red;
+        x.right = Some(Arc::new(h));
+        x
+    }
+
+    /// 变色会改动两个子节点, 共享的子节点需要先复制
+    fn flip_colors(h: &mut Node<K, V>) {
+        h.color = Self::flip(h.color);
+        for child in [&mut h.left, &mut h.right] {
+            if let Some(c) = child.take() {
+                let mut c = Self::own(c);
+                c.color = Self::flip(c.color);
+                *child = Some(Arc::new(c));
+            }
+        }
+    }
+
+    fn flip(color: Color) -> Color {
+        match color {
+            Color::Red => Color::Black,
+            Color::Black => Color::Red,
+        }
+    }
+
+    /// 取得节点的可写副本: 只被当前版本引用时直接拿走, 被共享时复制
+    fn own(node: Arc<Node<K, V>>) -> Node<K, V> {
+        Arc::unwrap_or_clone(node)
+    }
+
+    fn is_red(node: &Link<K, V>) -> bool {
+        node.as_ref().is_some_and(|n| n.color == Color::Red)
+    }
+
+    fn min(mut n: &Node<K, V>) -> (&K, &V) {
+        while let Some(left) = n.left.as_deref() {
+            n = left;
+        }
+        (&n.key, &n.value)
+    }
+
+    /// 本版本的节点数
+    pub fn node_count(&self) -> usize {
+        Self::distinct_nodes([self])
+    }
+
+    /// 多个版本合计占用的不同节点数; 共享的子树只计一次
+    pub fn distinct_nodes<'a>(versions: impl IntoIterator<Item = &'a Self>) -> usize
+    where
+        K: 'a,
+        V: 'a,
+    {
+        let mut seen = HashSet::new();
+        let mut stack: Vec<&Arc<Node<K, V>>> = versions.into_iter().filter_map(|v| v.root.as_ref()).collect();
+        while let Some(n) = stack.pop() {
+            // 节点不可变, 见过的节点其子树也一定见过
+            if seen.insert(Arc::as_ptr(n)) {
+                stack.extend(n.left.as_ref());
+                stack.extend(n.right.as_ref());
+            }
+        }
+        seen.len()
+    }
+
+    /// 单个版本的内存占用 (不考虑与其他版本的共享)
+    pub fn memory_usage(&self) -> MemoryReport {
+        let nodes = self.node_count();
+        MemoryReport {
+            node_count: nodes,
+            entry_count: self.len,
+            key_bytes: nodes * size_of::<K>(),
+            value_bytes: nodes * size_of::<V>(),
+            // Arc 的两个引用计数 + 左右子指针 + 颜色 + 对齐填充
+            pointer_overhead_bytes: nodes * (Self::node_bytes() - size_of::<K>() - size_of::<V>()),
+            fill_factor: if nodes > 0 { 1.0 } else { 0.0 },
+            height: self.height(),
+            ..Default::default()
+        }
+        .finish()
+    }
+
+    /// 每个节点 (含 Arc 头) 的字节数
+    pub fn node_bytes() -> usize {
+        size_of::<Node<K, V>>() + 2 * size_of::<usize>()
+    }
+}
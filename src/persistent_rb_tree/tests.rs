@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use crate::persistent_rb_tree::persistent_rb_tree::PersistentRBTree;
+
+    #[test]
+    fn test_persistent_rb_tree_matches_reference() {
+        let mut tree = PersistentRBTree::new();
+        let mut reference = std::collections::BTreeMap::new();
+        for i in 0..20_000u32 {
+            let k = (i * 7919) % 3_000;
+            if i % 3 == 0 {
+                tree = tree.delete(&k);
+                reference.remove(&k);
+            } else {
+                tree = tree.insert(k, i);
+                reference.insert(k, i);
+            }
+        }
+
+        assert_eq!(tree.len(), reference.len());
+        assert_eq!(tree.node_count(), reference.len());
+        assert_eq!(tree.first(), reference.iter().next());
+        assert_eq!(tree.last(), reference.iter().next_back());
+        let got: Vec<u32> = tree.range_query(&0, &u32::MAX).into_iter().copied().collect();
+        let want: Vec<u32> = reference.values().copied().collect();
+        assert_eq!(got, want);
+        // LLRB 高度不超过 2 * log2(n + 1)
+        assert!((tree.height() as f64) <= 2.0 * ((tree.len() + 1) as f64).log2());
+    }
+
+    #[test]
+    fn test_persistent_rb_tree_versions_share_nodes() {
+        let mut versions = vec![PersistentRBTree::new()];
+        for k in 0..1_000u32 {
+            versions.push(versions.last().unwrap().insert(k, k));
+        }
+        let base = versions.last().unwrap().clone();
+        let updated = base.insert(500, 0).delete(&10).insert(5_000, 5_000);
+
+        // 旧版本保持不变
+        assert_eq!(versions[10].len(), 10);
+        assert_eq!(versions[10].find(&9), Some(&9));
+        assert_eq!(versions[10].find(&10), None);
+        assert_eq!(base.find(&500), Some(&500));
+        assert_eq!(base.find(&10), Some(&10));
+        assert_eq!(updated.find(&500), Some(&0));
+        assert_eq!(updated.find(&10), None);
+        assert_eq!(updated.len(), 1_000);
+
+        // 新版本只复制了少数几条路径
+        let both = PersistentRBTree::distinct_nodes([&base, &updated]);
+        assert!(both - base.node_count() <= 6 * base.height());
+        // 删除不存在的 key 不产生任何新节点
+        assert_eq!(PersistentRBTree::distinct_nodes([&base, &base.delete(&9_999)]), base.node_count());
+        // 1001 个版本合计的节点数远小于各自完整复制
+        let total: usize = versions.iter().map(|v| v.node_count()).sum();
+        assert!(PersistentRBTree::distinct_nodes(&versions) * 20 < total);
+    }
+}
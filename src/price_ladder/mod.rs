@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod price_ladder;
+#[allow(clippy::module_inception)]
+pub mod tests;
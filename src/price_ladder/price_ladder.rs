@@ -0,0 +1,289 @@
+use std::mem::size_of;
+
+use crate::memory::memory::MemoryReport;
+
+/// 分层位图: levels[0] 每一位对应一个价位, levels[i + 1] 每一位表示 levels[i] 中对应的字是否非零
+///
+/// 置位/清位 O(层数), 查找下一个/上一个置位 O(层数), 层数为 log64(价位数)。
+#[derive(Clone, Debug)]
+pub struct HierBitmap {
+    levels: Vec<Vec<u64>>,
+    len: usize,
+}
+
+impl HierBitmap {
+    pub fn new(len: usize) -> Self {
+        let mut levels = Vec::new();
+        let mut bits = len.max(1);
+        loop {
+            let words = bits.div_ceil(64);
+            levels.push(vec![0u64; words]);
+            if words == 1 {
+                break;
+            }
+            bits = words;
+        }
+        HierBitmap { levels, len }
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        i < self.len && self.levels[0][i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn set(&mut self, mut i: usize) {
+        for words in self.levels.iter_mut() {
+            let was = words[i / 64];
+            words[i / 64] = was | (1 << (i % 64));
+            if was != 0 {
+                // 上层对应位已经置位
+                break;
+            }
+            i /= 64;
+        }
+    }
+
+    pub fn clear(&mut self, mut i: usize) {
+        for words in self.levels.iter_mut() {
+            words[i / 64] &= !(1 << (i % 64));
+            if words[i / 64] != 0 {
+                break;
+            }
+            i /= 64;
+        }
+    }
+
+    /// 第一个 >= from 的置位
+    pub fn next_set(&self, from: usize) -> Option<usize> {
+        if from >= self.len {
+            return None;
+        }
+        let mut level = 0;
+        let mut i = from;
+        // 向上找到包含候选位的字
+        loop {
+            let words = self.levels.get(level)?;
+            let w = i / 64;
+            let masked = words.get(w)? & (!0u64 << (i % 64));
+            if masked != 0 {
+                i = w * 64 + masked.trailing_zeros() as usize;
+                break;
+            }
+            i = w + 1;
+            level += 1;
+        }
+        // 向下取每层最低位
+        while level > 0 {
+            level -= 1;
+            i = i * 64 + self.levels[level][i].trailing_zeros() as usize;
+        }
+        Some(i)
+    }
+
+    /// 最后一个 <= from 的置位
+    pub fn prev_set(&self, from: usize) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+        let mut level = 0;
+        let mut i = from.min(self.len - 1);
+        loop {
+            let words = self.levels.get(level)?;
+            let w = i / 64;
+            let bit = i % 64;
+            let mask = if bit == 63 { !0u64 } else { (1u64 << (bit + 1)) - 1 };
+            let masked = words[w] & mask;
+            if masked != 0 {
+                i = w * 64 + 63 - masked.leading_zeros() as usize;
+                break;
+            }
+            if w == 0 {
+                return None;
+            }
+            i = w - 1;
+            level += 1;
+        }
+        while level > 0 {
+            level -= 1;
+            i = i * 64 + 63 - self.levels[level][i].leading_zeros() as usize;
+        }
+        Some(i)
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.levels.iter().map(|l| l.capacity() * size_of::<u64>()).sum::<usize>()
+            + self.levels.capacity() * size_of::<Vec<u64>>()
+    }
+}
+
+/// 固定 tick 区间内的直接寻址价位表
+///
+/// 价位 `price` 存放在 `(price - min_price) / tick` 处, 插入/删除/查询为 O(1),
+/// 最优价 (最低/最高) 通过分层位图查找。适用于价格区间有界的品种。
+#[derive(Clone, Debug)]
+pub struct PriceLadder<V> {
+    min_price: u64,
+    max_price: u64,
+    tick: u64,
+    levels: Vec<Option<V>>,
+    occupied: HierBitmap,
+    len: usize,
+}
+
+impl<V> PriceLadder<V> {
+    /// 创建 [min_price, max_price] 区间、步长为 tick 的价位表
+    pub fn new(min_price: u64, max_price: u64, tick: u64) -> Self {
+        assert!(tick > 0, "[PriceLadder] tick must be > 0");
+        assert!(max_price >= min_price, "[PriceLadder] max_price must be >= min_price");
+        let slots = ((max_price - min_price) / tick + 1) as usize;
+        let mut levels = Vec::with_capacity(slots);
+        levels.resize_with(slots, || None);
+        PriceLadder {
+            min_price,
+            max_price: min_price + (slots as u64 - 1) * tick,
+            tick,
+            levels,
+            occupied: HierBitmap::new(slots),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn min_price(&self) -> u64 {
+        self.min_price
+    }
+
+    pub fn max_price(&self) -> u64 {
+        self.max_price
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// 价格是否在区间内且落在 tick 上
+    pub fn is_valid_price(&self, price: u64) -> bool {
+        self.index_of(price).is_some()
+    }
+
+    fn index_of(&self, price: u64) -> Option<usize> {
+        if price < self.min_price || price > self.max_price {
+            return None;
+        }
+        let offset = price - self.min_price;
+        if !offset.is_multiple_of(self.tick) {
+            return None;
+        }
+        Some((offset / self.tick) as usize)
+    }
+
+    fn price_at(&self, idx: usize) -> u64 {
+        self.min_price + idx as u64 * self.tick
+    }
+
+    /// 插入, 价位已存在时更新 value
+    ///
+    /// 价格不在区间内或不在 tick 上时 panic, 调用方可先用 `is_valid_price` 校验
+    pub fn insert(&mut self, price: u64, value: V) {
+        let idx = self
+            .index_of(price)
+            .unwrap_or_else(|| panic!("[PriceLadder] price {} out of ladder or off tick", price));
+        if self.levels[idx].replace(value).is_none() {
+            self.occupied.set(idx);
+            self.len += 1;
+        }
+    }
+
+    /// 查询
+    pub fn get(&self, price: &u64) -> Option<&V> {
+        self.levels[self.index_of(*price)?].as_ref()
+    }
+
+    pub fn get_mut(&mut self, price: &u64) -> Option<&mut V> {
+        let idx = self.index_of(*price)?;
+        self.levels[idx].as_mut()
+    }
+
+    /// 删除
+    pub fn delete(&mut self, price: &u64) {
+        if let Some(idx) = self.index_of(*price) {
+            if self.levels[idx].take().is_some() {
+                self.occupied.clear(idx);
+                self.len -= 1;
+            }
+        }
+    }
+
+    /// 区间查询 [start..end], 按价格升序
+    pub fn range_query(&self, start: &u64, end: &u64) -> Vec<&V> {
+        let mut result = Vec::new();
+        if start > end || *end < self.min_price || *start > self.max_price {
+            return result;
+        }
+        // start 向上取整到 tick
+        let from = ((start.max(&self.min_price) - self.min_price).div_ceil(self.tick)) as usize;
+        let to = ((end.min(&self.max_price) - self.min_price) / self.tick) as usize;
+        let mut idx = self.occupied.next_set(from);
+        while let Some(i) = idx {
+            if i > to {
+                break;
+            }
+            result.push(self.levels[i].as_ref().unwrap());
+            idx = self.occupied.next_set(i + 1);
+        }
+        result
+    }
+
+    /// 最低价位 (卖盘最优价)
+    pub fn first(&self) -> Option<(u64, &V)> {
+        let idx = self.occupied.next_set(0)?;
+        Some((self.price_at(idx), self.levels[idx].as_ref().unwrap()))
+    }
+
+    /// 最高价位 (买盘最优价)
+    pub fn last(&self) -> Option<(u64, &V)> {
+        let idx = self.occupied.prev_set(self.levels.len() - 1)?;
+        Some((self.price_at(idx), self.levels[idx].as_ref().unwrap()))
+    }
+
+    /// 第一个 >= price 的价位
+    pub fn next_price(&self, price: u64) -> Option<u64> {
+        let from = price.saturating_sub(self.min_price).div_ceil(self.tick) as usize;
+        self.occupied.next_set(from).map(|i| self.price_at(i))
+    }
+
+    /// 最后一个 <= price 的价位
+    pub fn prev_price(&self, price: u64) -> Option<u64> {
+        if price < self.min_price {
+            return None;
+        }
+        let from = ((price - self.min_price) / self.tick) as usize;
+        self.occupied.prev_set(from).map(|i| self.price_at(i))
+    }
+
+    /// 内存占用: key 由下标隐式表示, 不占空间
+    pub fn memory_usage(&self) -> MemoryReport {
+        let slot = size_of::<Option<V>>();
+        let report = MemoryReport {
+            node_count: self.levels.len(),
+            entry_count: self.len,
+            key_bytes: 0,
+            value_bytes: self.len * size_of::<V>(),
+            // 空价位整槽浪费
+            wasted_capacity_bytes: (self.levels.capacity() - self.len) * slot,
+            // Option 标记 + 位图
+            pointer_overhead_bytes: self.len * (slot - size_of::<V>()) + self.occupied.heap_bytes(),
+            duplicated_bytes: 0,
+            total_bytes: 0,
+            fill_factor: if self.levels.is_empty() { 0.0 } else { self.len as f64 / self.levels.len() as f64 },
+            height: self.occupied.levels.len(),
+        };
+        report.finish()
+    }
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use crate::price_ladder::price_ladder::{HierBitmap, PriceLadder};
+
+    #[test]
+    fn test_price_ladder_basic() {
+        let mut ladder = PriceLadder::new(1000, 2000, 5);
+        assert!(ladder.is_empty());
+        assert_eq!(ladder.first(), None);
+
+        ladder.insert(1500, "a");
+        ladder.insert(1005, "b");
+        ladder.insert(1995, "c");
+        ladder.insert(1500, "A");
+
+        assert_eq!(ladder.len(), 3);
+        assert_eq!(ladder.get(&1500), Some(&"A"));
+        assert_eq!(ladder.get(&1501), None);
+        assert_eq!(ladder.get(&5000), None);
+        assert!(!ladder.is_valid_price(1002));
+        assert_eq!(ladder.first(), Some((1005, &"b")));
+        assert_eq!(ladder.last(), Some((1995, &"c")));
+        assert_eq!(ladder.range_query(&1001, &1500), vec![&"b", &"A"]);
+        assert_eq!(ladder.next_price(1006), Some(1500));
+        assert_eq!(ladder.prev_price(1499), Some(1005));
+
+        ladder.delete(&1005);
+        ladder.delete(&1995);
+        ladder.delete(&1234);
+        assert_eq!(ladder.len(), 1);
+        assert_eq!(ladder.first(), Some((1500, &"A")));
+        assert_eq!(ladder.last(), Some((1500, &"A")));
+    }
+
+    #[test]
+    fn test_hier_bitmap_matches_std() {
+        // 三层位图 (> 64 * 64 位)
+        let len = 300_000;
+        let mut bitmap = HierBitmap::new(len);
+        let mut reference = std::collections::BTreeSet::new();
+        for i in 0..20_000usize {
+            let idx = (i * 7919) % len;
+            if i % 3 == 0 {
+                bitmap.clear(idx);
+                reference.remove(&idx);
+            } else {
+                bitmap.set(idx);
+                reference.insert(idx);
+            }
+        }
+        for from in (0..len).step_by(997) {
+            assert_eq!(bitmap.next_set(from), reference.range(from..).next().copied());
+            assert_eq!(bitmap.prev_set(from), reference.range(..=from).next_back().copied());
+        }
+        assert_eq!(bitmap.next_set(len), None);
+        assert_eq!(bitmap.prev_set(len * 2), reference.iter().next_back().copied());
+    }
+}
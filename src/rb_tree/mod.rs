@@ -1,2 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod rb_tree;
+#[allow(clippy::module_inception)]
 pub mod tests;
\ No newline at end of file
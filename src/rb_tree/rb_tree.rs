@@ -1,4 +1,9 @@
 use std::cmp::Ordering;
+use std::mem::size_of;
+use std::ptr::NonNull;
+
+use crate::handle::handle::{Handle, HandleTable};
+use crate::memory::memory::MemoryReport;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Color {
@@ -11,16 +16,19 @@ pub struct Node<K, V> {
     pub key: K,
     pub value: V,
     pub color: Color,
+    /// 句柄表中的槽位, 随键值一起移动
+    pub slot: usize,
     pub left: Option<Box<Node<K, V>>>,
     pub right: Option<Box<Node<K, V>>>,
 }
 
 impl<K, V> Node<K, V> {
-    pub fn new(key: K, value: V, color: Color) -> Self {
+    pub fn new(key: K, value: V, slot: usize, color: Color) -> Self {
         Node {
             key,
             value,
             color,
+            slot,
             left: None,
             right: None,
         }
@@ -29,11 +37,39 @@ impl<K, V> Node<K, V> {
 
 pub struct RBTree<K, V> {
     pub root: Option<Box<Node<K, V>>>,
+    /// 条目句柄; 按句柄删除只打墓碑, 节点在 purge 时摘除
+    slots: HandleTable,
+    /// 最小和最大节点的槽位, 让 `remove_at` 以 O(1) 判断删的是不是两端
+    ends: Option<(usize, usize)>,
+}
+
+impl<K: Ord + Clone, V: Clone> Default for RBTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
     pub fn new() -> Self {
-        RBTree { root: None }
+        RBTree {
+            root: None,
+            slots: HandleTable::new(),
+            ends: None,
+        }
+    }
+
+    /// 存活条目数
+    pub fn len(&self) -> usize {
+        self.slots.live()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.live() == 0
+    }
+
+    /// 已按句柄删除、尚未摘除的节点数
+    pub fn tombstones(&self) -> usize {
+        self.slots.tombstones()
     }
 
     /// 查找
@@ -43,38 +79,150 @@ impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
             match key.cmp(&node.key) {
                 Ordering::Less => curr = &node.left,
                 Ordering::Greater => curr = &node.right,
-                Ordering::Equal => return Some(&node.value),
+                Ordering::Equal => return self.slots.is_live(node.slot).then_some(&node.value),
             }
         }
         None
     }
 
-    /// 插入
-    pub fn insert(&mut self, key: K, value: V) {
-        self.root = Self::insert_node(self.root.take(), key, value);
+    /// 句柄是否仍指向存活条目
+    pub fn is_valid(&self, handle: Handle) -> bool {
+        self.slots.resolve(handle).is_some()
+    }
+
+    /// 最小键 (卖盘最优价); 两端节点总是存活条目, 不需要跳过墓碑
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let n = Self::min(&self.root)?;
+        debug_assert!(self.slots.is_live(n.slot));
+        Some((&n.key, &n.value))
+    }
+
+    /// 最大键 (买盘最优价)
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let n = Self::max(&self.root)?;
+        debug_assert!(self.slots.is_live(n.slot));
+        Some((&n.key, &n.value))
+    }
+
+    /// 第一个 key >= 给定 key 的存活条目, 跳过墓碑
+    pub fn lower_bound(&self, key: &K) -> Option<(&K, &V)> {
+        // 栈中是下降时向左转的节点, 即 >= key 的祖先, 按中序依次弹出
+        let mut stack = Vec::new();
+        let mut curr = self.root.as_deref();
+        while let Some(n) = curr {
+            if &n.key >= key {
+                stack.push(n);
+                curr = n.left.as_deref();
+            } else {
+                curr = n.right.as_deref();
+            }
+        }
+        while let Some(n) = stack.pop() {
+            if self.slots.is_live(n.slot) {
+                return Some((&n.key, &n.value));
+            }
+            let mut curr = n.right.as_deref();
+            while let Some(m) = curr {
+                stack.push(m);
+                curr = m.left.as_deref();
+            }
+        }
+        None
+    }
+
+    /// 遍历统计内存占用, 每个节点是一次独立的 Box 分配
+    ///
+    /// 墓碑节点的键值计为浪费, 句柄表计入指针开销
+    pub fn memory_usage(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+        let mut stack = Vec::new();
+        if let Some(ref root) = self.root {
+            stack.push((root, 1));
+        }
+        while let Some((node, depth)) = stack.pop() {
+            report.node_count += 1;
+            if self.slots.is_live(node.slot) {
+                report.entry_count += 1;
+                report.key_bytes += size_of::<K>();
+                report.value_bytes += size_of::<V>();
+            } else {
+                report.wasted_capacity_bytes += size_of::<K>() + size_of::<V>();
+            }
+            // 左右子指针 + 颜色 + 槽位 + 对齐填充
+            report.pointer_overhead_bytes += size_of::<Node<K, V>>() - size_of::<K>() - size_of::<V>();
+            report.height = report.height.max(depth);
+            for child in [&node.left, &node.right].into_iter().flatten() {
+                stack.push((child, depth + 1));
+            }
+        }
+        report.pointer_overhead_bytes += self.slots.heap_bytes();
+        // 每个节点恰好存一个键值对, 墓碑节点除外
+        report.fill_factor = if report.node_count > 0 {
+            report.entry_count as f64 / report.node_count as f64
+        } else {
+            0.0
+        };
+        report.finish()
+    }
+
+    /// 插入, 返回该条目的句柄; key 已存在时更新 value 并返回原句柄
+    pub fn insert(&mut self, key: K, value: V) -> Handle {
+        let mut new_end = (false, false);
+        let (mut root, handle) = Self::insert_node(self.root.take(), key, value, &mut self.slots, (true, true), &mut new_end);
         // 根节点必须是黑色
-        if let Some(ref mut root) = self.root {
-            root.color = Color::Black;
+        root.color = Color::Black;
+        self.root = Some(root);
+        // 新节点沿最左 (右) 路径落下时成为新的最小 (大) 节点, 不必重新遍历
+        if new_end != (false, false) {
+            let slot = self.slots.resolve(handle).unwrap();
+            let (first, last) = self.ends.get_or_insert((slot, slot));
+            if new_end.0 {
+                *first = slot;
+            }
+            if new_end.1 {
+                *last = slot;
+            }
         }
+        handle
     }
 
-    fn insert_node(node: Option<Box<Node<K, V>>>, key: K, value: V) -> Option<Box<Node<K, V>>> {
+    /// edge 表示从根到 node 是否一路向左 / 向右; 新建节点时把它写入 new_end
+    fn insert_node(
+        node: Option<Box<Node<K, V>>>,
+        key: K,
+        value: V,
+        slots: &mut HandleTable,
+        edge: (bool, bool),
+        new_end: &mut (bool, bool),
+    ) -> (Box<Node<K, V>>, Handle) {
         // 标准 BST 插入
-        let mut n = match node {
+        let (mut n, handle) = match node {
             None => {
-                let new_node = Box::new(Node::new(key, value, Color::Red));
-                return Some(new_node);
+                let slot = slots.alloc();
+                let new_node = Box::new(Node::new(key, value, slot, Color::Red));
+                *new_end = edge;
+                return (new_node, slots.handle(slot));
             }
             Some(mut n) => {
+                let handle;
                 if key < n.key {
-                    n.left = Self::insert_node(n.left.take(), key, value);
+                    let (child, h) = Self::insert_node(n.left.take(), key, value, slots, (edge.0, false), new_end);
+                    n.left = Some(child);
+                    handle = h;
                 } else if key > n.key {
-                    n.right = Self::insert_node(n.right.take(), key, value);
+                    let (child, h) = Self::insert_node(n.right.take(), key, value, slots, (false, edge.1), new_end);
+                    n.right = Some(child);
+                    handle = h;
                 } else {
-                    // key 相等，更新 value
+                    // key 相等，更新 value; 墓碑节点复活并换发新句柄
                     n.value = value;
+                    handle = if slots.is_live(n.slot) {
+                        slots.handle(n.slot)
+                    } else {
+                        slots.revive(n.slot)
+                    };
                 }
-                n
+                (n, handle)
             }
         };
 
@@ -92,11 +240,121 @@ impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
             Self::flip_colors(&mut n);
         }
 
-        Some(n)
+        (n, handle)
+    }
+
+    /// 按句柄删除, 不按 key 查找: 中间的条目只打墓碑, O(1)
+    ///
+    /// 删的是最小或最大条目时沿最左 (右) 路径立即摘除, O(log n), 保证 `first`/`last` 不会落在墓碑上;
+    /// 墓碑比例超过阈值时 `purge` 整体重建. 句柄失效时返回 false
+    pub fn remove_at(&mut self, handle: Handle) -> bool {
+        let Some(slot) = self.slots.resolve(handle) else {
+            return false;
+        };
+        self.slots.tombstone(handle);
+        self.settle_removed(slot);
+        true
+    }
+
+    /// 槽位刚被打上墓碑之后的整理, 返回树的结构是否变化
+    fn settle_removed(&mut self, slot: usize) -> bool {
+        if self.slots.needs_purge() {
+            self.purge();
+            return true;
+        }
+        let Some((first, last)) = self.ends else { return false };
+        if slot == first {
+            self.trim_end(false);
+        }
+        if slot == last {
+            self.trim_end(true);
+        }
+        slot == first || slot == last
+    }
+
+    /// 摘除两端的墓碑节点, 直到最小和最大节点都是存活条目; 每个墓碑最多被摘除一次
+    fn trim_ends(&mut self) {
+        self.trim_end(false);
+        self.trim_end(true);
+    }
+
+    /// 摘除一端 (right 时为最大端) 的墓碑节点, 并用找到的存活端点更新缓存
+    fn trim_end(&mut self, right: bool) {
+        loop {
+            let end = if right { Self::max(&self.root) } else { Self::min(&self.root) };
+            let Some(n) = end else {
+                self.ends = None;
+                return;
+            };
+            if self.slots.is_live(n.slot) {
+                let slot = n.slot;
+                let ends = self.ends.get_or_insert((slot, slot));
+                if right {
+                    ends.1 = slot;
+                } else {
+                    ends.0 = slot;
+                }
+                return;
+            }
+            self.unlink_end(right);
+        }
+    }
+
+    /// 摘除最小 (right 时为最大) 节点, 只沿一侧下降, 不比较 key
+    fn unlink_end(&mut self, right: bool) {
+        let Some(root) = self.root.as_mut() else { return };
+        if !Self::is_red(&root.left) && !Self::is_red(&root.right) {
+            root.color = Color::Red;
+        }
+        let mut removed = None;
+        self.root = Self::delete_end(self.root.take(), right, &mut removed);
+        if let Some(slot) = removed {
+            self.slots.release(slot);
+        }
+        if let Some(root) = self.root.as_mut() {
+            root.color = Color::Black;
+        }
+    }
+
+    fn refresh_ends(&mut self) {
+        self.ends = Self::min(&self.root).zip(Self::max(&self.root)).map(|(a, b)| (a.slot, b.slot));
+    }
+
+    /// 摘除所有墓碑节点: 按序取出存活条目, 用 `from_sorted` 的方式自底向上重建
+    ///
+    /// O(n), 不做旋转; 存活条目的槽位 (句柄) 保持不变
+    pub fn purge(&mut self) {
+        if self.slots.tombstones() == 0 {
+            return;
+        }
+        let mut entries = Vec::with_capacity(self.slots.live());
+        Self::drain(self.root.take(), &mut self.slots, &mut entries);
+        let n = entries.len();
+        self.root = Self::build(&mut entries.into_iter(), n, (n + 1).ilog2());
+        self.refresh_ends();
+    }
+
+    /// 按中序取出存活条目, 墓碑节点的槽位直接回收
+    fn drain(node: Option<Box<Node<K, V>>>, slots: &mut HandleTable, out: &mut Vec<(K, V, usize)>) {
+        let Some(node) = node else { return };
+        let Node { key, value, slot, left, right, .. } = *node;
+        Self::drain(left, slots, out);
+        if slots.is_live(slot) {
+            out.push((key, value, slot));
+        } else {
+            slots.release(slot);
+        }
+        Self::drain(right, slots, out);
     }
 
     /// 外部删除接口
     pub fn delete(&mut self, key: &K) {
+        self.unlink(key);
+        // 删掉端点后, 相邻的墓碑可能成为新的端点
+        self.trim_ends();
+    }
+
+    fn unlink(&mut self, key: &K) {
         if self.root.is_none() {
             return;
         }
@@ -109,7 +367,11 @@ impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
             }
         }
 
-        self.root = Self::delete_node(self.root.take(), key);
+        let mut removed = None;
+        self.root = Self::delete_node(self.root.take(), key, &mut removed);
+        if let Some(slot) = removed {
+            self.slots.release(slot);
+        }
 
         // 将根设为黑色
         if let Some(ref mut root) = self.root {
@@ -118,7 +380,8 @@ impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
     }
 
     /// 内部删除逻辑 (Left-Leaning Red-Black Tree)
-    fn delete_node(node: Option<Box<Node<K, V>>>, key: &K) -> Option<Box<Node<K, V>>> {
+    /// removed 记录被摘除条目的槽位
+    fn delete_node(node: Option<Box<Node<K, V>>>, key: &K, removed: &mut Option<usize>) -> Option<Box<Node<K, V>>> {
         let mut h = node?;
 
         if key < &h.key {
@@ -131,7 +394,7 @@ impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
                     }
                 }
             }
-            h.left = Self::delete_node(h.left.take(), key);
+            h.left = Self::delete_node(h.left.take(), key, removed);
         } else {
             // 如果左子是红 => 右旋
             if Self::is_red(&h.left) {
@@ -139,6 +402,7 @@ impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
             }
             // 找到 key 且右子为空 => 删除该节点
             if key == &h.key && h.right.is_none() {
+                *removed = Some(h.slot);
                 return None;
             }
             // 检查右子和右子的左子，若都不是红 => move_red_right
@@ -150,31 +414,46 @@ impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
             if key == &h.key {
                 // 用右子中的最小节点替换
                 if let Some(min_node) = Self::min(&h.right) {
+                    *removed = Some(h.slot);
                     h.key = min_node.key.clone();
                     h.value = min_node.value.clone();
-                    h.right = Self::delete_min(h.right.take());
+                    h.slot = min_node.slot;
+                    h.right = Self::delete_end(h.right.take(), false, &mut None);
                 }
             } else {
-                h.right = Self::delete_node(h.right.take(), key);
+                h.right = Self::delete_node(h.right.take(), key, removed);
             }
         }
 
         Some(Self::fix_up(h))
     }
 
-    fn delete_min(node: Option<Box<Node<K, V>>>) -> Option<Box<Node<K, V>>> {
+    /// 删除子树的最小 (right 时为最大) 节点, removed 记录其槽位
+    fn delete_end(node: Option<Box<Node<K, V>>>, right: bool, removed: &mut Option<usize>) -> Option<Box<Node<K, V>>> {
         let mut h = node?;
-        if h.left.is_none() {
-            return None;
-        }
-        if !Self::is_red(&h.left) {
-            if let Some(ref left_child) = h.left {
-                if !Self::is_red(&left_child.left) {
-                    h = Self::move_red_left(h);
-                }
+        if right {
+            if Self::is_red(&h.left) {
+                h = Self::rotate_right(h);
             }
+            let Some(r) = h.right.as_ref() else {
+                debug_assert!(h.left.is_none());
+                *removed = Some(h.slot);
+                return None;
+            };
+            if !Self::is_red(&h.right) && !Self::is_red(&r.left) {
+                h = Self::move_red_right(h);
+            }
+            h.right = Self::delete_end(h.right.take(), true, removed);
+        } else {
+            let Some(l) = h.left.as_ref() else {
+                *removed = Some(h.slot);
+                return None;
+            };
+            if !Self::is_red(&h.left) && !Self::is_red(&l.left) {
+                h = Self::move_red_left(h);
+            }
+            h.left = Self::delete_end(h.left.take(), false, removed);
         }
-        h.left = Self::delete_min(h.left.take());
         Some(Self::fix_up(h))
     }
 
@@ -262,7 +541,15 @@ impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
         }
     }
 
-    fn min(node: &Option<Box<Node<K, V>>>) -> Option<&Box<Node<K, V>>> {
+    fn max(node: &Option<Box<Node<K, V>>>) -> Option<&Node<K, V>> {
+        let mut n = node.as_deref()?;
+        while let Some(right) = n.right.as_deref() {
+            n = right;
+        }
+        Some(n)
+    }
+
+    fn min(node: &Option<Box<Node<K, V>>>) -> Option<&Node<K, V>> {
         match node {
             Some(n) => match &n.left {
                 Some(_) => Self::min(&n.left),
@@ -290,7 +577,7 @@ impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
             if start < &n.key {
                 self.range_query_node(&n.left, start, end, result);
             }
-            if start <= &n.key && end >= &n.key {
+            if start <= &n.key && end >= &n.key && self.slots.is_live(n.slot) {
                 result.push(&n.value);
             }
             if end > &n.key {
@@ -298,4 +585,400 @@ impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
             }
         }
     }
-}
\ No newline at end of file
+
+    /// 按 key 升序的全部存活条目
+    pub fn entries(&self) -> Vec<(&K, &V)> {
+        self.walk(self.len(), false)
+    }
+
+    /// 按 key 升序的前 n 个存活条目, 走到第 n 个即停止
+    pub fn first_n(&self, n: usize) -> Vec<(&K, &V)> {
+        self.walk(n, false)
+    }
+
+    /// 按 key 降序的前 n 个存活条目
+    pub fn last_n(&self, n: usize) -> Vec<(&K, &V)> {
+        self.walk(n, true)
+    }
+
+    /// 中序遍历 (rev 时从右往左), 收集 n 个存活条目后停止
+    fn walk(&self, n: usize, rev: bool) -> Vec<(&K, &V)> {
+        fn side<K, V>(node: &Node<K, V>, right: bool) -> Option<&Node<K, V>> {
+            if right { node.right.as_deref() } else { node.left.as_deref() }
+        }
+        let mut out = Vec::with_capacity(n.min(self.len()));
+        let mut stack = Vec::new();
+        let mut curr = self.root.as_deref();
+        while out.len() < n {
+            while let Some(node) = curr {
+                stack.push(node);
+                curr = side(node, rev);
+            }
+            let Some(node) = stack.pop() else { break };
+            if self.slots.is_live(node.slot) {
+                out.push((&node.key, &node.value));
+            }
+            curr = side(node, !rev);
+        }
+        out
+    }
+
+    /// 由按 key 严格递增的条目自底向上建树, O(n), 不做任何旋转
+    ///
+    /// 按 2-3 树的形状分配: 黑高为 b 的子树可容纳 2^b - 1 到 3^b - 1 个条目; 两个孩子放得下就用 2-节点,
+    /// 否则用 3-节点 (黑节点带一个红色左孩子), 结果是合法的左倾红黑树
+    pub fn from_sorted(entries: Vec<(K, V)>) -> Self {
+        debug_assert!(entries.windows(2).all(|w| w[0].0 < w[1].0), "entries must be strictly increasing");
+        let n = entries.len();
+        let mut slots = HandleTable::new();
+        let root = Self::build(&mut entries.into_iter().map(|(k, v)| (k, v, slots.alloc())), n, (n + 1).ilog2());
+        let mut tree = RBTree { root, slots, ends: None };
+        tree.refresh_ends();
+        tree
+    }
+
+    /// 用接下来的 n 个 (key, value, 槽位) 建一棵黑高为 black_height 的子树
+    fn build(
+        entries: &mut impl Iterator<Item = (K, V, usize)>,
+        n: usize,
+        black_height: u32,
+    ) -> Option<Box<Node<K, V>>> {
+        if n == 0 {
+            return None;
+        }
+        fn take<K, V>(entries: &mut impl Iterator<Item = (K, V, usize)>, color: Color) -> Box<Node<K, V>> {
+            let (key, value, slot) = entries.next().unwrap();
+            Box::new(Node::new(key, value, slot, color))
+        }
+        // 子树按中序消费条目: 左子树, 节点, 右子树
+        let child_max = 3usize.saturating_pow(black_height - 1) - 1;
+        let left = (n - 1).div_ceil(2);
+        if left <= child_max {
+            let l = Self::build(entries, left, black_height - 1);
+            let mut x = take(entries, Color::Black);
+            x.left = l;
+            x.right = Self::build(entries, n - 1 - left, black_height - 1);
+            return Some(x);
+        }
+        let rest = n - 2;
+        let c0 = Self::build(entries, rest.div_ceil(3), black_height - 1);
+        let mut y = take(entries, Color::Red);
+        y.left = c0;
+        y.right = Self::build(entries, (rest + 1) / 3, black_height - 1);
+        let mut x = take(entries, Color::Black);
+        x.left = Some(y);
+        x.right = Self::build(entries, rest / 3, black_height - 1);
+        Some(x)
+    }
+}
+impl<K: Ord + Clone, V: Clone> RBTree<K, V> {
+    /// 定位在第一个存活条目上的可变游标
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut { tree: self, stack: Stack { nodes: Vec::new() } };
+        cursor.move_next();
+        cursor
+    }
+
+    /// 定位在第一个 key >= 给定 key 的存活条目上的可变游标
+    pub fn lower_bound_mut(&mut self, key: &K) -> CursorMut<'_, K, V> {
+        let mut cursor = CursorMut { tree: self, stack: Stack { nodes: Vec::new() } };
+        cursor.lower_bound(key);
+        cursor
+    }
+}
+
+/// 从根到当前节点的节点栈, 为空表示空位
+///
+/// 指针只在游标持有树的可变借用期间使用; 取出的引用受栈的借用约束, 不会比栈活得更久
+#[derive(Clone)]
+struct Stack<K, V> {
+    nodes: Vec<NonNull<Node<K, V>>>,
+}
+
+impl<K, V> Stack<K, V> {
+    fn node(&self, d: usize) -> &Node<K, V> {
+        // SAFETY: 栈中的指针都取自游标独占的树; 节点在 Box 中, 旋转只移动 Box 不移动节点,
+        // 节点只在 `settle_removed` 改变结构时被释放, 之后游标会重新定位
+        unsafe { self.nodes[d].as_ref() }
+    }
+
+    fn node_mut(&mut self, d: usize) -> &mut Node<K, V> {
+        // SAFETY: 同 node; 每次只通过一个指针改动节点
+        unsafe { self.nodes[d].as_mut() }
+    }
+
+    fn top(&self) -> Option<&Node<K, V>> {
+        let d = self.nodes.len().checked_sub(1)?;
+        Some(self.node(d))
+    }
+
+    fn top_mut(&mut self) -> Option<&mut Node<K, V>> {
+        let d = self.nodes.len().checked_sub(1)?;
+        Some(self.node_mut(d))
+    }
+
+    /// child 是否是第 d 层节点的右 (或左) 子节点; 只比较地址
+    fn is_child(&self, d: usize, child: NonNull<Node<K, V>>, right: bool) -> bool {
+        let parent = self.node(d);
+        let link = if right { &parent.right } else { &parent.left };
+        link.as_deref().is_some_and(|n| std::ptr::eq(n, child.as_ptr()))
+    }
+
+    /// 从栈顶一路向左 (或向右) 下降
+    fn descend(&mut self, right: bool) {
+        loop {
+            let node = self.top_mut().unwrap();
+            let next = if right { node.right.as_deref_mut() } else { node.left.as_deref_mut() };
+            let Some(child) = next else { return };
+            let child = NonNull::from(child);
+            self.nodes.push(child);
+        }
+    }
+}
+
+/// 可变游标: 停在某个存活节点上, 或停在首尾之间的空位
+///
+/// LLRB 没有父指针, 游标保存从根到当前节点的节点栈, 前后移动只在栈上进出, 不回到根, 也不比较 key。
+/// `remove_current` 只打墓碑; `insert_after` 把新节点挂在当前节点之后, 再沿栈自底向上做旋转和变色。
+pub struct CursorMut<'a, K, V> {
+    tree: &'a mut RBTree<K, V>,
+    stack: Stack<K, V>,
+}
+
+impl<'a, K: Ord + Clone, V: Clone> CursorMut<'a, K, V> {
+    /// 中序后继 (right = true) 或前驱
+    fn step(root: &mut Option<Box<Node<K, V>>>, stack: &mut Stack<K, V>, right: bool) {
+        let Some(node) = stack.top_mut() else {
+            // 空位 -> 第一个 / 最后一个节点
+            if let Some(root) = root.as_deref_mut() {
+                stack.nodes.push(NonNull::from(root));
+                stack.descend(!right);
+            }
+            return;
+        };
+        if let Some(child) = if right { node.right.as_deref_mut() } else { node.left.as_deref_mut() } {
+            let child = NonNull::from(child);
+            stack.nodes.push(child);
+            stack.descend(!right);
+            return;
+        }
+        // 回溯到第一个从另一侧子树返回的祖先
+        while let Some(child) = stack.nodes.pop() {
+            let d = stack.nodes.len();
+            if d > 0 && stack.is_child(d - 1, child, !right) {
+                return;
+            }
+        }
+    }
+
+    fn is_live(&self, stack: &Stack<K, V>) -> bool {
+        stack.top().is_none_or(|node| self.tree.slots.is_live(node.slot))
+    }
+
+    fn current(&self) -> Option<&Node<K, V>> {
+        self.stack.top()
+    }
+
+    pub fn key(&self) -> Option<&K> {
+        self.current().map(|node| &node.key)
+    }
+
+    pub fn value(&self) -> Option<&V> {
+        self.current().map(|node| &node.value)
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        self.stack.top_mut().map(|node| &mut node.value)
+    }
+
+    /// 当前条目的句柄
+    pub fn handle(&self) -> Option<Handle> {
+        self.current().map(|node| self.tree.slots.handle(node.slot))
+    }
+
+    /// 后移一个存活条目, 越过末尾后停在空位; 返回是否停在条目上
+    pub fn move_next(&mut self) -> bool {
+        loop {
+            Self::step(&mut self.tree.root, &mut self.stack, true);
+            if self.is_live(&self.stack) {
+                return !self.stack.nodes.is_empty();
+            }
+        }
+    }
+
+    /// 前移一个存活条目, 越过开头后停在空位; 返回是否停在条目上
+    pub fn move_prev(&mut self) -> bool {
+        loop {
+            Self::step(&mut self.tree.root, &mut self.stack, false);
+            if self.is_live(&self.stack) {
+                return !self.stack.nodes.is_empty();
+            }
+        }
+    }
+
+    /// 从根重新定位到第一个 key >= 给定 key 的存活条目
+    pub fn lower_bound(&mut self, key: &K) {
+        self.stack.nodes.clear();
+        // 候选节点在栈中的深度, 越深越接近
+        let mut candidate = None;
+        let mut node = self.tree.root.as_deref_mut();
+        while let Some(n) = node {
+            self.stack.nodes.push(NonNull::from(&mut *n));
+            if &n.key >= key {
+                candidate = Some(self.stack.nodes.len());
+                if &n.key == key {
+                    break;
+                }
+                node = n.left.as_deref_mut();
+            } else {
+                node = n.right.as_deref_mut();
+            }
+        }
+        self.stack.nodes.truncate(candidate.unwrap_or(0));
+        if !self.is_live(&self.stack) {
+            self.move_next();
+        }
+    }
+
+    /// 删除当前条目 (打墓碑, O(1)), 游标移到其后继
+    ///
+    /// 删的是端点或触发了 purge 时树的结构会变化, 此时按后继的 key 重新定位
+    pub fn remove_current(&mut self) -> Option<(K, V)> {
+        let node = self.current()?;
+        let entry = (node.key.clone(), node.value.clone());
+        let slot = node.slot;
+        let handle = self.tree.slots.handle(slot);
+        self.tree.slots.tombstone(handle);
+        self.move_next();
+        let next = self.key().cloned();
+        if self.tree.settle_removed(slot) {
+            match next {
+                Some(key) => self.lower_bound(&key),
+                None => self.stack.nodes.clear(),
+            }
+        }
+        Some(entry)
+    }
+
+    /// 在当前条目之后插入, key 必须严格介于当前条目与下一个存活条目之间; 游标位置不变
+    ///
+    /// 从当前节点向上找到 key 所在的子树 (中间只隔着墓碑时才会越过当前节点), 向下挂上新的红节点,
+    /// 再沿栈自底向上做与 `insert` 相同的旋转和变色; 同 key 的墓碑直接复活
+    pub fn insert_after(&mut self, key: K, value: V) -> Result<Handle, String> {
+        let current = self.current().ok_or("cursor is not on an entry")?;
+        if key <= current.key {
+            return Err("key must be greater than the current key".to_string());
+        }
+        let current_slot = current.slot;
+        let mut next = self.stack.clone();
+        loop {
+            Self::step(&mut self.tree.root, &mut next, true);
+            match next.top() {
+                Some(n) if !self.tree.slots.is_live(n.slot) => continue,
+                Some(n) if key >= n.key => {
+                    return Err("key must be less than the next key".to_string());
+                }
+                _ => break,
+            }
+        }
+
+        // 子树的上界是最近的一个从左侧下来的祖先
+        let top = self.stack.nodes.len() - 1;
+        let mut d = top;
+        for e in (0..top).rev() {
+            if self.stack.is_child(e, self.stack.nodes[e + 1], false) {
+                if key < self.stack.node(e).key {
+                    break;
+                }
+                d = e;
+            }
+        }
+        let mut chain = Stack { nodes: self.stack.nodes[..=d].to_vec() };
+        let slot = loop {
+            let n = chain.top_mut().unwrap();
+            let link = match key.cmp(&n.key) {
+                Ordering::Less => &mut n.left,
+                Ordering::Greater => &mut n.right,
+                Ordering::Equal => {
+                    // 介于当前与后继之间的同 key 节点只可能是墓碑
+                    n.value = value;
+                    return Ok(self.tree.slots.revive(n.slot));
+                }
+            };
+            match link {
+                Some(child) => {
+                    let child = NonNull::from(child.as_mut());
+                    chain.nodes.push(child);
+                }
+                None => {
+                    let slot = self.tree.slots.alloc();
+                    let child = link.insert(Box::new(Node::new(key, value, slot, Color::Red)));
+                    let child = NonNull::from(child.as_mut());
+                    chain.nodes.push(child);
+                    break slot;
+                }
+            }
+        };
+
+        for e in (0..chain.nodes.len() - 1).rev() {
+            self.fix_up(&mut chain, e);
+        }
+        self.tree.root.as_mut().unwrap().color = Color::Black;
+        // 当前节点是存活的最大节点时, 新节点成为新的最大节点
+        if let Some((_, last)) = self.tree.ends.as_mut().filter(|(_, last)| *last == current_slot) {
+            *last = slot;
+        }
+
+        // chain 停在新节点上, 它的前一个存活节点就是原来的当前节点
+        self.stack = chain;
+        self.move_prev();
+        Ok(self.tree.slots.handle(slot))
+    }
+
+    /// chain[e] 所在的链接: 根或父节点的左右子节点
+    fn link<'n>(root: &'n mut Option<Box<Node<K, V>>>, chain: &'n mut Stack<K, V>, e: usize) -> &'n mut Option<Box<Node<K, V>>> {
+        if e == 0 {
+            return root;
+        }
+        let right = chain.is_child(e - 1, chain.nodes[e], true);
+        let parent = chain.node_mut(e - 1);
+        if right { &mut parent.right } else { &mut parent.left }
+    }
+
+    /// 对 chain[e] 做 `insert` 中的修正
+    fn fix_up(&mut self, chain: &mut Stack<K, V>, e: usize) {
+        let h = chain.node(e);
+        if RBTree::is_red(&h.right) && !RBTree::is_red(&h.left) {
+            self.rotate(chain, e, false);
+        }
+        let h = chain.node(e);
+        if RBTree::is_red(&h.left) && RBTree::is_red(&h.left.as_ref().unwrap().left) {
+            self.rotate(chain, e, true);
+        }
+        let h = chain.node(e);
+        if RBTree::is_red(&h.left) && RBTree::is_red(&h.right) {
+            RBTree::flip_colors(Self::link(&mut self.tree.root, chain, e).as_mut().unwrap());
+        }
+    }
+
+    /// 左旋 (或右旋) chain[e], 并让 chain 仍然是从根到原栈顶节点的路径
+    fn rotate(&mut self, chain: &mut Stack<K, V>, e: usize, right: bool) {
+        let link = Self::link(&mut self.tree.root, chain, e);
+        let h = link.take().unwrap();
+        let mut x = if right { RBTree::rotate_right(h) } else { RBTree::rotate_left(h) };
+        let new_top = NonNull::from(x.as_mut());
+        *link = Some(x);
+
+        // 旋转只改变 h 与 x 两层: 路径经过 x 时, x 的内侧子节点改挂到 h 下; chain[e] 仍指向 h
+        if chain.nodes.get(e + 1) == Some(&new_top) {
+            let inner = chain.nodes.get(e + 2).is_some_and(|&c| chain.is_child(e, c, false) || chain.is_child(e, c, true));
+            if inner {
+                chain.nodes.swap(e, e + 1);
+            } else {
+                chain.nodes.remove(e);
+            }
+        } else {
+            chain.nodes.insert(e, new_top);
+        }
+    }
+}
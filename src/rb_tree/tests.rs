@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use crate::rb_tree::rb_tree::RBTree;
+    use crate::rb_tree::rb_tree::{Color, Node, RBTree};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
 
     #[test]
     fn test_rb_tree_insert_find() {
@@ -14,4 +16,307 @@ mod tests {
         assert_eq!(tree.find(&15), Some(&"fifteen"));
         assert_eq!(tree.find(&999), None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_rb_tree_memory_usage() {
+        let mut tree = RBTree::new();
+        assert_eq!(tree.memory_usage().total_bytes, 0);
+        for k in 0..1000u32 {
+            tree.insert(k, k as u64);
+        }
+
+        let report = tree.memory_usage();
+        assert_eq!(report.node_count, 1000);
+        assert_eq!(report.entry_count, 1000);
+        assert_eq!(report.key_bytes, 4000);
+        assert_eq!(report.value_bytes, 8000);
+        // 节点 + 句柄表
+        assert!(report.total_bytes > 1000 * std::mem::size_of::<crate::rb_tree::rb_tree::Node<u32, u64>>());
+        assert_eq!(report.fill_factor, 1.0);
+        // 红黑树高度不超过 2 * log2(n + 1)
+        assert!(report.height <= 20);
+    }
+
+    #[test]
+    fn test_rb_tree_remove_at() {
+        let mut tree = RBTree::new();
+        let handles: Vec<_> = (0..100u32).map(|k| tree.insert(k, k * 10)).collect();
+        // 更新已有 key 返回同一句柄
+        assert_eq!(tree.insert(5, 55), handles[5]);
+
+        assert!(tree.remove_at(handles[0]));
+        assert!(!tree.remove_at(handles[0]));
+        assert!(tree.remove_at(handles[99]));
+        assert_eq!(tree.find(&0), None);
+        assert_eq!(tree.first(), Some((&1, &10)));
+        assert_eq!(tree.last(), Some((&98, &980)));
+        assert_eq!(tree.range_query(&0, &2), vec![&10, &20]);
+        // 两端的条目被立即摘除, 不留墓碑
+        assert_eq!((tree.len(), tree.tombstones()), (98, 0));
+
+        // 其他条目的删除和旋转不影响句柄
+        for k in (10..50).step_by(2) {
+            tree.delete(&k);
+        }
+        assert!(tree.is_valid(handles[11]));
+        assert!(tree.remove_at(handles[11]));
+        assert_eq!(tree.find(&11), None);
+        assert_eq!(tree.tombstones(), 1);
+        // 有界遍历跳过墓碑, 只取前 n 个
+        assert_eq!(tree.first_n(4), vec![(&1, &10), (&2, &20), (&3, &30), (&4, &40)]);
+        assert_eq!(tree.first_n(11).last(), Some(&(&15, &150)));
+        assert_eq!(tree.last_n(2), vec![(&98, &980), (&97, &970)]);
+        assert_eq!(tree.last_n(1000).len(), tree.len());
+        assert_eq!(tree.lower_bound(&11), Some((&13, &130)));
+        assert_eq!(tree.lower_bound(&10), Some((&13, &130)));
+        assert_eq!(tree.lower_bound(&0), Some((&1, &10)));
+        assert_eq!(tree.lower_bound(&99), None);
+
+        // 墓碑 key 重新插入得到新句柄
+        let h = tree.insert(0, 1);
+        assert_ne!(h, handles[0]);
+        assert_eq!(tree.find(&0), Some(&1));
+
+        tree.purge();
+        assert_eq!(tree.tombstones(), 0);
+        assert_eq!(tree.len(), 78);
+        assert_eq!(tree.memory_usage().node_count, 78);
+        assert!(tree.is_valid(handles[12 + 1]));
+    }
+
+    #[test]
+    fn test_rb_tree_remove_at_keeps_ends_live() {
+        let mut tree = RBTree::new();
+        let handles: Vec<_> = (0..1000u32).map(|k| tree.insert(k, k)).collect();
+
+        // 先在中间打墓碑, 再从两端逐个删除: 端点总是存活条目
+        for k in [1u32, 2, 3, 997, 996] {
+            assert!(tree.remove_at(handles[k as usize]));
+        }
+        assert_eq!(tree.tombstones(), 5);
+        assert!(tree.remove_at(handles[0]));
+        assert_eq!(tree.first(), Some((&4, &4)));
+        assert!(tree.remove_at(handles[999]));
+        assert!(tree.remove_at(handles[998]));
+        assert_eq!(tree.last(), Some((&995, &995)));
+        // 紧邻端点的墓碑随端点一起被摘除
+        assert_eq!(tree.tombstones(), 0);
+        assert_eq!(tree.memory_usage().node_count, tree.len());
+
+        // delete 删掉端点后同样不会露出墓碑
+        assert!(tree.remove_at(handles[5]));
+        tree.delete(&4);
+        assert_eq!(tree.first(), Some((&6, &6)));
+        assert_eq!(tree.tombstones(), 0);
+    }
+
+    #[test]
+    fn test_rb_tree_best_end_churn() {
+        // 订单簿的典型模式: 新价位插在两端之外, 最优价位被吃光后按句柄删除
+        let mut rng = StdRng::seed_from_u64(35);
+        let mut tree = RBTree::new();
+        let mut reference = std::collections::BTreeMap::new();
+        for i in 0..5_000u32 {
+            let k = match rng.gen_range(0..3) {
+                0 => reference.keys().next().map_or(5_000, |k: &u32| k.saturating_sub(rng.gen_range(1..4))),
+                1 => reference.keys().next_back().map_or(5_000, |k| k + rng.gen_range(1..4)),
+                _ => rng.gen_range(0..10_000),
+            };
+            reference.insert(k, tree.insert(k, i));
+            if rng.gen_bool(0.45) {
+                let (&k, &h) = if rng.gen_bool(0.5) { reference.iter().next() } else { reference.iter().next_back() }.unwrap();
+                assert!(tree.remove_at(h));
+                reference.remove(&k);
+            }
+            assert_eq!(tree.first().map(|(k, _)| *k), reference.keys().next().copied());
+            assert_eq!(tree.last().map(|(k, _)| *k), reference.keys().next_back().copied());
+        }
+        check_tree(&tree);
+        assert_eq!(tree.len(), reference.len());
+    }
+
+    #[test]
+    fn test_rb_tree_remove_at_compacts() {
+        let mut tree = RBTree::new();
+        let handles: Vec<_> = (0..3000u32).map(|k| tree.insert(k, k)).collect();
+
+        // 只删除, 不再插入: 墓碑比例超过阈值时由 remove_at 触发重建
+        let mut max_tombstones = 0;
+        for h in handles.iter().skip(1).step_by(2).take(1400) {
+            assert!(tree.remove_at(*h));
+            max_tombstones = max_tombstones.max(tree.tombstones());
+            assert!(tree.tombstones() * 2 <= tree.len() || tree.tombstones() < 32);
+        }
+        assert!(max_tombstones >= 32);
+        assert!(tree.tombstones() < max_tombstones);
+        assert_eq!(tree.len(), 1600);
+        // 重建后存活句柄仍然有效
+        for (k, h) in handles.iter().enumerate() {
+            assert_eq!(tree.is_valid(*h), k % 2 == 0 || k >= 2801, "{}", k);
+        }
+        let keys: Vec<u32> = tree.entries().into_iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, (0..3000).filter(|k| k % 2 == 0 || *k >= 2801).collect::<Vec<_>>());
+        assert!(tree.memory_usage().height <= 2 * 11);
+    }
+
+    /// 校验红黑性质: 根为黑, 红节点没有红子节点, 各路径黑高相同, 中序有序; 返回黑高
+    fn check(node: &Option<Box<Node<u32, u32>>>, lo: Option<u32>, hi: Option<u32>) -> usize {
+        let Some(n) = node else {
+            return 1;
+        };
+        assert!(lo.is_none_or(|lo| n.key > lo) && hi.is_none_or(|hi| n.key < hi));
+        if n.color == Color::Red {
+            for child in [&n.left, &n.right].into_iter().flatten() {
+                assert_eq!(child.color, Color::Black, "red node {} has a red child", n.key);
+            }
+        }
+        let left = check(&n.left, lo, Some(n.key));
+        assert_eq!(left, check(&n.right, Some(n.key), hi), "black height differs under {}", n.key);
+        left + usize::from(n.color == Color::Black)
+    }
+
+    fn check_tree(tree: &RBTree<u32, u32>) {
+        assert!(tree.root.as_ref().is_none_or(|root| root.color == Color::Black));
+        check(&tree.root, None, None);
+    }
+
+    #[test]
+    fn test_rb_tree_cursor_mut() {
+        let mut tree = RBTree::new();
+        for k in (0..100u32).step_by(2) {
+            tree.insert(k, k);
+        }
+
+        let mut cursor = tree.lower_bound_mut(&31);
+        assert_eq!(cursor.key(), Some(&32));
+        *cursor.value_mut().unwrap() = 320;
+        assert!(cursor.insert_after(32, 0).is_err());
+        assert!(cursor.insert_after(34, 0).is_err());
+        assert!(cursor.insert_after(33, 33).is_ok());
+        assert_eq!(cursor.value(), Some(&320));
+        assert_eq!(cursor.remove_current(), Some((32, 320)));
+        assert_eq!(cursor.key(), Some(&33));
+
+        cursor.lower_bound(&1000);
+        assert_eq!(cursor.key(), None);
+        let mut got = Vec::new();
+        while cursor.move_prev() {
+            got.push(*cursor.key().unwrap());
+        }
+        // 墓碑 32 被跳过
+        assert_eq!(got.len(), 50);
+        assert!(got.windows(2).all(|w| w[0] > w[1]) && !got.contains(&32));
+        assert!(cursor.move_next());
+        assert_eq!(cursor.key(), Some(&0));
+    }
+
+    #[test]
+    fn test_rb_tree_cursor_insert_rebalances() {
+        // 只用 insert_after 顺序追加: 每次都挂在最右侧, 沿栈向上的旋转和变色必须保持红黑性质
+        let mut tree = RBTree::new();
+        tree.insert(0u32, 0);
+        let mut cursor = tree.cursor_mut();
+        for k in 1..1024u32 {
+            cursor.insert_after(k * 4, k).unwrap();
+            assert_eq!(cursor.key(), Some(&((k - 1) * 4)));
+            assert!(cursor.move_next());
+        }
+        drop(cursor);
+        check_tree(&tree);
+        assert_eq!(tree.last(), Some((&4092, &1023)));
+        assert!(tree.memory_usage().height <= 2 * 11);
+
+        // 在每对相邻条目之间插入, 新节点挂在当前节点的右子树或当前节点下
+        for step in [2u32, 1] {
+            let mut cursor = tree.cursor_mut();
+            while let Some(&k) = cursor.key() {
+                if k % 4 == 0 && k < 4092 {
+                    cursor.insert_after(k + step, 0).unwrap();
+                    assert_eq!(cursor.key(), Some(&k));
+                }
+                cursor.move_next();
+            }
+            drop(cursor);
+            check_tree(&tree);
+        }
+        assert_eq!(tree.len(), 1024 + 2 * 1023);
+        assert!((0..4092u32).filter(|k| k % 4 != 3).all(|k| tree.find(&k).is_some()));
+        assert!(tree.memory_usage().height <= 2 * 12);
+    }
+
+    #[test]
+    fn test_rb_tree_cursor_keeps_ends_live() {
+        let mut tree = RBTree::new();
+        let handles: Vec<_> = (0..20u32).map(|k| tree.insert(k * 10, k)).collect();
+        for k in [3, 4, 5, 6, 7] {
+            assert!(tree.remove_at(handles[k]));
+        }
+
+        // 当前条目与后继之间隔着一串墓碑: 新节点可能要挂到当前节点的祖先下面
+        let mut cursor = tree.lower_bound_mut(&20);
+        assert!(cursor.insert_after(80, 0).is_err());
+        let h = cursor.insert_after(65, 65).unwrap();
+        assert_eq!(cursor.key(), Some(&20));
+        let revived = cursor.insert_after(40, 40).unwrap();
+        assert_ne!(revived, handles[4]);
+        assert!(cursor.move_next() && cursor.move_next());
+        assert_eq!(cursor.handle(), Some(h));
+
+        // 在最大条目之后追加, 新节点成为最大条目
+        cursor.lower_bound(&190);
+        cursor.insert_after(500, 500).unwrap();
+        assert!(cursor.move_next());
+        assert_eq!(cursor.remove_current(), Some((500, 500)));
+        assert_eq!(cursor.key(), None);
+        drop(cursor);
+        check_tree(&tree);
+        assert_eq!(tree.last(), Some((&190, &19)));
+        assert_eq!(tree.find(&65), Some(&65));
+        assert_eq!(tree.find(&40), Some(&40));
+        assert_eq!(tree.find(&50), None);
+
+        // 从最小条目开始删: 端点立即摘除, 游标按后继重新定位
+        let mut cursor = tree.cursor_mut();
+        for k in [0, 10, 20, 40, 65, 80] {
+            assert_eq!(cursor.key(), Some(&k));
+            cursor.remove_current();
+        }
+        assert_eq!(cursor.key(), Some(&90));
+        drop(cursor);
+        assert_eq!(tree.first(), Some((&90, &9)));
+        assert_eq!(tree.tombstones(), 0);
+        check_tree(&tree);
+    }
+
+    #[test]
+    fn test_rb_tree_cursor_matches_reference() {
+        // 撮合式遍历: 边走边删除和插入, 期间会触发 purge; 每一轮后校验红黑性质并与 BTreeMap 对照
+        let mut tree = RBTree::new();
+        let mut reference = std::collections::BTreeMap::new();
+        for k in (0..3000u32).step_by(3) {
+            tree.insert(k, k);
+            reference.insert(k, k);
+        }
+        for round in 1..4u32 {
+            let mut cursor = tree.cursor_mut();
+            let mut step = 0;
+            while let Some(&k) = cursor.key() {
+                step += 1;
+                if step % (round + 1) == 0 {
+                    assert_eq!(cursor.remove_current(), reference.remove_entry(&k));
+                    continue;
+                }
+                if !reference.contains_key(&(k + 1)) && step % 2 == 0 {
+                    cursor.insert_after(k + 1, k + 1).unwrap();
+                    reference.insert(k + 1, k + 1);
+                }
+                cursor.move_next();
+            }
+            drop(cursor);
+            check_tree(&tree);
+            let keys: Vec<u32> = tree.entries().into_iter().map(|(k, _)| *k).collect();
+            assert_eq!(keys, reference.keys().copied().collect::<Vec<_>>());
+            assert!(reference.iter().all(|(k, v)| tree.find(k) == Some(v)));
+        }
+    }
+}
@@ -0,0 +1,601 @@
+use std::fmt::Write as _;
+
+use crate::latency::latency::{csv_field, json_string};
+use crate::runner::runner::{parse_number, ScenarioResult};
+
+/// 一行基准测试结果 (一个场景中的一个操作类型)
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultRecord {
+    pub structure: String,
+    /// 仅 btree/bptree 有意义
+    pub degree: Option<usize>,
+    pub workload: String,
+    pub op: String,
+    pub n: usize,
+    pub iterations: usize,
+    /// 所有迭代中该操作的总次数
+    pub count: u64,
+    /// 各次迭代平均延迟的均值 (纳秒)
+    pub mean_ns: f64,
+    /// 各次迭代平均延迟的样本标准差 (纳秒)
+    pub stddev_ns: f64,
+    pub p50_ns: u64,
+    pub p90_ns: u64,
+    pub p99_ns: u64,
+    pub p999_ns: u64,
+    pub max_ns: u64,
+    /// 该操作自身的吞吐, 即 1e9 / mean_ns
+    pub ops_per_sec: f64,
+    pub memory_bytes: Option<usize>,
+    /// 以下三项仅在开启 `--alloc-stats` 时存在
+    pub allocs_per_op: Option<f64>,
+    pub bytes_per_op: Option<f64>,
+    pub peak_heap_bytes: Option<usize>,
+}
+
+const COLUMNS: [&str; 19] = [
+    "structure",
+    "degree",
+    "workload",
+    "op",
+    "n",
+    "iterations",
+    "count",
+    "mean_ns",
+    "stddev_ns",
+    "p50_ns",
+    "p90_ns",
+    "p99_ns",
+    "p999_ns",
+    "max_ns",
+    "ops_per_sec",
+    "memory_bytes",
+    "allocs_per_op",
+    "bytes_per_op",
+    "peak_heap_bytes",
+];
+
+impl ResultRecord {
+    /// 场景唯一标识, 用于对比两份结果
+    pub fn key(&self) -> String {
+        let degree = self.degree.map(|d| d.to_string()).unwrap_or_else(|| "-".to_string());
+        format!("{}/d{}/{}/{}/n{}", self.structure, degree, self.workload, self.op, self.n)
+    }
+
+    /// 按 COLUMNS 顺序输出字段, 数值以字符串形式给出, None 为空串
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.structure.clone(),
+            opt_to_string(self.degree),
+            self.workload.clone(),
+            self.op.clone(),
+            self.n.to_string(),
+            self.iterations.to_string(),
+            self.count.to_string(),
+            format!("{:.2}", self.mean_ns),
+            format!("{:.2}", self.stddev_ns),
+            self.p50_ns.to_string(),
+            self.p90_ns.to_string(),
+            self.p99_ns.to_string(),
+            self.p999_ns.to_string(),
+            self.max_ns.to_string(),
+            format!("{:.2}", self.ops_per_sec),
+            opt_to_string(self.memory_bytes),
+            self.allocs_per_op.map(|v| format!("{:.2}", v)).unwrap_or_default(),
+            self.bytes_per_op.map(|v| format!("{:.2}", v)).unwrap_or_default(),
+            opt_to_string(self.peak_heap_bytes),
+        ]
+    }
+
+    /// 由 (列名, 值) 构造, 值为 None 表示缺失或 null; 可选列缺失时按 None 处理
+    fn from_fields(get: impl Fn(&str) -> Option<String>) -> Result<Self, String> {
+        let text = |col: &str| get(col).ok_or_else(|| format!("missing column `{}`", col));
+        let num = |col: &str| -> Result<f64, String> { parse_number(col, &text(col)?) };
+        let int = |col: &str| -> Result<u64, String> { Ok(num(col)? as u64) };
+        let opt_f64 = |col: &str| -> Result<Option<f64>, String> {
+            match get(col) {
+                Some(v) if !v.is_empty() => Ok(Some(parse_number(col, &v)?)),
+                _ => Ok(None),
+            }
+        };
+        let opt = |col: &str| -> Result<Option<usize>, String> { Ok(opt_f64(col)?.map(|v| v as usize)) };
+        Ok(ResultRecord {
+            structure: text("structure")?,
+            degree: opt("degree")?,
+            workload: text("workload")?,
+            op: text("op")?,
+            n: int("n")? as usize,
+            iterations: int("iterations")? as usize,
+            count: int("count")?,
+            mean_ns: num("mean_ns")?,
+            stddev_ns: num("stddev_ns")?,
+            p50_ns: int("p50_ns")?,
+            p90_ns: int("p90_ns")?,
+            p99_ns: int("p99_ns")?,
+            p999_ns: int("p999_ns")?,
+            max_ns: int("max_ns")?,
+            ops_per_sec: num("ops_per_sec")?,
+            memory_bytes: opt("memory_bytes")?,
+            allocs_per_op: opt_f64("allocs_per_op")?,
+            bytes_per_op: opt_f64("bytes_per_op")?,
+            peak_heap_bytes: opt("peak_heap_bytes")?,
+        })
+    }
+}
+
+fn opt_to_string(v: Option<usize>) -> String {
+    v.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// 将运行结果展开为逐操作的结果记录
+pub fn records_from_results(results: &[ScenarioResult]) -> Vec<ResultRecord> {
+    let mut out = Vec::new();
+    for r in results {
+        let degree = if r.config.structure.uses_degree() {
+            Some(r.config.degree)
+        } else {
+            None
+        };
+        for s in &r.latency {
+            let (mean_ns, stddev_ns) = mean_stddev(r.op_iteration_means(&s.op));
+            let op_alloc = r.alloc.as_ref().and_then(|a| a.op(&s.op));
+            out.push(ResultRecord {
+                structure: r.config.structure.name().to_string(),
+                degree,
+                workload: r.config.workload.name().to_string(),
+                op: s.op.clone(),
+                n: r.config.size,
+                iterations: r.iteration_ns.len(),
+                count: s.count,
+                mean_ns,
+                stddev_ns,
+                p50_ns: s.p50,
+                p90_ns: s.p90,
+                p99_ns: s.p99,
+                p999_ns: s.p999,
+                max_ns: s.max,
+                ops_per_sec: if mean_ns > 0.0 { 1e9 / mean_ns } else { 0.0 },
+                memory_bytes: r.memory_bytes,
+                allocs_per_op: op_alloc.map(|a| a.allocations_per_op),
+                bytes_per_op: op_alloc.map(|a| a.bytes_per_op),
+                peak_heap_bytes: r.alloc.as_ref().map(|a| a.peak_heap_bytes),
+            });
+        }
+    }
+    out
+}
+
+/// 均值与样本标准差
+pub fn mean_stddev(samples: &[f64]) -> (f64, f64) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    if samples.len() < 2 {
+        return (mean, 0.0);
+    }
+    let var = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, var.sqrt())
+}
+
+// ------------------- 导出 / 读取 -------------------
+
+pub fn to_csv(records: &[ResultRecord]) -> String {
+    let mut out = COLUMNS.join(",");
+    out.push('\n');
+    for r in records {
+        let fields: Vec<String> = COLUMNS
+            .iter()
+            .zip(r.fields())
+            .map(|(col, v)| if is_text_column(col) { csv_field(&v) } else { v })
+            .collect();
+        out.push_str(&fields.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn to_json(records: &[ResultRecord]) -> String {
+    let rows: Vec<String> = records
+        .iter()
+        .map(|r| {
+            let fields: Vec<String> = COLUMNS
+                .iter()
+                .zip(r.fields())
+                .map(|(col, v)| {
+                    if is_text_column(col) {
+                        format!("\"{}\": {}", col, json_string(&v))
+                    } else if v.is_empty() {
+                        format!("\"{}\": null", col)
+                    } else {
+                        format!("\"{}\": {}", col, v)
+                    }
+                })
+                .collect();
+            format!("  {{{}}}", fields.join(", "))
+        })
+        .collect();
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
+
+fn is_text_column(col: &str) -> bool {
+    matches!(col, "structure" | "workload" | "op")
+}
+
+/// 读取 `to_csv` 写出的格式: 表头与 COLUMNS 一致, 每行按列顺序给出全部字段并以换行结束;
+/// 只有文本列可能被 `csv_field` 加上引号, 数值列原样写出, 空串表示缺失
+pub fn parse_csv(input: &str) -> Result<Vec<ResultRecord>, String> {
+    let header = COLUMNS.join(",") + "\n";
+    let mut rest = input.strip_prefix(header.as_str()).ok_or("csv header does not match the exported columns")?;
+    let mut out = Vec::new();
+    while !rest.is_empty() {
+        let row = out.len() + 2;
+        let mut values = Vec::with_capacity(COLUMNS.len());
+        for (i, col) in COLUMNS.iter().enumerate() {
+            let sep = if i + 1 == COLUMNS.len() { '\n' } else { ',' };
+            let (value, tail) = match rest.strip_prefix('"') {
+                Some(quoted) if is_text_column(col) => csv_unquote(quoted)?,
+                _ => {
+                    let end = rest.find([',', '\n']).unwrap_or(rest.len());
+                    (rest[..end].to_string(), &rest[end..])
+                }
+            };
+            rest = tail.strip_prefix(sep).ok_or_else(|| format!("csv row {}: bad field `{}`", row, col))?;
+            values.push(Some(value));
+        }
+        out.push(ResultRecord::from_fields(|col| values[column(col)].clone())?);
+    }
+    Ok(out)
+}
+
+/// `csv_field` 加了引号的字段 (已去掉左引号), 返回字段值和右引号之后的部分
+fn csv_unquote(s: &str) -> Result<(String, &str), String> {
+    let mut value = String::new();
+    let mut rest = s;
+    loop {
+        let end = rest.find('"').ok_or("unterminated quoted csv field")?;
+        value.push_str(&rest[..end]);
+        rest = &rest[end + 1..];
+        match rest.strip_prefix('"') {
+            Some(tail) => {
+                value.push('"');
+                rest = tail;
+            }
+            None => return Ok((value, rest)),
+        }
+    }
+}
+
+/// 读取 `to_json` 写出的格式: 每个对象占一行, 字段按 COLUMNS 顺序写成 `"列名": 值` 并以 `, ` 分隔;
+/// 文本列是 `json_string` 的输出, 其余列是数字或 null
+pub fn parse_json(input: &str) -> Result<Vec<ResultRecord>, String> {
+    let body = input
+        .strip_prefix("[\n")
+        .and_then(|s| s.strip_suffix("\n]\n"))
+        .ok_or("json is not an exported result array")?;
+    if body.is_empty() {
+        return Ok(Vec::new());
+    }
+    // json_string 会转义换行, 所以每行恰好是一个对象
+    body.split(",\n")
+        .enumerate()
+        .map(|(i, line)| {
+            let row = i + 1;
+            let mut rest = line
+                .strip_prefix("  {")
+                .and_then(|s| s.strip_suffix('}'))
+                .ok_or_else(|| format!("json row {}: not an exported object", row))?;
+            let mut values = Vec::with_capacity(COLUMNS.len());
+            for (i, col) in COLUMNS.iter().enumerate() {
+                let key = format!("{}\"{}\": ", if i == 0 { "" } else { ", " }, col);
+                rest = rest.strip_prefix(key.as_str()).ok_or_else(|| format!("json row {}: expected `{}`", row, col))?;
+                let (value, tail) = if is_text_column(col) {
+                    let (text, tail) = json_unquote(rest).map_err(|e| format!("json row {}: {}", row, e))?;
+                    (Some(text), tail)
+                } else {
+                    let end = rest.find(',').unwrap_or(rest.len());
+                    let value = &rest[..end];
+                    ((value != "null").then(|| value.to_string()), &rest[end..])
+                };
+                values.push(value);
+                rest = tail;
+            }
+            if !rest.is_empty() {
+                return Err(format!("json row {}: unexpected `{}`", row, rest));
+            }
+            ResultRecord::from_fields(|col| values[column(col)].clone())
+        })
+        .collect()
+}
+
+/// `json_string` 写出的字符串字面量, 返回字符串和右引号之后的部分; 只接受它会写出的转义
+fn json_unquote(s: &str) -> Result<(String, &str), String> {
+    let body = s.strip_prefix('"').ok_or("expected a string")?;
+    let mut out = String::new();
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((out, &body[i + 1..])),
+            '\\' => out.push(match chars.next().map(|(_, e)| e) {
+                Some('"') => '"',
+                Some('\\') => '\\',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).map(|(_, h)| h).collect();
+                    // 只有控制字符会写成 \u00XX
+                    Some(hex.as_str())
+                        .filter(|h| h.len() == 4 && h.chars().all(|h| h.is_ascii_hexdigit()))
+                        .and_then(|h| u32::from_str_radix(h, 16).ok())
+                        .filter(|&v| v < 0x20)
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("unexpected escape `\\u{}`", hex))?
+                }
+                e => return Err(format!("unexpected escape {:?}", e)),
+            }),
+            c => out.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+fn column(col: &str) -> usize {
+    COLUMNS.iter().position(|c| *c == col).unwrap()
+}
+
+/// 按扩展名写出结果文件
+pub fn write_results(path: &str, records: &[ResultRecord]) -> Result<(), String> {
+    let body = if path.ends_with(".csv") {
+        to_csv(records)
+    } else {
+        to_json(records)
+    };
+    std::fs::write(path, body).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// 按扩展名 (或内容) 读取结果文件
+pub fn load_results(path: &str) -> Result<Vec<ResultRecord>, String> {
+    let body = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let parsed = if path.ends_with(".csv") {
+        parse_csv(&body)
+    } else if path.ends_with(".json") || body.trim_start().starts_with('[') {
+        parse_json(&body)
+    } else {
+        parse_csv(&body)
+    };
+    parsed.map_err(|e| format!("{}: {}", path, e))
+}
+
+// ------------------- 回归对比 -------------------
+
+/// 对比结论
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// 显著变慢且超过阈值
+    Regression,
+    /// 显著变快且超过阈值
+    Improvement,
+    /// 无显著变化
+    Unchanged,
+    /// 仅存在于新结果
+    Added,
+    /// 仅存在于基线
+    Removed,
+    /// 任一方迭代次数 < 2, 无法估计方差, 不做判定
+    InsufficientSamples,
+}
+
+impl Verdict {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Verdict::Regression => "REGRESSION",
+            Verdict::Improvement => "improvement",
+            Verdict::Unchanged => "unchanged",
+            Verdict::Added => "added",
+            Verdict::Removed => "removed",
+            Verdict::InsufficientSamples => "insufficient samples",
+        }
+    }
+}
+
+/// 单个场景的对比结果
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    pub key: String,
+    pub baseline_mean_ns: Option<f64>,
+    pub candidate_mean_ns: Option<f64>,
+    /// 平均延迟变化百分比 (正数表示变慢)
+    pub change_pct: f64,
+    pub t_stat: f64,
+    pub significant: bool,
+    pub memory_change_pct: Option<f64>,
+    pub verdict: Verdict,
+}
+
+/// Welch t 检验: 返回 (t 统计量, 是否在 p < 0.05 下显著)
+///
+/// 任一方迭代次数 < 2 时无法估计方差, 返回 (0, false), 由调用方标记为样本不足
+pub fn welch_t_test(a: &ResultRecord, b: &ResultRecord) -> (f64, bool) {
+    if !has_enough_samples(a) || !has_enough_samples(b) {
+        return (0.0, false);
+    }
+    let diff = b.mean_ns - a.mean_ns;
+    let va = variance_of_mean(a);
+    let vb = variance_of_mean(b);
+    let se2 = va + vb;
+    if se2 == 0.0 {
+        return if diff == 0.0 { (0.0, false) } else { (f64::INFINITY.copysign(diff), true) };
+    }
+    let t = diff / se2.sqrt();
+    // Welch–Satterthwaite 自由度
+    let df_a = va * va / (a.iterations - 1) as f64;
+    let df_b = vb * vb / (b.iterations - 1) as f64;
+    let df = if df_a + df_b > 0.0 { se2 * se2 / (df_a + df_b) } else { f64::INFINITY };
+    (t, t.abs() > t_critical_95(df))
+}
+
+fn has_enough_samples(r: &ResultRecord) -> bool {
+    r.iterations >= 2
+}
+
+fn variance_of_mean(r: &ResultRecord) -> f64 {
+    r.stddev_ns * r.stddev_ns / r.iterations as f64
+}
+
+/// 双侧 0.05 显著性水平下 t 分布的临界值
+fn t_critical_95(df: f64) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179,
+        2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064,
+        2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+    ];
+    if !df.is_finite() || df > 30.0 {
+        return 1.96;
+    }
+    // 向下取整自由度, 结果更保守
+    TABLE[(df.floor() as usize).clamp(1, 30) - 1]
+}
+
+/// 对比两份结果, threshold_pct 为判定回归的最小变化百分比
+pub fn compare(
+    baseline: &[ResultRecord],
+    candidate: &[ResultRecord],
+    threshold_pct: f64,
+) -> Vec<Comparison> {
+    let mut out = Vec::new();
+    for base in baseline {
+        let key = base.key();
+        match candidate.iter().find(|c| c.key() == key) {
+            Some(cand) => {
+                let change_pct = pct_change(base.mean_ns, cand.mean_ns);
+                let (t_stat, significant) = welch_t_test(base, cand);
+                let verdict = if !has_enough_samples(base) || !has_enough_samples(cand) {
+                    Verdict::InsufficientSamples
+                } else if significant && change_pct > threshold_pct {
+                    Verdict::Regression
+                } else if significant && change_pct < -threshold_pct {
+                    Verdict::Improvement
+                } else {
+                    Verdict::Unchanged
+                };
+                // 优先使用分配器统计的堆峰值
+                let memory = |r: &ResultRecord| r.peak_heap_bytes.or(r.memory_bytes);
+                let memory_change_pct = match (memory(base), memory(cand)) {
+                    (Some(a), Some(b)) => Some(pct_change(a as f64, b as f64)),
+                    _ => None,
+                };
+                out.push(Comparison {
+                    key,
+                    baseline_mean_ns: Some(base.mean_ns),
+                    candidate_mean_ns: Some(cand.mean_ns),
+                    change_pct,
+                    t_stat,
+                    significant,
+                    memory_change_pct,
+                    verdict,
+                });
+            }
+            None => out.push(Comparison {
+                key,
+                baseline_mean_ns: Some(base.mean_ns),
+                candidate_mean_ns: None,
+                change_pct: 0.0,
+                t_stat: 0.0,
+                significant: false,
+                memory_change_pct: None,
+                verdict: Verdict::Removed,
+            }),
+        }
+    }
+    for cand in candidate {
+        let key = cand.key();
+        if !baseline.iter().any(|b| b.key() == key) {
+            out.push(Comparison {
+                key,
+                baseline_mean_ns: None,
+                candidate_mean_ns: Some(cand.mean_ns),
+                change_pct: 0.0,
+                t_stat: 0.0,
+                significant: false,
+                memory_change_pct: None,
+                verdict: Verdict::Added,
+            });
+        }
+    }
+    out
+}
+
+fn pct_change(base: f64, new: f64) -> f64 {
+    if base == 0.0 {
+        0.0
+    } else {
+        (new - base) / base * 100.0
+    }
+}
+
+pub fn has_regression(comparisons: &[Comparison]) -> bool {
+    comparisons.iter().any(|c| c.verdict == Verdict::Regression)
+}
+
+pub fn format_comparison(comparisons: &[Comparison]) -> String {
+    let fmt_ns = |v: Option<f64>| v.map(|v| format!("{:.1}", v)).unwrap_or_else(|| "-".to_string());
+    let mut out = format!(
+        "{:<40} {:>12} {:>12} {:>9} {:>8} {:>9} {:>12}\n",
+        "scenario", "base(ns)", "new(ns)", "change", "t", "mem", "verdict"
+    );
+    for c in comparisons {
+        let mem = c
+            .memory_change_pct
+            .map(|m| format!("{:+.1}%", m))
+            .unwrap_or_else(|| "-".to_string());
+        let _ = writeln!(
+            out,
+            "{:<40} {:>12} {:>12} {:>8.1}% {:>8.2} {:>9} {:>12}",
+            c.key,
+            fmt_ns(c.baseline_mean_ns),
+            fmt_ns(c.candidate_mean_ns),
+            c.change_pct,
+            c.t_stat,
+            mem,
+            c.verdict.name()
+        );
+    }
+    out
+}
+
+/// `compare` 子命令参数
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompareArgs {
+    pub baseline: String,
+    pub candidate: String,
+    pub threshold_pct: f64,
+}
+
+impl CompareArgs {
+    /// 解析 `compare` 之后的参数
+    pub fn parse(args: &[String]) -> Result<CompareArgs, String> {
+        let mut files = Vec::new();
+        let mut threshold_pct = 5.0;
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "-t" | "--threshold" => {
+                    let v = iter.next().ok_or("missing value for `--threshold`")?;
+                    threshold_pct = parse_number(arg, v)?;
+                }
+                _ if arg.starts_with('-') => return Err(format!("unknown option `{}`", arg)),
+                _ => files.push(arg.clone()),
+            }
+        }
+        match files.as_slice() {
+            [baseline, candidate] => Ok(CompareArgs {
+                baseline: baseline.clone(),
+                candidate: candidate.clone(),
+                threshold_pct,
+            }),
+            _ => Err("compare expects <BASELINE> <CANDIDATE>".to_string()),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+#[cfg(test)]
+mod tests {
+    use crate::report::report::{
+        compare, has_regression, parse_csv, parse_json, records_from_results, to_csv, to_json,
+        CompareArgs, ResultRecord, Verdict,
+    };
+    use crate::runner::runner::{run_scenario, ScenarioConfig, Structure, Workload};
+
+    fn record(op: &str, mean_ns: f64, stddev_ns: f64) -> ResultRecord {
+        ResultRecord {
+            structure: "bptree".to_string(),
+            degree: Some(3),
+            workload: "mixed".to_string(),
+            op: op.to_string(),
+            n: 1000,
+            iterations: 10,
+            count: 10_000,
+            mean_ns,
+            stddev_ns,
+            p50_ns: 100,
+            p90_ns: 200,
+            p99_ns: 300,
+            p999_ns: 400,
+            max_ns: 500,
+            ops_per_sec: 1e6,
+            memory_bytes: Some(4096),
+            allocs_per_op: None,
+            bytes_per_op: None,
+            peak_heap_bytes: None,
+        }
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let results: Vec<_> = [Structure::RBTree, Structure::BPTree]
+            .into_iter()
+            .map(|structure| {
+                run_scenario(&ScenarioConfig {
+                    structure,
+                    degree: 4,
+                    size: 300,
+                    workload: Workload::Mixed,
+                    seed: 3,
+                    iterations: 3,
+                })
+            })
+            .collect();
+        let records = records_from_results(&results);
+        assert_eq!(records.len(), 6);
+        assert_eq!(records[0].degree, None);
+        assert_eq!(records[3].degree, Some(4));
+        assert!(records[3].memory_bytes.is_some());
+
+        // 数值字段以两位小数导出, 比较时按导出后的结果比较
+        let from_csv = parse_csv(&to_csv(&records)).unwrap();
+        let from_json = parse_json(&to_json(&records)).unwrap();
+        assert_eq!(from_csv, from_json);
+        assert_eq!(from_csv.len(), records.len());
+        assert_eq!(from_csv[0].key(), records[0].key());
+        assert!((from_csv[0].mean_ns - records[0].mean_ns).abs() < 0.01);
+
+        // ops_per_sec 按操作各自计算, 不再是整个场景的吞吐
+        for r in &records {
+            assert!((r.ops_per_sec - 1e9 / r.mean_ns).abs() < 1e-6);
+        }
+        assert_ne!(records[0].ops_per_sec, records[1].ops_per_sec);
+    }
+
+    #[test]
+    fn test_export_escapes_text_columns() {
+        let mut r = record("get", 100.0, 2.0);
+        r.structure = "tree, \"v2\"".to_string();
+        r.workload = "a\\b\nc\td".to_string();
+        r.op = "插入 \u{1F600}".to_string();
+        let records = vec![r, record("insert", 50.0, 1.0)];
+
+        assert_eq!(parse_csv(&to_csv(&records)).unwrap(), records);
+        assert_eq!(parse_json(&to_json(&records)).unwrap(), records);
+        // 导出的 JSON 本身是合法的
+        let value: serde_json::Value = serde_json::from_str(&to_json(&records)).unwrap();
+        assert_eq!(value[0]["structure"], "tree, \"v2\"");
+    }
+
+    #[test]
+    fn test_parse_only_exported_format() {
+        // 读取端只认 to_csv / to_json 写出的布局; 控制字符以 \u00XX 往返
+        let mut r = record("get", 100.0, 2.0);
+        r.op = "a\u{1}b\r".to_string();
+        let records = vec![r, record("insert", 50.0, 1.0)];
+        let json = to_json(&records);
+        let csv = to_csv(&records);
+        assert_eq!(parse_json(&json).unwrap(), records);
+        assert_eq!(parse_csv(&csv).unwrap(), records);
+        assert!(parse_json(&to_json(&[])).unwrap().is_empty());
+        assert!(parse_csv(&to_csv(&[])).unwrap().is_empty());
+
+        // 别的 JSON 写法 (紧凑输出、字段换序、其他转义) 和别的 CSV 表头都会被拒绝
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(parse_json(&serde_json::to_string(&value).unwrap()).is_err());
+        assert!(parse_json(&json.replace("\"structure\": \"bptree\", \"degree\": 3", "\"degree\": 3, \"structure\": \"bptree\"")).is_err());
+        assert!(parse_json(&json.replace("\"op\": \"insert\"", "\"op\": \"\\u0069nsert\"")).is_err());
+        assert!(parse_json(&json.replace("\"op\": \"insert\"", "\"op\": \"insert")).is_err());
+        assert!(parse_csv(&csv.replacen("structure,degree", "degree,structure", 1)).is_err());
+        assert!(parse_csv(csv.trim_end()).is_err());
+    }
+
+    #[test]
+    fn test_compare_insufficient_samples() {
+        let mut single = record("insert", 200.0, 0.0);
+        single.iterations = 1;
+        let result = compare(&[record("insert", 100.0, 2.0)], &[single.clone()], 5.0);
+        assert_eq!(result[0].verdict, Verdict::InsufficientSamples);
+        assert!(!result[0].significant);
+        assert!(!has_regression(&result));
+
+        let result = compare(&[single.clone()], &[single], 5.0);
+        assert_eq!(result[0].verdict, Verdict::InsufficientSamples);
+    }
+
+    #[test]
+    fn test_compare_flags_significant_regressions() {
+        let baseline = vec![record("insert", 100.0, 2.0), record("get", 50.0, 20.0), record("delete", 80.0, 1.0)];
+        let candidate = vec![record("insert", 130.0, 2.0), record("get", 60.0, 20.0), record("range_query", 1.0, 0.0)];
+
+        let result = compare(&baseline, &candidate, 5.0);
+        let verdict = |op: &str| result.iter().find(|c| c.key.ends_with(&format!("/{}/n1000", op))).unwrap().verdict;
+        assert_eq!(verdict("insert"), Verdict::Regression);
+        // 噪声大, 不显著
+        assert_eq!(verdict("get"), Verdict::Unchanged);
+        assert_eq!(verdict("delete"), Verdict::Removed);
+        assert_eq!(verdict("range_query"), Verdict::Added);
+        assert!(has_regression(&result));
+
+        let improved = compare(&baseline[..1], &[record("insert", 70.0, 2.0)], 5.0);
+        assert_eq!(improved[0].verdict, Verdict::Improvement);
+        assert!(!has_regression(&improved));
+    }
+
+    #[test]
+    fn test_compare_args() {
+        let args: Vec<String> = ["a.json", "b.csv", "--threshold", "2.5"].iter().map(|s| s.to_string()).collect();
+        let parsed = CompareArgs::parse(&args).unwrap();
+        assert_eq!(parsed.baseline, "a.json");
+        assert_eq!(parsed.candidate, "b.csv");
+        assert_eq!(parsed.threshold_pct, 2.5);
+        assert!(CompareArgs::parse(&args[..1]).is_err());
+    }
+}
@@ -0,0 +1,825 @@
+use std::fmt::Write as _;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::bptree::bptree::BPTree;
+use crate::alloc_counter::alloc_counter::{self, AllocSnapshot};
+use crate::btree_map::btree_map::BTreeMap;
+use crate::latency::latency::{LatencyHistogram, LatencyRecorder, LatencySummary};
+use crate::rb_tree::rb_tree::RBTree;
+use crate::avl_tree::avl_tree::AVLTree;
+use crate::classic_rb_tree::classic_rb_tree::ClassicRBTree;
+use crate::price_ladder::price_ladder::PriceLadder;
+use crate::skiplist::skiplist::SkipList;
+use crate::concurrent_skiplist::concurrent_skiplist::ConcurrentSkipList;
+
+/// 基准测试统一使用的 map 接口 (key/value 均为 u32)
+pub trait BenchMap {
+    fn insert(&mut self, key: u32, value: u32);
+    fn get(&self, key: &u32) -> Option<u32>;
+    fn delete(&mut self, key: &u32);
+    /// 返回 [start, end] 区间内的条目数
+    fn range_count(&self, start: &u32, end: &u32) -> usize;
+    /// 内存占用 (字节), 不支持时返回 None
+    fn memory_bytes(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl BenchMap for RBTree<u32, u32> {
+    fn insert(&mut self, key: u32, value: u32) {
+        RBTree::insert(self, key, value);
+    }
+    fn get(&self, key: &u32) -> Option<u32> {
+        self.find(key).copied()
+    }
+    fn delete(&mut self, key: &u32) {
+        RBTree::delete(self, key)
+    }
+    fn range_count(&self, start: &u32, end: &u32) -> usize {
+        self.range_query(start, end).len()
+    }
+    fn memory_bytes(&self) -> Option<usize> {
+        Some(self.memory_usage().total_bytes)
+    }
+}
+
+impl BenchMap for AVLTree<u32, u32> {
+    fn insert(&mut self, key: u32, value: u32) {
+        AVLTree::insert(self, key, value)
+    }
+    fn get(&self, key: &u32) -> Option<u32> {
+        self.find(key).copied()
+    }
+    fn delete(&mut self, key: &u32) {
+        AVLTree::delete(self, key)
+    }
+    fn range_count(&self, start: &u32, end: &u32) -> usize {
+        self.range_query(start, end).len()
+    }
+    fn memory_bytes(&self) -> Option<usize> {
+        Some(self.memory_usage().total_bytes)
+    }
+}
+
+impl BenchMap for ClassicRBTree<u32, u32> {
+    fn insert(&mut self, key: u32, value: u32) {
+        ClassicRBTree::insert(self, key, value);
+    }
+    fn get(&self, key: &u32) -> Option<u32> {
+        self.find(key).copied()
+    }
+    fn delete(&mut self, key: &u32) {
+        ClassicRBTree::delete(self, key)
+    }
+    fn range_count(&self, start: &u32, end: &u32) -> usize {
+        self.range_query(start, end).len()
+    }
+    fn memory_bytes(&self) -> Option<usize> {
+        Some(self.memory_usage().total_bytes)
+    }
+}
+
+impl BenchMap for BTreeMap<u32, u32> {
+    fn insert(&mut self, key: u32, value: u32) {
+        BTreeMap::insert(self, key, value)
+    }
+    fn get(&self, key: &u32) -> Option<u32> {
+        BTreeMap::get(self, key).copied()
+    }
+    fn delete(&mut self, key: &u32) {
+        BTreeMap::delete(self, key)
+    }
+    fn range_count(&self, start: &u32, end: &u32) -> usize {
+        self.range_query(start, end).len()
+    }
+    fn memory_bytes(&self) -> Option<usize> {
+        Some(self.memory_usage().total_bytes)
+    }
+}
+
+impl BenchMap for BPTree<u32, u32> {
+    fn insert(&mut self, key: u32, value: u32) {
+        BPTree::insert(self, key, value);
+    }
+    fn get(&self, key: &u32) -> Option<u32> {
+        BPTree::get(self, key)
+    }
+    fn delete(&mut self, key: &u32) {
+        BPTree::delete(self, key)
+    }
+    fn range_count(&self, start: &u32, end: &u32) -> usize {
+        self.range_query(start, end).len()
+    }
+    fn memory_bytes(&self) -> Option<usize> {
+        Some(self.memory_usage().total_bytes)
+    }
+}
+
+impl BenchMap for SkipList<u32, u32> {
+    fn insert(&mut self, key: u32, value: u32) {
+        SkipList::insert(self, key, value)
+    }
+    fn get(&self, key: &u32) -> Option<u32> {
+        SkipList::get(self, key).copied()
+    }
+    fn delete(&mut self, key: &u32) {
+        SkipList::delete(self, key)
+    }
+    fn range_count(&self, start: &u32, end: &u32) -> usize {
+        self.range_query(start, end).len()
+    }
+    fn memory_bytes(&self) -> Option<usize> {
+        Some(self.memory_usage().total_bytes)
+    }
+}
+
+impl BenchMap for ConcurrentSkipList<u32, u32> {
+    fn insert(&mut self, key: u32, value: u32) {
+        ConcurrentSkipList::insert(self, key, value);
+    }
+    fn get(&self, key: &u32) -> Option<u32> {
+        ConcurrentSkipList::get(self, key)
+    }
+    fn delete(&mut self, key: &u32) {
+        ConcurrentSkipList::delete(self, key);
+    }
+    fn range_count(&self, start: &u32, end: &u32) -> usize {
+        self.range_query(start, end).len()
+    }
+}
+
+impl BenchMap for PriceLadder<u32> {
+    fn insert(&mut self, key: u32, value: u32) {
+        PriceLadder::insert(self, key as u64, value)
+    }
+    fn get(&self, key: &u32) -> Option<u32> {
+        PriceLadder::get(self, &(*key as u64)).copied()
+    }
+    fn delete(&mut self, key: &u32) {
+        PriceLadder::delete(self, &(*key as u64))
+    }
+    fn range_count(&self, start: &u32, end: &u32) -> usize {
+        self.range_query(&(*start as u64), &(*end as u64)).len()
+    }
+    fn memory_bytes(&self) -> Option<usize> {
+        Some(self.memory_usage().total_bytes)
+    }
+}
+
+impl BenchMap for std::collections::BTreeMap<u32, u32> {
+    fn insert(&mut self, key: u32, value: u32) {
+        std::collections::BTreeMap::insert(self, key, value);
+    }
+    fn get(&self, key: &u32) -> Option<u32> {
+        std::collections::BTreeMap::get(self, key).copied()
+    }
+    fn delete(&mut self, key: &u32) {
+        self.remove(key);
+    }
+    fn range_count(&self, start: &u32, end: &u32) -> usize {
+        self.range(start..=end).count()
+    }
+}
+
+/// 被测数据结构
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Structure {
+    RBTree,
+    Avl,
+    ClassicRB,
+    BTree,
+    BPTree,
+    SkipList,
+    ConcurrentSkipList,
+    Ladder,
+    Std,
+}
+
+impl Structure {
+    pub const ALL: [Structure; 9] = [
+        Structure::RBTree,
+        Structure::Avl,
+        Structure::ClassicRB,
+        Structure::BTree,
+        Structure::BPTree,
+        Structure::SkipList,
+        Structure::ConcurrentSkipList,
+        Structure::Ladder,
+        Structure::Std,
+    ];
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "rbtree" => Ok(Structure::RBTree),
+            "avl" => Ok(Structure::Avl),
+            "clrs" => Ok(Structure::ClassicRB),
+            "btree" => Ok(Structure::BTree),
+            "bptree" => Ok(Structure::BPTree),
+            "skiplist" => Ok(Structure::SkipList),
+            "cskiplist" => Ok(Structure::ConcurrentSkipList),
+            "ladder" => Ok(Structure::Ladder),
+            "std" => Ok(Structure::Std),
+            _ => Err(format!("unknown structure `{}`", s)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Structure::RBTree => "rbtree",
+            Structure::Avl => "avl",
+            Structure::ClassicRB => "clrs",
+            Structure::BTree => "btree",
+            Structure::BPTree => "bptree",
+            Structure::SkipList => "skiplist",
+            Structure::ConcurrentSkipList => "cskiplist",
+            Structure::Ladder => "ladder",
+            Structure::Std => "std",
+        }
+    }
+
+    /// 是否使用 degree 参数
+    pub fn uses_degree(&self) -> bool {
+        matches!(self, Structure::BTree | Structure::BPTree)
+    }
+}
+
+/// 负载类型
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Workload {
+    /// 从空树开始逐条插入
+    Insert,
+    /// 预填充后逐条查询
+    Query,
+    /// 预填充后逐条删除
+    Delete,
+    /// 预填充后做区间查询
+    Range,
+    /// 预填充一半后混合 50% 插入 / 30% 查询 / 20% 删除
+    Mixed,
+}
+
+impl Workload {
+    pub const ALL: [Workload; 5] = [
+        Workload::Insert,
+        Workload::Query,
+        Workload::Delete,
+        Workload::Range,
+        Workload::Mixed,
+    ];
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "insert" => Ok(Workload::Insert),
+            "query" => Ok(Workload::Query),
+            "delete" => Ok(Workload::Delete),
+            "range" => Ok(Workload::Range),
+            "mixed" => Ok(Workload::Mixed),
+            _ => Err(format!("unknown workload `{}`", s)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Workload::Insert => "insert",
+            Workload::Query => "query",
+            Workload::Delete => "delete",
+            Workload::Range => "range",
+            Workload::Mixed => "mixed",
+        }
+    }
+}
+
+/// 单个场景的配置
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScenarioConfig {
+    pub structure: Structure,
+    pub degree: usize,
+    pub size: usize,
+    pub workload: Workload,
+    pub seed: u64,
+    pub iterations: usize,
+}
+
+/// 单个场景的结果
+#[derive(Clone, Debug)]
+pub struct ScenarioResult {
+    pub config: ScenarioConfig,
+    /// 每次迭代中被计时操作的总耗时 (纳秒)
+    pub iteration_ns: Vec<u64>,
+    /// 每次迭代中被计时的操作数
+    pub ops_per_iteration: usize,
+    /// 按操作类型汇总的延迟
+    pub latency: Vec<LatencySummary>,
+    /// 每个操作类型在各次迭代中的平均延迟 (纳秒), 用于显著性检验
+    pub op_iteration_means: Vec<(String, Vec<f64>)>,
+    /// 最后一次迭代结束时的内存占用 (字节), 不支持的数据结构为 None
+    pub memory_bytes: Option<usize>,
+    /// 分配器统计, 仅在计数分配器生效时 (`--alloc-stats`) 存在
+    pub alloc: Option<AllocReport>,
+}
+
+/// 分配器层面的内存统计
+#[derive(Clone, Debug, PartialEq)]
+pub struct AllocReport {
+    /// 按操作类型统计的每次操作平均分配次数与字节数
+    pub per_op: Vec<OpAllocStats>,
+    /// 各次迭代中数据结构引起的堆峰值 (字节) 的最大值
+    pub peak_heap_bytes: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OpAllocStats {
+    pub op: String,
+    pub allocations_per_op: f64,
+    pub bytes_per_op: f64,
+}
+
+impl AllocReport {
+    pub fn op(&self, op: &str) -> Option<&OpAllocStats> {
+        self.per_op.iter().find(|o| o.op == op)
+    }
+}
+
+impl ScenarioResult {
+    pub fn mean_iteration_ns(&self) -> f64 {
+        if self.iteration_ns.is_empty() {
+            return 0.0;
+        }
+        self.iteration_ns.iter().sum::<u64>() as f64 / self.iteration_ns.len() as f64
+    }
+
+    /// 某个操作类型在各次迭代中的平均延迟 (纳秒)
+    pub fn op_iteration_means(&self, op: &str) -> &[f64] {
+        self.op_iteration_means
+            .iter()
+            .find(|(o, _)| o == op)
+            .map(|(_, v)| v.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// 吞吐量 (ops/s)
+    pub fn throughput(&self) -> f64 {
+        let mean = self.mean_iteration_ns();
+        if mean == 0.0 {
+            0.0
+        } else {
+            self.ops_per_iteration as f64 * 1e9 / mean
+        }
+    }
+}
+
+/// n 个 key 时的取值空间, 生成的 key 落在 [1, key_space]
+pub fn key_space(n: usize) -> usize {
+    (n * 10).max(100_000).min(u32::MAX as usize - 1)
+}
+
+/// 生成可复现的随机 (key, value), key 互不相同
+pub fn generate_pairs(n: usize, seed: u64) -> Vec<(u32, u32)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    rand::seq::index::sample(&mut rng, key_space(n), n)
+        .into_iter()
+        .map(|k| (k as u32 + 1, rng.gen_range(1..100_000)))
+        .collect()
+}
+
+/// 执行一个场景
+pub fn run_scenario(config: &ScenarioConfig) -> ScenarioResult {
+    let degree = config.degree;
+    match config.structure {
+        Structure::RBTree => run_with(config, RBTree::new),
+        Structure::Avl => run_with(config, AVLTree::new),
+        Structure::ClassicRB => run_with(config, ClassicRBTree::new),
+        Structure::BTree => run_with(config, || BTreeMap::new(degree)),
+        Structure::BPTree => run_with(config, || BPTree::new(degree)),
+        Structure::SkipList => run_with(config, || SkipList::new(config.seed)),
+        Structure::ConcurrentSkipList => run_with(config, || ConcurrentSkipList::new(config.seed)),
+        Structure::Ladder => run_with(config, || PriceLadder::new(1, key_space(config.size) as u64, 1)),
+        Structure::Std => run_with(config, std::collections::BTreeMap::new),
+    }
+}
+
+fn run_with<M: BenchMap, F: Fn() -> M>(config: &ScenarioConfig, make: F) -> ScenarioResult {
+    let data = generate_pairs(config.size, config.seed);
+    let name = config.structure.name();
+    let mut recorder = LatencyRecorder::new();
+    let mut iteration_ns = Vec::with_capacity(config.iterations);
+    let mut ops_per_iteration = 0;
+    let mut op_iteration_means: Vec<(String, Vec<f64>)> = Vec::new();
+    let mut memory_bytes = None;
+    let count_allocs = alloc_counter::is_active();
+    // (op, 分配次数, 分配字节数, 操作次数)
+    let mut op_allocs: Vec<(&'static str, u64, u64, u64)> = Vec::new();
+    let mut peak_heap_bytes = 0;
+
+    for iter in 0..config.iterations {
+        // 峰值从构建数据结构之前开始计算, 扣除已有的存活内存
+        alloc_counter::reset_peak();
+        let base_bytes = AllocSnapshot::take().current_bytes;
+
+        let mut map = make();
+        let mut hist = OpHistograms::default();
+        let mut rng = StdRng::seed_from_u64(config.seed.wrapping_add(iter as u64 + 1));
+        let ops = run_workload(config.workload, &mut map, &data, &mut rng, &mut hist);
+        peak_heap_bytes = peak_heap_bytes.max(AllocSnapshot::take().peak_bytes.saturating_sub(base_bytes));
+        ops_per_iteration = ops;
+        iteration_ns.push(hist.total_ns());
+        hist.merge_into(&mut recorder, name);
+        for (op, mean) in hist.means() {
+            match op_iteration_means.iter_mut().find(|(o, _)| o == op) {
+                Some((_, v)) => v.push(mean),
+                None => op_iteration_means.push((op.to_string(), vec![mean])),
+            }
+        }
+        for (op, stats) in hist.by_op() {
+            if stats.count() == 0 {
+                continue;
+            }
+            match op_allocs.iter_mut().find(|(o, ..)| *o == op) {
+                Some((_, allocs, bytes, count)) => {
+                    *allocs += stats.allocations;
+                    *bytes += stats.bytes_allocated;
+                    *count += stats.count();
+                }
+                None => op_allocs.push((op, stats.allocations, stats.bytes_allocated, stats.count())),
+            }
+        }
+        memory_bytes = map.memory_bytes();
+        std::hint::black_box(&map);
+    }
+
+    let alloc = count_allocs.then(|| AllocReport {
+        per_op: op_allocs
+            .iter()
+            .map(|(op, allocs, bytes, count)| OpAllocStats {
+                op: op.to_string(),
+                allocations_per_op: *allocs as f64 / *count as f64,
+                bytes_per_op: *bytes as f64 / *count as f64,
+            })
+            .collect(),
+        peak_heap_bytes,
+    });
+
+    ScenarioResult {
+        config: config.clone(),
+        iteration_ns,
+        ops_per_iteration,
+        latency: recorder.summaries(),
+        op_iteration_means,
+        memory_bytes,
+        alloc,
+    }
+}
+
+/// 单个操作类型的延迟直方图与分配计数
+#[derive(Default)]
+struct OpStats {
+    hist: LatencyHistogram,
+    allocations: u64,
+    bytes_allocated: u64,
+}
+
+impl OpStats {
+    fn count(&self) -> u64 {
+        self.hist.count()
+    }
+}
+
+/// 单次迭代内按操作类型记录的统计
+#[derive(Default)]
+struct OpHistograms {
+    insert: OpStats,
+    get: OpStats,
+    delete: OpStats,
+    range: OpStats,
+    total_ns: u64,
+}
+
+impl OpHistograms {
+    fn total_ns(&self) -> u64 {
+        self.total_ns
+    }
+
+    /// 在操作结束后调用, `before` 为操作开始前的分配计数
+    fn record(stats: &mut OpStats, total: &mut u64, start: Instant, before: AllocSnapshot) {
+        let ns = start.elapsed().as_nanos() as u64;
+        let after = AllocSnapshot::take();
+        stats.hist.record(ns);
+        stats.allocations += after.allocations - before.allocations;
+        stats.bytes_allocated += after.bytes_allocated - before.bytes_allocated;
+        *total += ns;
+    }
+
+    fn by_op(&self) -> [(&'static str, &OpStats); 4] {
+        [
+            ("insert", &self.insert),
+            ("get", &self.get),
+            ("delete", &self.delete),
+            ("range_query", &self.range),
+        ]
+    }
+
+    fn merge_into(&self, recorder: &mut LatencyRecorder, structure: &str) {
+        for (op, stats) in self.by_op() {
+            if stats.count() > 0 {
+                recorder.histogram_mut(structure, op).merge(&stats.hist);
+            }
+        }
+    }
+
+    fn means(&self) -> Vec<(&'static str, f64)> {
+        self.by_op()
+            .into_iter()
+            .filter(|(_, h)| h.count() > 0)
+            .map(|(op, h)| (op, h.hist.mean()))
+            .collect()
+    }
+}
+
+/// 区间查询宽度
+const RANGE_WIDTH: u32 = 1_000;
+
+/// 执行一次负载, 返回被计时的操作数
+fn run_workload<M: BenchMap>(
+    workload: Workload,
+    map: &mut M,
+    data: &[(u32, u32)],
+    rng: &mut StdRng,
+    hist: &mut OpHistograms,
+) -> usize {
+    // 非计时部分 (预填充) 产生的分配不计入每次操作的统计
+    let OpHistograms {
+        insert,
+        get,
+        delete,
+        range,
+        total_ns,
+    } = hist;
+
+    match workload {
+        Workload::Insert => {
+            for (k, v) in data {
+                let before = AllocSnapshot::take();
+                let start = Instant::now();
+                map.insert(*k, *v);
+                OpHistograms::record(insert, total_ns, start, before);
+            }
+            data.len()
+        }
+        Workload::Query => {
+            for (k, v) in data {
+                map.insert(*k, *v);
+            }
+            for (k, _) in data {
+                let before = AllocSnapshot::take();
+                let start = Instant::now();
+                std::hint::black_box(map.get(k));
+                OpHistograms::record(get, total_ns, start, before);
+            }
+            data.len()
+        }
+        Workload::Delete => {
+            for (k, v) in data {
+                map.insert(*k, *v);
+            }
+            for (k, _) in data {
+                let before = AllocSnapshot::take();
+                let start = Instant::now();
+                map.delete(k);
+                OpHistograms::record(delete, total_ns, start, before);
+            }
+            data.len()
+        }
+        Workload::Range => {
+            for (k, v) in data {
+                map.insert(*k, *v);
+            }
+            let queries = (data.len() / 100).max(1);
+            for _ in 0..queries {
+                let (k, _) = data[rng.gen_range(0..data.len())];
+                let end = k.saturating_add(RANGE_WIDTH);
+                let before = AllocSnapshot::take();
+                let start = Instant::now();
+                std::hint::black_box(map.range_count(&k, &end));
+                OpHistograms::record(range, total_ns, start, before);
+            }
+            queries
+        }
+        Workload::Mixed => {
+            let half = data.len() / 2;
+            for (k, v) in &data[..half] {
+                map.insert(*k, *v);
+            }
+            // live 中为当前在树中的 key, pending 为尚未插入的数据
+            let mut live: Vec<u32> = data[..half].iter().map(|(k, _)| *k).collect();
+            let mut pending = data[half..].iter();
+            let ops = data.len();
+            for _ in 0..ops {
+                let roll = rng.gen_range(0..100);
+                if roll < 50 {
+                    if let Some((k, v)) = pending.next() {
+                        let before = AllocSnapshot::take();
+                        let start = Instant::now();
+                        map.insert(*k, *v);
+                        OpHistograms::record(insert, total_ns, start, before);
+                        live.push(*k);
+                        continue;
+                    }
+                }
+                if live.is_empty() {
+                    continue;
+                }
+                let idx = rng.gen_range(0..live.len());
+                if roll < 80 {
+                    let k = live[idx];
+                    let before = AllocSnapshot::take();
+                    let start = Instant::now();
+                    std::hint::black_box(map.get(&k));
+                    OpHistograms::record(get, total_ns, start, before);
+                } else {
+                    let k = live.swap_remove(idx);
+                    let before = AllocSnapshot::take();
+                    let start = Instant::now();
+                    map.delete(&k);
+                    OpHistograms::record(delete, total_ns, start, before);
+                }
+            }
+            (insert.count() + get.count() + delete.count()) as usize
+        }
+    }
+}
+
+/// 命令行参数
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunnerArgs {
+    pub structures: Vec<Structure>,
+    pub degree: usize,
+    pub size: usize,
+    pub workloads: Vec<Workload>,
+    pub seed: u64,
+    pub iterations: usize,
+    /// 结果输出文件, 按扩展名 (.json / .csv) 决定格式
+    pub outputs: Vec<String>,
+    /// 开启计数分配器, 统计每次操作的分配次数/字节数和堆峰值
+    pub alloc_stats: bool,
+}
+
+impl Default for RunnerArgs {
+    fn default() -> Self {
+        RunnerArgs {
+            structures: Structure::ALL.to_vec(),
+            degree: 3,
+            size: 100_000,
+            workloads: vec![Workload::Insert, Workload::Query],
+            seed: 42,
+            iterations: 5,
+            outputs: Vec::new(),
+            alloc_stats: false,
+        }
+    }
+}
+
+pub const USAGE: &str = "\
+Usage: rust-orderbook-benchmark [OPTIONS]
+
+Options:
+  -s, --structure <LIST>   rbtree,avl,clrs,btree,bptree,skiplist,cskiplist,ladder,std or all [default: all]
+  -d, --degree <N>         min degree for btree/bptree (>= 2)  [default: 3]
+  -n, --size <N>           number of entries                   [default: 100000]
+  -w, --workload <LIST>    insert,query,delete,range,mixed or all [default: insert,query]
+      --seed <N>           random seed                         [default: 42]
+  -i, --iterations <N>     iterations per scenario             [default: 5]
+  -o, --output <FILE>      write results to FILE (.json or .csv), repeatable
+      --alloc-stats        count allocations per op and peak heap (adds overhead)
+  -h, --help               print this help
+
+       rust-orderbook-benchmark compare <BASELINE> <CANDIDATE> [--threshold <PCT>]
+
+  Compare two result files and exit with status 1 if any scenario regressed
+  significantly (Welch's t-test, p < 0.05) by more than PCT percent [default: 5].";
+
+impl RunnerArgs {
+    /// 解析命令行参数 (不含程序名), `--help` 返回 Ok(None)
+    pub fn parse(args: &[String]) -> Result<Option<RunnerArgs>, String> {
+        let mut out = RunnerArgs::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg == "-h" || arg == "--help" {
+                return Ok(None);
+            }
+            if arg == "--alloc-stats" {
+                out.alloc_stats = true;
+                continue;
+            }
+            let mut value = || {
+                iter.next()
+                    .cloned()
+                    .ok_or_else(|| format!("missing value for `{}`", arg))
+            };
+            match arg.as_str() {
+                "-s" | "--structure" => {
+                    let v = value()?;
+                    out.structures = if v == "all" {
+                        Structure::ALL.to_vec()
+                    } else {
+                        v.split(',').map(Structure::parse).collect::<Result<_, _>>()?
+                    };
+                }
+                "-w" | "--workload" => {
+                    let v = value()?;
+                    out.workloads = if v == "all" {
+                        Workload::ALL.to_vec()
+                    } else {
+                        v.split(',').map(Workload::parse).collect::<Result<_, _>>()?
+                    };
+                }
+                "-d" | "--degree" => out.degree = parse_number(arg, &value()?)?,
+                "-n" | "--size" => out.size = parse_number(arg, &value()?)?,
+                "--seed" => out.seed = parse_number(arg, &value()?)?,
+                "-i" | "--iterations" => out.iterations = parse_number(arg, &value()?)?,
+                "-o" | "--output" => {
+                    let v = value()?;
+                    if !(v.ends_with(".json") || v.ends_with(".csv")) {
+                        return Err(format!("output `{}` must end with .json or .csv", v));
+                    }
+                    out.outputs.push(v);
+                }
+                _ => return Err(format!("unknown option `{}`", arg)),
+            }
+        }
+        if out.degree < 2 {
+            return Err("degree must be >= 2".to_string());
+        }
+        if out.size == 0 || out.iterations == 0 {
+            return Err("size and iterations must be > 0".to_string());
+        }
+        Ok(Some(out))
+    }
+
+    /// 展开为场景列表
+    pub fn scenarios(&self) -> Vec<ScenarioConfig> {
+        let mut out = Vec::new();
+        for structure in &self.structures {
+            for workload in &self.workloads {
+                out.push(ScenarioConfig {
+                    structure: *structure,
+                    degree: self.degree,
+                    size: self.size,
+                    workload: *workload,
+                    seed: self.seed,
+                    iterations: self.iterations,
+                });
+            }
+        }
+        out
+    }
+}
+
+pub fn parse_number<T: std::str::FromStr>(opt: &str, v: &str) -> Result<T, String> {
+    v.replace('_', "")
+        .parse()
+        .map_err(|_| format!("invalid value `{}` for `{}`", v, opt))
+}
+
+/// 结果表格
+pub fn format_table(results: &[ScenarioResult]) -> String {
+    let mut out = format!(
+        "{:<8} {:>6} {:<8} {:<12} {:>9} {:>12} {:>12} {:>9} {:>9} {:>10} {:>10} {:>9} {:>10} {:>12}\n",
+        "struct", "degree", "workload", "op", "n", "iter(ms)", "ops/s", "p50(ns)", "p99(ns)", "p99.9(ns)", "max(ns)",
+        "allocs/op", "bytes/op", "peak_heap"
+    );
+    for r in results {
+        let degree = if r.config.structure.uses_degree() {
+            r.config.degree.to_string()
+        } else {
+            "-".to_string()
+        };
+        for s in &r.latency {
+            let op_alloc = r.alloc.as_ref().and_then(|a| a.op(&s.op));
+            let allocs = op_alloc.map_or("-".to_string(), |a| format!("{:.2}", a.allocations_per_op));
+            let bytes = op_alloc.map_or("-".to_string(), |a| format!("{:.1}", a.bytes_per_op));
+            let peak = r.alloc.as_ref().map_or("-".to_string(), |a| a.peak_heap_bytes.to_string());
+            let _ = writeln!(
+                out,
+                "{:<8} {:>6} {:<8} {:<12} {:>9} {:>12.3} {:>12.0} {:>9} {:>9} {:>10} {:>10} {:>9} {:>10} {:>12}",
+                r.config.structure.name(),
+                degree,
+                r.config.workload.name(),
+                s.op,
+                r.config.size,
+                r.mean_iteration_ns() / 1e6,
+                r.throughput(),
+                s.p50,
+                s.p99,
+                s.p999,
+                s.max,
+                allocs,
+                bytes,
+                peak
+            );
+        }
+    }
+    out
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use crate::runner::runner::{
+        generate_pairs, run_scenario, RunnerArgs, ScenarioConfig, Structure, Workload,
+    };
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = RunnerArgs::parse(&args("-s rbtree,bptree -d 8 -n 1_000 -w all --seed 7 -i 2"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(parsed.structures, vec![Structure::RBTree, Structure::BPTree]);
+        assert_eq!(parsed.degree, 8);
+        assert_eq!(parsed.size, 1000);
+        assert_eq!(parsed.workloads.len(), 5);
+        assert_eq!(parsed.scenarios().len(), 10);
+
+        assert_eq!(RunnerArgs::parse(&args("--help")).unwrap(), None);
+        assert!(RunnerArgs::parse(&args("-s splay")).is_err());
+        assert!(RunnerArgs::parse(&args("-d 1")).is_err());
+        assert!(RunnerArgs::parse(&args("-n")).is_err());
+    }
+
+    #[test]
+    fn test_run_scenarios() {
+        assert_eq!(generate_pairs(100, 1), generate_pairs(100, 1));
+
+        for structure in Structure::ALL {
+            for workload in Workload::ALL {
+                let result = run_scenario(&ScenarioConfig {
+                    structure,
+                    degree: 3,
+                    size: 500,
+                    workload,
+                    seed: 1,
+                    iterations: 2,
+                });
+                assert_eq!(result.iteration_ns.len(), 2);
+                assert!(result.ops_per_iteration > 0);
+                assert!(!result.latency.is_empty());
+            }
+        }
+    }
+}
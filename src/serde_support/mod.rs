@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod serde_support;
+#[allow(clippy::module_inception)]
+pub mod tests;
@@ -0,0 +1,147 @@
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+use serde::de::{DeserializeSeed, Error as _, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::bptree::bptree::BPTree;
+use crate::btree_map::btree_map::BTreeMap;
+use crate::order_book::order_book::{BookState, OrderBook, Price, PriceIndex};
+use crate::rb_tree::rb_tree::RBTree;
+
+/// 反序列化 `BTreeMap`/`BPTree` 时默认的最小度数, 与 CLI 的默认值一致
+pub const DEFAULT_MIN_DEGREE: usize = 3;
+
+/// 可以由按 key 严格递增的条目批量建立的树
+pub trait FromSortedEntries: Sized {
+    type Key;
+    type Value;
+    /// `RBTree` 忽略 min_degree
+    fn build(min_degree: usize, entries: Vec<(Self::Key, Self::Value)>) -> Self;
+}
+
+impl<K: Ord + Clone, V: Clone> FromSortedEntries for RBTree<K, V> {
+    type Key = K;
+    type Value = V;
+    fn build(_: usize, entries: Vec<(K, V)>) -> Self {
+        RBTree::from_sorted(entries)
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone + Debug> FromSortedEntries for BTreeMap<K, V> {
+    type Key = K;
+    type Value = V;
+    fn build(min_degree: usize, entries: Vec<(K, V)>) -> Self {
+        BTreeMap::from_sorted(min_degree, entries)
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone + Debug> FromSortedEntries for BPTree<K, V> {
+    type Key = K;
+    type Value = V;
+    fn build(min_degree: usize, entries: Vec<(K, V)>) -> Self {
+        BPTree::from_sorted(min_degree, entries)
+    }
+}
+
+/// 指定最小度数的反序列化种子: `WithDegree::<BPTree<_, _>>::new(8).deserialize(d)`
+pub struct WithDegree<T> {
+    min_degree: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T> WithDegree<T> {
+    pub fn new(min_degree: usize) -> Self {
+        WithDegree { min_degree, _marker: PhantomData }
+    }
+}
+
+impl<'de, T> DeserializeSeed<'de> for WithDegree<T>
+where
+    T: FromSortedEntries,
+    T::Key: Deserialize<'de> + Ord,
+    T::Value: Deserialize<'de>,
+{
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+/// 读入 map 的全部条目后一次性建树; 输入乱序时按 key 稳定排序, 重复 key 保留最后一个 (与依次 insert 一致)
+impl<'de, T> Visitor<'de> for WithDegree<T>
+where
+    T: FromSortedEntries,
+    T::Key: Deserialize<'de> + Ord,
+    T::Value: Deserialize<'de>,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<T, A::Error> {
+        let mut entries: Vec<(T::Key, T::Value)> = Vec::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        if !entries.windows(2).all(|w| w[0].0 < w[1].0) {
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            let mut deduped: Vec<(T::Key, T::Value)> = Vec::with_capacity(entries.len());
+            for entry in entries {
+                match deduped.last_mut() {
+                    Some(last) if last.0 == entry.0 => *last = entry,
+                    _ => deduped.push(entry),
+                }
+            }
+            entries = deduped;
+        }
+        Ok(T::build(self.min_degree, entries))
+    }
+}
+
+/// 三棵树都序列化为按 key 升序的 map
+macro_rules! impl_serde_map {
+    ($tree:ident, $($bound:path),*) => {
+        impl<K: Ord + Clone $(+ $bound)* + Serialize, V: Clone $(+ $bound)* + Serialize> Serialize for $tree<K, V> {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_map(self.entries())
+            }
+        }
+
+        impl<'de, K, V> Deserialize<'de> for $tree<K, V>
+        where
+            K: Ord + Clone $(+ $bound)* + Deserialize<'de>,
+            V: Clone $(+ $bound)* + Deserialize<'de>,
+        {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                WithDegree::new(DEFAULT_MIN_DEGREE).deserialize(deserializer)
+            }
+        }
+    };
+}
+
+impl_serde_map!(RBTree,);
+impl_serde_map!(BTreeMap, Debug);
+impl_serde_map!(BPTree, Debug);
+
+/// 订单簿序列化为 `BookState` (价格-时间优先级排列的全部档位), 与价格索引的实现无关
+impl<I: PriceIndex> Serialize for OrderBook<I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.state().serialize(serializer)
+    }
+}
+
+/// 反序列化时用 `FromSortedEntries` 建两棵空的价格索引, 再由 `OrderBook::restore` 批量建立
+impl<'de, I> Deserialize<'de> for OrderBook<I>
+where
+    I: PriceIndex + FromSortedEntries<Key = Price, Value = usize>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = BookState::deserialize(deserializer)?;
+        let empty = || I::build(DEFAULT_MIN_DEGREE, Vec::new());
+        OrderBook::restore(state, empty(), empty()).map_err(D::Error::custom)
+    }
+}
@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod tests {
+    use crate::bptree::bptree::BPTree;
+    use crate::btree_map::btree_map::BTreeMap;
+    use crate::order_book::order_book::{BPOrderBook, Command, NewOrder, RBOrderBook, Side};
+    use crate::rb_tree::rb_tree::RBTree;
+    use crate::serde_support::serde_support::WithDegree;
+    use serde::de::DeserializeSeed;
+
+    #[test]
+    fn test_serde_trees_as_ordered_maps() {
+        let mut rb = RBTree::new();
+        let mut bt = BTreeMap::new(3);
+        let mut bp = BPTree::new(3);
+        for i in 0..500u32 {
+            let k = (i * 7919) % 1_000;
+            rb.insert(k, i);
+            bt.insert(k, i);
+            bp.insert(k, i);
+        }
+        // 三棵树输出相同的 JSON, key 升序
+        let json = serde_json::to_string(&rb).unwrap();
+        assert!(json.starts_with(r#"{"0":0,"#));
+        assert_eq!(serde_json::to_string(&bt).unwrap(), json);
+        assert_eq!(serde_json::to_string(&bp).unwrap(), json);
+
+        let rb2: RBTree<u32, u32> = serde_json::from_str(&json).unwrap();
+        let bt2: BTreeMap<u32, u32> = serde_json::from_str(&json).unwrap();
+        let bp2 = WithDegree::<BPTree<u32, u32>>::new(8).deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+        assert_eq!(rb2.entries(), rb.entries());
+        assert_eq!(bt2.entries(), rb.entries());
+        assert_eq!(bp2.entries(), rb.entries());
+        assert_eq!(bp2.min_degree, 8);
+
+        // 乱序和重复 key: 排序, 后出现的值覆盖前面的
+        let messy: BPTree<u32, u32> = serde_json::from_str(r#"{"5":1,"2":2,"5":3,"9":4}"#).unwrap();
+        assert_eq!(messy.entries(), vec![(&2, &2), (&5, &3), (&9, &4)]);
+    }
+
+    #[test]
+    fn test_serde_order_book() {
+        let mut book = RBOrderBook::with_rb_tree();
+        for (id, side, price, qty) in [(1, Side::Buy, 99, 5), (2, Side::Buy, 100, 3), (3, Side::Sell, 102, 4), (4, Side::Sell, 100, 1)] {
+            book.apply(&Command::Limit(NewOrder::limit(id, side, price, qty))).unwrap();
+        }
+        let json = serde_json::to_string(&book).unwrap();
+        let mut restored: BPOrderBook = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.state(), book.state());
+        assert_eq!(restored.best_bid(), Some(100));
+
+        let cmd = Command::Cancel { id: 2 };
+        let cmd_json = serde_json::to_string(&cmd).unwrap();
+        assert_eq!(serde_json::from_str::<Command>(&cmd_json).unwrap(), cmd);
+        restored.apply(&cmd).unwrap();
+        assert_eq!(restored.best_bid(), Some(99));
+
+        // 交叉的订单簿被拒绝
+        let crossed = json.replace("102", "98");
+        assert!(serde_json::from_str::<BPOrderBook>(&crossed).is_err());
+    }
+}
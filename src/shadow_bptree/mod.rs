@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod shadow_bptree;
+#[allow(clippy::module_inception)]
+pub mod tests;
@@ -0,0 +1,590 @@
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{self, Read};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use crate::buffer_pool::buffer_pool::{BufferPool, PageId, PoolStats};
+use crate::paged_bptree::paged_bptree::FixedCodec;
+
+const MAGIC: &[u8; 4] = b"OBSH";
+const FORMAT_VERSION: u32 = 1;
+/// 两个元数据页轮流写: 第 txn % 2 页
+const META_PAGES: PageId = 2;
+/// MAGIC | 版本 u32 | 页大小 u32 | key 大小 u32 | value 大小 u32 | txn u64 | 根 u32 | 页数 u32 | 条目数 u64 | crc32 u32
+const META_LEN: usize = 48;
+
+/// 节点页头: 类型 u8 | 保留 u8 | 条目数 u16 | 保留 u32
+const HEADER: usize = 8;
+const LEAF: u8 = 0;
+const INTERNAL: u8 = 1;
+
+/// 一次提交的元数据
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Meta {
+    txn: u64,
+    root: PageId,
+    /// 提交时文件中的页数
+    pages: u32,
+    len: u64,
+}
+
+/// 解码后的节点; 没有叶子兄弟指针, 写时复制时只需改根到叶子的一条路径
+struct Node<K, V> {
+    leaf: bool,
+    keys: Vec<K>,
+    /// 仅叶子
+    vals: Vec<V>,
+    /// 仅内部节点, children.len() = keys.len() + 1
+    children: Vec<PageId>,
+}
+
+impl<K, V> Node<K, V> {
+    fn leaf() -> Self {
+        Node { leaf: true, keys: Vec::new(), vals: Vec::new(), children: Vec::new() }
+    }
+}
+
+fn read_u32(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(buf[at..at + 4].try_into().unwrap())
+}
+
+fn write_u32(buf: &mut [u8], at: usize, v: u32) {
+    buf[at..at + 4].copy_from_slice(&v.to_le_bytes());
+}
+
+fn read_u64(buf: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(buf[at..at + 8].try_into().unwrap())
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// 影子分页 (写时复制) 的磁盘 B+Tree, 思路同 LMDB
+///
+/// 已提交版本可达的页永不原地修改: 事务中的修改写到新页 (同一事务里新分配的页可以反复原地改),
+/// `commit` 先 fsync 全部新页, 再把新根写进两个元数据页中较旧的那个并 fsync。
+/// 元数据带 txn 号和校验和, 打开时取校验通过且 txn 最大的一个, 因此任意时刻崩溃后文件都停在
+/// 最近一次成功提交的版本上, 不需要单独的预写日志。
+///
+/// 被新版本替换的页要等本次提交落盘后才能复用 (上一个元数据页仍指向它们);
+/// 空闲页不落盘, 打开时从根做一次可达性扫描重建。
+pub struct ShadowBPTree<K: FixedCodec, V: FixedCodec> {
+    pool: BufferPool,
+    committed: Meta,
+    /// 当前 (未提交) 版本的根和条目数
+    root: PageId,
+    len: u64,
+    /// 本事务新分配的页, 可以原地修改
+    dirty: HashSet<PageId>,
+    /// 本事务替换掉的已提交页, 提交后才能复用
+    retired: Vec<PageId>,
+    free: Vec<PageId>,
+    max_leaf: usize,
+    max_internal: usize,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K: FixedCodec + Ord + Clone, V: FixedCodec + Clone> ShadowBPTree<K, V> {
+    /// 新建 (覆盖) 文件, 空树作为 txn 0 提交
+    pub fn create<P: AsRef<Path>>(path: P, page_size: usize, pool_pages: usize) -> io::Result<Self> {
+        let (max_leaf, max_internal) = Self::capacities(page_size)?;
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        let mut pool = BufferPool::new(file, page_size, pool_pages)?;
+        for _ in 0..META_PAGES {
+            pool.allocate()?;
+        }
+        let root = pool.allocate()?;
+        let meta = Meta { txn: 0, root, pages: pool.page_count(), len: 0 };
+        let mut tree = ShadowBPTree {
+            pool,
+            committed: meta,
+            root,
+            len: 0,
+            dirty: HashSet::new(),
+            retired: Vec::new(),
+            free: Vec::new(),
+            max_leaf,
+            max_internal,
+            _marker: PhantomData,
+        };
+        tree.write_node(root, &Node::leaf())?;
+        for slot in 0..META_PAGES {
+            tree.write_meta(slot, meta)?;
+        }
+        tree.pool.sync()?;
+        Ok(tree)
+    }
+
+    /// 打开已有文件, 回到最近一次成功提交的版本; 之后写了一半的页都视为空闲
+    pub fn open<P: AsRef<Path>>(path: P, pool_pages: usize) -> io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        // 两个元数据页的页大小相同, 撕裂写也不会改变它
+        let mut head = [0u8; 12];
+        file.read_exact(&mut head)?;
+        if &head[0..4] != MAGIC {
+            return Err(invalid("bad magic"));
+        }
+        let page_size = read_u32(&head, 8) as usize;
+        let (max_leaf, max_internal) = Self::capacities(page_size)?;
+        // 崩溃时追加到一半的页: 在最近一次提交之后, 直接截掉
+        let len = file.metadata()?.len();
+        file.set_len(len - len % page_size as u64)?;
+        let mut pool = BufferPool::new(file, page_size, pool_pages)?;
+        let mut best: Option<Meta> = None;
+        for slot in 0..META_PAGES.min(pool.page_count()) {
+            if let Some(meta) = Self::parse_meta(pool.read(slot)?, page_size)? {
+                if best.is_none_or(|b| meta.txn > b.txn) {
+                    best = Some(meta);
+                }
+            }
+        }
+        let meta = best.ok_or_else(|| invalid("no valid meta page"))?;
+        if meta.pages > pool.page_count() || meta.root < META_PAGES || meta.root >= meta.pages {
+            return Err(invalid(format!("meta of txn {} points past the end of the file", meta.txn)));
+        }
+        let mut tree = ShadowBPTree {
+            pool,
+            committed: meta,
+            root: meta.root,
+            len: meta.len,
+            dirty: HashSet::new(),
+            retired: Vec::new(),
+            free: Vec::new(),
+            max_leaf,
+            max_internal,
+            _marker: PhantomData,
+        };
+        let reachable = tree.reachable()?;
+        tree.free = (META_PAGES..tree.pool.page_count()).rev().filter(|p| !reachable.contains(p)).collect();
+        Ok(tree)
+    }
+
+    /// 每页最多容纳的 (叶子条目数, 内部节点 key 数)
+    fn capacities(page_size: usize) -> io::Result<(usize, usize)> {
+        let max_leaf = page_size.saturating_sub(HEADER) / (K::SIZE + V::SIZE);
+        let max_internal = page_size.saturating_sub(HEADER + 4) / (K::SIZE + 4);
+        if page_size < META_LEN || max_leaf < 3 || max_internal < 3 || max_leaf > u16::MAX as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("page size {} cannot hold 3 entries per node", page_size),
+            ));
+        }
+        Ok((max_leaf, max_internal))
+    }
+
+    /// 校验和不符 (撕裂写) 时返回 None; 格式不符时报错
+    fn parse_meta(buf: &[u8], page_size: usize) -> io::Result<Option<Meta>> {
+        if &buf[0..4] != MAGIC || read_u32(buf, META_LEN - 4) != crc32fast::hash(&buf[..META_LEN - 4]) {
+            return Ok(None);
+        }
+        if read_u32(buf, 4) != FORMAT_VERSION {
+            return Err(invalid(format!("unsupported format version {}", read_u32(buf, 4))));
+        }
+        if read_u32(buf, 8) as usize != page_size {
+            return Err(invalid("meta pages disagree on the page size"));
+        }
+        if read_u32(buf, 12) as usize != K::SIZE || read_u32(buf, 16) as usize != V::SIZE {
+            return Err(invalid("key/value size mismatch"));
+        }
+        Ok(Some(Meta { txn: read_u64(buf, 20), root: read_u32(buf, 28), pages: read_u32(buf, 32), len: read_u64(buf, 36) }))
+    }
+
+    /// 已提交版本可达的页; 页号越界或被引用两次说明文件已损坏
+    fn reachable(&mut self) -> io::Result<HashSet<PageId>> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![self.root];
+        while let Some(page) = stack.pop() {
+            if page < META_PAGES || page >= self.committed.pages || !seen.insert(page) {
+                return Err(invalid(format!("bad child pointer to page {}", page)));
+            }
+            let node = self.read_node(page)?;
+            stack.extend(node.children);
+        }
+        Ok(seen)
+    }
+
+    /// 当前版本 (含未提交修改) 的条目数
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 最近一次成功提交的事务号
+    pub fn committed_txn(&self) -> u64 {
+        self.committed.txn
+    }
+
+    /// 文件中的页数 (含元数据页和空闲页)
+    pub fn page_count(&self) -> u32 {
+        self.pool.page_count()
+    }
+
+    /// 可复用的空闲页数
+    pub fn free_pages(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+
+    /// 测试用: 再写 writes 页后模拟崩溃, 见 `BufferPool::inject_crash_after`
+    pub fn inject_crash_after(&mut self, writes: Option<u64>) {
+        self.pool.inject_crash_after(writes);
+    }
+
+    /// 提交当前版本, 返回新的事务号; 没有修改时不写盘
+    ///
+    /// 出错 (含注入的崩溃) 后内存状态不再可信, 应丢弃实例并重新 open
+    pub fn commit(&mut self) -> io::Result<u64> {
+        if self.root == self.committed.root && self.dirty.is_empty() {
+            return Ok(self.committed.txn);
+        }
+        // 先让新页落盘, 再切换根; 两次 fsync 之间崩溃时旧元数据仍然有效
+        self.pool.sync()?;
+        let meta = Meta { txn: self.committed.txn + 1, root: self.root, pages: self.pool.page_count(), len: self.len };
+        self.write_meta((meta.txn % META_PAGES as u64) as PageId, meta)?;
+        self.pool.sync()?;
+        self.committed = meta;
+        self.dirty.clear();
+        self.free.append(&mut self.retired);
+        Ok(meta.txn)
+    }
+
+    /// 丢弃未提交的修改, 回到最近一次提交的版本
+    pub fn rollback(&mut self) {
+        self.root = self.committed.root;
+        self.len = self.committed.len;
+        self.free.extend(self.dirty.drain());
+        self.retired.clear();
+    }
+
+    pub fn get(&mut self, key: &K) -> io::Result<Option<V>> {
+        let mut node = self.read_node(self.root)?;
+        while !node.leaf {
+            node = self.read_node(node.children[Self::child_index(&node, key)])?;
+        }
+        Ok(node.keys.binary_search(key).ok().map(|i| node.vals[i].clone()))
+    }
+
+    /// [start, end] 闭区间; 没有叶子链, 从根向下只进入与区间相交的子树
+    pub fn range_query(&mut self, start: &K, end: &K) -> io::Result<Vec<(K, V)>> {
+        let mut out = Vec::new();
+        if start <= end {
+            self.range_rec(self.root, start, end, &mut out)?;
+        }
+        Ok(out)
+    }
+
+    fn range_rec(&mut self, page: PageId, start: &K, end: &K, out: &mut Vec<(K, V)>) -> io::Result<()> {
+        let node = self.read_node(page)?;
+        if node.leaf {
+            let from = node.keys.partition_point(|k| k < start);
+            let to = node.keys.partition_point(|k| k <= end);
+            out.extend(node.keys[from..to].iter().cloned().zip(node.vals[from..to].iter().cloned()));
+            return Ok(());
+        }
+        for &child in &node.children[Self::child_index(&node, start)..=Self::child_index(&node, end)] {
+            self.range_rec(child, start, end, out)?;
+        }
+        Ok(())
+    }
+
+    /// 插入或覆盖; 返回是否新增了条目
+    pub fn insert(&mut self, key: K, value: V) -> io::Result<bool> {
+        let mut added = false;
+        let (root, split) = self.insert_rec(self.root, key, value, &mut added)?;
+        self.root = root;
+        if let Some((sep, right)) = split {
+            // 根分裂, 树长高一层
+            let node = Node { leaf: false, keys: vec![sep], vals: Vec::new(), children: vec![root, right] };
+            self.root = self.alloc_page()?;
+            self.write_node(self.root, &node)?;
+        }
+        if added {
+            self.len += 1;
+        }
+        Ok(added)
+    }
+
+    pub fn delete(&mut self, key: &K) -> io::Result<Option<V>> {
+        let (root, removed) = self.delete_rec(self.root, key)?;
+        self.root = root;
+        if removed.is_some() {
+            self.len -= 1;
+            // 根只剩一个孩子时树高减一
+            let node = self.read_node(root)?;
+            if !node.leaf && node.keys.is_empty() {
+                self.retire(root);
+                self.root = node.children[0];
+            }
+        }
+        Ok(removed)
+    }
+
+    /// 内部节点中 key 所在子树的下标 (keys[i] 是 children[i + 1] 的最小键)
+    fn child_index(node: &Node<K, V>, key: &K) -> usize {
+        node.keys.partition_point(|k| k <= key)
+    }
+
+    /// 递归插入; 返回节点的新页号, 以及分裂出的 (分隔键, 右节点页号)
+    fn insert_rec(
+        &mut self,
+        page: PageId,
+        key: K,
+        value: V,
+        added: &mut bool,
+    ) -> io::Result<(PageId, Option<(K, PageId)>)> {
+        let mut node = self.read_node(page)?;
+        if node.leaf {
+            match node.keys.binary_search(&key) {
+                Ok(i) => node.vals[i] = value,
+                Err(i) => {
+                    node.keys.insert(i, key);
+                    node.vals.insert(i, value);
+                    *added = true;
+                }
+            }
+            if node.keys.len() <= self.max_leaf {
+                return Ok((self.put(page, &node)?, None));
+            }
+            let mid = node.keys.len() / 2;
+            let right = Node { leaf: true, keys: node.keys.split_off(mid), vals: node.vals.split_off(mid), children: Vec::new() };
+            return self.split(page, &node, right.keys[0].clone(), right);
+        }
+
+        let idx = Self::child_index(&node, &key);
+        let (child, split) = self.insert_rec(node.children[idx], key, value, added)?;
+        if child == node.children[idx] && split.is_none() {
+            // 孩子是本事务的页, 原地改过了, 父节点不用复制
+            return Ok((page, None));
+        }
+        node.children[idx] = child;
+        if let Some((sep, right)) = split {
+            node.keys.insert(idx, sep);
+            node.children.insert(idx + 1, right);
+        }
+        if node.keys.len() <= self.max_internal {
+            return Ok((self.put(page, &node)?, None));
+        }
+        // 内部节点分裂: 中间键上提
+        let mid = node.keys.len() / 2;
+        let right = Node {
+            leaf: false,
+            keys: node.keys.split_off(mid + 1),
+            vals: Vec::new(),
+            children: node.children.split_off(mid + 1),
+        };
+        let sep = node.keys.pop().unwrap();
+        self.split(page, &node, sep, right)
+    }
+
+    fn split(&mut self, page: PageId, left: &Node<K, V>, sep: K, right: Node<K, V>) -> io::Result<(PageId, Option<(K, PageId)>)> {
+        let left_page = self.put(page, left)?;
+        let right_page = self.alloc_page()?;
+        self.write_node(right_page, &right)?;
+        Ok((left_page, Some((sep, right_page))))
+    }
+
+    /// 递归删除; 返回节点的新页号和被删除的值
+    fn delete_rec(&mut self, page: PageId, key: &K) -> io::Result<(PageId, Option<V>)> {
+        let mut node = self.read_node(page)?;
+        if node.leaf {
+            let Ok(i) = node.keys.binary_search(key) else {
+                return Ok((page, None));
+            };
+            node.keys.remove(i);
+            let value = node.vals.remove(i);
+            return Ok((self.put(page, &node)?, Some(value)));
+        }
+
+        let idx = Self::child_index(&node, key);
+        let (child_page, removed) = self.delete_rec(node.children[idx], key)?;
+        if removed.is_none() {
+            return Ok((page, None));
+        }
+        let child = self.read_node(child_page)?;
+        let underflow = child.keys.len() < self.min_keys(&child);
+        if child_page == node.children[idx] && !underflow {
+            return Ok((page, removed));
+        }
+        node.children[idx] = child_page;
+        if underflow {
+            self.rebalance(&mut node, idx, child)?;
+        }
+        Ok((self.put(page, &node)?, removed))
+    }
+
+    fn min_keys(&self, node: &Node<K, V>) -> usize {
+        if node.leaf {
+            self.max_leaf / 2
+        } else {
+            self.max_internal / 2
+        }
+    }
+
+    /// 修复下溢的 children[idx]: 先向左/右兄弟借, 借不到就合并; 改动的兄弟同样写时复制
+    fn rebalance(&mut self, parent: &mut Node<K, V>, idx: usize, mut child: Node<K, V>) -> io::Result<()> {
+        let left = match idx {
+            0 => None,
+            _ => Some(self.read_node(parent.children[idx - 1])?),
+        };
+        if let Some(mut left) = left.filter(|l| l.keys.len() > self.min_keys(l)) {
+            if child.leaf {
+                child.keys.insert(0, left.keys.pop().unwrap());
+                child.vals.insert(0, left.vals.pop().unwrap());
+                parent.keys[idx - 1] = child.keys[0].clone();
+            } else {
+                let sep = std::mem::replace(&mut parent.keys[idx - 1], left.keys.pop().unwrap());
+                child.keys.insert(0, sep);
+                child.children.insert(0, left.children.pop().unwrap());
+            }
+            parent.children[idx - 1] = self.put(parent.children[idx - 1], &left)?;
+            parent.children[idx] = self.put(parent.children[idx], &child)?;
+            return Ok(());
+        }
+
+        if idx + 1 < parent.children.len() {
+            let mut right = self.read_node(parent.children[idx + 1])?;
+            if right.keys.len() > self.min_keys(&right) {
+                if child.leaf {
+                    child.keys.push(right.keys.remove(0));
+                    child.vals.push(right.vals.remove(0));
+                    parent.keys[idx] = right.keys[0].clone();
+                } else {
+                    let sep = std::mem::replace(&mut parent.keys[idx], right.keys.remove(0));
+                    child.keys.push(sep);
+                    child.children.push(right.children.remove(0));
+                }
+                parent.children[idx + 1] = self.put(parent.children[idx + 1], &right)?;
+                parent.children[idx] = self.put(parent.children[idx], &child)?;
+                return Ok(());
+            }
+            // 右兄弟并入 child
+            return self.merge(parent, idx, child, right);
+        }
+        // 最右的孩子: 并入左兄弟
+        let left = self.read_node(parent.children[idx - 1])?;
+        self.merge(parent, idx - 1, left, child)
+    }
+
+    /// 把 children[i + 1] 并入 children[i], 右边的页退役
+    fn merge(&mut self, parent: &mut Node<K, V>, i: usize, mut left: Node<K, V>, right: Node<K, V>) -> io::Result<()> {
+        let sep = parent.keys.remove(i);
+        let right_page = parent.children.remove(i + 1);
+        if !left.leaf {
+            left.keys.push(sep);
+        }
+        left.keys.extend(right.keys);
+        left.vals.extend(right.vals);
+        left.children.extend(right.children);
+        parent.children[i] = self.put(parent.children[i], &left)?;
+        self.retire(right_page);
+        Ok(())
+    }
+
+    /// 写时复制: 本事务的页原地写, 已提交的页换一个新页写; 返回实际写入的页号
+    fn put(&mut self, page: PageId, node: &Node<K, V>) -> io::Result<PageId> {
+        let target = if self.dirty.contains(&page) {
+            page
+        } else {
+            self.retire(page);
+            self.alloc_page()?
+        };
+        self.write_node(target, node)?;
+        Ok(target)
+    }
+
+    /// 不再被当前版本引用的页: 本事务新分配的立即回收, 已提交的等提交之后
+    fn retire(&mut self, page: PageId) {
+        if self.dirty.remove(&page) {
+            self.free.push(page);
+        } else {
+            self.retired.push(page);
+        }
+    }
+
+    fn alloc_page(&mut self) -> io::Result<PageId> {
+        let page = match self.free.pop() {
+            Some(page) => page,
+            None => self.pool.allocate()?,
+        };
+        self.dirty.insert(page);
+        Ok(page)
+    }
+
+    fn read_node(&mut self, page: PageId) -> io::Result<Node<K, V>> {
+        let buf = self.pool.read(page)?;
+        let count = u16::from_le_bytes([buf[2], buf[3]]) as usize;
+        let mut node = Node::leaf();
+        match buf[0] {
+            LEAF => {
+                let entry = K::SIZE + V::SIZE;
+                for i in 0..count {
+                    let at = HEADER + i * entry;
+                    node.keys.push(K::decode(&buf[at..]));
+                    node.vals.push(V::decode(&buf[at + K::SIZE..]));
+                }
+            }
+            INTERNAL => {
+                node.leaf = false;
+                node.children.push(read_u32(buf, HEADER));
+                let entry = K::SIZE + 4;
+                for i in 0..count {
+                    let at = HEADER + 4 + i * entry;
+                    node.keys.push(K::decode(&buf[at..]));
+                    node.children.push(read_u32(buf, at + K::SIZE));
+                }
+            }
+            kind => return Err(invalid(format!("page {} is not a tree node (kind {})", page, kind))),
+        }
+        Ok(node)
+    }
+
+    fn write_node(&mut self, page: PageId, node: &Node<K, V>) -> io::Result<()> {
+        let buf = self.pool.write(page)?;
+        buf.fill(0);
+        buf[0] = if node.leaf { LEAF } else { INTERNAL };
+        buf[2..4].copy_from_slice(&(node.keys.len() as u16).to_le_bytes());
+        if node.leaf {
+            let entry = K::SIZE + V::SIZE;
+            for (i, (k, v)) in node.keys.iter().zip(&node.vals).enumerate() {
+                let at = HEADER + i * entry;
+                k.encode(&mut buf[at..]);
+                v.encode(&mut buf[at + K::SIZE..]);
+            }
+        } else {
+            write_u32(buf, HEADER, node.children[0]);
+            let entry = K::SIZE + 4;
+            for (i, (k, c)) in node.keys.iter().zip(&node.children[1..]).enumerate() {
+                let at = HEADER + 4 + i * entry;
+                k.encode(&mut buf[at..]);
+                write_u32(buf, at + K::SIZE, *c);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_meta(&mut self, slot: PageId, meta: Meta) -> io::Result<()> {
+        let page_size = self.pool.page_size() as u32;
+        let buf = self.pool.write(slot)?;
+        buf.fill(0);
+        buf[0..4].copy_from_slice(MAGIC);
+        write_u32(buf, 4, FORMAT_VERSION);
+        write_u32(buf, 8, page_size);
+        write_u32(buf, 12, K::SIZE as u32);
+        write_u32(buf, 16, V::SIZE as u32);
+        buf[20..28].copy_from_slice(&meta.txn.to_le_bytes());
+        write_u32(buf, 28, meta.root);
+        write_u32(buf, 32, meta.pages);
+        buf[36..44].copy_from_slice(&meta.len.to_le_bytes());
+        let crc = crc32fast::hash(&buf[..META_LEN - 4]);
+        write_u32(buf, META_LEN - 4, crc);
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use crate::shadow_bptree::shadow_bptree::ShadowBPTree;
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::path::Path;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("shadow_bptree_{}_{}.db", name, std::process::id()))
+    }
+
+    fn contents(tree: &mut ShadowBPTree<u32, u64>) -> Vec<(u32, u64)> {
+        tree.range_query(&0, &u32::MAX).unwrap()
+    }
+
+    #[test]
+    fn test_shadow_bptree_commit_and_rollback() {
+        let path = temp_path("basic");
+        // 128 字节的页每个叶子只放 10 个条目, 缓冲池只有 8 页
+        let mut tree = ShadowBPTree::create(&path, 128, 8).unwrap();
+        let mut committed = BTreeMap::new();
+        let mut reference = BTreeMap::new();
+        for i in 0..20_000u32 {
+            let k = (i * 7919) % 3_000;
+            if i % 3 == 0 {
+                assert_eq!(tree.delete(&k).unwrap(), reference.remove(&k));
+            } else {
+                assert_eq!(tree.insert(k, i as u64).unwrap(), reference.insert(k, i as u64).is_none());
+            }
+            if i % 700 == 699 {
+                if i % 2_100 == 2_099 {
+                    tree.rollback();
+                    reference = committed.clone();
+                } else {
+                    tree.commit().unwrap();
+                    committed = reference.clone();
+                }
+            }
+        }
+        assert_eq!(tree.len(), reference.len());
+        let want: Vec<(u32, u64)> = reference.range(100..=2_000).map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(tree.range_query(&100, &2_000).unwrap(), want);
+
+        // 未提交的修改在重新打开后消失
+        let txn = tree.committed_txn();
+        let pages = tree.page_count();
+        drop(tree);
+        let mut tree = ShadowBPTree::<u32, u64>::open(&path, 4).unwrap();
+        assert_eq!(tree.committed_txn(), txn);
+        assert_eq!(contents(&mut tree), committed.into_iter().collect::<Vec<_>>());
+        // 旧版本的页在提交后被复用, 文件不会随事务数无限增长
+        assert!(tree.free_pages() > 0);
+        for k in 0..3_000 {
+            tree.insert(k, 1).unwrap();
+            if k % 100 == 0 {
+                tree.commit().unwrap();
+            }
+        }
+        tree.commit().unwrap();
+        assert!(tree.page_count() < pages * 2, "{} pages after, {} before", tree.page_count(), pages);
+        drop(tree);
+
+        assert!(ShadowBPTree::<u64, u64>::open(&path, 4).is_err());
+        assert!(ShadowBPTree::<u32, u64>::create(&path, 16, 4).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// 一个事务: 覆盖、新增和删除混合
+    fn batch(tree: &mut ShadowBPTree<u32, u64>) -> io::Result<u64> {
+        for k in 0..120u32 {
+            let key = (k * 13) % 1_500;
+            if k % 3 == 0 {
+                tree.delete(&key)?;
+            } else {
+                tree.insert(key, 1_000_000 + k as u64)?;
+            }
+        }
+        tree.commit()
+    }
+
+    fn reopen(path: &Path) -> ShadowBPTree<u32, u64> {
+        ShadowBPTree::open(path, 32).unwrap()
+    }
+
+    #[test]
+    fn test_shadow_bptree_crash_injection() {
+        let base = temp_path("crash_base");
+        let run = temp_path("crash_run");
+        let mut tree = ShadowBPTree::create(&base, 128, 8).unwrap();
+        for k in 0..1_000u32 {
+            tree.insert(k, k as u64).unwrap();
+        }
+        tree.commit().unwrap();
+        let before = contents(&mut tree);
+        drop(tree);
+
+        // 不注入故障跑一遍, 得到提交后的内容和整个事务写盘的页数
+        std::fs::copy(&base, &run).unwrap();
+        let mut tree = reopen(&run);
+        let writes_before = tree.pool_stats().writes;
+        batch(&mut tree).unwrap();
+        let writes = tree.pool_stats().writes - writes_before;
+        let after = contents(&mut tree);
+        assert_ne!(before, after);
+        drop(tree);
+
+        // 在第 n 次写盘时崩溃 (该页只写一半, 之后的写全部丢失), 包括事务中途淘汰脏页和写元数据页
+        for n in 0..=writes {
+            std::fs::copy(&base, &run).unwrap();
+            let mut tree = reopen(&run);
+            tree.inject_crash_after(Some(n));
+            let result = batch(&mut tree);
+            drop(tree);
+
+            let mut tree = reopen(&run);
+            if n + 1 < writes {
+                assert!(result.is_err(), "crash after {} of {} writes", n, writes);
+                assert_eq!(tree.committed_txn(), 1);
+                assert_eq!(contents(&mut tree), before, "crash after {} of {} writes", n, writes);
+            } else {
+                // 最后一次写是元数据页; 元数据只占页首 48 字节, 撕裂写的前半页已包含完整的新元数据,
+                // 这时提交已经生效, 只是调用方没有收到确认
+                assert_eq!(result.is_ok(), n == writes);
+                assert_eq!(tree.committed_txn(), 2);
+                assert_eq!(contents(&mut tree), after);
+            }
+            // 恢复后的文件可以继续使用: 空闲页由可达性扫描重建, 复用它们不会破坏已提交的数据
+            let mut expected: BTreeMap<u32, u64> = contents(&mut tree).into_iter().collect();
+            for k in (0..1_500u32).step_by(50) {
+                tree.insert(k, 7).unwrap();
+                expected.insert(k, 7);
+            }
+            tree.commit().unwrap();
+            drop(tree);
+            let mut tree = reopen(&run);
+            assert_eq!(contents(&mut tree), expected.into_iter().collect::<Vec<_>>());
+        }
+        std::fs::remove_file(&base).unwrap();
+        std::fs::remove_file(&run).unwrap();
+    }
+}
@@ -0,0 +1,272 @@
+use std::mem::size_of;
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+use crate::memory::memory::MemoryReport;
+
+/// 最大层数, p = 1/4 时可支撑 4^16 级别的数据量
+pub const MAX_LEVEL: usize = 16;
+/// 空指针
+const NIL: usize = usize::MAX;
+
+/// 跳表节点, 通过 arena 下标互相链接
+#[derive(Debug, Clone)]
+pub struct Node<K, V> {
+    pub key: K,
+    pub value: V,
+    /// forward[i] 为第 i 层的后继节点下标
+    pub forward: Vec<usize>,
+}
+
+/// 跳表 (Redis 风格, p = 1/4)
+///
+/// 节点存放在 arena (`Vec<Option<Node>>`) 中, 删除后的槽位进入空闲链表复用。
+/// 层数由种子确定的 `StdRng` 生成, 相同种子 + 相同操作序列得到完全相同的结构。
+#[derive(Debug, Clone)]
+pub struct SkipList<K, V> {
+    nodes: Vec<Option<Node<K, V>>>,
+    free: Vec<usize>,
+    /// 头节点在每一层的后继
+    head: [usize; MAX_LEVEL],
+    /// 当前最高层数
+    level: usize,
+    len: usize,
+    rng: StdRng,
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    pub fn new(seed: u64) -> Self {
+        SkipList {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: [NIL; MAX_LEVEL],
+            level: 1,
+            len: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 随机层数: 每层以 1/4 的概率继续升高
+    fn random_level(&mut self) -> usize {
+        let mut level = 1;
+        while level < MAX_LEVEL && self.rng.next_u32() & 3 == 0 {
+            level += 1;
+        }
+        level
+    }
+
+    fn node(&self, idx: usize) -> &Node<K, V> {
+        self.nodes[idx].as_ref().expect("skiplist: dangling index")
+    }
+
+    fn node_mut(&mut self, idx: usize) -> &mut Node<K, V> {
+        self.nodes[idx].as_mut().expect("skiplist: dangling index")
+    }
+
+    /// 第 level 层中 prev 的后继 (prev 为 NIL 表示头节点)
+    fn next_of(&self, prev: usize, level: usize) -> usize {
+        if prev == NIL {
+            self.head[level]
+        } else {
+            self.node(prev).forward[level]
+        }
+    }
+
+    fn set_next(&mut self, prev: usize, level: usize, next: usize) {
+        if prev == NIL {
+            self.head[level] = next;
+        } else {
+            self.node_mut(prev).forward[level] = next;
+        }
+    }
+
+    /// 找到每一层中最后一个 key < 目标 key 的节点
+    fn find_predecessors(&self, key: &K) -> [usize; MAX_LEVEL] {
+        let mut update = [NIL; MAX_LEVEL];
+        let mut prev = NIL;
+        for lvl in (0..self.level).rev() {
+            loop {
+                let next = self.next_of(prev, lvl);
+                if next != NIL && self.node(next).key < *key {
+                    prev = next;
+                } else {
+                    break;
+                }
+            }
+            update[lvl] = prev;
+        }
+        update
+    }
+
+    /// 查找第一个 key >= 目标 key 的节点
+    fn lower_bound(&self, key: &K) -> usize {
+        let mut prev = NIL;
+        for lvl in (0..self.level).rev() {
+            loop {
+                let next = self.next_of(prev, lvl);
+                if next != NIL && self.node(next).key < *key {
+                    prev = next;
+                } else {
+                    break;
+                }
+            }
+        }
+        self.next_of(prev, 0)
+    }
+
+    /// 查询
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let idx = self.lower_bound(key);
+        if idx != NIL && self.node(idx).key == *key {
+            Some(&self.node(idx).value)
+        } else {
+            None
+        }
+    }
+
+    /// 插入, key 已存在时更新 value
+    pub fn insert(&mut self, key: K, value: V) {
+        let update = self.find_predecessors(&key);
+        let candidate = self.next_of(update[0], 0);
+        if candidate != NIL && self.node(candidate).key == key {
+            self.node_mut(candidate).value = value;
+            return;
+        }
+
+        let level = self.random_level();
+        if level > self.level {
+            // 新增的层由头节点直接指向新节点, update 中对应位置已是 NIL
+            self.level = level;
+        }
+
+        let mut forward = vec![NIL; level];
+        for (lvl, slot) in forward.iter_mut().enumerate() {
+            *slot = self.next_of(update[lvl], lvl);
+        }
+        let node = Node { key, value, forward };
+        let idx = match self.free.pop() {
+            Some(idx) => {
+                self.nodes[idx] = Some(node);
+                idx
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        for (lvl, prev) in update.iter().enumerate().take(level) {
+            self.set_next(*prev, lvl, idx);
+        }
+        self.len += 1;
+    }
+
+    /// 删除
+    pub fn delete(&mut self, key: &K) {
+        let update = self.find_predecessors(key);
+        let target = self.next_of(update[0], 0);
+        if target == NIL || self.node(target).key != *key {
+            return;
+        }
+
+        let node = self.nodes[target].take().expect("skiplist: dangling index");
+        for (lvl, next) in node.forward.iter().enumerate() {
+            self.set_next(update[lvl], lvl, *next);
+        }
+        self.free.push(target);
+        self.len -= 1;
+
+        // 降低空层
+        while self.level > 1 && self.head[self.level - 1] == NIL {
+            self.level -= 1;
+        }
+    }
+
+    /// 区间查询 [start..end]
+    pub fn range_query(&self, start: &K, end: &K) -> Vec<&V> {
+        let mut result = Vec::new();
+        let mut idx = self.lower_bound(start);
+        while idx != NIL {
+            let node = self.node(idx);
+            if node.key > *end {
+                break;
+            }
+            result.push(&node.value);
+            idx = node.forward[0];
+        }
+        result
+    }
+
+    /// 最小的键值对 (买卖盘的最优价)
+    pub fn first(&self) -> Option<(&K, &V)> {
+        match self.head[0] {
+            NIL => None,
+            idx => {
+                let node = self.node(idx);
+                Some((&node.key, &node.value))
+            }
+        }
+    }
+
+    /// 按 key 升序遍历
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            list: self,
+            next: self.head[0],
+        }
+    }
+
+    /// 遍历统计内存占用
+    pub fn memory_usage(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+        let slot = size_of::<Option<Node<K, V>>>();
+        let mut used_levels = 0;
+        let mut level_capacity = 0;
+        for node in self.nodes.iter().flatten() {
+            report.node_count += 1;
+            used_levels += node.forward.len();
+            level_capacity += node.forward.capacity();
+            // 节点槽位中除 key/value 外的部分 (forward Vec 头 + 对齐)
+            report.pointer_overhead_bytes += slot - size_of::<K>() - size_of::<V>();
+        }
+        report.entry_count = self.len;
+        report.key_bytes = self.len * size_of::<K>();
+        report.value_bytes = self.len * size_of::<V>();
+        report.pointer_overhead_bytes += used_levels * size_of::<usize>() + size_of::<[usize; MAX_LEVEL]>();
+        report.wasted_capacity_bytes = (level_capacity - used_levels) * size_of::<usize>()
+            + (self.nodes.capacity() - report.node_count) * slot
+            + self.free.capacity() * size_of::<usize>();
+        report.height = self.level;
+        if !self.nodes.is_empty() {
+            report.fill_factor = report.node_count as f64 / self.nodes.len() as f64;
+        }
+        report.finish()
+    }
+}
+
+/// 升序迭代器
+pub struct Iter<'a, K, V> {
+    list: &'a SkipList<K, V>,
+    next: usize,
+}
+
+impl<'a, K: Ord, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == NIL {
+            return None;
+        }
+        let node = self.list.node(self.next);
+        self.next = node.forward[0];
+        Some((&node.key, &node.value))
+    }
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use crate::skiplist::skiplist::SkipList;
+
+    #[test]
+    fn test_skiplist_insert_get_delete() {
+        let mut list = SkipList::new(42);
+        list.insert(10, "ten");
+        list.insert(5, "five");
+        list.insert(15, "fifteen");
+        list.insert(10, "TEN");
+
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.get(&10), Some(&"TEN"));
+        assert_eq!(list.get(&5), Some(&"five"));
+        assert_eq!(list.get(&999), None);
+        assert_eq!(list.first(), Some((&5, &"five")));
+        assert_eq!(list.range_query(&6, &15), vec![&"TEN", &"fifteen"]);
+
+        list.delete(&999);
+        list.delete(&5);
+        assert_eq!(list.get(&5), None);
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.first(), Some((&10, &"TEN")));
+    }
+
+    #[test]
+    fn test_skiplist_matches_std_and_is_deterministic() {
+        let mut list = SkipList::new(7);
+        let mut other = SkipList::new(7);
+        let mut reference = std::collections::BTreeMap::new();
+        for i in 0..5000u32 {
+            let k = (i * 7919) % 2000;
+            if i % 3 == 0 {
+                list.delete(&k);
+                other.delete(&k);
+                reference.remove(&k);
+            } else {
+                list.insert(k, i);
+                other.insert(k, i);
+                reference.insert(k, i);
+            }
+        }
+
+        let got: Vec<_> = list.iter().map(|(k, v)| (*k, *v)).collect();
+        let want: Vec<_> = reference.iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(got, want);
+        assert_eq!(list.len(), reference.len());
+        assert_eq!(list.range_query(&100, &200).len(), reference.range(100..=200).count());
+        // 相同种子 + 相同操作序列 => 相同的层结构
+        assert_eq!(list.memory_usage(), other.memory_usage());
+    }
+}
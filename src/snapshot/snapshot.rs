@@ -0,0 +1,322 @@
+use std::fmt::Debug;
+use std::io::{self, Read, Write};
+
+use crate::bptree::bptree::BPTree;
+use crate::btree_map::btree_map::BTreeMap;
+use crate::order_book::order_book::{BookState, OrderBook, PriceIndex, SelfTrade, Side, SideState, StopOrder};
+use crate::paged_bptree::paged_bptree::FixedCodec;
+use crate::rb_tree::rb_tree::RBTree;
+
+const MAGIC: &[u8; 4] = b"OBSN";
+/// 只读取当前版本, 其他版本一律拒绝
+const VERSION: u32 = 1;
+/// MAGIC | 版本 u32 | 类型 u8
+const HEADER_LEN: usize = 9;
+
+const KIND_RB_TREE: u8 = 1;
+const KIND_BTREE_MAP: u8 = 2;
+const KIND_BPTREE: u8 = 3;
+const KIND_ORDER_BOOK: u8 = 4;
+/// 一个止损单的编码长度
+const STOP_LEN: usize = 43;
+
+/// 紧凑、带版本的二进制时间点快照
+///
+/// 格式 (小端): `MAGIC | 版本 u32 | 类型 u8 | 主体 | crc32(之前全部字节) u32`。
+/// 树的主体是 `key 大小 u32 | value 大小 u32 | 条目数 u64 | 按 key 升序的定长条目`,
+/// 加载时用 `from_sorted` 自底向上建树, 不经过 `insert`。
+pub trait Snapshot: Sized {
+    /// 加载时需要的额外参数, 如 B 树的最小度数
+    type Config;
+    fn save<W: Write>(&self, w: W) -> io::Result<()>;
+    fn load<R: Read>(r: R, config: Self::Config) -> io::Result<Self>;
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+fn begin(kind: u8) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    buf.push(kind);
+    buf
+}
+
+/// 追加校验和并一次写出
+fn finish<W: Write>(mut buf: Vec<u8>, mut w: W) -> io::Result<()> {
+    let crc = crc32fast::hash(&buf);
+    buf.extend_from_slice(&crc.to_le_bytes());
+    w.write_all(&buf)?;
+    w.flush()
+}
+
+/// 读入整个快照, 校验文件头与校验和, 返回主体
+fn open<R: Read>(mut r: R, kind: u8) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    r.read_to_end(&mut buf)?;
+    if buf.len() < HEADER_LEN + 4 || &buf[..4] != MAGIC {
+        return Err(invalid("not a snapshot"));
+    }
+    let (data, crc) = buf.split_at(buf.len() - 4);
+    if crc32fast::hash(data) != u32::from_le_bytes(crc.try_into().unwrap()) {
+        return Err(invalid("snapshot checksum mismatch"));
+    }
+    let version = u32::from_le_bytes(buf[4..8].try_into().unwrap());
+    if version != VERSION {
+        return Err(invalid(format!("unsupported snapshot version {}", version)));
+    }
+    if buf[8] != kind {
+        return Err(invalid(format!("snapshot holds kind {}, expected {}", buf[8], kind)));
+    }
+    buf.truncate(buf.len() - 4);
+    buf.drain(..HEADER_LEN);
+    Ok(buf)
+}
+
+/// 主体的顺序读取
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Reader { buf, pos: 0 }
+    }
+
+    fn bytes(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        let out = self.buf.get(self.pos..self.pos + n).ok_or_else(|| invalid("snapshot truncated"))?;
+        self.pos += n;
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    /// 数量字段; 超出剩余字节说明数据已损坏, 避免按它预分配内存
+    fn count(&mut self, item_size: usize) -> io::Result<usize> {
+        let n = self.u64()? as usize;
+        if n.saturating_mul(item_size) > self.buf.len() - self.pos {
+            return Err(invalid("snapshot count exceeds its length"));
+        }
+        Ok(n)
+    }
+
+    fn end(&self) -> io::Result<()> {
+        if self.pos != self.buf.len() {
+            return Err(invalid("trailing bytes in snapshot"));
+        }
+        Ok(())
+    }
+}
+
+fn write_entries<K: FixedCodec, V: FixedCodec>(buf: &mut Vec<u8>, entries: Vec<(&K, &V)>) {
+    buf.extend_from_slice(&(K::SIZE as u32).to_le_bytes());
+    buf.extend_from_slice(&(V::SIZE as u32).to_le_bytes());
+    buf.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+    let start = buf.len();
+    buf.resize(start + entries.len() * (K::SIZE + V::SIZE), 0);
+    for (chunk, (k, v)) in buf[start..].chunks_exact_mut(K::SIZE + V::SIZE).zip(entries) {
+        k.encode(&mut chunk[..K::SIZE]);
+        v.encode(&mut chunk[K::SIZE..]);
+    }
+}
+
+/// 读出条目并检查 key 严格递增
+fn read_entries<K: FixedCodec + Ord, V: FixedCodec>(body: &[u8]) -> io::Result<Vec<(K, V)>> {
+    let mut r = Reader::new(body);
+    let (key_size, value_size) = (r.u32()? as usize, r.u32()? as usize);
+    if (key_size, value_size) != (K::SIZE, V::SIZE) {
+        return Err(invalid(format!("snapshot entry is {}+{} bytes, expected {}+{}", key_size, value_size, K::SIZE, V::SIZE)));
+    }
+    let n = r.count(K::SIZE + V::SIZE)?;
+    let mut entries: Vec<(K, V)> = Vec::with_capacity(n);
+    for chunk in r.bytes(n * (K::SIZE + V::SIZE))?.chunks_exact(K::SIZE + V::SIZE) {
+        let key = K::decode(&chunk[..K::SIZE]);
+        if entries.last().is_some_and(|(last, _)| last >= &key) {
+            return Err(invalid("snapshot keys out of order"));
+        }
+        entries.push((key, V::decode(&chunk[K::SIZE..])));
+    }
+    r.end()?;
+    Ok(entries)
+}
+
+impl<K: FixedCodec + Ord + Clone, V: FixedCodec + Clone> Snapshot for RBTree<K, V> {
+    type Config = ();
+
+    fn save<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut buf = begin(KIND_RB_TREE);
+        write_entries(&mut buf, self.entries());
+        finish(buf, w)
+    }
+
+    fn load<R: Read>(r: R, _: ()) -> io::Result<Self> {
+        let body = open(r, KIND_RB_TREE)?;
+        Ok(RBTree::from_sorted(read_entries(&body)?))
+    }
+}
+
+impl<K: FixedCodec + Ord + Clone + Debug, V: FixedCodec + Clone + Debug> Snapshot for BTreeMap<K, V> {
+    /// 最小度数
+    type Config = usize;
+
+    fn save<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut buf = begin(KIND_BTREE_MAP);
+        write_entries(&mut buf, self.entries());
+        finish(buf, w)
+    }
+
+    fn load<R: Read>(r: R, min_degree: usize) -> io::Result<Self> {
+        let body = open(r, KIND_BTREE_MAP)?;
+        Ok(BTreeMap::from_sorted(min_degree, read_entries(&body)?))
+    }
+}
+
+impl<K: FixedCodec + Ord + Clone + Debug, V: FixedCodec + Clone + Debug> Snapshot for BPTree<K, V> {
+    /// 最小度数
+    type Config = usize;
+
+    fn save<W: Write>(&self, w: W) -> io::Result<()> {
+        let mut buf = begin(KIND_BPTREE);
+        write_entries(&mut buf, self.entries());
+        finish(buf, w)
+    }
+
+    fn load<R: Read>(r: R, min_degree: usize) -> io::Result<Self> {
+        let body = open(r, KIND_BPTREE)?;
+        Ok(BPTree::from_sorted(min_degree, read_entries(&body)?))
+    }
+}
+
+/// 订单簿的主体: `seq u64 | 有无成交价 u8 | 最新成交价 u64 | 买盘 | 卖盘 | 止损单 | 冰山单 | 订单账户 | 持仓 | 交易组`,
+/// 每一侧为 `档位数 u64`, 每个档位 `价格 u64 | 订单数 u64 | (订单号 u64, 剩余数量 u64)*`, 从优到劣;
+/// 止损单为 `个数 u64 | (订单号 u64, 方向 u8, 触发价 u64, 有无限价 u8, 限价 u64, 数量 u64, 账户 u64, 自成交防护 u8)*`, 按到达顺序;
+/// 冰山单为 `个数 u64 | (订单号 u64, 显示数量 u64, 隐藏数量 u64)*`, 档位中记录的是显示中的数量;
+/// 订单账户为 `个数 u64 | (订单号 u64, 账户 u64, 只减仓 u8)*`, 持仓为 `个数 u64 | (账户 u64, 持仓 i64)*`,
+/// 交易组为 `个数 u64 | (账户 u64, 组号 u64)*`
+impl<I: PriceIndex> Snapshot for OrderBook<I> {
+    /// 两棵空的价格索引树 (买, 卖)
+    type Config = (I, I);
+
+    fn save<W: Write>(&self, w: W) -> io::Result<()> {
+        let state = self.state();
+        let mut buf = begin(KIND_ORDER_BOOK);
+        buf.extend_from_slice(&state.seq.to_le_bytes());
+        buf.push(state.last_price.is_some() as u8);
+        buf.extend_from_slice(&state.last_price.unwrap_or(0).to_le_bytes());
+        for levels in [&state.bids, &state.asks] {
+            buf.extend_from_slice(&(levels.len() as u64).to_le_bytes());
+            for (price, orders) in levels {
+                buf.extend_from_slice(&price.to_le_bytes());
+                buf.extend_from_slice(&(orders.len() as u64).to_le_bytes());
+                for (id, qty) in orders {
+                    buf.extend_from_slice(&id.to_le_bytes());
+                    buf.extend_from_slice(&qty.to_le_bytes());
+                }
+            }
+        }
+        buf.extend_from_slice(&(state.stops.len() as u64).to_le_bytes());
+        for stop in &state.stops {
+            buf.extend_from_slice(&stop.id.to_le_bytes());
+            buf.push(stop.side as u8);
+            buf.extend_from_slice(&stop.trigger.to_le_bytes());
+            buf.push(stop.limit.is_some() as u8);
+            buf.extend_from_slice(&stop.limit.unwrap_or(0).to_le_bytes());
+            buf.extend_from_slice(&stop.qty.to_le_bytes());
+            buf.extend_from_slice(&stop.account.to_le_bytes());
+            buf.push(SelfTrade::encode(stop.self_trade));
+        }
+        buf.extend_from_slice(&(state.icebergs.len() as u64).to_le_bytes());
+        for (id, display, hidden) in &state.icebergs {
+            for field in [id, display, hidden] {
+                buf.extend_from_slice(&field.to_le_bytes());
+            }
+        }
+        buf.extend_from_slice(&(state.accounts.len() as u64).to_le_bytes());
+        for (id, account, reduce_only) in &state.accounts {
+            buf.extend_from_slice(&id.to_le_bytes());
+            buf.extend_from_slice(&account.to_le_bytes());
+            buf.push(*reduce_only as u8);
+        }
+        buf.extend_from_slice(&(state.positions.len() as u64).to_le_bytes());
+        for (account, position) in &state.positions {
+            buf.extend_from_slice(&account.to_le_bytes());
+            buf.extend_from_slice(&position.to_le_bytes());
+        }
+        buf.extend_from_slice(&(state.groups.len() as u64).to_le_bytes());
+        for (account, group) in &state.groups {
+            buf.extend_from_slice(&account.to_le_bytes());
+            buf.extend_from_slice(&group.to_le_bytes());
+        }
+        finish(buf, w)
+    }
+
+    fn load<R: Read>(r: R, (bid_index, ask_index): (I, I)) -> io::Result<Self> {
+        let body = open(r, KIND_ORDER_BOOK)?;
+        let mut r = Reader::new(&body);
+        let seq = r.u64()?;
+        let has_last = r.u8()?;
+        let last = r.u64()?;
+        let mut read_side = || -> io::Result<SideState> {
+            let n = r.count(16)?;
+            let mut levels = Vec::with_capacity(n);
+            for _ in 0..n {
+                let price = r.u64()?;
+                let m = r.count(16)?;
+                let orders = (0..m).map(|_| Ok((r.u64()?, r.u64()?))).collect::<io::Result<_>>()?;
+                levels.push((price, orders));
+            }
+            Ok(levels)
+        };
+        let bids = read_side()?;
+        let asks = read_side()?;
+        let mut stops = Vec::new();
+        for _ in 0..r.count(STOP_LEN)? {
+            let id = r.u64()?;
+            let side = match r.u8()? {
+                0 => Side::Buy,
+                1 => Side::Sell,
+                other => return Err(invalid(format!("bad side {}", other))),
+            };
+            let trigger = r.u64()?;
+            let has_limit = r.u8()?;
+            let limit = r.u64()?;
+            let qty = r.u64()?;
+            let account = r.u64()?;
+            let self_trade = SelfTrade::decode(r.u8()?).map_err(invalid)?;
+            stops.push(StopOrder { id, side, trigger, limit: (has_limit != 0).then_some(limit), qty, account, self_trade });
+        }
+        let mut icebergs = Vec::new();
+        for _ in 0..r.count(24)? {
+            icebergs.push((r.u64()?, r.u64()?, r.u64()?));
+        }
+        let mut accounts = Vec::new();
+        for _ in 0..r.count(17)? {
+            accounts.push((r.u64()?, r.u64()?, r.u8()? != 0));
+        }
+        let mut positions = Vec::new();
+        for _ in 0..r.count(16)? {
+            positions.push((r.u64()?, r.u64()? as i64));
+        }
+        let mut groups = Vec::new();
+        for _ in 0..r.count(16)? {
+            groups.push((r.u64()?, r.u64()?));
+        }
+        r.end()?;
+        let last_price = (has_last != 0).then_some(last);
+        let state = BookState { bids, asks, icebergs, accounts, positions, groups, stops, last_price, seq };
+        OrderBook::restore(state, bid_index, ask_index).map_err(invalid)
+    }
+}
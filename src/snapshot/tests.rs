@@ -0,0 +1,252 @@
+#[cfg(test)]
+mod tests {
+    use crate::bptree::bptree::BPTree;
+    use crate::btree_map::btree_map::BTreeMap;
+    use crate::order_book::order_book::{BPOrderBook, Command, NewOrder, OrderBook, RBOrderBook, SelfTrade, Side, StopOrder};
+    use crate::rb_tree::rb_tree::{Color, Node, RBTree};
+    use crate::snapshot::snapshot::Snapshot;
+    use crate::wal::wal::{FsyncPolicy, JournaledBook};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// 检查左倾红黑树的性质, 返回黑高
+    fn black_height(node: &Option<Box<Node<u32, u64>>>) -> usize {
+        let Some(n) = node else { return 0 };
+        assert!(n.right.as_ref().is_none_or(|r| r.color == Color::Black), "right-leaning red");
+        if n.color == Color::Red {
+            assert!(n.left.as_ref().is_none_or(|l| l.color == Color::Black), "two reds in a row");
+        }
+        let (l, r) = (black_height(&n.left), black_height(&n.right));
+        assert_eq!(l, r, "unbalanced black height");
+        l + (n.color == Color::Black) as usize
+    }
+
+    /// BPTree 按 key 删除走游标 (打墓碑)
+    fn bp_delete(bp: &mut BPTree<u32, u64>, key: &u32) {
+        let mut cursor = bp.lower_bound_mut(key);
+        if cursor.key() == Some(key) {
+            cursor.remove_current();
+        }
+    }
+
+    #[test]
+    fn test_snapshot_trees_round_trip() {
+        // 自底向上建树对任意大小都满足各自的平衡性质, 之后还能正常增删
+        for n in 0..300u32 {
+            let entries: Vec<(u32, u64)> = (0..n).map(|k| (k * 2, k as u64)).collect();
+            let mut rb = RBTree::from_sorted(entries.clone());
+            assert_eq!(rb.root.as_ref().map_or(Color::Black, |r| r.color), Color::Black);
+            black_height(&rb.root);
+            let mut bt = BTreeMap::from_sorted(3, entries.clone());
+            let mut bp = BPTree::from_sorted(3, entries.clone());
+            for k in (0..n).step_by(3) {
+                rb.delete(&(k * 2));
+                bt.delete(&(k * 2));
+                bp_delete(&mut bp, &(k * 2));
+                rb.insert(k * 2 + 1, 0);
+                bt.insert(k * 2 + 1, 0);
+                bp.insert(k * 2 + 1, 0);
+            }
+            black_height(&rb.root);
+            assert_eq!(rb.entries(), bt.entries());
+            assert_eq!(rb.entries(), bp.entries());
+        }
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut rb = RBTree::new();
+        let mut bt = BTreeMap::new(4);
+        let mut bp = BPTree::new(4);
+        let mut reference = std::collections::BTreeMap::new();
+        for i in 0..20_000u64 {
+            let k: u32 = rng.gen_range(0..8_000);
+            if i % 3 == 0 {
+                rb.delete(&k);
+                bt.delete(&k);
+                bp_delete(&mut bp, &k);
+                reference.remove(&k);
+            } else {
+                // BTreeMap 的 insert 会留下重复 key, 先删再插
+                rb.insert(k, i);
+                bt.delete(&k);
+                bt.insert(k, i);
+                bp.insert(k, i);
+                reference.insert(k, i);
+            }
+        }
+        let want: Vec<(&u32, &u64)> = reference.iter().collect();
+
+        let mut buf = Vec::new();
+        rb.save(&mut buf).unwrap();
+        // 每个条目 12 字节, 另加 9 字节文件头、16 字节条目头和 4 字节校验和
+        assert_eq!(buf.len(), 9 + 16 + reference.len() * 12 + 4);
+        let loaded = RBTree::<u32, u64>::load(&buf[..], ()).unwrap();
+        assert_eq!(loaded.entries(), want);
+        black_height(&loaded.root);
+
+        let mut buf = Vec::new();
+        bt.save(&mut buf).unwrap();
+        assert_eq!(BTreeMap::<u32, u64>::load(&buf[..], 4).unwrap().entries(), want);
+
+        let mut buf = Vec::new();
+        bp.save(&mut buf).unwrap();
+        let loaded = BPTree::<u32, u64>::load(&buf[..], 4).unwrap();
+        assert_eq!(loaded.entries(), want);
+        let range: Vec<u64> = reference.range(1_000..=5_000).map(|(_, v)| *v).collect();
+        assert_eq!(loaded.range_query(&1_000, &5_000), range);
+
+        // 类型不符、条目大小不符、数据损坏都被拒绝
+        assert!(BTreeMap::<u32, u64>::load(&buf[..], 4).is_err());
+        assert!(BPTree::<u64, u64>::load(&buf[..], 4).is_err());
+        buf[40] ^= 1;
+        assert!(BPTree::<u32, u64>::load(&buf[..], 4).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_order_book_fixture() {
+        // 订单簿快照的字节布局是固定的; 其他版本号一律拒绝
+        let le = |v: u64| v.to_le_bytes().to_vec();
+        let mut book = RBOrderBook::with_rb_tree();
+        book.apply(&Command::Group { account: 2, group: 5 }).unwrap();
+        book.apply(&Command::Limit(NewOrder { display: Some(2), account: 2, ..NewOrder::limit(1, Side::Buy, 99, 6) })).unwrap();
+        book.apply(&Command::Limit(NewOrder { account: 3, ..NewOrder::limit(2, Side::Sell, 99, 1) })).unwrap();
+        let stop = StopOrder { id: 3, side: Side::Sell, trigger: 90, limit: Some(89), qty: 4, account: 2, self_trade: Some(SelfTrade::CancelNewest) };
+        book.apply(&Command::Stop(stop)).unwrap();
+        let body = [
+            vec![1, 0, 0, 0, 4],
+            le(4),
+            vec![1],
+            le(99),
+            // 买盘: 一个档位, 订单 1 显示 2 (已成交 1), 隐藏 4; 卖盘为空
+            le(1),
+            le(99),
+            le(1),
+            le(1),
+            le(1),
+            le(0),
+            // 止损单
+            le(1),
+            le(3),
+            vec![1],
+            le(90),
+            vec![1],
+            le(89),
+            le(4),
+            le(2),
+            vec![1],
+            // 冰山单 (订单号, 显示数量, 隐藏数量)
+            le(1),
+            le(1),
+            le(2),
+            le(4),
+            // 订单账户
+            le(1),
+            le(1),
+            le(2),
+            vec![0],
+            // 持仓
+            le(2),
+            le(2),
+            le(1),
+            le(3),
+            (-1i64).to_le_bytes().to_vec(),
+            // 交易组
+            le(1),
+            le(2),
+            le(5),
+        ]
+        .concat();
+        let mut want = [b"OBSN".to_vec(), body].concat();
+        want.extend_from_slice(&crc32fast::hash(&want).to_le_bytes());
+        let mut buf = Vec::new();
+        book.save(&mut buf).unwrap();
+        assert_eq!(buf, want);
+        let loaded = OrderBook::load(&want[..], (RBTree::new(), RBTree::new())).unwrap();
+        assert_eq!(loaded.state(), book.state());
+
+        for version in [0u32, 2, 5] {
+            let mut other = want[..want.len() - 4].to_vec();
+            other[4..8].copy_from_slice(&version.to_le_bytes());
+            let crc = crc32fast::hash(&other);
+            other.extend_from_slice(&crc.to_le_bytes());
+            assert!(OrderBook::load(&other[..], (RBTree::new(), RBTree::new())).is_err());
+        }
+    }
+
+    #[test]
+    fn test_snapshot_plus_journal_tail() {
+        let dir = std::env::temp_dir();
+        let journal = dir.join(format!("snapshot_journal_{}.log", std::process::id()));
+        let snapshot = dir.join(format!("snapshot_book_{}.snap", std::process::id()));
+        let old_journal = dir.join(format!("snapshot_old_journal_{}.log", std::process::id()));
+        let mut book = JournaledBook::create(&journal, FsyncPolicy::Never, RBOrderBook::with_rb_tree()).unwrap();
+        let mut rng = StdRng::seed_from_u64(11);
+        for id in 0..6_000u64 {
+            let cmd = if rng.gen_bool(0.25) {
+                Command::Cancel { id: rng.gen_range(0..id.max(1)) }
+            } else if rng.gen_bool(0.01) {
+                Command::Group { account: rng.gen_range(1..3), group: rng.gen_range(0..3) }
+            } else if rng.gen_bool(0.1) {
+                // 止损单也进日志和快照
+                let side = if rng.gen_bool(0.5) { Side::Buy } else { Side::Sell };
+                let trigger = rng.gen_range(960..1040);
+                let limit = rng.gen_bool(0.5).then(|| rng.gen_range(950..1050));
+                let self_trade = rng.gen_bool(0.5).then_some(SelfTrade::CancelOldest);
+                Command::Stop(StopOrder { id, side, trigger, limit, qty: rng.gen_range(1..30), account: rng.gen_range(0..3), self_trade })
+            } else {
+                let (side, price) = if rng.gen_bool(0.5) {
+                    (Side::Buy, rng.gen_range(900..1010))
+                } else {
+                    (Side::Sell, rng.gen_range(990..1100))
+                };
+                let qty = rng.gen_range(1..30);
+                let display = rng.gen_bool(0.2).then(|| qty / 3 + 1);
+                let account = rng.gen_range(0..3);
+                let reduce_only = account > 0 && rng.gen_bool(0.1);
+                let self_trade = rng.gen_bool(0.2).then_some(SelfTrade::DecrementAndCancel);
+                Command::Limit(NewOrder { display, account, reduce_only, self_trade, ..NewOrder::limit(id, side, price, qty) })
+            };
+            book.submit(&cmd).unwrap().ok();
+            if id == 4_000 {
+                // 检查点之前的日志, 用来模拟快照改名后、日志轮换前的崩溃
+                std::fs::copy(&journal, &old_journal).unwrap();
+                book.checkpoint(&snapshot).unwrap();
+                // 日志已轮换为从快照序号开始的空日志
+                assert_eq!(book.wal().base(), book.book().seq());
+                assert!(book.wal().is_empty());
+            }
+        }
+        let want = book.book().state();
+        assert!(want.bids.len() > 10 && want.asks.len() > 10 && !want.stops.is_empty() && !want.icebergs.is_empty());
+        assert!(!want.positions.is_empty() && !want.accounts.is_empty() && !want.groups.is_empty());
+        drop(book);
+
+        // 快照 + 日志尾部, 换一种价格索引恢复
+        let restored = BPOrderBook::load(std::fs::File::open(&snapshot).unwrap(), (BPTree::new(3), BPTree::new(3))).unwrap();
+        let at = restored.seq();
+        assert!(at > 0 && at < want.seq);
+        let (book, recovery) = JournaledBook::recover(&journal, FsyncPolicy::Never, restored).unwrap();
+        assert_eq!(recovery.base, at);
+        assert_eq!(recovery.base + recovery.commands.len() as u64, want.seq);
+        assert_eq!(book.book().state(), want);
+
+        // 轮换后的日志不含检查点之前的记录, 不能单独从空订单簿恢复
+        assert!(JournaledBook::recover(&journal, FsyncPolicy::Never, RBOrderBook::with_rb_tree()).is_err());
+
+        // 日志轮换前崩溃: 旧日志仍在, 跳过快照已包含的记录
+        let restored = BPOrderBook::load(std::fs::File::open(&snapshot).unwrap(), (BPTree::new(3), BPTree::new(3))).unwrap();
+        let (old, recovery) = JournaledBook::recover(&old_journal, FsyncPolicy::Never, restored).unwrap();
+        assert_eq!((recovery.base, recovery.commands.len() as u64), (0, at));
+        assert_eq!(old.book().seq(), at);
+        std::fs::remove_file(&old_journal).unwrap();
+
+        // 快照比日志新 (日志被截断) 时拒绝恢复
+        let mut buf = Vec::new();
+        book.book().save(&mut buf).unwrap();
+        drop(book);
+        std::fs::write(&journal, b"").unwrap();
+        let ahead = OrderBook::load(&buf[..], (RBTree::new(), RBTree::new())).unwrap();
+        assert!(JournaledBook::recover(&journal, FsyncPolicy::Never, ahead).is_err());
+        std::fs::remove_file(&journal).unwrap();
+        std::fs::remove_file(&snapshot).unwrap();
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod wal;
+#[allow(clippy::module_inception)]
+pub mod tests;
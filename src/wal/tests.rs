@@ -0,0 +1,231 @@
+#[cfg(test)]
+mod tests {
+    use crate::order_book::order_book::{BPOrderBook, Command, NewOrder, RBOrderBook, Side};
+    use crate::wal::wal::{FsyncPolicy, JournaledBook, LogFile, Wal};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::cell::RefCell;
+    use std::fs::OpenOptions;
+    use std::io::{self, Seek, SeekFrom, Write};
+    use std::rc::Rc;
+
+    /// 随机命令流, 其中混有会被拒绝的命令
+    fn commands(n: u64, seed: u64) -> Vec<Command> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|id| {
+                if rng.gen_bool(0.3) {
+                    Command::Cancel { id: rng.gen_range(0..id.max(1)) }
+                } else {
+                    let side = if rng.gen_bool(0.5) { Side::Buy } else { Side::Sell };
+                    let order = NewOrder::limit(id, side, rng.gen_range(950..1050), rng.gen_range(1..20));
+                    Command::Limit(order)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_wal_recovery_after_truncation() {
+        let path = std::env::temp_dir().join(format!("wal_truncate_{}.log", std::process::id()));
+        let mut journaled = JournaledBook::create(&path, FsyncPolicy::Batch(64), RBOrderBook::with_rb_tree()).unwrap();
+        // 每条被接受的命令及其记录结束后的日志长度
+        let mut accepted = Vec::new();
+        let mut ends = Vec::new();
+        for cmd in commands(3_000, 7) {
+            if journaled.submit(&cmd).unwrap().is_ok() {
+                accepted.push(cmd);
+                ends.push(journaled.wal().len());
+            }
+        }
+        journaled.sync().unwrap();
+        let full_state = journaled.book().state();
+        drop(journaled);
+        let intact = std::fs::read(&path).unwrap();
+
+        // 完整日志回放到两种后端都得到相同状态
+        let (rb, recovery) = JournaledBook::recover(&path, FsyncPolicy::Never, RBOrderBook::with_rb_tree()).unwrap();
+        assert_eq!(recovery.discarded, 0);
+        assert_eq!(rb.book().state(), full_state);
+
+        let mut rng = StdRng::seed_from_u64(99);
+        let mut cuts: Vec<u64> = (0..30).map(|_| rng.gen_range(0..intact.len() as u64)).collect();
+        cuts.extend([0, 3, 8, *ends.last().unwrap() - 1]);
+        for cut in cuts {
+            std::fs::write(&path, &intact[..cut as usize]).unwrap();
+            let complete = ends.iter().take_while(|&&end| end <= cut).count();
+            let mut expect = RBOrderBook::with_rb_tree();
+            for cmd in &accepted[..complete] {
+                expect.apply(cmd).unwrap();
+            }
+
+            let (mut bp, recovery) = JournaledBook::recover(&path, FsyncPolicy::Never, BPOrderBook::with_bptree(3)).unwrap();
+            assert_eq!(recovery.commands.len(), complete, "cut at {}", cut);
+            assert_eq!(bp.book().state(), expect.state(), "cut at {}", cut);
+            // 残缺尾部已被截掉, 之后的追加可以再次恢复
+            let next = accepted[complete].clone();
+            bp.submit(&next).unwrap().unwrap();
+            drop(bp);
+            expect.apply(&next).unwrap();
+            let (rb, recovery) = JournaledBook::recover(&path, FsyncPolicy::Never, RBOrderBook::with_rb_tree()).unwrap();
+            assert_eq!(recovery.discarded, 0);
+            assert_eq!(rb.book().state(), expect.state());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_wal_stops_at_corrupt_record() {
+        let path = std::env::temp_dir().join(format!("wal_corrupt_{}.log", std::process::id()));
+        let mut wal = Wal::create(&path, FsyncPolicy::Always).unwrap();
+        let cmds: Vec<Command> = (0..10).map(|id| Command::Cancel { id }).collect();
+        let mut ends = Vec::new();
+        for cmd in &cmds {
+            wal.append(cmd).unwrap();
+            ends.push(wal.len());
+        }
+        assert_eq!(wal.records(), 10);
+        drop(wal);
+
+        // 翻转第 5 条记录负载中的一个字节: 校验失败, 只保留前 4 条
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[ends[3] as usize + 10] ^= 0xff;
+        std::fs::write(&path, &bytes).unwrap();
+        let recovery = Wal::read(&path).unwrap();
+        assert_eq!(recovery.commands, cmds[..4]);
+        assert_eq!(recovery.valid_len, ends[3]);
+
+        let (wal, _) = Wal::open(&path, FsyncPolicy::Never).unwrap();
+        assert_eq!(wal.records(), 4);
+        assert_eq!(OpenOptions::new().read(true).open(&path).unwrap().metadata().unwrap().len(), ends[3]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// 内存中的日志文件, 可以注入写入、截断和 fsync 故障
+    #[derive(Default)]
+    struct Disk {
+        data: Vec<u8>,
+        pos: usize,
+        /// 还能写入的字节数, 用完后写入报错
+        write_budget: Option<usize>,
+        fail_set_len: bool,
+        fail_sync: bool,
+    }
+
+    #[derive(Clone, Default)]
+    struct FaultyFile(Rc<RefCell<Disk>>);
+
+    impl Write for FaultyFile {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut disk = self.0.borrow_mut();
+            let n = match disk.write_budget {
+                Some(0) => return Err(io::Error::other("disk full")),
+                Some(budget) => buf.len().min(budget),
+                None => buf.len(),
+            };
+            if let Some(budget) = disk.write_budget.as_mut() {
+                *budget -= n;
+            }
+            let pos = disk.pos;
+            if disk.data.len() < pos + n {
+                disk.data.resize(pos + n, 0);
+            }
+            disk.data[pos..pos + n].copy_from_slice(&buf[..n]);
+            disk.pos += n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for FaultyFile {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            let mut disk = self.0.borrow_mut();
+            disk.pos = match pos {
+                SeekFrom::Start(p) => p as usize,
+                SeekFrom::End(d) => (disk.data.len() as i64 + d) as usize,
+                SeekFrom::Current(d) => (disk.pos as i64 + d) as usize,
+            };
+            Ok(disk.pos as u64)
+        }
+    }
+
+    impl LogFile for FaultyFile {
+        fn set_len(&mut self, len: u64) -> io::Result<()> {
+            let mut disk = self.0.borrow_mut();
+            if disk.fail_set_len {
+                return Err(io::Error::other("set_len failed"));
+            }
+            disk.data.resize(len as usize, 0);
+            Ok(())
+        }
+
+        fn sync_data(&mut self) -> io::Result<()> {
+            if self.0.borrow().fail_sync {
+                return Err(io::Error::other("fsync failed"));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_wal_rolls_back_failed_append() {
+        let file = FaultyFile::default();
+        let disk = file.0.clone();
+        let mut wal = Wal::with_file(file, FsyncPolicy::Never, 0).unwrap();
+        let cmds: Vec<Command> = (0..6).map(|id| Command::Cancel { id }).collect();
+        for cmd in &cmds[..3] {
+            wal.append(cmd).unwrap();
+        }
+        let len = wal.len();
+        assert_eq!(disk.borrow().data.len() as u64, len);
+
+        // 写到一半磁盘满: 残缺记录被截掉, 序号不前进
+        disk.borrow_mut().write_budget = Some(5);
+        assert!(wal.append(&cmds[3]).is_err());
+        assert!(!wal.is_poisoned());
+        assert_eq!(wal.records(), 3);
+        assert_eq!(wal.len(), len);
+        assert_eq!(disk.borrow().data.len() as u64, len);
+
+        // 恢复写入后, 下一条记录紧接在最后一条完整记录之后
+        disk.borrow_mut().write_budget = None;
+        assert_eq!(wal.append(&cmds[4]).unwrap(), 4);
+        let path = std::env::temp_dir().join(format!("wal_rollback_{}.log", std::process::id()));
+        std::fs::write(&path, &disk.borrow().data).unwrap();
+        let recovery = Wal::read(&path).unwrap();
+        assert_eq!(recovery.commands, [&cmds[..3], &cmds[4..5]].concat());
+        assert_eq!(recovery.discarded, 0);
+        std::fs::remove_file(&path).unwrap();
+
+        // 截断也失败: 文件状态未知, 日志被毒化
+        disk.borrow_mut().write_budget = Some(5);
+        disk.borrow_mut().fail_set_len = true;
+        assert!(wal.append(&cmds[5]).is_err());
+        assert!(wal.is_poisoned());
+        disk.borrow_mut().write_budget = None;
+        assert!(wal.append(&cmds[5]).is_err());
+        assert!(wal.sync().is_err());
+        assert_eq!(wal.records(), 4);
+    }
+
+    #[test]
+    fn test_wal_poisoned_by_failed_sync() {
+        let file = FaultyFile::default();
+        let disk = file.0.clone();
+        let mut wal = Wal::with_file(file, FsyncPolicy::Always, 0).unwrap();
+        wal.append(&Command::Cancel { id: 0 }).unwrap();
+        let len = wal.len();
+
+        // fsync 失败的记录没有被确认, 截掉后日志不再接受追加
+        disk.borrow_mut().fail_sync = true;
+        assert!(wal.append(&Command::Cancel { id: 1 }).is_err());
+        assert!(wal.is_poisoned());
+        assert_eq!(wal.records(), 1);
+        assert_eq!(disk.borrow().data.len() as u64, len);
+        disk.borrow_mut().fail_sync = false;
+        assert!(wal.append(&Command::Cancel { id: 1 }).is_err());
+    }
+}
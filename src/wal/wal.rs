@@ -0,0 +1,370 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::order_book::order_book::{Command, Execution, OrderBook, PriceIndex, Trade};
+use crate::snapshot::snapshot::Snapshot;
+
+/// 文件头: 魔数 + 格式版本 + 起始序号 (之前的记录已包含在快照中)
+const MAGIC: &[u8; 4] = b"OBWL";
+const VERSION: u32 = 1;
+const HEADER_LEN: u64 = 16;
+/// 记录头: 负载长度 u32 + 负载的 CRC32
+const RECORD_HEADER: usize = 8;
+/// 单条记录负载的上限, 超过说明长度字段已损坏
+const MAX_PAYLOAD: usize = 1 << 16;
+
+/// 何时把日志 fsync 到磁盘
+///
+/// 每条记录都会立即写入文件 (进程崩溃不丢), fsync 决定掉电时最多丢多少条
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// 每条记录 fsync 一次
+    Always,
+    /// 每 n 条记录 fsync 一次
+    Batch(usize),
+    /// 从不主动 fsync, 交给操作系统
+    Never,
+}
+
+/// 读日志的结果: 完好的命令前缀, 以及末尾被丢弃的残缺部分
+#[derive(Debug)]
+pub struct Recovery {
+    /// 日志的起始序号: 第一条记录的序号为 base + 1
+    pub base: u64,
+    pub commands: Vec<Command>,
+    /// 最后一条完好记录的结束偏移
+    pub valid_len: u64,
+    /// 被丢弃的尾部字节数 (写到一半的记录或校验失败的记录及其之后的内容)
+    pub discarded: u64,
+}
+
+/// 日志的底层文件; 测试中可以换成注入写入故障的实现
+pub trait LogFile: Write + Seek {
+    fn set_len(&mut self, len: u64) -> io::Result<()>;
+    fn sync_data(&mut self) -> io::Result<()>;
+}
+
+impl LogFile for File {
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+
+    fn sync_data(&mut self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+}
+
+/// 只追加、带校验的命令日志
+///
+/// 记录格式: `len: u32 | crc32(payload): u32 | payload`, payload 为 `seq: u64` + `Command::encode`。
+/// 恢复时读到第一条残缺、校验失败或序号不连续的记录即停止, 之后的内容全部丢弃。
+///
+/// 追加失败时把文件截回上一条完整记录的末尾; 截断失败或 fsync 失败后日志被毒化, 之后的追加一律报错
+pub struct Wal<F: LogFile = File> {
+    file: F,
+    policy: FsyncPolicy,
+    /// 起始序号, 之前的记录已包含在快照中
+    base: u64,
+    /// 下一条记录的序号 (从 base + 1 开始)
+    next_seq: u64,
+    /// 上次 fsync 之后追加的记录数
+    unsynced: usize,
+    len: u64,
+    /// 文件状态已无法确定 (截断或 fsync 失败)
+    poisoned: bool,
+}
+
+impl Wal {
+    /// 新建空日志, 已存在的文件会被截断
+    pub fn create(path: impl AsRef<Path>, policy: FsyncPolicy) -> io::Result<Self> {
+        Self::create_from(path, policy, 0)
+    }
+
+    /// 新建从 base 之后开始的空日志, 第一条记录的序号为 base + 1
+    pub fn create_from(path: impl AsRef<Path>, policy: FsyncPolicy, base: u64) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        Self::with_file(file, policy, base)
+    }
+
+    /// 打开已有日志: 读出完好的命令, 截掉残缺的尾部, 之后的追加接在最后一条完好记录后面
+    ///
+    /// 文件头本身残缺 (创建时崩溃) 视为空日志
+    pub fn open(path: impl AsRef<Path>, policy: FsyncPolicy) -> io::Result<(Self, Recovery)> {
+        let path = path.as_ref();
+        let recovery = Self::read(path)?;
+        if recovery.valid_len < HEADER_LEN {
+            return Ok((Self::create(path, policy)?, recovery));
+        }
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        if recovery.discarded > 0 {
+            file.set_len(recovery.valid_len)?;
+            file.sync_data()?;
+        }
+        file.seek(SeekFrom::Start(recovery.valid_len))?;
+        let wal = Wal {
+            file,
+            policy,
+            base: recovery.base,
+            next_seq: recovery.base + recovery.commands.len() as u64 + 1,
+            unsynced: 0,
+            len: recovery.valid_len,
+            poisoned: false,
+        };
+        Ok((wal, recovery))
+    }
+
+    /// 只读地解析日志
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Recovery> {
+        let mut buf = Vec::new();
+        File::open(path)?.read_to_end(&mut buf)?;
+        let total = buf.len() as u64;
+        let mut commands = Vec::new();
+        if buf.len() < HEADER_LEN as usize {
+            return Ok(Recovery { base: 0, commands, valid_len: 0, discarded: total });
+        }
+        if &buf[..4] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a book journal"));
+        }
+        let version = u32::from_le_bytes(buf[4..8].try_into().unwrap());
+        if version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported journal version {}", version),
+            ));
+        }
+        let base = u64::from_le_bytes(buf[8..16].try_into().unwrap());
+        let mut pos = HEADER_LEN as usize;
+        while let Some(cmd) = Self::parse_record(&buf[pos..], base + commands.len() as u64 + 1) {
+            let (cmd, used) = cmd;
+            commands.push(cmd);
+            pos += used;
+        }
+        Ok(Recovery { base, commands, valid_len: pos as u64, discarded: total - pos as u64 })
+    }
+
+    /// 解析 buf 开头的一条记录, 返回 (命令, 占用字节数); 残缺或损坏时返回 None
+    fn parse_record(buf: &[u8], expect_seq: u64) -> Option<(Command, usize)> {
+        let header = buf.get(..RECORD_HEADER)?;
+        let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+        let crc = u32::from_le_bytes(header[4..].try_into().unwrap());
+        if !(8..=MAX_PAYLOAD).contains(&len) {
+            return None;
+        }
+        let payload = buf.get(RECORD_HEADER..RECORD_HEADER + len)?;
+        if crc32fast::hash(payload) != crc {
+            return None;
+        }
+        let seq = u64::from_le_bytes(payload[..8].try_into().unwrap());
+        if seq != expect_seq {
+            return None;
+        }
+        let cmd = Command::decode(&payload[8..]).ok()?;
+        Some((cmd, RECORD_HEADER + len))
+    }
+}
+
+impl<F: LogFile> Wal<F> {
+    /// 在空文件上新建从 base 之后开始的日志: 写入文件头并 fsync
+    pub fn with_file(mut file: F, policy: FsyncPolicy, base: u64) -> io::Result<Self> {
+        let mut header = Vec::with_capacity(HEADER_LEN as usize);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&base.to_le_bytes());
+        file.write_all(&header)?;
+        file.sync_data()?;
+        Ok(Wal { file, policy, base, next_seq: base + 1, unsynced: 0, len: HEADER_LEN, poisoned: false })
+    }
+
+    pub fn policy(&self) -> FsyncPolicy {
+        self.policy
+    }
+
+    /// 起始序号, 之前的记录已被检查点截掉
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// 本日志中的记录数
+    pub fn records(&self) -> u64 {
+        self.next_seq - 1 - self.base
+    }
+
+    /// 日志文件当前长度 (字节)
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records() == 0
+    }
+
+    /// 之前的写入失败后无法确定文件状态, 日志不再接受追加
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// 追加一条命令, 按 fsync 策略决定是否落盘; 返回其序号
+    ///
+    /// 写入或 fsync 失败时这条记录不算追加成功: 文件截回追加前的长度, 序号不前进
+    pub fn append(&mut self, cmd: &Command) -> io::Result<u64> {
+        if self.poisoned {
+            return Err(io::Error::other("journal is poisoned by an earlier write failure"));
+        }
+        let seq = self.next_seq;
+        let mut record = vec![0u8; RECORD_HEADER];
+        record.extend_from_slice(&seq.to_le_bytes());
+        cmd.encode(&mut record);
+        let payload_len = (record.len() - RECORD_HEADER) as u32;
+        let crc = crc32fast::hash(&record[RECORD_HEADER..]);
+        record[..4].copy_from_slice(&payload_len.to_le_bytes());
+        record[4..8].copy_from_slice(&crc.to_le_bytes());
+        // 一次 write_all 写完整条记录, 崩溃时最多留下一条残缺记录
+        if let Err(e) = self.file.write_all(&record) {
+            self.rollback();
+            return Err(e);
+        }
+        self.unsynced += 1;
+        let synced = match self.policy {
+            FsyncPolicy::Always => self.sync(),
+            FsyncPolicy::Batch(n) if self.unsynced >= n => self.sync(),
+            _ => Ok(()),
+        };
+        if let Err(e) = synced {
+            // 调用方会把命令当作未写入, 恢复时不能回放它
+            self.rollback();
+            return Err(e);
+        }
+        self.next_seq += 1;
+        self.len += record.len() as u64;
+        Ok(seq)
+    }
+
+    /// 截掉写了一部分的记录, 下一次追加接在最后一条完整记录之后; 截断失败则毒化日志
+    fn rollback(&mut self) {
+        let len = self.len;
+        if self.file.set_len(len).and_then(|_| self.file.seek(SeekFrom::Start(len))).is_err() {
+            self.poisoned = true;
+        }
+    }
+
+    /// 立即 fsync; 失败后已追加的记录是否落盘无法确定, 日志被毒化
+    pub fn sync(&mut self) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::other("journal is poisoned by an earlier write failure"));
+        }
+        if self.unsynced > 0 {
+            if let Err(e) = self.file.sync_data() {
+                self.poisoned = true;
+                return Err(e);
+            }
+            self.unsynced = 0;
+        }
+        Ok(())
+    }
+}
+
+/// 带预写日志的订单簿: 命令先校验, 再写日志, 最后执行
+pub struct JournaledBook<I> {
+    book: OrderBook<I>,
+    wal: Wal,
+    path: PathBuf,
+}
+
+impl<I: PriceIndex> JournaledBook<I> {
+    /// 在空订单簿上新建日志
+    pub fn create(path: impl AsRef<Path>, policy: FsyncPolicy, book: OrderBook<I>) -> io::Result<Self> {
+        assert!(book.seq() == 0, "journal must start from an empty book");
+        let path = path.as_ref().to_path_buf();
+        Ok(JournaledBook { book, wal: Wal::create(&path, policy)?, path })
+    }
+
+    /// 崩溃恢复: 把日志中完好的命令回放到 book 上 (任意价格索引)
+    ///
+    /// book 可以是全新的空订单簿, 也可以是从快照加载的订单簿: 序号不超过 `book.seq()` 的记录已包含在快照中,
+    /// 只回放之后的日志尾部。检查点之后的日志从快照的序号开始, 订单簿不能早于这个序号。
+    /// 回放的每条命令都必须被接受, 否则说明日志与订单簿不一致, 返回 InvalidData
+    pub fn recover(path: impl AsRef<Path>, policy: FsyncPolicy, mut book: OrderBook<I>) -> io::Result<(Self, Recovery)> {
+        let path = path.as_ref().to_path_buf();
+        let (wal, recovery) = Wal::open(&path, policy)?;
+        let end = recovery.base + recovery.commands.len() as u64;
+        if book.seq() < recovery.base || book.seq() > end {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("journal covers seq {}..={} but the book is at seq {}", recovery.base, end, book.seq()),
+            ));
+        }
+        let skip = (book.seq() - recovery.base) as usize;
+        for (i, cmd) in recovery.commands.iter().enumerate().skip(skip) {
+            book.apply(cmd).map_err(|e| {
+                let seq = recovery.base + i as u64 + 1;
+                io::Error::new(io::ErrorKind::InvalidData, format!("replay of record {} failed: {}", seq, e))
+            })?;
+        }
+        Ok((JournaledBook { book, wal, path }, recovery))
+    }
+
+    /// 写一份订单簿快照并轮换日志
+    ///
+    /// 先 fsync 日志, 再写临时文件并原子改名, 崩溃时旧快照仍然完好。快照落盘后用一份从快照序号开始的
+    /// 空日志原子替换旧日志; 两步之间崩溃时旧日志仍在, 恢复会跳过快照已包含的记录
+    pub fn checkpoint(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        self.wal.sync()?;
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        self.book.save(&mut file)?;
+        file.sync_all()?;
+        std::fs::rename(&tmp, path)?;
+        sync_dir(path)?;
+
+        let tmp = self.path.with_extension("tmp");
+        let wal = Wal::create_from(&tmp, self.wal.policy(), self.book.seq())?;
+        std::fs::rename(&tmp, &self.path)?;
+        sync_dir(&self.path)?;
+        self.wal = wal;
+        Ok(())
+    }
+
+    /// 提交命令: 被拒绝的命令不写日志 (外层 Err 为 IO 错误, 内层 Err 为拒绝原因)
+    pub fn submit(&mut self, cmd: &Command) -> io::Result<Result<Vec<Trade>, String>> {
+        Ok(self.execute(cmd)?.map(|e| e.trades))
+    }
+
+    /// 同 `submit`, 另外返回撮合中撤掉的订单
+    pub fn execute(&mut self, cmd: &Command) -> io::Result<Result<Execution, String>> {
+        if let Err(e) = self.book.validate(cmd) {
+            return Ok(Err(e));
+        }
+        self.wal.append(cmd)?;
+        Ok(self.book.execute(cmd))
+    }
+
+    /// 立即 fsync 日志
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.wal.sync()
+    }
+
+    /// 只读访问订单簿; 改动只能经 `submit`/`execute` 先写日志
+    pub fn book(&self) -> &OrderBook<I> {
+        &self.book
+    }
+
+    pub fn wal(&self) -> &Wal {
+        &self.wal
+    }
+}
+
+/// fsync 文件所在的目录, 让改名落盘
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => File::open(dir)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! 计数分配器的集成测试
+//!
+//! `#[global_allocator]` 会作用于整个测试二进制, 因此单独放在这里, 不影响库的单元测试.
+//! 默认 harness 会在多个线程上并行跑测试, 计数会互相干扰, 所以关闭 harness,
+//! 在 main 中顺序执行, 每个用例开始前清零计数.
+
+use rust_orderbook_benchmark::alloc_counter::alloc_counter::{self, AllocSnapshot, CountingAllocator};
+use rust_orderbook_benchmark::rb_tree::rb_tree::RBTree;
+use rust_orderbook_benchmark::runner::runner::{run_scenario, ScenarioConfig, Structure, Workload};
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// 在计数开启的状态下执行 f, 返回期间的计数增量
+fn measure<R>(f: impl FnOnce() -> R) -> (R, AllocSnapshot) {
+    alloc_counter::set_enabled(true);
+    alloc_counter::reset();
+    let before = AllocSnapshot::take();
+    let result = f();
+    let delta = AllocSnapshot::take().since(&before);
+    alloc_counter::set_enabled(false);
+    (result, delta)
+}
+
+fn test_disabled_counts_nothing() {
+    alloc_counter::set_enabled(false);
+    assert!(!alloc_counter::is_active());
+    let before = AllocSnapshot::take();
+    let buf = std::hint::black_box(vec![0u8; 4096]);
+    drop(buf);
+    assert_eq!(AllocSnapshot::take(), before);
+}
+
+fn test_single_allocation() {
+    let (buf, delta) = measure(|| Vec::<u8>::with_capacity(1 << 20));
+    assert_eq!(delta.allocations, 1);
+    assert_eq!(delta.deallocations, 0);
+    assert_eq!(delta.bytes_allocated, 1 << 20);
+    assert!(delta.peak_bytes >= delta.current_bytes);
+    assert!(delta.current_bytes >= 1 << 20);
+
+    let live = delta.current_bytes;
+    let ((), delta) = measure(|| drop(buf));
+    assert_eq!(delta.allocations, 0);
+    assert_eq!(delta.deallocations, 1);
+    assert_eq!(delta.current_bytes, live - (1 << 20));
+}
+
+fn test_realloc_counts_as_alloc_and_free() {
+    let (buf, delta) = measure(|| {
+        let mut buf = Vec::<u64>::with_capacity(4);
+        buf.reserve_exact(1024);
+        buf
+    });
+    // 一次 alloc + 一次 realloc, realloc 同时计一次释放
+    assert_eq!(delta.allocations, 2);
+    assert_eq!(delta.deallocations, 1);
+    assert_eq!(delta.bytes_allocated, (4 + 1024) * 8);
+    drop(buf);
+}
+
+fn test_tree_insert_allocations() {
+    const N: u64 = 1000;
+    let (tree, delta) = measure(|| {
+        let mut tree = RBTree::new();
+        for k in 0..N as u32 {
+            tree.insert(k, k);
+        }
+        tree
+    });
+    // 每个节点一次分配, 外加句柄表倍增扩容的 O(log n) 次分配
+    assert!(delta.allocations >= N, "{:?}", delta);
+    assert!(delta.allocations <= N + 2 * 10, "{:?}", delta);
+    drop(tree);
+}
+
+fn test_scenario_alloc_stats() {
+    alloc_counter::set_enabled(true);
+    alloc_counter::reset();
+    assert!(alloc_counter::is_active());
+    let result = run_scenario(&ScenarioConfig {
+        structure: Structure::RBTree,
+        degree: 3,
+        size: 1000,
+        workload: Workload::Insert,
+        seed: 1,
+        iterations: 2,
+    });
+    alloc_counter::set_enabled(false);
+    let alloc = result.alloc.expect("alloc stats");
+    let per_op = alloc.op("insert").unwrap().allocations_per_op;
+    assert!((1.0..1.2).contains(&per_op), "{}", per_op);
+    assert!(alloc.peak_heap_bytes >= 1000 * std::mem::size_of::<(u32, u32)>());
+}
+
+fn main() {
+    let tests: [(&str, fn()); 5] = [
+        ("disabled_counts_nothing", test_disabled_counts_nothing),
+        ("single_allocation", test_single_allocation),
+        ("realloc_counts_as_alloc_and_free", test_realloc_counts_as_alloc_and_free),
+        ("tree_insert_allocations", test_tree_insert_allocations),
+        ("scenario_alloc_stats", test_scenario_alloc_stats),
+    ];
+    for (name, test) in tests {
+        test();
+        println!("test {} ... ok", name);
+    }
+}